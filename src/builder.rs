@@ -0,0 +1,2081 @@
+// Copyright 2015 Sam Payson. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Building buffers.
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::cmp;
+use core::fmt;
+use core::marker;
+use core::mem;
+use core::ops;
+use core::slice;
+#[cfg(feature = "std")]
+use std::error;
+
+use {ByRef, Endian, OrdTable, Offset, SOffset, Str, Struct, Table, UOffset, UnionOffset, VOffset,
+     Vector, View, field_pos, get_root, get_size_prefixed_root, FILE_IDENTIFIER_LENGTH};
+
+// Return a byte slice which refers to the same region of memory as `v`.
+fn view_slice_bytes<T: Struct>(v: &[T]) -> &[u8] {
+    // This is fine because a `Struct` has no padding bytes.
+    unsafe { slice::from_raw_parts(v.as_ptr() as *const u8, mem::size_of_val(v)) }
+}
+
+// Return a byte slice which refers to the same region of memory as `t`.
+fn view_bytes<T: Struct>(t: &T) -> &[u8] {
+    view_slice_bytes(slice::from_ref(t))
+}
+
+// The largest alignment that anything in a buffer can need (`force_align` allows up to 16).
+const MAX_ALIGN: usize = 16;
+
+// The largest buffer which can be built, since offsets within it are signed 32-bit integers.
+const MAX_BUFFER_SIZE: usize = (1 << 31) - 1;
+
+/// The ways in which building a buffer can fail.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BuilderError {
+    /// The builder's memory doesn't have room for what was being added, and can't grow (e.g.
+    /// because it's a slice given to `FlatBufferBuilder::from_slice`, or its `Allocator` refused).
+    OutOfSpace,
+
+    /// The buffer would be larger than 2 GiB, which is as large as a flatbuffer can be.
+    BufferTooLarge,
+
+    /// A table, string or vector was started while a table was being built. Tables must be built
+    /// one at a time, after everything that they refer to.
+    Nested,
+
+    /// An offset was given which isn't to something that's already in the buffer.
+    InvalidOffset,
+
+    /// A table's fields take up more than 64 KiB, which is as much as a vtable can describe.
+    ObjectTooLarge,
+
+    /// The field with this `VOffset` was added to a table more than once.
+    DuplicateField(VOffset),
+
+    /// A field was added with this `VOffset`, which isn't one of the table's fields (e.g. because
+    /// it's past the `num_fields` given to `end_table`).
+    InvalidField(VOffset),
+
+    /// A union was added whose type tag is 0 (`NONE`), but whose value isn't null.
+    InvalidUnionTag,
+
+    /// A file identifier was given which isn't 4 bytes long.
+    InvalidIdentifier,
+
+    /// A table was finished without the required field with this `VOffset`.
+    MissingRequiredField(VOffset),
+}
+
+impl fmt::Display for BuilderError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            BuilderError::OutOfSpace        => write!(f, "the buffer is out of space"),
+            BuilderError::BufferTooLarge    => write!(f, "the buffer is larger than 2 GiB"),
+            BuilderError::Nested            => {
+                write!(f, "a table, string or vector was started inside a table")
+            }
+            BuilderError::InvalidOffset     => write!(f, "an offset is outside the buffer"),
+            BuilderError::ObjectTooLarge    => write!(f, "a table is larger than 64 KiB"),
+            BuilderError::DuplicateField(v) => write!(f, "the field at {} was added twice", v),
+            BuilderError::InvalidField(v)   => write!(f, "there's no field at {}", v),
+            BuilderError::InvalidUnionTag   => write!(f, "a union value has the type NONE"),
+            BuilderError::InvalidIdentifier => write!(f, "file identifiers must be 4 bytes"),
+            BuilderError::MissingRequiredField(v) => {
+                write!(f, "missing required field at {}", v)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl error::Error for BuilderError {}
+
+// Unwrap the result of one of the `try_*` methods of `FlatBufferBuilder`, for the methods which
+// panic instead of returning an error.
+fn check<T>(r: Result<T, BuilderError>) -> T {
+    match r {
+        Ok(t)  => t,
+        Err(e) => panic!("{}", e),
+    }
+}
+
+/// The memory which a `FlatBufferBuilder` builds its buffer in. Implement this to build buffers in
+/// an arena, a pool of buffers, or memory which has been set aside for I/O, and pass it to
+/// `FlatBufferBuilder::with_allocator`.
+///
+/// The builder keeps its bookkeeping (the vtables it has written, the shared strings, and the fields
+/// of the table being built) at the start of the same memory, so this is the only memory it uses.
+pub trait Allocator {
+    /// The memory which the buffer is built in.
+    fn memory(&self) -> &[u8];
+
+    fn memory_mut(&mut self) -> &mut [u8];
+
+    /// Make `memory` at least `len` bytes long, keeping its contents at the same indices (though
+    /// it may move in memory). If it can't grow, return `BuilderError::OutOfSpace`.
+    fn grow(&mut self, len: usize) -> Result<(), BuilderError>;
+
+    /// Called by `FlatBufferBuilder::clear`, when nothing in `memory` is needed any more, e.g. to
+    /// give some of it back. The default does nothing.
+    fn reset(&mut self) {}
+}
+
+impl Allocator for Vec<u8> {
+    fn memory(&self) -> &[u8] { self }
+
+    fn memory_mut(&mut self) -> &mut [u8] { self }
+
+    fn grow(&mut self, len: usize) -> Result<(), BuilderError> {
+        if len > self.len() {
+            self.resize(len, 0);
+        }
+
+        Ok(())
+    }
+}
+
+/// A slice can't grow, so a builder which writes into one fails when it runs out of space.
+impl Allocator for &mut [u8] {
+    fn memory(&self) -> &[u8] { self }
+
+    fn memory_mut(&mut self) -> &mut [u8] { self }
+
+    fn grow(&mut self, len: usize) -> Result<(), BuilderError> {
+        if len > self.len() { Err(BuilderError::OutOfSpace) } else { Ok(()) }
+    }
+}
+
+/// Lets an allocator be borrowed by a builder, rather than moved into it, so that it can be looked
+/// at or reused after the builder is dropped.
+impl<A: Allocator + ?Sized> Allocator for &mut A {
+    fn memory(&self) -> &[u8] { (**self).memory() }
+
+    fn memory_mut(&mut self) -> &mut [u8] { (**self).memory_mut() }
+
+    fn grow(&mut self, len: usize) -> Result<(), BuilderError> { (**self).grow(len) }
+
+    fn reset(&mut self) { (**self).reset() }
+}
+
+// The memory which a `VecDownward` is built in. The common cases don't need a `Box`, so that
+// building into a slice doesn't allocate.
+enum Storage<'buf> {
+    Owned(Vec<u8>),
+    Borrowed(&'buf mut [u8]),
+    Custom(Box<dyn Allocator + 'buf>),
+}
+
+impl<'buf> Storage<'buf> {
+    fn allocator(&self) -> &dyn Allocator {
+        match *self {
+            Storage::Owned(ref v)    => v,
+            Storage::Borrowed(ref s) => s,
+            Storage::Custom(ref a)   => &**a,
+        }
+    }
+
+    fn allocator_mut(&mut self) -> &mut dyn Allocator {
+        match *self {
+            Storage::Owned(ref mut v)    => v,
+            Storage::Borrowed(ref mut s) => s,
+            Storage::Custom(ref mut a)   => &mut **a,
+        }
+    }
+
+    fn bytes(&self) -> &[u8] { self.allocator().memory() }
+
+    fn bytes_mut(&mut self) -> &mut [u8] { self.allocator_mut().memory_mut() }
+}
+
+// Reverse-growing vector, along with some scratch space for the builder's bookkeeping. The data
+// lives in `inner[next..end]`, where `end` is chosen so that the end of the data is aligned to
+// `MAX_ALIGN` in memory. Everything in a flatbuffer is aligned relative to its end, so this keeps
+// the finished buffer readable in place. The scratch space grows upwards from the start of `inner`,
+// into `inner[..scratch]`, so that the builder doesn't need any memory other than `inner`.
+struct VecDownward<'buf> {
+    inner:    Storage<'buf>,
+    next:     usize,
+    end:      usize,
+    scratch:  usize,
+
+    // The error which the builder has failed with, if it has. Nothing more can be written until
+    // it's cleared, since the buffer and the bookkeeping may be half-changed.
+    error:    Option<BuilderError>,
+
+    // Whether the data is a finished buffer, i.e. nothing has been added or removed since `finish`.
+    finished: bool,
+
+    // The number of bytes which have been moved around in the scratch space, so that tests can
+    // check that the bookkeeping doesn't get slower as it grows.
+    #[cfg(test)]
+    moved:    usize,
+}
+
+// The largest index into `v` whose address is a multiple of `MAX_ALIGN`.
+fn aligned_end(v: &[u8]) -> usize {
+    let addr = v.as_ptr() as usize + v.len();
+    v.len().saturating_sub(addr % MAX_ALIGN)
+}
+
+impl<'buf> VecDownward<'buf> {
+    fn new(inner: Storage<'buf>) -> VecDownward<'buf> {
+        let end = aligned_end(inner.bytes());
+
+        VecDownward {
+            inner,
+            next:     end,
+            end,
+            scratch:  0,
+            error:    None,
+            finished: false,
+            #[cfg(test)]
+            moved:    0,
+        }
+    }
+
+    fn data(&self) -> &[u8] { &self.inner.bytes()[self.next..self.end] }
+
+    fn data_mut(&mut self) -> &mut [u8] { &mut self.inner.bytes_mut()[self.next..self.end] }
+
+    fn data_at_mut(&mut self, offset: usize) -> &mut [u8] {
+        &mut self.inner.bytes_mut()[self.end - offset..self.end]
+    }
+
+    fn len(&self) -> usize { self.end - self.next }
+
+    fn clear(&mut self) {
+        self.inner.allocator_mut().reset();
+
+        self.end      = aligned_end(self.inner.bytes());
+        self.next     = self.end;
+        self.scratch  = 0;
+        self.error    = None;
+        self.finished = false;
+    }
+
+    // If the data is in a `Vec`, take it, along with the index that the data starts at. The `Vec`
+    // is truncated to the end of the data, and the `VecDownward` is left empty.
+    fn take(&mut self) -> Option<(Vec<u8>, usize)> {
+        let mut vec = match self.inner {
+            Storage::Owned(ref mut v) => mem::take(v),
+            _                         => return None,
+        };
+
+        vec.truncate(self.end);
+        let start = self.next;
+
+        self.end  = 0;
+        self.next = 0;
+        self.clear();
+
+        Some((vec, start))
+    }
+
+    // Make sure that there are at least `len` bytes free between the scratch space and the data.
+    fn reserve(&mut self, len: usize) -> Result<(), BuilderError> {
+        if let Some(e) = self.error {
+            return Err(e)
+        }
+
+        if len <= self.next - self.scratch {
+            return Ok(())
+        }
+
+        let data_len = self.len();
+        let used     = data_len + self.scratch;
+
+        if let Err(e) = self.inner.allocator_mut().grow(2*used + len + MAX_ALIGN) {
+            return self.fail(e)
+        }
+
+        // The scratch space stays where it is, but the data has to move to the new end.
+        let new      = self.inner.bytes_mut();
+        let new_end  = aligned_end(new);
+        let new_next = new_end - data_len;
+
+        new.copy_within(self.next..self.end, new_next);
+
+        self.next = new_next;
+        self.end  = new_end;
+
+        Ok(())
+    }
+
+    // Adds space to the front of the vector, growing towards lower addresses. The returned `usize`
+    // is the index of the new space in `inner`.
+    fn make_space(&mut self, len: usize) -> Result<usize, BuilderError> {
+        if len > MAX_BUFFER_SIZE - self.len() {
+            return self.fail(BuilderError::BufferTooLarge)
+        }
+
+        self.reserve(len)?;
+
+        self.next    -= len;
+        self.finished = false;
+
+        Ok(self.next)
+    }
+
+    // Append some raw bytes to the front of the buffer.
+    fn push(&mut self, dat: &[u8]) -> Result<(), BuilderError> {
+        let off = self.make_space(dat.len())?;
+
+        self.inner.bytes_mut()[off..off + dat.len()].copy_from_slice(dat);
+
+        Ok(())
+    }
+
+    // Add `len` *NUL* bytes to the front of the buffer.
+    fn fill(&mut self, len: usize) -> Result<(), BuilderError> {
+        let off = self.make_space(len)?;
+
+        for b in &mut self.inner.bytes_mut()[off..off + len] {
+            *b = 0;
+        }
+
+        Ok(())
+    }
+
+    // Remember that the builder has failed with `e`, and return it.
+    fn fail<T>(&mut self, e: BuilderError) -> Result<T, BuilderError> {
+        self.error = Some(e);
+        Err(e)
+    }
+
+    // Return the error which the builder has failed with, if there is one.
+    fn check_error(&self) -> Result<(), BuilderError> {
+        match self.error {
+            Some(e) => Err(e),
+            None    => Ok(()),
+        }
+    }
+
+    // Remove `len` bytes from the front of the buffer.
+    fn pop(&mut self, len: usize) {
+        self.next    += len;
+        self.finished = false;
+    }
+
+    fn scratch(&self) -> &[u8] { &self.inner.bytes()[..self.scratch] }
+
+    // Split `inner` into the scratch space (along with the free space after it) and the data.
+    fn split_mut(&mut self) -> (&mut [u8], &[u8]) {
+        let (next, end)     = (self.next, self.end);
+        let (scratch, data) = self.inner.bytes_mut().split_at_mut(next);
+
+        (scratch, &data[..end - next])
+    }
+
+    // Move the bytes at `src` in the scratch space (or the free space after it) to `dest`.
+    fn scratch_move(&mut self, src: ops::Range<usize>, dest: usize) {
+        #[cfg(test)]
+        {
+            self.moved += src.len();
+        }
+
+        self.inner.bytes_mut()[..self.next].copy_within(src, dest);
+    }
+
+    // Insert `dat` into the scratch space at `pos`, moving everything after it up.
+    fn scratch_insert(&mut self, pos: usize, dat: &[u8]) -> Result<(), BuilderError> {
+        self.reserve(dat.len())?;
+
+        let end = self.scratch;
+        self.scratch_move(pos..end, pos + dat.len());
+        self.inner.bytes_mut()[pos..pos + dat.len()].copy_from_slice(dat);
+
+        self.scratch += dat.len();
+
+        Ok(())
+    }
+
+    // Remove `len` bytes from the end of the scratch space.
+    fn scratch_pop(&mut self, len: usize) {
+        self.scratch -= len;
+    }
+}
+
+/// The vectors of type tags and offsets which make up a vector of unions. This is returned by
+/// `FlatBufferBuilder::create_union_vector`.
+pub struct UnionVectorOffset<U> {
+    types:  Offset<Vector<'static, u8>>,
+    values: Offset<Vector<'static, Offset<Table<'static>>>>,
+    _u:     marker::PhantomData<U>,
+}
+
+impl<U> Clone for UnionVectorOffset<U> {
+    fn clone(&self) -> Self { *self }
+}
+
+impl<U> Copy for UnionVectorOffset<U> {}
+
+/// A vector of `Option<U>`s is stored the same way as a vector of `U`s, with `None` elements stored
+/// as `NONE`, so it can be added to a table wherever a vector of `U`s can.
+impl<U> From<UnionVectorOffset<Option<U>>> for UnionVectorOffset<U> {
+    fn from(o: UnionVectorOffset<Option<U>>) -> UnionVectorOffset<U> {
+        UnionVectorOffset {
+            types:  o.types,
+            values: o.values,
+            _u:     marker::PhantomData,
+        }
+    }
+}
+
+/// A finished buffer, which is returned by `FlatBufferBuilder::finished_data`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FinishedBuffer<'buf> {
+    data:          &'buf [u8],
+    size_prefixed: bool,
+}
+
+impl<'buf> FinishedBuffer<'buf> {
+    /// The bytes of the buffer, including its size prefix if it has one.
+    pub fn as_bytes(&self) -> &'buf [u8] { self.data }
+
+    /// Whether the buffer was finished with `finish_size_prefixed` (or
+    /// `finish_size_prefixed_with_identifier`).
+    pub fn is_size_prefixed(&self) -> bool { self.size_prefixed }
+
+    /// The root table of the buffer, read as `T`. Like `get_root`, this does no checking, but the
+    /// builder has just written the buffer, so it's well-formed.
+    pub fn root<T: View<'buf>>(&self) -> T {
+        if self.size_prefixed { get_size_prefixed_root(self.data) } else { get_root(self.data) }
+    }
+}
+
+impl AsRef<[u8]> for FinishedBuffer<'_> {
+    fn as_ref(&self) -> &[u8] { self.data }
+}
+
+// Given a field's ID number, convert it to a VOffset
+fn field_index_to_offset(field_id: VOffset) -> VOffset {
+    let fixed_fields = 2; // VTable size and Object size.
+    (field_id + fixed_fields) * (mem::size_of::<VOffset>() as VOffset)
+}
+
+// Return the number of bytes needed to pad a scalar for alignment (see usage in e.g.
+// `FlatBufferBuilder::align(..)`).
+fn padding_bytes(buf_size: usize, scalar_size: usize) -> usize {
+    (!buf_size).wrapping_add(1) & (scalar_size - 1)
+}
+
+// The 64-bit FNV-1a hash of `bytes`.
+fn hash_bytes(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |h, &b| (h ^ b as u64).wrapping_mul(0x100000001b3))
+}
+
+// The vtable which is `off` bytes from the end of `data`.
+fn vtable_at(data: &[u8], off: UOffset) -> &[u8] {
+    let vt = &data[data.len() - off as usize..];
+    &vt[..VOffset::read_le(vt) as usize]
+}
+
+// The contents of the string which is `off` bytes from the end of `data`.
+fn string_at(data: &[u8], off: UOffset) -> &[u8] {
+    Str::view(&data[data.len() - off as usize..], 0).as_bytes()
+}
+
+// The builder's bookkeeping is kept in the scratch space of its `VecDownward`, as three arrays of
+// fixed-size entries, one after another:
+//
+//     inner: [ vtable slots | string slots | fields | free space | data ]
+//            0              |              |        scratch      next   end
+//
+// * The vtables which have been written, as a hash table of `vtable_slots` `UOffset`s, keyed by a
+//   hash of their contents.
+// * The shared strings which have been written, as a hash table of `string_slots` `UOffset`s,
+//   keyed by a hash of their contents.
+// * The fields of the table which is being built, as `num_fields` `FieldLoc`s.
+//
+// Both hash tables use linear probing, and a `UOffset` of 0 marks an empty slot (no vtable or
+// string can be at the very end of the buffer). They're kept at most half full, and double in size
+// when they would be fuller than that, so a table only moves what's after it when it grows. The new
+// table is built in the free space first, which is why growing briefly needs twice its size.
+//
+// This relies on a few invariants:
+//
+// * Every entry is an offset from the end of the data, so entries stay valid when `reserve` moves
+//   the data to the end of a bigger allocation. Nothing may be popped off the data (with
+//   `pop_bytes`) which a vtable or shared string entry points at.
+// * Fields are only tracked between `start_table` and `end_table`, and `end_table` removes them
+//   before it adds a vtable, so the fields are always the last thing in the scratch space and
+//   adding one never moves anything else.
+// * The scratch space never overlaps the data: everything that adds to either goes through
+//   `reserve`, which makes room between `scratch` and `next` first.
+// * If anything fails part way through, the builder stays failed until `clear`, which throws
+//   away all of the bookkeeping, so a half-moved table is never read.
+const SLOT_SIZE:        usize = 4;
+const FIELD_ENTRY_SIZE: usize = 6;
+
+// The number of slots in a hash table when the first entry is added.
+const MIN_SLOTS: usize = 2;
+
+// The location of a field, stored as a UOffset from the end of the buffer and a field ID.
+struct FieldLoc {
+    off: UOffset,
+    id:  VOffset,
+}
+
+/// This type is used by the generated `.*Builder` types for Tables. A `FlatBufferBuilder` can be
+/// re-used if the `clear()` method is called between uses; this will avoid some allocations.
+///
+/// A builder either owns its buffer, which grows as needed, writes into a slice which it's given,
+/// and never allocates, or gets its memory from an `Allocator`. Each method which adds to the
+/// buffer has a `try_*` counterpart, which returns a `BuilderError` (e.g. when the memory can't grow,
+/// or when a table is started inside another one); the other methods panic. Once a `try_*` method
+/// has failed, the buffer may be half-written, so every later `try_*` call returns the same error
+/// until the builder is cleared.
+pub struct FlatBufferBuilder<'buf> {
+    buf:            VecDownward<'buf>,
+    vtable_slots:   usize,
+    string_slots:   usize,
+    num_vtables:    usize,
+    num_strings:    usize,
+    num_fields:     usize,
+    min_align:      usize,
+    force_defaults: bool,
+
+    // Whether the buffer was last finished with a size prefix.
+    size_prefixed:  bool,
+}
+
+impl FlatBufferBuilder<'static> {
+    pub fn new(initial_capacity: usize) -> FlatBufferBuilder<'static> {
+        let vec = alloc::vec![0; initial_capacity + MAX_ALIGN];
+        FlatBufferBuilder::with_storage(Storage::Owned(vec))
+    }
+
+    /// Create a builder which builds in `vec`, e.g. one which was returned by `take_buffer`. All
+    /// of `vec`'s capacity is used, and it grows as needed.
+    pub fn from_vec(vec: Vec<u8>) -> FlatBufferBuilder<'static> {
+        FlatBufferBuilder::with_storage(Storage::Owned(fill_capacity(vec)))
+    }
+}
+
+// Extend `vec` to its capacity, so that a builder can use all of it.
+fn fill_capacity(mut vec: Vec<u8>) -> Vec<u8> {
+    let cap = vec.capacity();
+    vec.resize(cap, 0);
+    vec
+}
+
+// Put `off` in the first empty slot of the hash table of `slots` offsets at the start of `table`,
+// starting from the slot for `hash`.
+fn insert_slot(table: &mut [u8], slots: usize, hash: u64, off: UOffset) {
+    let mut idx = hash as usize & (slots - 1);
+
+    while UOffset::read_le(&table[idx * SLOT_SIZE..]) != 0 {
+        idx = (idx + 1) & (slots - 1);
+    }
+
+    off.write_le(&mut table[idx * SLOT_SIZE..]);
+}
+
+impl<'buf> FlatBufferBuilder<'buf> {
+    /// Create a builder which writes into `buf`, rather than allocating its own buffer. The
+    /// finished buffer is at the end of `buf`, and can be read with `get_buffer`. Up to 15 bytes
+    /// at the end of `buf` may go unused, so that the finished buffer is aligned in memory.
+    ///
+    /// The builder's bookkeeping is kept at the start of `buf`, so `buf` needs room for it as well
+    /// as the buffer: 6 bytes for each field of the table being built, and up to 16 for each
+    /// distinct vtable and each string created with `create_shared_string` (briefly 32, while
+    /// their tables grow).
+    pub fn from_slice(buf: &'buf mut [u8]) -> FlatBufferBuilder<'buf> {
+        FlatBufferBuilder::with_storage(Storage::Borrowed(buf))
+    }
+
+    /// Create a builder which builds in memory from `allocator`, asking it to grow when it runs out
+    /// of space.
+    pub fn with_allocator<A: Allocator + 'buf>(allocator: A) -> FlatBufferBuilder<'buf> {
+        FlatBufferBuilder::with_storage(Storage::Custom(Box::new(allocator)))
+    }
+
+    fn with_storage(storage: Storage<'buf>) -> FlatBufferBuilder<'buf> {
+        FlatBufferBuilder {
+            buf:            VecDownward::new(storage),
+            vtable_slots:   0,
+            string_slots:   0,
+            num_vtables:    0,
+            num_strings:    0,
+            num_fields:     0,
+            min_align:      1,
+            force_defaults: false,
+            size_prefixed:  false,
+        }
+    }
+
+    /// Prepare to build another FlatBuffer from scratch (forgetting everything about any previous
+    /// FlatBuffer), but reuse the memory from the internal buffers to avoid extra reallocations.
+    pub fn clear(&mut self) {
+        self.buf.clear();
+        self.vtable_slots = 0;
+        self.string_slots = 0;
+        self.num_vtables  = 0;
+        self.num_strings  = 0;
+        self.num_fields   = 0;
+        self.min_align    = 1;
+    }
+
+    pub fn get_size(&self) -> usize {
+        self.buf.len()
+    }
+
+    pub fn get_buffer(&self) -> &[u8] { self.buf.data() }
+
+    /// The finished buffer, or `None` if the buffer hasn't been finished, or has been added to
+    /// since.
+    pub fn finished_data(&self) -> Option<FinishedBuffer<'_>> {
+        if !self.buf.finished {
+            return None
+        }
+
+        Some(FinishedBuffer { data: self.buf.data(), size_prefixed: self.size_prefixed })
+    }
+
+    /// Take the finished buffer without copying it, as a `Vec` and the index in it where the
+    /// buffer starts; the buffer runs to the end of the `Vec`. The builder is left empty, and
+    /// allocates a new buffer when it's next used, unless it's given one with `reuse_vec`.
+    ///
+    /// Return `None` if the buffer hasn't been finished, or the builder isn't building in a `Vec`
+    /// (i.e. it was made with `from_slice` or `with_allocator`).
+    pub fn take_buffer(&mut self) -> Option<(Vec<u8>, usize)> {
+        if !self.buf.finished {
+            return None
+        }
+
+        let taken = self.buf.take()?;
+        self.clear();
+
+        Some(taken)
+    }
+
+    /// Like `take_buffer`, but consumes the builder.
+    pub fn into_vec(mut self) -> Option<(Vec<u8>, usize)> {
+        self.take_buffer()
+    }
+
+    /// Clear the builder and build in `vec` from now on, e.g. to give back a `Vec` from
+    /// `take_buffer` once it's no longer needed. All of `vec`'s capacity is used.
+    pub fn reuse_vec(&mut self, vec: Vec<u8>) {
+        self.buf = VecDownward::new(Storage::Owned(fill_capacity(vec)));
+        self.clear();
+    }
+
+    /// Determines whether or not default values should be hard-coded into the wire representation.
+    pub fn force_defaults(&mut self, fd: bool) {
+        self.force_defaults = fd;
+    }
+
+    pub fn pad(&mut self, num_bytes: usize) {
+        check(self.try_pad(num_bytes))
+    }
+
+    pub fn try_pad(&mut self, num_bytes: usize) -> Result<(), BuilderError> {
+        self.buf.fill(num_bytes)
+    }
+
+    pub fn align(&mut self, elem_size: usize) {
+        check(self.try_align(elem_size))
+    }
+
+    pub fn try_align(&mut self, elem_size: usize) -> Result<(), BuilderError> {
+        self.track_min_align(elem_size);
+
+        let len = self.buf.len();
+
+        self.buf.fill(padding_bytes(len, elem_size))
+    }
+
+    // Remember the largest alignment used so far, so that `finish` can align the whole buffer to
+    // it.
+    fn track_min_align(&mut self, align: usize) {
+        if align > self.min_align {
+            self.min_align = align;
+        }
+    }
+
+    pub fn push_bytes(&mut self, dat: &[u8]) {
+        check(self.try_push_bytes(dat))
+    }
+
+    pub fn try_push_bytes(&mut self, dat: &[u8]) -> Result<(), BuilderError> {
+        self.buf.push(dat)
+    }
+
+    /// Remove the last `len` bytes which were added. These mustn't include a vtable or a string
+    /// created with `create_shared_string`, since the builder would go on sharing them.
+    pub fn pop_bytes(&mut self, len: usize) {
+        check(self.try_pop_bytes(len))
+    }
+
+    /// Like `pop_bytes`, but return `BuilderError::InvalidOffset` if the buffer is smaller than
+    /// `len`.
+    pub fn try_pop_bytes(&mut self, len: usize) -> Result<(), BuilderError> {
+        self.buf.check_error()?;
+
+        if len > self.get_size() {
+            return self.buf.fail(BuilderError::InvalidOffset)
+        }
+
+        self.buf.pop(len);
+
+        Ok(())
+    }
+
+    pub fn push_scalar<T: Endian>(&mut self, elem: T) -> usize {
+        check(self.try_push_scalar(elem))
+    }
+
+    pub fn try_push_scalar<T: Endian>(&mut self, elem: T) -> Result<usize, BuilderError> {
+        self.try_align(mem::size_of::<T>())?;
+
+        self.buf.fill(mem::size_of::<T>())?;
+        elem.write_le(self.buf.data_mut());
+
+        Ok(self.get_size())
+    }
+
+    pub fn push_offset<T>(&mut self, off: Offset<T>) -> usize {
+        check(self.try_push_offset(off))
+    }
+
+    pub fn try_push_offset<T>(&mut self, off: Offset<T>) -> Result<usize, BuilderError> {
+        let adjusted = self.try_refer_to(off.inner)?;
+        self.try_push_scalar(adjusted)
+    }
+
+    pub fn refer_to(&mut self, off: UOffset) -> UOffset {
+        check(self.try_refer_to(off))
+    }
+
+    pub fn try_refer_to(&mut self, off: UOffset) -> Result<UOffset, BuilderError> {
+        self.try_align(mem::size_of::<UOffset>())?;
+        let buf_size = self.get_size() as UOffset;
+
+        if off > buf_size {
+            return self.buf.fail(BuilderError::InvalidOffset)
+        }
+
+        Ok(buf_size - off + (mem::size_of::<UOffset>() as UOffset))
+    }
+
+    pub fn track_field(&mut self, field: VOffset, off: UOffset) {
+        check(self.try_track_field(field, off))
+    }
+
+    pub fn try_track_field(&mut self, field: VOffset, off: UOffset) -> Result<(), BuilderError> {
+        let mut entry = [0; FIELD_ENTRY_SIZE];
+        off.write_le(&mut entry);
+        field.write_le(&mut entry[4..]);
+
+        let pos = self.buf.scratch;
+        self.buf.scratch_insert(pos, &entry)?;
+        self.num_fields += 1;
+
+        Ok(())
+    }
+
+    // The `idx`th field of the table which is being built.
+    fn field_loc(&self, idx: usize) -> FieldLoc {
+        let start = self.string_table() + (self.string_slots * SLOT_SIZE);
+        let entry = &self.buf.scratch()[start + idx * FIELD_ENTRY_SIZE..];
+
+        FieldLoc {
+            off: UOffset::read_le(entry),
+            id:  VOffset::read_le(&entry[4..]),
+        }
+    }
+
+    pub fn add_scalar<T: Endian>(&mut self, field: VOffset, e: T, def: T) {
+        check(self.try_add_scalar(field, e, def))
+    }
+
+    pub fn try_add_scalar<T: Endian>(&mut self, field: VOffset, e: T, def: T)
+        -> Result<(), BuilderError> {
+
+        self.buf.check_error()?;
+
+        if e == def && !self.force_defaults { return Ok(()) }
+
+        let off = self.try_push_scalar(e)? as UOffset;
+
+        self.try_track_field(field, off)
+    }
+
+    pub fn add_offset<T>(&mut self, field: VOffset, off: Offset<T>) {
+        check(self.try_add_offset(field, off))
+    }
+
+    pub fn try_add_offset<T>(&mut self, field: VOffset, off: Offset<T>) -> Result<(), BuilderError> {
+        self.buf.check_error()?;
+
+        if off.inner == 0 { return Ok(()) }
+
+        let adjusted = self.try_refer_to(off.inner)?;
+        self.try_add_scalar(field, adjusted, 0)
+    }
+
+    /// Add a union, storing the type tag of `value` in `type_field` and its offset in
+    /// `value_field`. Nothing is added if the offset is null.
+    pub fn add_union<U: UnionOffset>(&mut self, type_field: VOffset, value_field: VOffset, value: U) {
+        check(self.try_add_union(type_field, value_field, value))
+    }
+
+    pub fn try_add_union<U: UnionOffset>(&mut self, type_field: VOffset, value_field: VOffset,
+                                         value: U) -> Result<(), BuilderError> {
+
+        self.buf.check_error()?;
+
+        let (tag, off) = (value.tag(), value.value());
+
+        if off.inner == 0 { return Ok(()) }
+
+        if tag == 0 {
+            return self.buf.fail(BuilderError::InvalidUnionTag)
+        }
+
+        self.try_add_offset(value_field, off)?;
+        self.try_add_scalar(type_field, tag, 0)
+    }
+
+    /// Add a vector of unions, storing the type tags in `types_field` and the values in
+    /// `values_field`.
+    pub fn add_union_vector<U>(&mut self, types_field: VOffset, values_field: VOffset,
+                               v: UnionVectorOffset<U>) {
+        check(self.try_add_union_vector(types_field, values_field, v))
+    }
+
+    pub fn try_add_union_vector<U>(&mut self, types_field: VOffset, values_field: VOffset,
+                                   v: UnionVectorOffset<U>) -> Result<(), BuilderError> {
+        self.try_add_offset(values_field, v.values)?;
+        self.try_add_offset(types_field, v.types)
+    }
+
+    pub fn add_struct<T: Struct>(&mut self, field: VOffset, ptr: &T) {
+        check(self.try_add_struct(field, ptr))
+    }
+
+    pub fn try_add_struct<T: Struct>(&mut self, field: VOffset, ptr: &T) -> Result<(), BuilderError> {
+        self.try_align(mem::align_of::<T>())?;
+        self.try_push_bytes(view_bytes(ptr))?;
+
+        let off = self.get_size() as UOffset;
+        self.try_track_field(field, off)
+    }
+
+    pub fn add_struct_offset(&mut self, field: VOffset, off: UOffset) {
+        self.track_field(field, off);
+    }
+
+    pub fn try_add_struct_offset(&mut self, field: VOffset, off: UOffset)
+        -> Result<(), BuilderError> {
+
+        self.try_track_field(field, off)
+    }
+
+    pub fn not_nested(&self) {
+        check(self.try_not_nested())
+    }
+
+    /// Return `BuilderError::Nested` if a table is being built. Nothing has been changed when this
+    /// fails, so it doesn't stop the builder from being used.
+    pub fn try_not_nested(&self) -> Result<(), BuilderError> {
+        self.buf.check_error()?;
+
+        if self.num_fields != 0 {
+            return Err(BuilderError::Nested)
+        }
+
+        Ok(())
+    }
+
+    pub fn start_table(&self) -> UOffset {
+        check(self.try_start_table())
+    }
+
+    pub fn try_start_table(&self) -> Result<UOffset, BuilderError> {
+        self.try_not_nested()?;
+        Ok(self.get_size() as UOffset)
+    }
+
+    pub fn end_table(&mut self, start: UOffset, num_fields: VOffset) -> UOffset {
+        check(self.try_end_table(start, num_fields))
+    }
+
+    pub fn try_end_table(&mut self, start: UOffset, num_fields: VOffset)
+        -> Result<UOffset, BuilderError> {
+
+        let vtable_offset_loc = self.write_vtable(start, num_fields)?;
+
+        // If an identical vtable has already been written, use that one and throw this one away.
+        // Vtables are kept in a hash table, so this doesn't get slower as the number of distinct
+        // vtables grows.
+
+        let hash = hash_bytes(self.current_vtable());
+
+        let vt_use = match self.find_vtable(hash) {
+            Some(off) => {
+                let to_pop = self.get_size() - vtable_offset_loc;
+                self.buf.pop(to_pop);
+                off
+            }
+            None => {
+                let off = self.get_size() as UOffset;
+                self.insert_vtable(hash, off)?;
+                off
+            }
+        };
+
+        self.set_vtable(vtable_offset_loc, vt_use);
+
+        Ok(vtable_offset_loc as UOffset)
+    }
+
+    // Write the vtable for the table which was started at `start` (along with the table's offset
+    // to it, which is filled in by `set_vtable`), and return the location of the table.
+    fn write_vtable(&mut self, start: UOffset, num_fields: VOffset) -> Result<usize, BuilderError> {
+        let vtable_offset_loc = self.try_push_scalar::<SOffset>(0)?;
+
+        let table_object_size = vtable_offset_loc - (start as usize);
+        let vtable_size       = (num_fields as usize + 2) * mem::size_of::<VOffset>();
+
+        // 16-bit offsets
+        if table_object_size > VOffset::MAX as usize || vtable_size > VOffset::MAX as usize {
+            return self.buf.fail(BuilderError::ObjectTooLarge)
+        }
+
+        self.buf.fill((num_fields as usize) * mem::size_of::<VOffset>())?;
+        self.try_push_scalar(table_object_size as VOffset)?;
+        self.try_push_scalar(vtable_size as VOffset)?;
+
+        for i in 0..self.num_fields {
+            let field_loc = self.field_loc(i);
+            let id        = field_loc.id as usize;
+
+            if id < field_index_to_offset(0) as usize || id >= vtable_size || !id.is_multiple_of(2) {
+                return self.buf.fail(BuilderError::InvalidField(field_loc.id))
+            }
+
+            // A field's offset is from the start of the table, so it's less than the table's size
+            // unless it was added to a different table.
+            let pos = match vtable_offset_loc.checked_sub(field_loc.off as usize) {
+                Some(pos) if pos <= table_object_size => pos as VOffset,
+                _                                     => {
+                    return self.buf.fail(BuilderError::ObjectTooLarge)
+                }
+            };
+
+            let slot = &mut self.buf.data_mut()[id..];
+
+            if VOffset::read_le(slot) != 0 {
+                return self.buf.fail(BuilderError::DuplicateField(field_loc.id))
+            }
+
+            pos.write_le(slot);
+        }
+
+        self.buf.scratch_pop(self.num_fields * FIELD_ENTRY_SIZE);
+        self.num_fields = 0;
+
+        Ok(vtable_offset_loc)
+    }
+
+    // The vtable which was just written by `write_vtable`.
+    fn current_vtable(&self) -> &[u8] {
+        let len = VOffset::read_le(self.buf.data()) as usize;
+        &self.buf.data()[..len]
+    }
+
+    // The offset in the `idx`th slot of the hash table at `table` in the scratch space, or 0 if
+    // it's empty.
+    fn slot(&self, table: usize, idx: usize) -> UOffset {
+        UOffset::read_le(&self.buf.scratch()[table + idx * SLOT_SIZE..])
+    }
+
+    // Find the offset in the hash table of `slots` slots at `table` for which `matches` returns
+    // `true`, starting from the slot for `hash`.
+    fn find_slot<F>(&self, table: usize, slots: usize, hash: u64, matches: F) -> Option<UOffset>
+        where F: Fn(UOffset) -> bool {
+
+        if slots == 0 {
+            return None
+        }
+
+        let mask = slots - 1;
+        let mut idx = hash as usize & mask;
+
+        loop {
+            let off = self.slot(table, idx);
+
+            if off == 0 {
+                return None
+            }
+
+            if matches(off) {
+                return Some(off)
+            }
+
+            idx = (idx + 1) & mask;
+        }
+    }
+
+    // Find a vtable which was written earlier, whose contents hash to `hash` and are the same as
+    // `current_vtable`.
+    fn find_vtable(&self, hash: u64) -> Option<UOffset> {
+        let vt1 = self.current_vtable();
+
+        self.find_slot(0, self.vtable_slots, hash, |off| vt1 == vtable_at(self.buf.data(), off))
+    }
+
+    // Add the vtable at `off`, whose contents hash to `hash`, to the hash table.
+    fn insert_vtable(&mut self, hash: u64, off: UOffset) -> Result<(), BuilderError> {
+        if 2 * (self.num_vtables + 1) > self.vtable_slots {
+            self.vtable_slots = self.grow_table(0, self.vtable_slots, vtable_at)?;
+        }
+
+        let (scratch, _) = self.buf.split_mut();
+        insert_slot(scratch, self.vtable_slots, hash, off);
+
+        self.num_vtables += 1;
+
+        Ok(())
+    }
+
+    // Double the size of the hash table of `old_slots` slots at `table`, and return its new number
+    // of slots. `contents` gives the bytes which each entry was hashed from. The new table is built
+    // in the free space after the scratch space, then whatever follows the old table is moved up
+    // to make room for it.
+    fn grow_table(&mut self, table: usize, old_slots: usize, contents: fn(&[u8], UOffset) -> &[u8])
+        -> Result<usize, BuilderError> {
+
+        let new_slots = cmp::max(MIN_SLOTS, 2 * old_slots);
+        let old_len   = old_slots * SLOT_SIZE;
+        let new_len   = new_slots * SLOT_SIZE;
+        let growth    = new_len - old_len;
+
+        self.buf.reserve(growth + new_len)?;
+
+        let end = self.buf.scratch;
+        let tmp = end + growth;
+
+        {
+            let (scratch, data) = self.buf.split_mut();
+
+            for b in &mut scratch[tmp..tmp + new_len] {
+                *b = 0;
+            }
+
+            for idx in 0..old_slots {
+                let off = UOffset::read_le(&scratch[table + idx * SLOT_SIZE..]);
+
+                if off != 0 {
+                    let hash = hash_bytes(contents(data, off));
+                    insert_slot(&mut scratch[tmp..], new_slots, hash, off);
+                }
+            }
+        }
+
+        self.buf.scratch_move(table + old_len..end, table + new_len);
+        self.buf.scratch_move(tmp..tmp + new_len, table);
+
+        self.buf.scratch += growth;
+
+        Ok(new_slots)
+    }
+
+    // The position in the scratch space of the hash table of shared strings.
+    fn string_table(&self) -> usize {
+        self.vtable_slots * SLOT_SIZE
+    }
+
+    // Point the table at `table_loc` to the vtable at `vt_use`.
+    fn set_vtable(&mut self, table_loc: usize, vt_use: UOffset) {
+        let vt_off = (vt_use as SOffset) - (table_loc as SOffset);
+        vt_off.write_le(self.buf.data_at_mut(table_loc));
+    }
+
+    /// Panic if the table which was just finished at `table` (the result of `end_table`) doesn't
+    /// have `field` set. This is used by the generated code for fields marked `required`.
+    pub fn required(&self, table: UOffset, field: VOffset) {
+        check(self.try_required(table, field))
+    }
+
+    /// Like `required`, but return `BuilderError::MissingRequiredField` instead of panicking. The
+    /// table has already been written, so this doesn't stop the builder from being used.
+    pub fn try_required(&self, table: UOffset, field: VOffset) -> Result<(), BuilderError> {
+        self.buf.check_error()?;
+
+        let pos = match self.get_size().checked_sub(table as usize) {
+            Some(pos) if table != 0 => pos,
+            _                       => return Err(BuilderError::InvalidOffset),
+        };
+
+        match field_pos(self.buf.data(), pos, field) {
+            Some(_) => Ok(()),
+            None    => Err(BuilderError::MissingRequiredField(field)),
+        }
+    }
+
+    pub fn pre_align(&mut self, len: usize, align: usize) {
+        check(self.try_pre_align(len, align))
+    }
+
+    pub fn try_pre_align(&mut self, len: usize, align: usize) -> Result<(), BuilderError> {
+        self.track_min_align(align);
+
+        let size = self.get_size();
+        self.buf.fill(padding_bytes(size + len, align))
+    }
+
+    pub fn create_string<'x>(&mut self, s: &str) -> Offset<Str<'x>> {
+        check(self.try_create_string(s))
+    }
+
+    pub fn try_create_string<'x>(&mut self, s: &str) -> Result<Offset<Str<'x>>, BuilderError> {
+        self.try_not_nested()?;
+
+        self.try_pre_align(s.len() + 1, mem::size_of::<UOffset>())?;
+        self.buf.fill(1)?;
+        self.try_push_bytes(s.as_bytes())?;
+        self.try_push_scalar(s.len() as UOffset)?;
+
+        Ok(Offset::new(self.get_size() as UOffset))
+    }
+
+    /// Like `create_string`, but if the same string has already been created with this method,
+    /// return the offset of that copy instead of writing another one.
+    pub fn create_shared_string<'x>(&mut self, s: &str) -> Offset<Str<'x>> {
+        check(self.try_create_shared_string(s))
+    }
+
+    pub fn try_create_shared_string<'x>(&mut self, s: &str)
+        -> Result<Offset<Str<'x>>, BuilderError> {
+
+        self.buf.check_error()?;
+
+        let hash  = hash_bytes(s.as_bytes());
+        let table = self.string_table();
+        let found = self.find_slot(table, self.string_slots, hash,
+                                   |off| string_at(self.buf.data(), off) == s.as_bytes());
+
+        if let Some(off) = found {
+            return Ok(Offset::new(off))
+        }
+
+        let off = self.try_create_string(s)?;
+
+        if 2 * (self.num_strings + 1) > self.string_slots {
+            self.string_slots = self.grow_table(table, self.string_slots, string_at)?;
+        }
+
+        let (scratch, _) = self.buf.split_mut();
+        insert_slot(&mut scratch[table..], self.string_slots, hash, off.inner);
+
+        self.num_strings += 1;
+
+        Ok(off)
+    }
+
+    pub fn start_vector(&mut self, len: usize, elem_size: usize) {
+        check(self.try_start_vector(len, elem_size))
+    }
+
+    pub fn try_start_vector(&mut self, len: usize, elem_size: usize) -> Result<(), BuilderError> {
+        self.try_start_vector_aligned(len, elem_size, elem_size)
+    }
+
+    /// Like `start_vector`, for elements whose alignment isn't the same as their size, like
+    /// structs. `align` must be a power of two.
+    pub fn start_vector_aligned(&mut self, len: usize, elem_size: usize, align: usize) {
+        check(self.try_start_vector_aligned(len, elem_size, align))
+    }
+
+    pub fn try_start_vector_aligned(&mut self, len: usize, elem_size: usize, align: usize)
+        -> Result<(), BuilderError> {
+
+        self.try_pre_align(len * elem_size, mem::size_of::<UOffset>())?;
+        self.try_pre_align(len * elem_size, align)
+    }
+
+    pub fn reserve_elements(&mut self, len: usize, elem_size: usize) -> usize {
+        check(self.try_reserve_elements(len, elem_size))
+    }
+
+    pub fn try_reserve_elements(&mut self, len: usize, elem_size: usize)
+        -> Result<usize, BuilderError> {
+
+        self.buf.make_space(len * elem_size)
+    }
+
+    pub fn end_vector(&mut self, len: usize) -> UOffset {
+        check(self.try_end_vector(len))
+    }
+
+    pub fn try_end_vector(&mut self, len: usize) -> Result<UOffset, BuilderError> {
+        Ok(self.try_push_scalar(len as UOffset)? as UOffset)
+    }
+
+    pub fn create_vector<'x, T: Endian>(&mut self, v: &[T]) -> Offset<Vector<'x, T>> {
+        check(self.try_create_vector(v))
+    }
+
+    pub fn try_create_vector<'x, T: Endian>(&mut self, v: &[T])
+        -> Result<Offset<Vector<'x, T>>, BuilderError> {
+
+        self.try_not_nested()?;
+        self.try_start_vector(v.len(), mem::size_of::<T>())?;
+        for &elem in v.iter().rev() {
+            self.try_push_scalar(elem)?;
+        }
+
+        Ok(Offset::new(self.try_end_vector(v.len())?))
+    }
+
+    /// Create a vector of offsets to tables, strings or vectors. Unlike `create_vector`, this
+    /// converts each offset to be relative to its position in the vector.
+    pub fn create_vector_of_offsets<'x, T>(&mut self, v: &[Offset<T>])
+        -> Offset<Vector<'x, Offset<T>>> {
+
+        check(self.try_create_vector_of_offsets(v))
+    }
+
+    pub fn try_create_vector_of_offsets<'x, T>(&mut self, v: &[Offset<T>])
+        -> Result<Offset<Vector<'x, Offset<T>>>, BuilderError> {
+
+        self.try_not_nested()?;
+        self.try_start_vector(v.len(), mem::size_of::<UOffset>())?;
+        for &elem in v.iter().rev() {
+            self.try_push_offset(elem)?;
+        }
+
+        Ok(Offset::new(self.try_end_vector(v.len())?))
+    }
+
+    /// Create a vector of unions, which is stored as a vector of type tags and a vector of
+    /// offsets. Add it to a table with `add_union_vector`. Elements with a type tag of 0 are
+    /// stored as `NONE`, with a null offset.
+    pub fn create_union_vector<U: UnionOffset>(&mut self, v: &[U]) -> UnionVectorOffset<U> {
+        check(self.try_create_union_vector(v))
+    }
+
+    pub fn try_create_union_vector<U: UnionOffset>(&mut self, v: &[U])
+        -> Result<UnionVectorOffset<U>, BuilderError> {
+
+        self.try_not_nested()?;
+
+        self.try_start_vector(v.len(), mem::size_of::<UOffset>())?;
+        for u in v.iter().rev() {
+            if u.tag() == 0 {
+                self.try_push_scalar::<UOffset>(0)?;
+            } else {
+                self.try_push_offset(u.value())?;
+            }
+        }
+        let values = Offset::new(self.try_end_vector(v.len())?);
+
+        self.try_start_vector(v.len(), mem::size_of::<u8>())?;
+        for u in v.iter().rev() {
+            self.try_push_scalar(u.tag())?;
+        }
+        let types = Offset::new(self.try_end_vector(v.len())?);
+
+        Ok(UnionVectorOffset {
+            types,
+            values,
+            _u: marker::PhantomData,
+        })
+    }
+
+    pub fn create_vector_of_structs<'x, T: Struct>(&mut self, v: &[T])
+        -> Offset<Vector<'x, ByRef<T>>> {
+
+        check(self.try_create_vector_of_structs(v))
+    }
+
+    pub fn try_create_vector_of_structs<'x, T: Struct>(&mut self, v: &[T])
+        -> Result<Offset<Vector<'x, ByRef<T>>>, BuilderError> {
+
+        self.try_not_nested()?;
+
+        self.try_start_vector_aligned(v.len(), mem::size_of::<T>(), mem::align_of::<T>())?;
+        self.try_push_bytes(view_slice_bytes(v))?;
+
+        Ok(Offset::new(self.try_end_vector(v.len())?))
+    }
+
+    pub fn create_vector_of_sorted_tables<'x, T: OrdTable>(&mut self, v: &mut [Offset<T>])
+        -> Offset<Vector<'x, Offset<T>>> {
+
+        check(self.try_create_vector_of_sorted_tables(v))
+    }
+
+    pub fn try_create_vector_of_sorted_tables<'x, T: OrdTable>(&mut self, v: &mut [Offset<T>])
+        -> Result<Offset<Vector<'x, Offset<T>>>, BuilderError> {
+
+        {
+            let size = self.get_size();
+            let data = self.buf.data();
+
+            v.sort_by(|a, b| {
+                let a = Table::view(data, size - a.inner as usize);
+                let b = Table::view(data, size - b.inner as usize);
+
+                T::key_cmp(a, b)
+            });
+        }
+
+        self.try_create_vector_of_offsets(v)
+    }
+
+    /// Create a vector of `len` elements of type `T` which are all zero, and return it along with
+    /// the bytes of its elements so that they can be filled in (e.g. with `Endian::write_le`).
+    pub fn create_uninitialized_vector<T>(&mut self, len: usize) -> (UOffset, &mut [u8]) {
+        check(self.try_create_uninitialized_vector::<T>(len))
+    }
+
+    pub fn try_create_uninitialized_vector<T>(&mut self, len: usize)
+        -> Result<(UOffset, &mut [u8]), BuilderError> {
+
+        self.try_not_nested()?;
+        self.try_start_vector(len, mem::size_of::<T>())?;
+        self.buf.fill(len * mem::size_of::<T>())?;
+        let off = self.try_end_vector(len)?;
+
+        let start = mem::size_of::<UOffset>();
+        let slc   = &mut self.buf.data_mut()[start..start + len * mem::size_of::<T>()];
+
+        Ok((off, slc))
+    }
+
+    pub fn finish<T>(&mut self, root: Offset<T>) {
+        check(self.try_finish(root))
+    }
+
+    pub fn try_finish<T>(&mut self, root: Offset<T>) -> Result<(), BuilderError> {
+        self.finish_internal(root, None, false)
+    }
+
+    /// Finish the buffer, storing the 4-byte file identifier `ident` after the root offset so that
+    /// readers can tell what kind of buffer it is with `buffer_has_identifier`.
+    pub fn finish_with_identifier<T>(&mut self, root: Offset<T>, ident: &str) {
+        check(self.try_finish_with_identifier(root, ident))
+    }
+
+    pub fn try_finish_with_identifier<T>(&mut self, root: Offset<T>, ident: &str)
+        -> Result<(), BuilderError> {
+
+        self.finish_internal(root, Some(ident), false)
+    }
+
+    /// Finish the buffer, preceding it with its length as a `UOffset`. This is useful when sending
+    /// several buffers over a stream. Read it with `get_size_prefixed_root`.
+    pub fn finish_size_prefixed<T>(&mut self, root: Offset<T>) {
+        check(self.try_finish_size_prefixed(root))
+    }
+
+    pub fn try_finish_size_prefixed<T>(&mut self, root: Offset<T>) -> Result<(), BuilderError> {
+        self.finish_internal(root, None, true)
+    }
+
+    /// Like `finish_size_prefixed`, but also store the file identifier `ident`.
+    pub fn finish_size_prefixed_with_identifier<T>(&mut self, root: Offset<T>, ident: &str) {
+        check(self.try_finish_size_prefixed_with_identifier(root, ident))
+    }
+
+    pub fn try_finish_size_prefixed_with_identifier<T>(&mut self, root: Offset<T>, ident: &str)
+        -> Result<(), BuilderError> {
+
+        self.finish_internal(root, Some(ident), true)
+    }
+
+    fn finish_internal<T>(&mut self, root: Offset<T>, ident: Option<&str>, size_prefix: bool)
+        -> Result<(), BuilderError> {
+
+        if ident.is_some_and(|i| i.len() != FILE_IDENTIFIER_LENGTH) {
+            return self.buf.fail(BuilderError::InvalidIdentifier)
+        }
+
+        let min_align  = self.min_align;
+        let ident_len  = ident.map_or(0, |i| i.len());
+        let prefix_len = if size_prefix { mem::size_of::<UOffset>() } else { 0 };
+        self.try_pre_align(mem::size_of::<UOffset>() + ident_len + prefix_len, min_align)?;
+
+        if let Some(ident) = ident {
+            self.try_push_bytes(ident.as_bytes())?;
+        }
+
+        let refer = self.try_refer_to(root.inner)?;
+        self.try_push_scalar(refer)?;
+
+        if size_prefix {
+            let size = self.get_size() as UOffset;
+            self.try_push_scalar(size)?;
+        }
+
+        self.buf.finished  = true;
+        self.size_prefixed = size_prefix;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use alloc::format;
+
+    // The original de-duping in `end_table`, which does a linear scan over every vtable written so
+    // far. The hashed version should produce exactly the same bytes.
+    fn end_table_linear(fbb: &mut FlatBufferBuilder, vtables: &mut Vec<UOffset>, start: UOffset,
+                        num_fields: VOffset) -> UOffset {
+
+        let vtable_offset_loc = fbb.write_vtable(start, num_fields).unwrap();
+
+        let found = {
+            let vt1 = fbb.current_vtable();
+
+            vtables.iter().cloned().find(|&off| vt1 == vtable_at(fbb.buf.data(), off))
+        };
+
+        let vt_use = match found {
+            Some(off) => {
+                let to_pop = fbb.get_size() - vtable_offset_loc;
+                fbb.buf.pop(to_pop);
+                off
+            }
+            None => {
+                let off = fbb.get_size() as UOffset;
+                vtables.push(off);
+                off
+            }
+        };
+
+        fbb.set_vtable(vtable_offset_loc, vt_use);
+
+        vtable_offset_loc as UOffset
+    }
+
+    // Build a vector of tables, finishing each with `end`. Each table has some of four fields,
+    // of one of three sizes, so some vtables are repeated many times and others only once or twice.
+    fn build_tables_with<F>(fbb: &mut FlatBufferBuilder, n: u32, mut end: F) -> Vec<u8>
+        where F: FnMut(&mut FlatBufferBuilder, UOffset, VOffset) -> UOffset {
+
+        let mut tables = Vec::new();
+
+        for i in 0..n {
+            let start = fbb.start_table();
+
+            for id in 0..4 {
+                if (i / 3) & (1 << id) == 0 {
+                    continue
+                }
+
+                let field = field_index_to_offset(id);
+
+                match (i + u32::from(id)) % 3 {
+                    0 => fbb.add_scalar(field, i as u8, 0),
+                    1 => fbb.add_scalar(field, i, 0),
+                    _ => fbb.add_scalar(field, u64::from(i), 0),
+                }
+            }
+
+            tables.push(Offset::<Table>::new(end(fbb, start, 4)));
+        }
+
+        let root = fbb.create_vector_of_offsets(&tables);
+        fbb.finish(root);
+
+        fbb.get_buffer().to_vec()
+    }
+
+    #[test]
+    fn hashed_dedup_matches_linear_scan() {
+        let hashed = build_tables_with(&mut FlatBufferBuilder::new(1), 500,
+                                       |fbb, start, n| fbb.end_table(start, n));
+
+        let mut vtables = Vec::new();
+        let linear = build_tables_with(&mut FlatBufferBuilder::new(1), 500, |fbb, start, n| {
+            end_table_linear(fbb, &mut vtables, start, n)
+        });
+
+        assert_eq!(hashed, linear);
+    }
+
+    #[test]
+    fn hashed_dedup_matches_linear_scan_after_clear() {
+        let mut fbb = FlatBufferBuilder::new(1);
+        build_tables_with(&mut fbb, 200, |fbb, start, n| fbb.end_table(start, n));
+        fbb.clear();
+
+        // The vtables from before `clear` are gone, so none of them can be shared.
+        let hashed = build_tables_with(&mut fbb, 100, |fbb, start, n| fbb.end_table(start, n));
+
+        let mut vtables = Vec::new();
+        let linear = build_tables_with(&mut FlatBufferBuilder::new(1), 100, |fbb, start, n| {
+            end_table_linear(fbb, &mut vtables, start, n)
+        });
+
+        assert_eq!(hashed, linear);
+    }
+
+    #[test]
+    fn identical_vtables_are_shared() {
+        let mut fbb = FlatBufferBuilder::new(1);
+
+        for i in 0..100u32 {
+            let start = fbb.start_table();
+            fbb.add_scalar(field_index_to_offset(0), i + 1, 0);
+            fbb.add_scalar(field_index_to_offset(1), i + 2, 0);
+            fbb.end_table(start, 2);
+        }
+
+        assert_eq!(fbb.num_vtables, 1);
+    }
+
+    #[test]
+    fn shared_strings_are_written_once() {
+        let mut fbb = FlatBufferBuilder::new(1);
+
+        let shared   = fbb.create_shared_string("hello");
+        let unshared = fbb.create_string("hello");
+        let size     = fbb.get_size();
+
+        assert_eq!(fbb.create_shared_string("hello").inner, shared.inner);
+        assert_eq!(fbb.get_size(), size);
+
+        // Strings made with `create_string` are never shared, even with the same contents.
+        assert_ne!(unshared.inner, shared.inner);
+        assert_ne!(fbb.create_string("hello").inner, shared.inner);
+        assert_eq!(fbb.num_strings, 1);
+    }
+
+    #[test]
+    fn strings_are_read_checked_or_unchecked() {
+        let mut fbb = FlatBufferBuilder::new(1);
+        let valid   = fbb.create_string("héllo");
+        let invalid = fbb.create_vector(&[b'a', 0xff]);
+        let root    = fbb.create_vector_of_offsets(&[valid, Offset::new(invalid.inner)]);
+        fbb.finish(root);
+
+        let strs: Vector<Offset<Str>> = ::get_root(fbb.get_buffer());
+        let valid   = strs.get(0).unwrap();
+        let invalid = strs.get(1).unwrap();
+
+        assert_eq!(valid.to_str(), Ok("héllo"));
+        assert_eq!(unsafe { valid.to_str_unchecked() }, "héllo");
+        assert_eq!(invalid.as_bytes(), [b'a', 0xff]);
+        assert!(invalid.to_str().is_err());
+    }
+
+    #[test]
+    fn shared_strings_survive_their_table_growing() {
+        let mut fbb = FlatBufferBuilder::new(1);
+        let mut tables = Vec::new();
+
+        // Interleave the strings with tables, so that both hash tables grow while the other has
+        // entries after it or before it.
+        for i in 0..1000u16 {
+            let name  = fbb.create_shared_string(&format!("s{}", i % 300));
+            let start = fbb.start_table();
+            fbb.add_offset(field_index_to_offset(i % 7), name);
+            tables.push(Offset::<Table>::new(fbb.end_table(start, 7)));
+        }
+
+        assert_eq!(fbb.num_strings, 300);
+        assert_eq!(fbb.num_vtables, 7);
+
+        let size = fbb.get_size();
+        for i in 0..300 {
+            let off = fbb.create_shared_string(&format!("s{}", i)).inner;
+            assert_eq!(string_at(fbb.buf.data(), off), format!("s{}", i).as_bytes());
+        }
+        assert_eq!(fbb.get_size(), size);
+
+        let root = fbb.create_vector_of_offsets(&tables);
+        fbb.finish(root);
+
+        let tables: Vector<Offset<Table>> = ::get_root(fbb.get_buffer());
+        for (i, t) in tables.iter().enumerate() {
+            let name = t.get_ref::<Str>(field_index_to_offset((i % 7) as VOffset)).unwrap();
+            assert_eq!(name.as_bytes(), format!("s{}", i % 300).as_bytes());
+        }
+    }
+
+    #[test]
+    fn many_distinct_vtables_are_indexed_in_linear_time() {
+        const N: usize = 50_000;
+
+        let mut fbb = FlatBufferBuilder::new(1);
+
+        // Each table has a different set of its 16 fields, so each one needs its own vtable.
+        for i in 0..N {
+            let start = fbb.start_table();
+
+            for id in 0..16 {
+                if i & (1 << id) != 0 {
+                    fbb.add_scalar(field_index_to_offset(id), 1u8, 0);
+                }
+            }
+
+            fbb.end_table(start, 16);
+        }
+
+        assert_eq!(fbb.num_vtables, N);
+
+        // Keeping the vtables in a sorted array would move about N * N / 2 entries.
+        assert!(fbb.buf.moved < 64 * N, "moved {} bytes", fbb.buf.moved);
+    }
+
+    #[test]
+    fn slice_builder_builds_at_the_end_of_the_slice() {
+        let mut mem = Aligned([0xff; 64]);
+
+        {
+            let mut fbb = FlatBufferBuilder::from_slice(&mut mem.0);
+
+            let start = fbb.start_table();
+            fbb.add_scalar(field_index_to_offset(0), 7u32, 0);
+            let root = fbb.end_table(start, 1);
+            fbb.finish(Offset::<Table>::new(root));
+
+            assert_eq!(fbb.get_size(), 20);
+        }
+
+        // The bookkeeping at the start of the slice is left as it is.
+        let table = ::get_root::<Table>(&mem.0[64 - 20..]);
+        assert_eq!(table.get_field::<u32>(field_index_to_offset(0), 0), 7);
+    }
+
+    #[test]
+    fn slice_builder_runs_out_of_space() {
+        let mut slice = [0; 64];
+        let mut fbb   = FlatBufferBuilder::from_slice(&mut slice);
+
+        assert!(fbb.try_create_shared_string("short").is_ok());
+        assert!(fbb.try_create_shared_string("short").is_ok());
+        assert_eq!(fbb.try_create_string(&"long".repeat(16)).err(), Some(BuilderError::OutOfSpace));
+    }
+
+    // An allocator which counts how often it's asked to grow and reset, and won't grow past `max`.
+    struct CountingAllocator {
+        mem:    Vec<u8>,
+        max:    usize,
+        grows:  usize,
+        resets: usize,
+    }
+
+    impl Allocator for CountingAllocator {
+        fn memory(&self) -> &[u8] { &self.mem }
+
+        fn memory_mut(&mut self) -> &mut [u8] { &mut self.mem }
+
+        fn grow(&mut self, len: usize) -> Result<(), BuilderError> {
+            if len > self.max {
+                return Err(BuilderError::OutOfSpace)
+            }
+
+            self.grows += 1;
+            self.mem.grow(len)
+        }
+
+        fn reset(&mut self) {
+            self.resets += 1;
+        }
+    }
+
+    #[test]
+    fn custom_allocator_is_grown_and_reset() {
+        let mut alloc = CountingAllocator { mem: Vec::new(), max: 1 << 16, grows: 0, resets: 0 };
+
+        {
+            let mut fbb = FlatBufferBuilder::with_allocator(&mut alloc);
+
+            let bytes = fbb.create_vector(&[7u8; 1000]);
+            fbb.finish(bytes);
+            assert_eq!(::get_root::<Vector<u8>>(fbb.get_buffer()).len(), 1000);
+
+            fbb.clear();
+            assert_eq!(fbb.try_create_string(&"x".repeat(1 << 16)).err(),
+                       Some(BuilderError::OutOfSpace));
+        }
+
+        assert!(alloc.grows > 0);
+        assert!(alloc.mem.len() >= 1000 && alloc.mem.len() <= 1 << 16);
+        assert_eq!(alloc.resets, 1);
+    }
+
+    #[test]
+    fn allocator_failing_to_grow_in_end_table() {
+        // Find a limit which leaves room for a table's fields, but not for its vtable.
+        let failed_in_end_table = (0..512).filter(|&max| {
+            let mut alloc = CountingAllocator { mem: Vec::new(), max, grows: 0, resets: 0 };
+            let mut fbb   = FlatBufferBuilder::with_allocator(&mut alloc);
+
+            let start = fbb.start_table();
+
+            for id in 0..4 {
+                if fbb.try_add_scalar(field_index_to_offset(id), 1u32, 0).is_err() {
+                    return false
+                }
+            }
+
+            match fbb.try_end_table(start, 4) {
+                Ok(_)  => false,
+                Err(e) => {
+                    assert_eq!(e, BuilderError::OutOfSpace);
+                    assert_eq!(fbb.try_create_string("").err(), Some(BuilderError::OutOfSpace));
+                    true
+                }
+            }
+        }).count();
+
+        assert!(failed_in_end_table > 0);
+    }
+
+    #[derive(Clone, Copy)]
+    struct TestUnion(u8, Offset<Table<'static>>);
+
+    impl UnionOffset for TestUnion {
+        fn tag(&self) -> u8 { self.0 }
+
+        fn value(&self) -> Offset<Table<'static>> { self.1 }
+    }
+
+    // Build a table with a `u32` field, and check that `misuse` fails with `err`.
+    fn check_misuse<F>(err: BuilderError, misuse: F)
+        where F: FnOnce(&mut FlatBufferBuilder, UOffset) -> Result<(), BuilderError> {
+
+        let mut fbb = FlatBufferBuilder::new(1);
+
+        let table = fbb.try_create_string("table").unwrap();
+        let start = fbb.try_start_table().unwrap();
+        fbb.try_add_scalar(field_index_to_offset(0), 1u32, 0).unwrap();
+
+        assert_eq!(misuse(&mut fbb, start.max(table.inner)), Err(err));
+    }
+
+    #[test]
+    fn misuse_is_reported_by_try_methods() {
+        check_misuse(BuilderError::Nested, |fbb, _| fbb.try_create_string("nested").map(|_| ()));
+        check_misuse(BuilderError::Nested, |fbb, _| fbb.try_start_table().map(|_| ()));
+
+        check_misuse(BuilderError::DuplicateField(field_index_to_offset(0)), |fbb, start| {
+            fbb.try_add_scalar(field_index_to_offset(0), 2u32, 0)?;
+            fbb.try_end_table(start, 1).map(|_| ())
+        });
+
+        check_misuse(BuilderError::InvalidField(field_index_to_offset(3)), |fbb, start| {
+            fbb.try_add_scalar(field_index_to_offset(3), 2u32, 0)?;
+            fbb.try_end_table(start, 2).map(|_| ())
+        });
+
+        check_misuse(BuilderError::InvalidOffset, |fbb, start| {
+            fbb.try_add_offset(field_index_to_offset(1), Offset::<Table>::new(start + 100))
+        });
+
+        check_misuse(BuilderError::InvalidUnionTag, |fbb, start| {
+            let table = Offset::new(start);
+            fbb.try_add_union(field_index_to_offset(1), field_index_to_offset(2),
+                              TestUnion(0, table))
+        });
+
+        check_misuse(BuilderError::InvalidIdentifier, |fbb, start| {
+            let root = Offset::<Table>::new(fbb.try_end_table(start, 1)?);
+            fbb.try_finish_with_identifier(root, "MONSTER")
+        });
+
+        check_misuse(BuilderError::InvalidIdentifier, |fbb, start| {
+            let root = Offset::<Table>::new(fbb.try_end_table(start, 1)?);
+            fbb.try_finish_size_prefixed_with_identifier(root, "MON")
+        });
+    }
+
+    #[test]
+    fn required_fields_and_popped_bytes_are_checked() {
+        let mut fbb = FlatBufferBuilder::new(1);
+
+        let start = fbb.try_start_table().unwrap();
+        fbb.try_add_scalar(field_index_to_offset(0), 42u32, 0).unwrap();
+        let table = fbb.try_end_table(start, 2).unwrap();
+
+        assert_eq!(fbb.try_required(table, field_index_to_offset(0)), Ok(()));
+        assert_eq!(fbb.try_required(table, field_index_to_offset(1)),
+                   Err(BuilderError::MissingRequiredField(field_index_to_offset(1))));
+        assert_eq!(fbb.try_required(table + 100, field_index_to_offset(0)),
+                   Err(BuilderError::InvalidOffset));
+
+        // A missing field doesn't stop the builder from being used, but popping too much does.
+        let size = fbb.get_size();
+        assert_eq!(fbb.try_pop_bytes(size + 1), Err(BuilderError::InvalidOffset));
+        assert_eq!(fbb.try_pop_bytes(0), Err(BuilderError::InvalidOffset));
+    }
+
+    #[test]
+    fn tables_can_be_built_after_64_kib_of_data() {
+        let mut fbb = FlatBufferBuilder::new(1);
+
+        let bytes = fbb.try_create_vector(&[7u8; 100_000]).unwrap();
+        let start = fbb.try_start_table().unwrap();
+        fbb.try_add_scalar(field_index_to_offset(0), 42u32, 0).unwrap();
+        fbb.try_add_offset(field_index_to_offset(1), bytes).unwrap();
+        let root = fbb.try_end_table(start, 2).unwrap();
+        fbb.try_finish(Offset::<Table>::new(root)).unwrap();
+
+        let table: Table = ::get_root(fbb.get_buffer());
+        assert_eq!(table.get_field::<u32>(field_index_to_offset(0), 0), 42);
+        assert_eq!(table.get_ref::<Vector<u8>>(field_index_to_offset(1)).unwrap().len(), 100_000);
+    }
+
+    #[test]
+    fn failed_builder_stays_failed_until_cleared() {
+        let mut fbb = FlatBufferBuilder::new(1);
+
+        // A failure when a table is finished, with the table's fields still in the bookkeeping.
+        let start = fbb.try_start_table().unwrap();
+        fbb.try_add_scalar(field_index_to_offset(0), 1u32, 0).unwrap();
+        fbb.try_add_scalar(field_index_to_offset(0), 2u32, 0).unwrap();
+
+        let err = BuilderError::DuplicateField(field_index_to_offset(0));
+        assert_eq!(fbb.try_end_table(start, 1), Err(err));
+
+        assert_eq!(fbb.try_start_table(), Err(err));
+        assert_eq!(fbb.try_create_string("s").err(), Some(err));
+        assert_eq!(fbb.try_create_shared_string("s").err(), Some(err));
+        assert_eq!(fbb.try_add_scalar(field_index_to_offset(1), 0u32, 0), Err(err));
+        assert_eq!(fbb.try_add_offset(field_index_to_offset(1), Offset::<Table>::new(0)), Err(err));
+        assert_eq!(fbb.try_finish(Offset::<Table>::new(0)), Err(err));
+
+        // Once it's cleared, the builder builds the same table that a new one would.
+        let build = |fbb: &mut FlatBufferBuilder| {
+            let start = fbb.start_table();
+            fbb.add_scalar(field_index_to_offset(0), 1u32, 0);
+            let root = fbb.end_table(start, 1);
+            fbb.finish(Offset::<Table>::new(root));
+            fbb.get_buffer().to_vec()
+        };
+
+        fbb.clear();
+        assert_eq!(build(&mut fbb), build(&mut FlatBufferBuilder::new(1)));
+    }
+
+    #[test]
+    fn builder_which_runs_out_of_space_stays_failed_until_cleared() {
+        let mut mem = [0; 256];
+        let mut fbb = FlatBufferBuilder::from_slice(&mut mem);
+
+        // A failure part way through a table.
+        let start = fbb.try_start_table().unwrap();
+        let err   = (0..).map(|i| fbb.try_add_scalar(field_index_to_offset(i), 1u64, 0))
+                         .find(|r| r.is_err())
+                         .unwrap();
+        assert_eq!(err, Err(BuilderError::OutOfSpace));
+
+        // Even things which would fit fail now.
+        assert_eq!(fbb.try_end_table(start, 1).err(), Some(BuilderError::OutOfSpace));
+        assert_eq!(fbb.try_create_string("").err(), Some(BuilderError::OutOfSpace));
+
+        fbb.clear();
+        let s = fbb.try_create_string("fits").unwrap();
+        fbb.try_finish(s).unwrap();
+        assert_eq!(::get_root::<Str>(fbb.get_buffer()).as_bytes(), b"fits");
+    }
+
+    #[test]
+    fn finished_buffer_can_be_taken_and_given_back() {
+        let mut fbb = FlatBufferBuilder::new(1);
+        assert_eq!(fbb.take_buffer(), None);
+
+        let build = |fbb: &mut FlatBufferBuilder| {
+            let s = fbb.create_string("taken");
+            fbb.finish(s);
+            fbb.get_buffer().to_vec()
+        };
+
+        let built = build(&mut fbb);
+        assert_eq!(fbb.finished_data().map(|f| f.as_bytes()), Some(&built[..]));
+
+        let (vec, start) = fbb.take_buffer().unwrap();
+        assert_eq!(&vec[start..], &built[..]);
+        assert_eq!(fbb.get_size(), 0);
+        assert_eq!(fbb.finished_data(), None);
+
+        let ptr = vec.as_ptr();
+        fbb.reuse_vec(vec);
+
+        assert_eq!(build(&mut fbb), built);
+
+        let (vec, start) = fbb.into_vec().unwrap();
+        assert_eq!(vec.as_ptr(), ptr);
+        assert_eq!(&vec[start..], &built[..]);
+    }
+
+    #[test]
+    fn finished_data_reads_the_root() {
+        for &size_prefixed in &[false, true] {
+            let mut fbb = FlatBufferBuilder::new(1);
+
+            let start = fbb.start_table();
+            fbb.add_scalar(field_index_to_offset(0), 7u16, 0);
+            let root = Offset::<Table>::new(fbb.end_table(start, 1));
+            assert_eq!(fbb.finished_data(), None);
+
+            if size_prefixed { fbb.finish_size_prefixed(root) } else { fbb.finish(root) }
+
+            let finished = fbb.finished_data().unwrap();
+            assert_eq!(finished.as_bytes(), fbb.get_buffer());
+            assert_eq!(finished.is_size_prefixed(), size_prefixed);
+            assert_eq!(finished.root::<Table>().get_field::<u16>(field_index_to_offset(0), 0), 7);
+        }
+    }
+
+    #[test]
+    fn buffer_cant_be_taken_before_finish() {
+        let mut fbb = FlatBufferBuilder::new(1);
+
+        let start = fbb.start_table();
+        fbb.add_scalar(field_index_to_offset(0), 7u16, 0);
+        let root = Offset::<Table>::new(fbb.end_table(start, 1));
+
+        assert_eq!(fbb.take_buffer(), None);
+
+        // The builder is left as it was, and can still be finished.
+        fbb.finish(root);
+        let (vec, start) = fbb.take_buffer().unwrap();
+        assert_eq!(get_root::<Table>(&vec[start..]).get_field::<u16>(field_index_to_offset(0), 0), 7);
+
+        // Nothing has been added to the empty builder since.
+        assert_eq!(fbb.take_buffer(), None);
+    }
+
+    #[test]
+    fn reuse_vec_of_any_size() {
+        let build = |fbb: &mut FlatBufferBuilder| {
+            let bytes = fbb.create_vector(&[7u8; 1000]);
+            fbb.finish(bytes);
+            fbb.get_buffer().to_vec()
+        };
+
+        let fresh = build(&mut FlatBufferBuilder::new(1));
+
+        // A smaller `Vec` grows, and a larger one is used as it is.
+        for &cap in &[0, 16, 1 << 16] {
+            let mut fbb = FlatBufferBuilder::new(1);
+            let vec     = Vec::with_capacity(cap);
+            let ptr     = vec.as_ptr();
+            fbb.reuse_vec(vec);
+
+            assert_eq!(build(&mut fbb), fresh);
+
+            let (vec, start) = fbb.take_buffer().unwrap();
+            assert_eq!(&vec[start..], &fresh[..]);
+            assert_eq!(vec.as_ptr() == ptr, cap == 1 << 16);
+        }
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    #[repr(C)]
+    struct Point { x: f32, y: f32 }
+
+    unsafe impl Struct for Point {}
+
+    #[test]
+    fn vectors_can_be_changed_in_place() {
+        let mut fbb = FlatBufferBuilder::new(1);
+
+        let counts = fbb.create_vector(&[1u32, 2, 3]);
+        let points = fbb.create_vector_of_structs(&[Point { x: 0.0, y: 0.0 }]);
+        let bytes  = fbb.create_vector(&[0u8; 4]);
+
+        let start = fbb.start_table();
+        fbb.add_offset(field_index_to_offset(0), counts);
+        fbb.add_offset(field_index_to_offset(1), points);
+        fbb.add_offset(field_index_to_offset(2), bytes);
+        let root = fbb.end_table(start, 3);
+        fbb.finish(Offset::<Table>::new(root));
+
+        let mut buf   = fbb.get_buffer().to_vec();
+        let mut table = ::get_root_mut(&mut buf);
+
+        let mut v = table.get_vector_mut::<u32>(field_index_to_offset(0)).unwrap();
+        assert!(v.set(1, 20));
+        assert!(!v.set(3, 30));
+        assert_eq!(v.as_vector().iter().collect::<Vec<_>>(), [1, 20, 3]);
+
+        let mut v = table.get_vector_mut::<ByRef<Point>>(field_index_to_offset(1)).unwrap();
+        assert!(v.set(0, Point { x: 0.0, y: 5.0 }));
+        assert!(!v.set(1, Point { x: 1.0, y: 1.0 }));
+        assert_eq!(v.as_vector().get(0), Some(Point { x: 0.0, y: 5.0 }));
+
+        let mut v = table.get_vector_mut::<u8>(field_index_to_offset(2)).unwrap();
+        v.as_bytes_mut().copy_from_slice(b"abcd");
+        assert_eq!(v.as_vector().iter().collect::<Vec<_>>(), b"abcd");
+    }
+
+    #[test]
+    fn structs_can_be_read_from_misaligned_buffers() {
+        let mut fbb = FlatBufferBuilder::new(1);
+
+        let points = fbb.create_vector_of_structs(&[Point { x: 1.0, y: 2.0 }]);
+
+        let start = fbb.start_table();
+        fbb.add_struct(field_index_to_offset(0), &Point { x: 3.0, y: 4.0 });
+        fbb.add_offset(field_index_to_offset(1), points);
+        let root = fbb.end_table(start, 2);
+        fbb.finish(Offset::<Table>::new(root));
+
+        // Copy the buffer to an odd address, so that nothing in it is aligned.
+        let mut mem = Aligned([0; 64]);
+        let len     = fbb.get_size();
+        assert!(len < 64);
+        mem.0[1..][..len].copy_from_slice(fbb.get_buffer());
+        let buf = &mut mem.0[1..][..len];
+
+        let table = ::get_root::<Table>(buf);
+        assert_eq!(table.get_struct(field_index_to_offset(0)), Some(Point { x: 3.0, y: 4.0 }));
+        let v = table.get_ref::<Vector<ByRef<Point>>>(field_index_to_offset(1)).unwrap();
+        assert_eq!(v.get(0), Some(Point { x: 1.0, y: 2.0 }));
+
+        let mut table = ::get_root_mut(buf);
+        assert!(table.set_struct(field_index_to_offset(0), Point { x: 5.0, y: 6.0 }));
+        assert!(!table.set_struct(field_index_to_offset(2), Point { x: 5.0, y: 6.0 }));
+        assert_eq!(table.as_table().get_struct(field_index_to_offset(0)), Some(Point { x: 5.0, y: 6.0 }));
+    }
+
+    // Memory whose end is aligned to `MAX_ALIGN`, so that a builder can use all of it.
+    #[repr(align(16))]
+    struct Aligned([u8; 64]);
+
+    #[test]
+    fn slice_builder_can_fill_the_slice_exactly() {
+        // A 3-byte string takes 8 bytes, and the root offset another 4.
+        let mut mem = Aligned([0; 64]);
+        let mut fbb = FlatBufferBuilder::from_slice(&mut mem.0[64 - 12..]);
+
+        let s = fbb.try_create_string("abc").unwrap();
+        fbb.try_finish(s).unwrap();
+        assert_eq!(fbb.get_buffer(), b"\x04\0\0\0\x03\0\0\0abc\0");
+
+        let mut mem = Aligned([0; 64]);
+        let mut fbb = FlatBufferBuilder::from_slice(&mut mem.0[64 - 11..]);
+
+        let s = fbb.try_create_string("abc").unwrap();
+        assert_eq!(fbb.try_finish(s), Err(BuilderError::OutOfSpace));
+    }
+}
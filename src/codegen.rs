@@ -0,0 +1,1512 @@
+// Copyright 2015 Sam Payson. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Generation of Rust code from schemas.
+//!
+//! This is meant to be used from a `build.rs` script, so that the generated code never has to be
+//! checked in:
+//!
+//! ```rust,no_run
+//! extern crate flatbuffers;
+//!
+//! fn main() {
+//!     let mut gen = flatbuffers::codegen::Generator::new();
+//!     gen.file("schemas/monster.fbs");
+//!     gen.run().unwrap();
+//! }
+//! ```
+//!
+//! This writes `monster_generated.rs` to `$OUT_DIR`, which can then be pulled into the crate with
+//! `include!(concat!(env!("OUT_DIR"), "/monster_generated.rs"));`. Each namespace in the schema
+//! becomes a nested module.
+
+use std::collections::BTreeMap;
+use std::env;
+use std::error;
+use std::fmt;
+use std::fmt::Write;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use schema;
+use schema::{BaseType, Enum, Field, Object, Schema, Type};
+
+/// Things that can go wrong while generating code.
+#[derive(Debug)]
+pub enum Error {
+    /// The schema couldn't be parsed or resolved.
+    Schema(schema::Error),
+
+    /// The generated code couldn't be written.
+    Io(PathBuf, io::Error),
+
+    /// No output directory was set, and `OUT_DIR` isn't set either (i.e. we're not running in a
+    /// build script).
+    NoOutDir,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::Schema(ref e)    => write!(f, "{}", e),
+            Error::Io(ref p, ref e) => write!(f, "couldn't write {}: {}", p.display(), e),
+            Error::NoOutDir         => {
+                write!(f, "no output directory given and OUT_DIR is not set")
+            }
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+impl From<schema::Error> for Error {
+    fn from(e: schema::Error) -> Error { Error::Schema(e) }
+}
+
+/// Generates Rust modules from `.fbs` files. Each schema file given to `file` produces a
+/// `<name>_generated.rs` file containing code for it and everything it includes.
+#[derive(Clone, Debug, Default)]
+pub struct Generator {
+    files:         Vec<PathBuf>,
+    include_paths: Vec<PathBuf>,
+    out_dir:       Option<PathBuf>,
+}
+
+impl Generator {
+    pub fn new() -> Generator {
+        Generator::default()
+    }
+
+    /// Add a schema to generate code for.
+    pub fn file<P: AsRef<Path>>(&mut self, path: P) {
+        self.files.push(path.as_ref().to_path_buf());
+    }
+
+    /// Add a directory to search for included files.
+    pub fn include_path<P: AsRef<Path>>(&mut self, path: P) {
+        self.include_paths.push(path.as_ref().to_path_buf());
+    }
+
+    /// Set the directory the generated files are written to. The default is `$OUT_DIR`.
+    pub fn out_dir<P: AsRef<Path>>(&mut self, path: P) {
+        self.out_dir = Some(path.as_ref().to_path_buf());
+    }
+
+    /// Generate code for every schema, returning the paths of the files which were written. When
+    /// writing to `$OUT_DIR`, this also tells cargo to re-run the build script whenever one of
+    /// the schemas (or something they include) changes.
+    pub fn run(&self) -> Result<Vec<PathBuf>, Error> {
+        let (out_dir, in_build_script) = match self.out_dir {
+            Some(ref dir) => (dir.clone(), false),
+            None          => match env::var_os("OUT_DIR") {
+                Some(dir) => (PathBuf::from(dir), true),
+                None      => return Err(Error::NoOutDir),
+            },
+        };
+
+        let mut written = Vec::new();
+
+        for file in self.files.iter() {
+            let sources = schema::load(file, &self.include_paths)?;
+            let schema  = schema::resolve(&sources)?;
+
+            if in_build_script {
+                for src in sources.iter() {
+                    println!("cargo:rerun-if-changed={}", src.path.display());
+                }
+            }
+
+            let stem = file.file_stem().map_or("schema".into(), |s| s.to_string_lossy());
+            let out  = out_dir.join(format!("{}_generated.rs", stem));
+
+            fs::write(&out, generate(&schema)).map_err(|e| Error::Io(out.clone(), e))?;
+            written.push(out);
+        }
+
+        Ok(written)
+    }
+}
+
+/// Generate the Rust code for a schema.
+pub fn generate(schema: &Schema) -> String {
+    let mut root = Module::default();
+
+    for (i, e) in schema.enums.iter().enumerate() {
+        root.get(&namespace_of(&e.name)).enums.push(i);
+    }
+
+    for (i, o) in schema.objects.iter().enumerate() {
+        root.get(&namespace_of(&o.name)).objects.push(i);
+    }
+
+    let gen = Gen { schema };
+    let mut out = String::new();
+
+    out.push_str("// automatically generated by the FlatBuffers compiler, do not modify\n\n");
+    gen.module(&mut out, &root, &[]);
+
+    out
+}
+
+// The generated code for one namespace, and the namespaces nested in it.
+#[derive(Default)]
+struct Module {
+    enums:    Vec<usize>,
+    objects:  Vec<usize>,
+    children: BTreeMap<String, Module>,
+}
+
+impl Module {
+    fn get(&mut self, path: &[String]) -> &mut Module {
+        match path.split_first() {
+            Some((first, rest)) => self.children.entry(first.clone()).or_default().get(rest),
+            None                => self,
+        }
+    }
+}
+
+// The module path for a fully qualified name's namespace.
+fn namespace_of(name: &str) -> Vec<String> {
+    let (ns, _) = schema::split_name(name);
+    ns.split('.').filter(|s| !s.is_empty()).map(module_name).collect()
+}
+
+fn base_name(name: &str) -> &str {
+    schema::split_name(name).1
+}
+
+const KEYWORDS: &[&str] = &[
+    "as", "break", "const", "continue", "crate", "else", "enum", "extern", "false", "fn", "for",
+    "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return",
+    "self", "Self", "static", "struct", "super", "trait", "true", "type", "unsafe", "use", "where",
+    "while", "abstract", "become", "box", "do", "final", "macro", "override", "priv", "typeof",
+    "unsized", "virtual", "yield", "async", "await", "dyn", "try",
+];
+
+fn escape_keyword(s: String) -> String {
+    if KEYWORDS.contains(&&s[..]) {
+        s + "_"
+    } else {
+        s
+    }
+}
+
+fn snake_case(s: &str) -> String {
+    let mut out = String::new();
+    let chars: Vec<char> = s.chars().collect();
+
+    for (i, &c) in chars.iter().enumerate() {
+        if c.is_uppercase() {
+            let prev       = if i > 0 { Some(chars[i - 1]) } else { None };
+            let prev_lower = prev.is_some_and(|p| p.is_lowercase() || p.is_ascii_digit());
+            let prev_upper = prev.is_some_and(|p| p.is_uppercase());
+            let next_lower = chars.get(i + 1).is_some_and(|n| n.is_lowercase());
+
+            if prev.is_some_and(|p| p != '_') && (prev_lower || (next_lower && prev_upper)) {
+                out.push('_');
+            }
+
+            out.extend(c.to_lowercase());
+        } else {
+            out.push(c);
+        }
+    }
+
+    out
+}
+
+fn module_name(s: &str) -> String {
+    escape_keyword(snake_case(s))
+}
+
+fn field_name(s: &str) -> String {
+    escape_keyword(snake_case(s))
+}
+
+fn const_name(s: &str) -> String {
+    snake_case(s).to_uppercase()
+}
+
+fn type_name(s: &str) -> String {
+    escape_keyword(s.to_string())
+}
+
+// "A" or "an", to go before `name` in the generated docs.
+fn article(name: &str) -> &'static str {
+    match name.chars().next().map(|c| c.to_ascii_lowercase()) {
+        Some('a') | Some('e') | Some('i') | Some('o') | Some('u') => "An",
+        _                                                         => "A",
+    }
+}
+
+// The generated code names `core` and `alloc` rather than `std`, so that it can be used in `no_std`
+// crates. `alloc` is reached through the `flatbuffers` crate, so that crates which do link `std`
+// don't need an `extern crate alloc`.
+const BOX:    &str = "::flatbuffers::alloc::boxed::Box";
+const STRING: &str = "::flatbuffers::alloc::string::String";
+const VEC:    &str = "::flatbuffers::alloc::vec::Vec";
+
+fn rust_scalar(b: BaseType) -> &'static str {
+    match b {
+        BaseType::Bool   => "bool",
+        BaseType::Byte   => "i8",
+        BaseType::UByte  => "u8",
+        BaseType::Short  => "i16",
+        BaseType::UShort => "u16",
+        BaseType::Int    => "i32",
+        BaseType::UInt   => "u32",
+        BaseType::Long   => "i64",
+        BaseType::ULong  => "u64",
+        BaseType::Float  => "f32",
+        BaseType::Double => "f64",
+        _                => "u8",
+    }
+}
+
+fn write_doc(out: &mut String, doc: &[String]) {
+    for line in doc.iter() {
+        out.push_str("///");
+        out.push_str(line);
+        out.push('\n');
+    }
+}
+
+fn indent(s: &str) -> String {
+    let mut out = String::new();
+
+    for line in s.lines() {
+        if !line.is_empty() {
+            out.push_str("    ");
+        }
+        out.push_str(line);
+        out.push('\n');
+    }
+
+    out
+}
+
+struct Gen<'a> {
+    schema: &'a Schema,
+}
+
+impl<'a> Gen<'a> {
+    fn module(&self, out: &mut String, m: &Module, path: &[String]) {
+        for &i in m.enums.iter() {
+            self.enum_decl(out, &self.schema.enums[i], path);
+        }
+
+        for &i in m.objects.iter() {
+            let obj = &self.schema.objects[i];
+
+            if obj.is_struct {
+                self.struct_decl(out, obj, path);
+            } else {
+                self.table_decl(out, obj, path);
+            }
+
+            if self.schema.root_table == Some(i) {
+                self.root_fns(out, obj);
+            }
+        }
+
+        for (name, child) in m.children.iter() {
+            let mut child_path = path.to_vec();
+            child_path.push(name.clone());
+
+            let mut body = String::new();
+            body.push_str("#![allow(dead_code, unused_imports, non_camel_case_types)]\n\n");
+            self.module(&mut body, child, &child_path);
+
+            let _ = writeln!(out, "pub mod {} {{", name);
+            out.push_str(&indent(&body));
+            out.push_str("}\n\n");
+        }
+    }
+
+    // The path to the type with fully qualified name `name`, relative to the module `from`.
+    fn path_to(&self, from: &[String], name: &str) -> String {
+        let to     = namespace_of(name);
+        let common = from.iter().zip(to.iter()).take_while(|&(a, b)| a == b).count();
+        let mut s  = String::new();
+
+        for _ in common..from.len() {
+            s.push_str("super::");
+        }
+
+        for part in to[common..].iter() {
+            s.push_str(part);
+            s.push_str("::");
+        }
+
+        s.push_str(&type_name(base_name(name)));
+        s
+    }
+
+    fn enum_path(&self, from: &[String], ty: Type) -> String {
+        self.path_to(from, &self.schema.enums[ty.index.unwrap()].name)
+    }
+
+    fn object_path(&self, from: &[String], ty: Type) -> String {
+        self.path_to(from, &self.schema.objects[ty.index.unwrap()].name)
+    }
+
+    fn is_struct(&self, ty: Type) -> bool {
+        ty.index.is_some_and(|i| self.schema.objects[i].is_struct)
+    }
+
+    // The Rust type used for a scalar (or enum) stored inline.
+    fn scalar_type(&self, from: &[String], ty: Type) -> String {
+        match ty.index {
+            Some(_) if ty.base_type.is_integer() => self.enum_path(from, ty),
+            _                                    => rust_scalar(ty.base_type).to_string(),
+        }
+    }
+
+    // The Rust literal for a scalar field's default value.
+    fn default_literal(&self, from: &[String], field: &Field) -> String {
+        let ty = field.ty;
+
+        if ty.base_type == BaseType::Bool {
+            return (field.default_integer != 0).to_string()
+        }
+
+        if ty.base_type.is_float() {
+            let f = field.default_real;
+            let t = rust_scalar(ty.base_type);
+
+            return if f.is_nan() {
+                format!("{}::NAN", t)
+            } else if f.is_infinite() {
+                format!("{}::{}", t, if f > 0.0 { "INFINITY" } else { "NEG_INFINITY" })
+            } else {
+                format!("{:?}", f)
+            }
+        }
+
+        let lit = if ty.base_type == BaseType::ULong {
+            (field.default_integer as u64).to_string()
+        } else {
+            field.default_integer.to_string()
+        };
+
+        match ty.index {
+            Some(_) => format!("{}({})", self.enum_path(from, ty), lit),
+            None    => lit,
+        }
+    }
+
+    // The type of a vector with elements of type `elem`, whose views have the lifetime `lt`.
+    fn vector_type(&self, from: &[String], elem: Type, lt: &str) -> String {
+        format!("::flatbuffers::Vector<{}, {}>", lt, self.vector_element_type(from, elem, lt))
+    }
+
+    // Whether a value of type `ty` can be changed in place, i.e. whether it's a scalar or a struct.
+    // Union types can't be, since changing one without its value would make the union refer to the
+    // wrong kind of table.
+    fn is_mutable(&self, ty: Type) -> bool {
+        (ty.base_type.is_scalar() && ty.base_type != BaseType::UType) || self.is_struct(ty)
+    }
+
+    // The type parameter of a `Vector` with elements of type `elem`.
+    fn vector_element_type(&self, from: &[String], elem: Type, lt: &str) -> String {
+        match elem.base_type {
+            BaseType::String => format!("::flatbuffers::Offset<::flatbuffers::Str<{}>>", lt),
+            BaseType::Obj if self.is_struct(elem) => {
+                format!("::flatbuffers::ByRef<{}>", self.object_path(from, elem))
+            }
+            BaseType::Obj => {
+                format!("::flatbuffers::Offset<{}<{}>>", self.object_path(from, elem), lt)
+            }
+            _ => self.scalar_type(from, elem),
+        }
+    }
+
+    fn enum_decl(&self, out: &mut String, e: &Enum, _path: &[String]) {
+        let name = type_name(base_name(&e.name));
+        let repr = rust_scalar(e.underlying_type.base_type);
+
+        write_doc(out, &e.documentation);
+        let _ = writeln!(out, "#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, \
+                               Hash, Default)]");
+        let _ = writeln!(out, "#[repr(transparent)]");
+        let _ = writeln!(out, "pub struct {}(pub {});\n", name, repr);
+
+        let _ = writeln!(out, "impl {} {{", name);
+        for v in e.values.iter() {
+            let lit = if e.underlying_type.base_type == BaseType::ULong {
+                (v.value as u64).to_string()
+            } else {
+                v.value.to_string()
+            };
+
+            for line in indent(&{
+                let mut d = String::new();
+                write_doc(&mut d, &v.documentation);
+                d
+            }).lines() {
+                let _ = writeln!(out, "{}", line);
+            }
+            let _ = writeln!(out, "    pub const {}: {} = {}({});",
+                             const_name(&v.name), name, name, lit);
+        }
+
+        let _ = writeln!(out, "\n    /// Every named value, in increasing order.");
+        let _ = writeln!(out, "    pub const VALUES: [{}; {}] = [", name, e.values.len());
+        for v in e.values.iter() {
+            let _ = writeln!(out, "        {}::{},", name, const_name(&v.name));
+        }
+        let _ = writeln!(out, "    ];\n");
+
+        let _ = writeln!(out, "    /// The name of this value as written in the \
+                               schema, if it has one.");
+        let _ = writeln!(out, "    pub fn variant_name(self) -> Option<&'static str> {{");
+        let _ = writeln!(out, "        match self {{");
+        for v in e.values.iter() {
+            let _ = writeln!(out, "            {}::{} => Some(\"{}\"),",
+                             name, const_name(&v.name), v.name);
+        }
+        let _ = writeln!(out, "            _ => None,");
+        let _ = writeln!(out, "        }}");
+        let _ = writeln!(out, "    }}");
+
+        if e.is_union {
+            self.union_verify_fn(out, e);
+        }
+
+        let _ = writeln!(out, "}}\n");
+
+        let _ = writeln!(out, "impl ::core::fmt::Debug for {} {{", name);
+        let _ = writeln!(out, "    fn fmt(&self, f: &mut ::core::fmt::Formatter) \
+                               -> ::core::fmt::Result {{");
+        let _ = writeln!(out, "        match self.variant_name() {{");
+        let _ = writeln!(out, "            Some(n) => f.write_str(n),");
+        let _ = writeln!(out, "            None => write!(f, \"{}({{}})\", self.0),", name);
+        let _ = writeln!(out, "        }}");
+        let _ = writeln!(out, "    }}");
+        let _ = writeln!(out, "}}\n");
+
+        let _ = writeln!(out, "impl ::flatbuffers::Endian for {} {{", name);
+        let _ = writeln!(out, "    fn read_le(buf: &[u8]) -> {} {{ \
+                               {}(::flatbuffers::Endian::read_le(buf)) }}", name, name);
+        let _ = writeln!(out, "    fn write_le(self, buf: &mut [u8]) {{ \
+                               ::flatbuffers::Endian::write_le(self.0, buf) }}");
+        let _ = writeln!(out, "    fn from_le(self) -> {} {{ \
+                               {}(::flatbuffers::Endian::from_le(self.0)) }}", name, name);
+        let _ = writeln!(out, "    fn to_le(self) -> {} {{ \
+                               {}(::flatbuffers::Endian::to_le(self.0)) }}", name, name);
+        let _ = writeln!(out, "}}\n");
+
+        if e.is_union {
+            self.union_types(out, e);
+        }
+    }
+
+    // The types used to read and write a union: `FooRef`, which holds a view of whichever table is
+    // stored, and `FooOffset`, which holds an offset to a table that has been built.
+    fn union_types(&self, out: &mut String, e: &Enum) {
+        let from    = namespace_of(&e.name);
+        let name    = type_name(base_name(&e.name));
+        let members: Vec<_> = e.values.iter()
+            .filter_map(|v| {
+                v.union_type.map(|ty| (v, type_name(&v.name), self.object_path(&from, ty)))
+            })
+            .collect();
+
+        let _ = writeln!(out, "/// A view of the value of {} `{}`.",
+                         article(&name).to_lowercase(), name);
+        let _ = writeln!(out, "#[derive(Clone, Copy)]");
+        let _ = writeln!(out, "pub enum {}Ref<'buf> {{", name);
+        for (_, variant, ty) in members.iter() {
+            let _ = writeln!(out, "    {}({}<'buf>),", variant, ty);
+        }
+        let _ = writeln!(out, "    /// A member which isn't in this version of the schema.");
+        let _ = writeln!(out, "    Unknown({}, ::flatbuffers::Table<'buf>),", name);
+        let _ = writeln!(out, "}}\n");
+
+        let _ = writeln!(out, "impl {}Ref<'_> {{", name);
+        let _ = writeln!(out, "    pub fn union_type(&self) -> {} {{", name);
+        let _ = writeln!(out, "        match *self {{");
+        for &(v, ref variant, _) in members.iter() {
+            let _ = writeln!(out, "            {}Ref::{}(_) => {}::{},",
+                             name, variant, name, const_name(&v.name));
+        }
+        let _ = writeln!(out, "            {}Ref::Unknown(t, _) => t,", name);
+        let _ = writeln!(out, "        }}");
+        let _ = writeln!(out, "    }}\n");
+        let _ = writeln!(out, "    /// Copy the value out of the buffer. Unknown \
+                               members become `NONE`.");
+        let _ = writeln!(out, "    pub fn unpack(&self) -> {}T {{", name);
+        let _ = writeln!(out, "        match *self {{");
+        for (_, variant, _) in members.iter() {
+            let _ = writeln!(out, "            {}Ref::{}(ref t) => {}T::{}({}::new(t.unpack())),",
+                             name, variant, name, variant, BOX);
+        }
+        let _ = writeln!(out, "            {}Ref::Unknown(..) => {}T::NONE,", name, name);
+        let _ = writeln!(out, "        }}");
+        let _ = writeln!(out, "    }}");
+        let _ = writeln!(out, "}}\n");
+
+        let _ = writeln!(out, "impl<'buf> ::flatbuffers::Union<'buf> for {}Ref<'buf> {{", name);
+        let _ = writeln!(out, "    fn from_table(tag: u8, table: ::flatbuffers::Table<'buf>) \
+                               -> {}Ref<'buf> {{", name);
+        let _ = writeln!(out, "        match {}(tag) {{", name);
+        for &(v, ref variant, _) in members.iter() {
+            let _ = writeln!(out, "            {}::{} => \
+                                   {}Ref::{}(::flatbuffers::View::view(table.buf(), \
+                                   table.pos())),",
+                             name, const_name(&v.name), name, variant);
+        }
+        let _ = writeln!(out, "            t => {}Ref::Unknown(t, table),", name);
+        let _ = writeln!(out, "        }}");
+        let _ = writeln!(out, "    }}");
+        let _ = writeln!(out, "}}\n");
+
+        let _ = writeln!(out, "/// {} `{}` value which has been built, \
+                               ready to be added to a table.", article(&name), name);
+        let _ = writeln!(out, "#[derive(Clone, Copy)]");
+        let _ = writeln!(out, "pub enum {}Offset {{", name);
+        for (_, variant, ty) in members.iter() {
+            let _ = writeln!(out, "    {}(::flatbuffers::Offset<{}<'static>>),", variant, ty);
+        }
+        let _ = writeln!(out, "}}\n");
+
+        let _ = writeln!(out, "impl {}Offset {{", name);
+        let _ = writeln!(out, "    pub fn union_type(&self) -> {} {{", name);
+        let _ = writeln!(out, "        match *self {{");
+        for &(v, ref variant, _) in members.iter() {
+            let _ = writeln!(out, "            {}Offset::{}(_) => {}::{},",
+                             name, variant, name, const_name(&v.name));
+        }
+        let _ = writeln!(out, "        }}");
+        let _ = writeln!(out, "    }}");
+        let _ = writeln!(out, "}}\n");
+
+        let _ = writeln!(out, "impl ::flatbuffers::UnionOffset for {}Offset {{", name);
+        let _ = writeln!(out, "    fn tag(&self) -> u8 {{ self.union_type().0 }}\n");
+        let _ = writeln!(out, "    fn value(&self) -> \
+                               ::flatbuffers::Offset<::flatbuffers::Table<'static>> {{");
+        let _ = writeln!(out, "        match *self {{");
+        for (_, variant, _) in members.iter() {
+            let _ = writeln!(out, "            {}Offset::{}(o) => \
+                                   ::flatbuffers::Offset::new(o.value()),", name, variant);
+        }
+        let _ = writeln!(out, "        }}");
+        let _ = writeln!(out, "    }}");
+        let _ = writeln!(out, "}}\n");
+
+        let _ = writeln!(out, "/// An owned `{}` value, for use with the object API.", name);
+        let _ = writeln!(out, "#[derive(Clone, Debug, PartialEq)]");
+        let _ = writeln!(out, "pub enum {}T {{", name);
+        let _ = writeln!(out, "    NONE,");
+        for (_, variant, ty) in members.iter() {
+            let _ = writeln!(out, "    {}({}<{}T>),", variant, BOX, ty);
+        }
+        let _ = writeln!(out, "}}\n");
+
+        let _ = writeln!(out, "impl Default for {}T {{", name);
+        let _ = writeln!(out, "    fn default() -> {}T {{ {}T::NONE }}", name, name);
+        let _ = writeln!(out, "}}\n");
+
+        let _ = writeln!(out, "impl {}T {{", name);
+        let _ = writeln!(out, "    pub fn union_type(&self) -> {} {{", name);
+        let _ = writeln!(out, "        match *self {{");
+        let _ = writeln!(out, "            {}T::NONE => {}(0),", name, name);
+        for &(v, ref variant, _) in members.iter() {
+            let _ = writeln!(out, "            {}T::{}(_) => {}::{},",
+                             name, variant, name, const_name(&v.name));
+        }
+        let _ = writeln!(out, "        }}");
+        let _ = writeln!(out, "    }}\n");
+        let _ = writeln!(out, "    /// Build the member table into `fbb`, \
+                               or return `None` if this is `NONE`.");
+        let _ = writeln!(out, "    pub fn pack(&self, \
+                               fbb: &mut ::flatbuffers::FlatBufferBuilder<'_>) \
+                               -> Option<{}Offset> {{", name);
+        let _ = writeln!(out, "        match self.try_pack(fbb) {{");
+        let _ = writeln!(out, "            Ok(o)  => o,");
+        let _ = writeln!(out, "            Err(e) => panic!(\"{{}}\", e),");
+        let _ = writeln!(out, "        }}");
+        let _ = writeln!(out, "    }}\n");
+        let _ = writeln!(out, "    /// Like `pack`, but return an error instead of panicking.");
+        let _ = writeln!(out, "    pub fn try_pack(&self, \
+                               fbb: &mut ::flatbuffers::FlatBufferBuilder<'_>)");
+        let _ = writeln!(out, "                    -> Result<Option<{}Offset>, \
+                               ::flatbuffers::BuilderError> {{", name);
+        let _ = writeln!(out, "        match *self {{");
+        let _ = writeln!(out, "            {}T::NONE => Ok(None),", name);
+        for (_, variant, _) in members.iter() {
+            let _ = writeln!(out, "            {}T::{}(ref t) => \
+                                   Ok(Some({}Offset::{}(t.try_pack(fbb)?))),",
+                             name, variant, name, variant);
+        }
+        let _ = writeln!(out, "        }}");
+        let _ = writeln!(out, "    }}");
+        let _ = writeln!(out, "}}\n");
+    }
+
+    fn union_verify_fn(&self, out: &mut String, e: &Enum) {
+        let from = namespace_of(&e.name);
+        let name = type_name(base_name(&e.name));
+
+        let _ = writeln!(out, "\n    /// Check a value of this union, \
+                               whose type is given by `self`. Unknown types are");
+        let _ = writeln!(out, "    /// accepted, since they may have been added in a newer \
+                               version of the schema.");
+        let _ = writeln!(out, "    pub fn verify_value(self, \
+                               v: &mut ::flatbuffers::Verifier, pos: usize)");
+        let _ = writeln!(out, "        -> Result<(), ::flatbuffers::VerifyError> {{");
+        let _ = writeln!(out, "        match self {{");
+        for v in e.values.iter() {
+            if let Some(ty) = v.union_type {
+                let _ = writeln!(out, "            {}::{} => \
+                                       <{} as ::flatbuffers::Verify>::verify(v, pos),",
+                                 name, const_name(&v.name), self.object_path(&from, ty));
+            }
+        }
+        let _ = writeln!(out, "            _ => Ok(()),");
+        let _ = writeln!(out, "        }}");
+        let _ = writeln!(out, "    }}");
+    }
+
+    fn struct_decl(&self, out: &mut String, obj: &Object, path: &[String]) {
+        let name = type_name(base_name(&obj.name));
+
+        // The fields, with padding fields inserted where needed.
+        let mut members = Vec::new();
+        let mut pos     = 0;
+        let mut natural = 1;
+
+        for f in obj.fields.iter() {
+            if f.offset as usize > pos {
+                members.push((format!("padding{}__", members.len()),
+                              format!("[u8; {}]", f.offset as usize - pos), None));
+            }
+
+            // Bools are stored as `u8`, so that any bit pattern is a valid struct.
+            let (ty, size, align) = if f.ty.base_type == BaseType::Obj {
+                let inner = &self.schema.objects[f.ty.index.unwrap()];
+                (self.object_path(path, f.ty), inner.bytesize, inner.minalign)
+            } else if f.ty.base_type == BaseType::Bool {
+                ("u8".to_string(), 1, 1)
+            } else {
+                (self.scalar_type(path, f.ty), f.ty.base_type.size(), f.ty.base_type.size())
+            };
+
+            natural = natural.max(align);
+            pos = f.offset as usize + size;
+            members.push((field_name(&f.name), ty, Some(f)));
+        }
+
+        if obj.bytesize > pos {
+            members.push((format!("padding{}__", members.len()),
+                          format!("[u8; {}]", obj.bytesize - pos), None));
+        }
+
+        write_doc(out, &obj.documentation);
+        if obj.minalign > natural {
+            let _ = writeln!(out, "#[repr(C, align({}))]", obj.minalign);
+        } else {
+            let _ = writeln!(out, "#[repr(C)]");
+        }
+        let _ = writeln!(out, "#[derive(Clone, Copy, PartialEq)]");
+        let _ = writeln!(out, "pub struct {} {{", name);
+        for (fname, ty, _) in members.iter() {
+            let _ = writeln!(out, "    {}: {},", fname, ty);
+        }
+        let _ = writeln!(out, "}}\n");
+
+        let _ = writeln!(out, "unsafe impl ::flatbuffers::Struct for {} {{}}\n", name);
+
+        let _ = writeln!(out, "impl {} {{", name);
+
+        let params: Vec<String> = obj.fields.iter().map(|f| {
+            let ty = if f.ty.base_type == BaseType::Obj {
+                format!("&{}", self.object_path(path, f.ty))
+            } else {
+                self.scalar_type(path, f.ty)
+            };
+            format!("{}: {}", field_name(&f.name), ty)
+        }).collect();
+
+        let _ = writeln!(out, "    pub fn new({}) -> {} {{", params.join(", "), name);
+        let _ = writeln!(out, "        {} {{", name);
+        for &(ref fname, ref ty, field) in members.iter() {
+            match field {
+                Some(f) if f.ty.base_type == BaseType::Obj => {
+                    let _ = writeln!(out, "            {}: *{},", fname, fname);
+                }
+                Some(f) if f.ty.base_type == BaseType::Bool => {
+                    let _ = writeln!(out, "            {}: {} as u8,", fname, fname);
+                }
+                Some(_) => {
+                    let _ = writeln!(out, "            {}: ::flatbuffers::Endian::to_le({}),",
+                                     fname, fname);
+                }
+                None => {
+                    let _ = writeln!(out, "            {}: [0; {}],", fname, &ty[5..ty.len() - 1]);
+                }
+            }
+        }
+        let _ = writeln!(out, "        }}");
+        let _ = writeln!(out, "    }}");
+
+        for f in obj.fields.iter() {
+            let fname = field_name(&f.name);
+
+            out.push('\n');
+            out.push_str(&indent(&{
+                let mut d = String::new();
+                write_doc(&mut d, &f.documentation);
+                d
+            }));
+
+            if f.ty.base_type == BaseType::Obj {
+                let ty = self.object_path(path, f.ty);
+                let _ = writeln!(out, "    pub fn {}(&self) -> &{} {{ &self.{} }}",
+                                 fname, ty, fname);
+                let _ = writeln!(out, "\n    pub fn {}_mut(&mut self) -> &mut {} {{ \
+                                       &mut self.{} }}", snake_case(&f.name), ty, fname);
+            } else if f.ty.base_type == BaseType::Bool {
+                let _ = writeln!(out, "    pub fn {}(&self) -> bool {{ self.{} != 0 }}",
+                                 fname, fname);
+                let _ = writeln!(out, "\n    pub fn set_{}(&mut self, {}: bool) {{ \
+                                       self.{} = {} as u8; }}",
+                                 snake_case(&f.name), fname, fname, fname);
+            } else {
+                let ty = self.scalar_type(path, f.ty);
+                let _ = writeln!(out, "    pub fn {}(&self) -> {} {{ \
+                                       ::flatbuffers::Endian::from_le(self.{}) }}",
+                                 fname, ty, fname);
+                let _ = writeln!(out, "\n    pub fn set_{}(&mut self, {}: {}) {{ \
+                                       self.{} = ::flatbuffers::Endian::to_le({}); }}",
+                                 snake_case(&f.name), fname, ty, fname, fname);
+            }
+        }
+
+        let _ = writeln!(out, "}}\n");
+
+        let _ = writeln!(out, "impl ::core::fmt::Debug for {} {{", name);
+        let _ = writeln!(out, "    fn fmt(&self, f: &mut ::core::fmt::Formatter) \
+                               -> ::core::fmt::Result {{");
+        let _ = writeln!(out, "        f.debug_struct(\"{}\")", name);
+        for fld in obj.fields.iter() {
+            let _ = writeln!(out, "            .field(\"{}\", &self.{}())",
+                             fld.name, field_name(&fld.name));
+        }
+        let _ = writeln!(out, "            .finish()");
+        let _ = writeln!(out, "    }}");
+        let _ = writeln!(out, "}}\n");
+    }
+
+    fn ord_impl(&self, out: &mut String, name: &str, key: &Field, path: &[String]) {
+        let fname = field_name(&key.name);
+        let (ty, cmp, cmp_with) = if key.ty.base_type == BaseType::String {
+            ("str".to_string(),
+             format!("{}(a).{}().cmp(&{}(b).{}())", name, fname, name, fname),
+             format!("match {}(a).{}() {{\n            \
+                          Some(s) => s.as_bytes().cmp(key.as_bytes()),\n            \
+                          None    => ::core::cmp::Ordering::Less,\n        \
+                      }}", name, fname))
+        } else if key.ty.base_type.is_float() {
+            (self.scalar_type(path, key.ty),
+             format!("{}(a).{}().partial_cmp(&{}(b).{}()).unwrap_or(::core::cmp::Ordering::Equal)",
+                     name, fname, name, fname),
+             format!("{}(a).{}().partial_cmp(key).unwrap_or(::core::cmp::Ordering::Equal)",
+                     name, fname))
+        } else {
+            (self.scalar_type(path, key.ty),
+             format!("{}(a).{}().cmp(&{}(b).{}())", name, fname, name, fname),
+             format!("{}(a).{}().cmp(key)", name, fname))
+        };
+
+        let _ = writeln!(out, "impl ::flatbuffers::OrdTable for {}<'_> {{", name);
+        let _ = writeln!(out, "    type Key = {};\n", ty);
+        let _ = writeln!(out, "    fn table(&self) -> ::flatbuffers::Table<'_> {{ self.0 }}\n");
+        let _ = writeln!(out, "    fn key_cmp(a: ::flatbuffers::Table, b: ::flatbuffers::Table) \
+                               -> ::core::cmp::Ordering {{");
+        let _ = writeln!(out, "        {}", cmp);
+        let _ = writeln!(out, "    }}\n");
+        let _ = writeln!(out, "    fn key_cmp_with(a: ::flatbuffers::Table, key: &{}) \
+                               -> ::core::cmp::Ordering {{", ty);
+        let _ = writeln!(out, "        {}", cmp_with);
+        let _ = writeln!(out, "    }}");
+        let _ = writeln!(out, "}}\n");
+    }
+
+    fn vt_name(&self, f: &Field) -> String {
+        format!("VT_{}", const_name(&f.name))
+    }
+
+    fn table_decl(&self, out: &mut String, obj: &Object, path: &[String]) {
+        let name   = type_name(base_name(&obj.name));
+        let fields: Vec<&Field> = obj.fields.iter().filter(|f| !f.deprecated).collect();
+
+        write_doc(out, &obj.documentation);
+        let _ = writeln!(out, "#[derive(Clone, Copy)]");
+        let _ = writeln!(out, "pub struct {}<'buf>(::flatbuffers::Table<'buf>);\n", name);
+
+        let _ = writeln!(out, "impl<'buf> ::flatbuffers::View<'buf> for {}<'buf> {{", name);
+        let _ = writeln!(out, "    fn view(buf: &'buf [u8], pos: usize) -> {}<'buf> {{", name);
+        let _ = writeln!(out, "        {}(::flatbuffers::View::view(buf, pos))", name);
+        let _ = writeln!(out, "    }}");
+        let _ = writeln!(out, "}}\n");
+
+        let _ = writeln!(out, "impl<'buf> {}<'buf> {{", name);
+        for f in obj.fields.iter() {
+            let _ = writeln!(out, "    pub const {}: ::flatbuffers::VOffset = {};",
+                             self.vt_name(f), f.offset);
+        }
+
+        for f in fields.iter() {
+            out.push('\n');
+            self.table_accessor(out, &name, f, path);
+        }
+
+        let _ = writeln!(out, "}}\n");
+
+        self.table_mut_decl(out, &name, &fields, path);
+        self.table_verify(out, &name, obj, path);
+
+        if let Some(key) = obj.key_field() {
+            self.ord_impl(out, &name, key, path);
+        }
+
+        self.table_builder(out, &name, obj, path);
+        self.table_object(out, &name, &fields, path);
+    }
+
+    fn table_accessor(&self, out: &mut String, name: &str, f: &Field, path: &[String]) {
+        let fname = field_name(&f.name);
+        let vt    = format!("{}::{}", name, self.vt_name(f));
+        let mut d = String::new();
+
+        write_doc(&mut d, &f.documentation);
+        out.push_str(&indent(&d));
+
+        match f.ty.base_type {
+            b if b.is_scalar() => {
+                let ty  = self.scalar_type(path, f.ty);
+                let def = self.default_literal(path, f);
+
+                let _ = writeln!(out, "    pub fn {}(&self) -> {} {{", fname, ty);
+                let _ = writeln!(out, "        self.0.get_field({}, {})", vt, def);
+                let _ = writeln!(out, "    }}");
+            }
+            BaseType::String => {
+                let _ = writeln!(out, "    pub fn {}(&self) -> Option<::flatbuffers::Str<'buf>> {{",
+                                 fname);
+                let _ = writeln!(out, "        self.0.get_ref({})", vt);
+                let _ = writeln!(out, "    }}");
+            }
+            BaseType::Vector if f.ty.element == BaseType::Union => {
+                let _ = writeln!(out, "    pub fn {}(&self) \
+                                       -> Option<::flatbuffers::UnionVector<'buf, \
+                                       {}Ref<'buf>>> {{",
+                                 fname, self.enum_path(path, f.ty));
+                let _ = writeln!(out, "        self.0.get_union_vector({}::VT_{}_TYPE, {})",
+                                 name, const_name(&f.name), vt);
+                let _ = writeln!(out, "    }}");
+            }
+            BaseType::Vector => {
+                let vec = self.vector_type(path, f.ty.element_type(), "'buf");
+
+                let _ = writeln!(out, "    pub fn {}(&self) -> Option<{}> {{", fname, vec);
+                let _ = writeln!(out, "        self.0.get_ref({})", vt);
+                let _ = writeln!(out, "    }}");
+            }
+            BaseType::Obj if self.is_struct(f.ty) => {
+                let _ = writeln!(out, "    pub fn {}(&self) -> Option<{}> {{",
+                                 fname, self.object_path(path, f.ty));
+                let _ = writeln!(out, "        self.0.get_struct({})", vt);
+                let _ = writeln!(out, "    }}");
+            }
+            BaseType::Obj => {
+                let _ = writeln!(out, "    pub fn {}(&self) -> Option<{}<'buf>> {{",
+                                 fname, self.object_path(path, f.ty));
+                let _ = writeln!(out, "        self.0.get_ref({})", vt);
+                let _ = writeln!(out, "    }}");
+            }
+            BaseType::Union => {
+                let en     = &self.schema.enums[f.ty.index.unwrap()];
+                let tag_fn = field_name(&format!("{}_type", f.name));
+
+                let _ = writeln!(out, "    pub fn {}(&self) -> Option<{}Ref<'buf>> {{",
+                                 fname, self.enum_path(path, f.ty));
+                let _ = writeln!(out, "        self.0.get_union({}::VT_{}_TYPE, {})",
+                                 name, const_name(&f.name), vt);
+                let _ = writeln!(out, "    }}");
+
+                for v in en.values.iter() {
+                    if let Some(ty) = v.union_type {
+                        let _ = writeln!(out, "\n    pub fn {}_as_{}(&self) -> Option<{}<'buf>> {{",
+                                         snake_case(&f.name), snake_case(&v.name),
+                                         self.object_path(path, ty));
+                        let _ = writeln!(out, "        if self.{}() != {}::{} {{ return None }}",
+                                         tag_fn, self.enum_path(path, f.ty), const_name(&v.name));
+                        let _ = writeln!(out, "        self.0.get_ref({})", vt);
+                        let _ = writeln!(out, "    }}");
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // The `FooMut` type, which changes the fields of a table in place.
+    fn table_mut_decl(&self, out: &mut String, name: &str, fields: &[&Field], path: &[String]) {
+        let _ = writeln!(out, "/// {} `{}` in a mutable buffer, \
+                               whose fields can be changed in place.", article(name), name);
+        let _ = writeln!(out, "pub struct {}Mut<'buf>(::flatbuffers::TableMut<'buf>);\n", name);
+
+        let _ = writeln!(out, "impl<'buf> From<::flatbuffers::TableMut<'buf>> for {}Mut<'buf> {{",
+                         name);
+        let _ = writeln!(out, "    fn from(t: ::flatbuffers::TableMut<'buf>) \
+                               -> {}Mut<'buf> {{ {}Mut(t) }}", name, name);
+        let _ = writeln!(out, "}}\n");
+
+        let _ = writeln!(out, "impl {}Mut<'_> {{", name);
+        let _ = writeln!(out, "    /// A read-only view of the table.");
+        let _ = writeln!(out, "    pub fn view(&self) -> {}<'_> {{ {}(self.0.as_table()) }}",
+                         name, name);
+
+        for f in fields.iter() {
+            let fname = field_name(&f.name);
+            let vt    = format!("{}::{}", name, self.vt_name(f));
+
+            match f.ty.base_type {
+                b if b.is_scalar() && self.is_mutable(f.ty) => {
+                    let _ = writeln!(out, "\n    /// Change the value of `{}` in place. This only \
+                                           works if the field is present", f.name);
+                    let _ = writeln!(out, "    /// in the buffer, \
+                                           and returns `false` if it isn't.");
+                    let _ = writeln!(out, "    pub fn mutate_{}(&mut self, {}: {}) -> bool {{",
+                                     snake_case(&f.name), fname, self.scalar_type(path, f.ty));
+                    let _ = writeln!(out, "        self.0.set_field({}, {})", vt, fname);
+                    let _ = writeln!(out, "    }}");
+                }
+                BaseType::Obj if self.is_struct(f.ty) => {
+                    let _ = writeln!(out, "\n    /// Change the value of `{}` in place. This only \
+                                           works if the field is present", f.name);
+                    let _ = writeln!(out, "    /// in the buffer, \
+                                           and returns `false` if it isn't.");
+                    let _ = writeln!(out, "    pub fn mutate_{}(&mut self, {}: {}) -> bool {{",
+                                     snake_case(&f.name), fname, self.object_path(path, f.ty));
+                    let _ = writeln!(out, "        self.0.set_struct({}, {})", vt, fname);
+                    let _ = writeln!(out, "    }}");
+                }
+                BaseType::Vector if self.is_mutable(f.ty.element_type()) => {
+                    let elem = self.vector_element_type(path, f.ty.element_type(), "'_");
+                    let _ = writeln!(out, "\n    /// The elements of `{}`, \
+                                           which can be changed in place.", f.name);
+                    let _ = writeln!(out, "    pub fn {}_mut(&mut self) \
+                                           -> Option<::flatbuffers::VectorMut<'_, {}>> {{",
+                                     snake_case(&f.name), elem);
+                    let _ = writeln!(out, "        self.0.get_vector_mut({})", vt);
+                    let _ = writeln!(out, "    }}");
+                }
+                _ => {}
+            }
+        }
+
+        let _ = writeln!(out, "}}\n");
+    }
+
+    fn table_verify(&self, out: &mut String, name: &str, obj: &Object, path: &[String]) {
+        let _ = writeln!(out, "impl ::flatbuffers::Verify for {}<'_> {{", name);
+        let _ = writeln!(out, "    fn verify(v: &mut ::flatbuffers::Verifier, pos: usize) \
+                               -> Result<(), ::flatbuffers::VerifyError> {{");
+        let _ = writeln!(out, "        let mut t = v.verify_table(pos)?;");
+
+        for f in obj.fields.iter().filter(|f| !f.deprecated) {
+            let vt  = format!("{}::{}", name, self.vt_name(f));
+            let req = f.required;
+
+            match f.ty.base_type {
+                BaseType::UType => {}
+                BaseType::Vector if f.ty.element == BaseType::UType => {}
+                b if b.is_scalar() => {
+                    let _ = writeln!(out, "        t.visit_field::<{}>({}, false)?;",
+                                     self.scalar_type(path, f.ty), vt);
+                }
+                BaseType::String => {
+                    let _ = writeln!(out, "        t.visit_string({}, {})?;", vt, req);
+                }
+                BaseType::Obj if self.is_struct(f.ty) => {
+                    let _ = writeln!(out, "        t.visit_field::<{}>({}, {})?;",
+                                     self.object_path(path, f.ty), vt, req);
+                }
+                BaseType::Obj => {
+                    let _ = writeln!(out, "        t.visit_table::<{}>({}, {})?;",
+                                     self.object_path(path, f.ty), vt, req);
+                }
+                BaseType::Vector if f.ty.element == BaseType::Union => {
+                    let _ = writeln!(out, "        t.visit_union_vector({}::VT_{}_TYPE, {}, {}, \
+                                           |v, tag, pos| {}(tag).verify_value(v, pos))?;",
+                                     name, const_name(&f.name), vt, req,
+                                     self.enum_path(path, f.ty));
+                }
+                BaseType::Vector => {
+                    let elem = f.ty.element_type();
+                    match elem.base_type {
+                        BaseType::String => {
+                            let _ = writeln!(out, "        t.visit_vector_of_strings({}, {})?;",
+                                             vt, req);
+                        }
+                        BaseType::Obj if !self.is_struct(elem) => {
+                            let _ = writeln!(out, "        \
+                                                   t.visit_vector_of_tables::<{}>({}, {})?;",
+                                             self.object_path(path, elem), vt, req);
+                        }
+                        BaseType::Obj => {
+                            let _ = writeln!(out, "        t.visit_vector::<{}>({}, {})?;",
+                                             self.object_path(path, elem), vt, req);
+                        }
+                        _ => {
+                            let _ = writeln!(out, "        t.visit_vector::<{}>({}, {})?;",
+                                             self.scalar_type(path, elem), vt, req);
+                        }
+                    }
+                }
+                BaseType::Union => {
+                    let tag = obj.fields.iter().find(|t| t.id + 1 == f.id).unwrap();
+                    let _ = writeln!(out, "        t.visit_union({}::{}, {}, {}, \
+                                           |v, tag, pos| {}(tag).verify_value(v, pos))?;",
+                                     name, self.vt_name(tag), vt, req,
+                                     self.enum_path(path, f.ty));
+                }
+                _ => {}
+            }
+        }
+
+        let _ = writeln!(out, "        t.end()");
+        let _ = writeln!(out, "    }}");
+        let _ = writeln!(out, "}}\n");
+    }
+
+    fn table_builder(&self, out: &mut String, name: &str, obj: &Object, path: &[String]) {
+        let bname = format!("{}Builder", name);
+
+        let _ = writeln!(out, "pub struct {}<'fbb, 'buf> {{", bname);
+        let _ = writeln!(out, "    fbb: &'fbb mut ::flatbuffers::FlatBufferBuilder<'buf>,");
+        let _ = writeln!(out, "    start: ::flatbuffers::UOffset,");
+        let _ = writeln!(out, "}}\n");
+
+        let _ = writeln!(out, "impl<'fbb, 'buf> {}<'fbb, 'buf> {{", bname);
+        let _ = writeln!(out, "    pub fn new(fbb: &'fbb mut \
+                               ::flatbuffers::FlatBufferBuilder<'buf>) -> {}<'fbb, 'buf> {{",
+                         bname);
+        let _ = writeln!(out, "        let start = fbb.start_table();");
+        let _ = writeln!(out, "        {} {{ fbb, start }}", bname);
+        let _ = writeln!(out, "    }}\n");
+        let _ = writeln!(out, "    pub fn try_new(fbb: &'fbb mut \
+                               ::flatbuffers::FlatBufferBuilder<'buf>)");
+        let _ = writeln!(out, "                   -> Result<{}<'fbb, 'buf>, \
+                               ::flatbuffers::BuilderError> {{", bname);
+        let _ = writeln!(out, "        let start = fbb.try_start_table()?;");
+        let _ = writeln!(out, "        Ok({} {{ fbb, start }})", bname);
+        let _ = writeln!(out, "    }}");
+
+        // Union type tags are added along with the union's values.
+        let is_tag = |f: &&Field| f.ty.base_type == BaseType::UType ||
+                                  (f.ty.base_type == BaseType::Vector &&
+                                   f.ty.element == BaseType::UType);
+
+        for f in obj.fields.iter().filter(|f| !f.deprecated && !is_tag(f)) {
+            let fname = field_name(&f.name);
+            let vt    = format!("{}::{}", name, self.vt_name(f));
+
+            // The type of the argument, and the `FlatBufferBuilder` method and arguments which add
+            // it.
+            let plain = format!("{}, {}", vt, fname);
+            let (ty, method, args) = match f.ty.base_type {
+                b if b.is_scalar() => {
+                    let def = self.default_literal(path, f);
+                    (self.scalar_type(path, f.ty), "add_scalar", format!("{}, {}", plain, def))
+                }
+                BaseType::String => {
+                    let ty = "::flatbuffers::Offset<::flatbuffers::Str<'_>>".to_string();
+                    (ty, "add_offset", plain)
+                }
+                BaseType::Obj if self.is_struct(f.ty) => {
+                    (format!("&{}", self.object_path(path, f.ty)), "add_struct", plain)
+                }
+                BaseType::Obj => {
+                    let table = self.object_path(path, f.ty);
+                    (format!("::flatbuffers::Offset<{}<'_>>", table), "add_offset", plain)
+                }
+                BaseType::Vector if f.ty.element == BaseType::Union => {
+                    let union = self.enum_path(path, f.ty);
+                    let types = format!("{}::VT_{}_TYPE", name, const_name(&f.name));
+                    (format!("::flatbuffers::UnionVectorOffset<{}Offset>", union),
+                     "add_union_vector", format!("{}, {}", types, plain))
+                }
+                BaseType::Vector => {
+                    let vec = self.vector_type(path, f.ty.element_type(), "'_");
+
+                    (format!("::flatbuffers::Offset<{}>", vec), "add_offset", plain)
+                }
+                BaseType::Union => {
+                    let tag = format!("{}::VT_{}_TYPE", name, const_name(&f.name));
+                    (format!("{}Offset", self.enum_path(path, f.ty)), "add_union",
+                     format!("{}, {}", tag, plain))
+                }
+                _ => continue,
+            };
+
+            let add = snake_case(&f.name);
+
+            let _ = writeln!(out, "\n    pub fn add_{}(&mut self, {}: {}) {{", add, fname, ty);
+            let _ = writeln!(out, "        self.fbb.{}({});", method, args);
+            let _ = writeln!(out, "    }}\n");
+            let _ = writeln!(out, "    pub fn try_add_{}(&mut self, {}: {}) \
+                                   -> Result<(), ::flatbuffers::BuilderError> {{",
+                             add, fname, ty);
+            let _ = writeln!(out, "        self.fbb.try_{}({})", method, args);
+            let _ = writeln!(out, "    }}");
+        }
+
+        let required: Vec<_> = obj.fields.iter().filter(|f| f.required && !f.deprecated).collect();
+
+        let _ = writeln!(out, "\n    pub fn finish(self) -> ::flatbuffers::Offset<{}<'static>> {{",
+                         name);
+        let _ = writeln!(out, "        let o = self.fbb.end_table(self.start, {});",
+                         obj.num_slots());
+        for f in required.iter() {
+            let _ = writeln!(out, "        self.fbb.required(o, {}::{});", name, self.vt_name(f));
+        }
+        let _ = writeln!(out, "        ::flatbuffers::Offset::new(o)");
+        let _ = writeln!(out, "    }}\n");
+
+        let _ = writeln!(out, "    /// Like `finish`, but return an error instead of panicking, \
+                               e.g. if a required field is");
+        let _ = writeln!(out, "    /// missing.");
+        let _ = writeln!(out, "    pub fn try_finish(self) \
+                               -> Result<::flatbuffers::Offset<{}<'static>>, \
+                               ::flatbuffers::BuilderError> {{",
+                         name);
+        let _ = writeln!(out, "        let o = self.fbb.try_end_table(self.start, {})?;",
+                         obj.num_slots());
+        for f in required.iter() {
+            let _ = writeln!(out, "        self.fbb.try_required(o, {}::{})?;",
+                             name, self.vt_name(f));
+        }
+        let _ = writeln!(out, "        Ok(::flatbuffers::Offset::new(o))");
+        let _ = writeln!(out, "    }}");
+        let _ = writeln!(out, "}}\n");
+    }
+
+    // The owned type used by the object API for a field, e.g. `Option<String>` for a string.
+    fn object_field_type(&self, path: &[String], f: &Field) -> String {
+        match f.ty.base_type {
+            b if b.is_scalar() => self.scalar_type(path, f.ty),
+            BaseType::String => format!("Option<{}>", STRING),
+            BaseType::Vector => {
+                let elem = f.ty.element_type();
+                let t = match elem.base_type {
+                    BaseType::String => STRING.to_string(),
+                    BaseType::Union => format!("{}T", self.enum_path(path, elem)),
+                    BaseType::Obj if self.is_struct(elem) => self.object_path(path, elem),
+                    BaseType::Obj => format!("{}T", self.object_path(path, elem)),
+                    _ => self.scalar_type(path, elem),
+                };
+                format!("Option<{}<{}>>", VEC, t)
+            }
+            BaseType::Obj if self.is_struct(f.ty) => {
+                format!("Option<{}>", self.object_path(path, f.ty))
+            }
+            BaseType::Obj => format!("Option<{}<{}T>>", BOX, self.object_path(path, f.ty)),
+            BaseType::Union => format!("{}T", self.enum_path(path, f.ty)),
+            _ => "()".to_string(),
+        }
+    }
+
+    // The object API for a table: `FooT`, an owned copy of a `Foo` which can be made with
+    // `Foo::unpack` and built back into a buffer with `FooT::pack`.
+    fn table_object(&self, out: &mut String, name: &str, fields: &[&Field], path: &[String]) {
+        let tname = format!("{}T", name);
+
+        // Union type fields are part of the union's owned type.
+        let fields: Vec<&Field> = fields.iter().cloned()
+            .filter(|f| f.ty.base_type != BaseType::UType && f.ty.element != BaseType::UType)
+            .collect();
+
+        let _ = writeln!(out, "/// An owned `{}`, which doesn't borrow a buffer. It can be made \
+                               from {} `{}` with", name, article(name).to_lowercase(), name);
+        let _ = writeln!(out, "/// `unpack`, changed freely, \
+                               and built into a buffer again with `pack`.");
+        let _ = writeln!(out, "#[derive(Clone, Debug, PartialEq)]");
+        let _ = writeln!(out, "pub struct {} {{", tname);
+        for f in fields.iter() {
+            let _ = writeln!(out, "    pub {}: {},",
+                             field_name(&f.name), self.object_field_type(path, f));
+        }
+        let _ = writeln!(out, "}}\n");
+
+        let _ = writeln!(out, "impl Default for {} {{", tname);
+        let _ = writeln!(out, "    fn default() -> {} {{", tname);
+        let _ = writeln!(out, "        {} {{", tname);
+        for f in fields.iter() {
+            let def = match f.ty.base_type {
+                b if b.is_scalar() => self.default_literal(path, f),
+                _                  => "Default::default()".to_string(),
+            };
+            let _ = writeln!(out, "            {}: {},", field_name(&f.name), def);
+        }
+        let _ = writeln!(out, "        }}");
+        let _ = writeln!(out, "    }}");
+        let _ = writeln!(out, "}}\n");
+
+        let _ = writeln!(out, "impl {}<'_> {{", name);
+        let _ = writeln!(out, "    /// Copy this table, and everything it refers to, \
+                               out of the buffer. Strings which aren't");
+        let _ = writeln!(out, "    /// valid UTF-8 are copied lossily.");
+        let _ = writeln!(out, "    pub fn unpack(&self) -> {} {{", tname);
+        let _ = writeln!(out, "        {} {{", tname);
+        for f in fields.iter() {
+            let fname = field_name(&f.name);
+            let string = format!("{}::from_utf8_lossy(s.as_bytes()).into_owned()", STRING);
+
+            let value = match f.ty.base_type {
+                b if b.is_scalar() => format!("self.{}()", fname),
+                BaseType::String => format!("self.{}().map(|s| {})", fname, string),
+                BaseType::Vector => {
+                    let elem = f.ty.element_type();
+                    let each = match elem.base_type {
+                        BaseType::String => format!(".map(|s| {})", string),
+                        BaseType::Union => {
+                            format!(".map(|u| u.map_or({}T::NONE, |u| u.unpack()))",
+                                    self.enum_path(path, elem))
+                        }
+                        BaseType::Obj if self.is_struct(elem) => String::new(),
+                        BaseType::Obj => ".map(|t| t.unpack())".to_string(),
+                        _ => String::new(),
+                    };
+                    format!("self.{}().map(|v| v.iter(){}.collect())", fname, each)
+                }
+                BaseType::Obj if self.is_struct(f.ty) => format!("self.{}()", fname),
+                BaseType::Obj => format!("self.{}().map(|t| {}::new(t.unpack()))", fname, BOX),
+                BaseType::Union => {
+                    format!("self.{}().map_or({}T::NONE, |u| u.unpack())",
+                            fname, self.enum_path(path, f.ty))
+                }
+                _ => continue,
+            };
+            let _ = writeln!(out, "            {}: {},", fname, value);
+        }
+        let _ = writeln!(out, "        }}");
+        let _ = writeln!(out, "    }}");
+        let _ = writeln!(out, "}}\n");
+
+        let _ = writeln!(out, "impl {} {{", tname);
+        let _ = writeln!(out, "    /// Build this table into `fbb`, \
+                               along with everything it refers to. Like `{}Builder`,", name);
+        let _ = writeln!(out, "    /// this panics if a required field is `None`.");
+        let _ = writeln!(out, "    pub fn pack(&self, \
+                               fbb: &mut ::flatbuffers::FlatBufferBuilder<'_>) \
+                               -> ::flatbuffers::Offset<{}<'static>> {{", name);
+        let _ = writeln!(out, "        match self.try_pack(fbb) {{");
+        let _ = writeln!(out, "            Ok(o)  => o,");
+        let _ = writeln!(out, "            Err(e) => panic!(\"{{}}\", e),");
+        let _ = writeln!(out, "        }}");
+        let _ = writeln!(out, "    }}\n");
+
+        let _ = writeln!(out, "    /// Like `pack`, but return an error instead of panicking.");
+        let _ = writeln!(out, "    pub fn try_pack(&self, \
+                               fbb: &mut ::flatbuffers::FlatBufferBuilder<'_>)");
+        let _ = writeln!(out, "                    -> Result<::flatbuffers::Offset<{}<'static>>, \
+                               ::flatbuffers::BuilderError> {{", name);
+
+        // Build each element of `v` with `each`, collecting them in `v`, or return the first error.
+        let collect = |each: &str| {
+            format!("let v = v.iter().map({}).collect::<Result<{}<_>, _>>()?;\n            ",
+                    each, VEC)
+        };
+
+        // Strings, vectors and tables have to be built before the table that refers to them. Each
+        // of these is built from an `Option`, giving an `Option<Result<_, _>>` to transpose.
+        for f in fields.iter() {
+            let fname = field_name(&f.name);
+
+            let value = match f.ty.base_type {
+                BaseType::String => {
+                    format!("self.{}.as_ref().map(|s| fbb.try_create_string(s))", fname)
+                }
+                BaseType::Vector => {
+                    let elem = f.ty.element_type();
+                    let create = match elem.base_type {
+                        BaseType::String => {
+                            format!("{}fbb.try_create_vector_of_offsets(&v)",
+                                    collect("|s| fbb.try_create_string(s)"))
+                        }
+                        BaseType::Union => {
+                            format!("{}Ok(fbb.try_create_union_vector(&v)?.into())",
+                                    collect("|u| u.try_pack(fbb)"))
+                        }
+                        BaseType::Obj if self.is_struct(elem) => {
+                            "fbb.try_create_vector_of_structs(v)".to_string()
+                        }
+                        BaseType::Obj => {
+                            format!("{}fbb.try_create_vector_of_offsets(&v)",
+                                    collect("|t| t.try_pack(fbb)"))
+                        }
+                        _ => "fbb.try_create_vector(v)".to_string(),
+                    };
+                    if create.contains('\n') {
+                        format!("self.{}.as_ref().map(|v| {{\n            {}\n        }})",
+                                fname, create)
+                    } else {
+                        format!("self.{}.as_ref().map(|v| {})", fname, create)
+                    }
+                }
+                BaseType::Obj if !self.is_struct(f.ty) => {
+                    format!("self.{}.as_ref().map(|t| t.try_pack(fbb))", fname)
+                }
+                BaseType::Union => {
+                    let _ = writeln!(out, "        let {} = self.{}.try_pack(fbb)?;", fname, fname);
+                    continue
+                }
+                _ => continue,
+            };
+            let _ = writeln!(out, "        let {} = {}.transpose()?;", fname, value);
+        }
+
+        let _ = writeln!(out, "\n        let mut builder = {}Builder::try_new(fbb)?;", name);
+
+        // Add the biggest fields first, so that there's as little padding between them as possible.
+        let mut sorted = fields.clone();
+        sorted.sort_by_key(|f| ::std::cmp::Reverse(match f.ty.base_type {
+            BaseType::Obj if self.is_struct(f.ty) => {
+                self.schema.objects[f.ty.index.unwrap()].bytesize
+            }
+            b => b.size(),
+        }));
+
+        for f in sorted.iter() {
+            let fname = field_name(&f.name);
+            let add   = format!("try_add_{}", snake_case(&f.name));
+
+            match f.ty.base_type {
+                b if b.is_scalar() => {
+                    let _ = writeln!(out, "        builder.{}(self.{})?;", add, fname);
+                }
+                BaseType::Obj if self.is_struct(f.ty) => {
+                    let _ = writeln!(out, "        if let Some(ref x) = self.{} {{ \
+                                           builder.{}(x)?; }}", fname, add);
+                }
+                _ => {
+                    let _ = writeln!(out, "        if let Some(x) = {} {{ builder.{}(x)?; }}",
+                                     fname, add);
+                }
+            }
+        }
+
+        let _ = writeln!(out, "        builder.try_finish()");
+        let _ = writeln!(out, "    }}");
+        let _ = writeln!(out, "}}\n");
+    }
+
+    fn root_fns(&self, out: &mut String, obj: &Object) {
+        let name  = type_name(base_name(&obj.name));
+        let snake = snake_case(base_name(&obj.name));
+        let upper = snake.to_uppercase();
+        let ident = self.schema.file_ident.as_ref();
+
+        if let Some(ident) = ident {
+            let _ = writeln!(out, "pub const {}_IDENTIFIER: &str = \"{}\";\n", upper, ident);
+
+            let _ = writeln!(out, "/// Check whether a buffer has the `{}` file identifier.", name);
+            let _ = writeln!(out, "pub fn {}_buffer_has_identifier(buf: &[u8]) -> bool {{", snake);
+            let _ = writeln!(out, "    ::flatbuffers::buffer_has_identifier(buf, {}_IDENTIFIER)",
+                             upper);
+            let _ = writeln!(out, "}}\n");
+        }
+
+        let _ = writeln!(out, "/// Access the root `{}` of a buffer. This does no checking; use",
+                         name);
+        let _ = writeln!(out, "/// `get_root_as_{}_verified` for untrusted buffers.", snake);
+        let _ = writeln!(out, "pub fn get_root_as_{}(buf: &[u8]) -> {}<'_> {{", snake, name);
+        let _ = writeln!(out, "    ::flatbuffers::get_root(buf)");
+        let _ = writeln!(out, "}}\n");
+
+        let _ = writeln!(out, "/// Access the root `{}` of a mutable buffer, \
+                               to change its fields in place.", name);
+        let _ = writeln!(out, "pub fn get_root_as_{}_mut(buf: &mut [u8]) -> {}Mut<'_> {{",
+                         snake, name);
+        let _ = writeln!(out, "    {}Mut::from(::flatbuffers::get_root_mut(buf))", name);
+        let _ = writeln!(out, "}}\n");
+
+        let _ = writeln!(out, "/// Verify a buffer and access its root `{}`.", name);
+        let _ = writeln!(out, "pub fn get_root_as_{}_verified(buf: &[u8]) \
+                               -> Result<{}<'_>, ::flatbuffers::VerifyError> {{", snake, name);
+        if ident.is_some() {
+            let _ = writeln!(out, "    ::flatbuffers::get_root_verified_with_identifier(buf, \
+                                   {}_IDENTIFIER)", upper);
+        } else {
+            let _ = writeln!(out, "    ::flatbuffers::get_root_verified(buf)");
+        }
+        let _ = writeln!(out, "}}\n");
+
+        let _ = writeln!(out, "/// Access the root `{}` of a size-prefixed buffer. This \
+                               does no checking.", name);
+        let _ = writeln!(out, "pub fn get_size_prefixed_root_as_{}(buf: &[u8]) -> {}<'_> {{",
+                         snake, name);
+        let _ = writeln!(out, "    ::flatbuffers::get_size_prefixed_root(buf)");
+        let _ = writeln!(out, "}}\n");
+
+        let _ = writeln!(out, "/// Verify a size-prefixed buffer and access its root `{}`.", name);
+        let _ = writeln!(out, "pub fn get_size_prefixed_root_as_{}_verified(buf: &[u8]) \
+                               -> Result<{}<'_>, ::flatbuffers::VerifyError> {{", snake, name);
+        if ident.is_some() {
+            let verify = "::flatbuffers::get_size_prefixed_root_verified_with_identifier";
+            let _ = writeln!(out, "    {}(buf, {}_IDENTIFIER)", verify, upper);
+        } else {
+            let _ = writeln!(out, "    ::flatbuffers::get_size_prefixed_root_verified(buf)");
+        }
+        let _ = writeln!(out, "}}\n");
+
+        let finishes = [("", "finish"), ("size_prefixed_", "finish_size_prefixed")];
+
+        for &(prefix, method) in finishes.iter() {
+            let _ = writeln!(out, "pub fn finish_{}{}_buffer(\
+                                   fbb: &mut ::flatbuffers::FlatBufferBuilder<'_>, \
+                                   root: ::flatbuffers::Offset<{}<'_>>) {{",
+                             prefix, snake, name);
+            if ident.is_some() {
+                let _ = writeln!(out, "    fbb.{}_with_identifier(root, {}_IDENTIFIER)",
+                                 method, upper);
+            } else {
+                let _ = writeln!(out, "    fbb.{}(root)", method);
+            }
+            let _ = writeln!(out, "}}\n");
+
+            let _ = writeln!(out, "pub fn try_finish_{}{}_buffer(\
+                                   fbb: &mut ::flatbuffers::FlatBufferBuilder<'_>, \
+                                   root: ::flatbuffers::Offset<{}<'_>>)",
+                             prefix, snake, name);
+            let _ = writeln!(out, "        -> Result<(), ::flatbuffers::BuilderError> {{");
+            if ident.is_some() {
+                let _ = writeln!(out, "    fbb.try_{}_with_identifier(root, {}_IDENTIFIER)",
+                                 method, upper);
+            } else {
+                let _ = writeln!(out, "    fbb.try_{}(root)", method);
+            }
+            let _ = writeln!(out, "}}\n");
+        }
+
+        if let Some(ref ext) = self.schema.file_ext {
+            let _ = writeln!(out, "pub const {}_EXTENSION: &str = \"{}\";\n", upper, ext);
+        }
+    }
+}
@@ -0,0 +1,428 @@
+// Copyright 2015 Sam Payson. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Building FlexBuffers.
+
+use std::cmp;
+use std::collections::HashMap;
+
+use super::{Type, width_f, width_i, width_u};
+
+// A value which has been pushed, but not yet written into a vector or map. Inline values are kept
+// here until then, since the width they're written with depends on their siblings.
+#[derive(Clone, Copy, Debug)]
+struct Value {
+    // For inline values, the value's bits. Otherwise, the position in the buffer of what it refers
+    // to.
+    data:  u64,
+    ty:    Type,
+
+    // For inline values, the number of bytes that the value needs. Otherwise, the width of the
+    // elements (or size) of what it refers to.
+    width: usize,
+}
+
+impl Value {
+    fn new(data: u64, ty: Type, width: usize) -> Value {
+        Value { data, ty, width }
+    }
+
+    // The width needed to store this value as element `idx` of a vector whose elements will start
+    // at `buf_len` (before padding).
+    fn elem_width(&self, buf_len: usize, idx: usize) -> usize {
+        if self.ty.is_inline() {
+            return self.width
+        }
+
+        for &w in [1, 2, 4].iter() {
+            let offset_loc = buf_len + padding(buf_len, w) + idx * w;
+            let offset     = (offset_loc as u64) - self.data;
+
+            if offset < 1 << (8 * w) {
+                return w
+            }
+        }
+
+        8
+    }
+
+    // The packed type byte for this value, if it's stored in a slot which is `parent` bytes wide.
+    fn packed_type(&self, parent: usize) -> u8 {
+        let width = if self.ty.is_inline() { cmp::max(self.width, parent) } else { self.width };
+        ((self.ty as u8) << 2) | width.trailing_zeros() as u8
+    }
+}
+
+// The number of bytes needed to pad `len` to a multiple of `align`.
+fn padding(len: usize, align: usize) -> usize {
+    (align - len % align) % align
+}
+
+/// Builds a FlexBuffer.
+///
+/// Scalars, strings and blobs are pushed one at a time. Vectors and maps are built by calling
+/// `start_vector` or `start_map`, pushing their contents, and then calling `end_vector` or
+/// `end_map`. Each value in a map must be preceded by its key. Once the root value is complete,
+/// `finish` writes it out:
+///
+/// ```
+/// use flatbuffers::flexbuffers::Builder;
+///
+/// let mut b = Builder::new();
+///
+/// let map = b.start_map();
+/// b.key("name");
+/// b.push_string("Orc");
+/// b.key("hp");
+/// b.push_int(300);
+/// b.end_map(map);
+///
+/// b.finish();
+///
+/// let root = flatbuffers::flexbuffers::get_root(b.get_buffer()).unwrap();
+/// assert_eq!(root.as_map().unwrap().get("hp").unwrap().as_i64(), Some(300));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Builder {
+    buf:      Vec<u8>,
+    stack:    Vec<Value>,
+    finished: bool,
+
+    // The position of each key which has been written, so that keys which are used in more than
+    // one map are only stored once.
+    keys:     HashMap<String, usize>,
+}
+
+impl Builder {
+    pub fn new() -> Builder {
+        Builder::default()
+    }
+
+    /// Reset the builder, so that it can be used to build a new buffer.
+    pub fn clear(&mut self) {
+        self.buf.clear();
+        self.stack.clear();
+        self.keys.clear();
+        self.finished = false;
+    }
+
+    /// The finished buffer. Panics if `finish` hasn't been called.
+    pub fn get_buffer(&self) -> &[u8] {
+        assert!(self.finished, "the buffer hasn't been finished");
+        &self.buf
+    }
+
+    /// Take the finished buffer, leaving the builder empty. Panics if `finish` hasn't been called.
+    pub fn take_buffer(&mut self) -> Vec<u8> {
+        assert!(self.finished, "the buffer hasn't been finished");
+
+        self.finished = false;
+        self.stack.clear();
+        self.keys.clear();
+        ::std::mem::take(&mut self.buf)
+    }
+
+    fn push(&mut self, v: Value) {
+        assert!(!self.finished, "the buffer has already been finished");
+        self.stack.push(v);
+    }
+
+    pub fn push_null(&mut self) {
+        self.push(Value::new(0, Type::Null, 1));
+    }
+
+    pub fn push_bool(&mut self, b: bool) {
+        self.push(Value::new(b as u64, Type::Bool, 1));
+    }
+
+    pub fn push_int(&mut self, i: i64) {
+        self.push(Value::new(i as u64, Type::Int, width_i(i)));
+    }
+
+    pub fn push_uint(&mut self, u: u64) {
+        self.push(Value::new(u, Type::UInt, width_u(u)));
+    }
+
+    /// Push a float, which is stored in 4 bytes (or more, if it's in a vector with wider values).
+    pub fn push_float(&mut self, f: f32) {
+        self.push(Value::new((f as f64).to_bits(), Type::Float, 4));
+    }
+
+    /// Push a double, which is stored in 4 bytes if that doesn't lose any precision.
+    pub fn push_double(&mut self, f: f64) {
+        self.push(Value::new(f.to_bits(), Type::Float, width_f(f)));
+    }
+
+    /// Push an integer which is stored out of line, so that it doesn't widen its siblings.
+    pub fn push_indirect_int(&mut self, i: i64) {
+        let width = width_i(i);
+        self.push_indirect(i as u64, Type::IndirectInt, width);
+    }
+
+    /// Like `push_indirect_int`, for unsigned integers.
+    pub fn push_indirect_uint(&mut self, u: u64) {
+        self.push_indirect(u, Type::IndirectUInt, width_u(u));
+    }
+
+    /// Like `push_indirect_int`, for doubles.
+    pub fn push_indirect_double(&mut self, f: f64) {
+        let width = width_f(f);
+        let bits  = if width == 4 { (f as f32).to_bits() as u64 } else { f.to_bits() };
+
+        self.push_indirect(bits, Type::IndirectFloat, width);
+    }
+
+    fn push_indirect(&mut self, bits: u64, ty: Type, width: usize) {
+        self.align(width);
+
+        let pos = self.buf.len();
+        self.write_uint(bits, width);
+        self.push(Value::new(pos as u64, ty, width));
+    }
+
+    pub fn push_string(&mut self, s: &str) {
+        self.push_sized(s.as_bytes(), Type::String);
+    }
+
+    pub fn push_blob(&mut self, bytes: &[u8]) {
+        self.push_sized(bytes, Type::Blob);
+    }
+
+    // Write `bytes` with their length in front, and a 0 after them if they're a string.
+    fn push_sized(&mut self, bytes: &[u8], ty: Type) {
+        let width = width_u(bytes.len() as u64);
+
+        self.align(width);
+        self.write_uint(bytes.len() as u64, width);
+
+        let pos = self.buf.len();
+        self.buf.extend_from_slice(bytes);
+
+        if ty == Type::String {
+            self.buf.push(0);
+        }
+
+        self.push(Value::new(pos as u64, ty, width));
+    }
+
+    /// Push the key of the next value in a map. Keys can't contain 0 bytes.
+    pub fn key(&mut self, key: &str) {
+        assert!(!key.as_bytes().contains(&0), "map keys can't contain 0 bytes");
+
+        let pos = match self.keys.get(key) {
+            Some(&pos) => pos,
+            None       => {
+                let pos = self.buf.len();
+                self.buf.extend_from_slice(key.as_bytes());
+                self.buf.push(0);
+
+                self.keys.insert(key.to_string(), pos);
+                pos
+            }
+        };
+
+        self.push(Value::new(pos as u64, Type::Key, 1));
+    }
+
+    /// Start a vector. The values pushed until `end_vector` is called will be its elements.
+    pub fn start_vector(&mut self) -> usize {
+        self.stack.len()
+    }
+
+    /// End the vector which `start_vector` returned `start` for.
+    ///
+    /// If `typed` is true, the elements must all be of the same type, which is only stored once.
+    /// Only ints, uints, floats, bools and keys can be in typed vectors. If `fixed` is also true,
+    /// the vector must have 2, 3 or 4 numeric elements, and its length won't be stored.
+    pub fn end_vector(&mut self, start: usize, typed: bool, fixed: bool) {
+        assert!(typed || !fixed, "a fixed length vector must be typed");
+
+        let len = self.stack.len() - start;
+        let vec = self.create_vector(start, len, 1, typed, fixed, None);
+
+        self.stack.truncate(start);
+        self.push(vec);
+    }
+
+    /// Start a map. Each value pushed until `end_map` is called must be preceded by a call to
+    /// `key`.
+    pub fn start_map(&mut self) -> usize {
+        self.stack.len()
+    }
+
+    /// End the map which `start_map` returned `start` for. Its keys are sorted, so that values can
+    /// be looked up by binary search. Panics if a key is given more than once.
+    pub fn end_map(&mut self, start: usize) {
+        let len = self.stack.len() - start;
+
+        assert!(len.is_multiple_of(2), "every value in a map needs a key");
+        assert!(self.stack[start..].iter().step_by(2).all(|k| k.ty == Type::Key),
+                "every value in a map needs a key");
+
+        let mut pairs: Vec<(Value, Value)> = self.stack[start..].chunks(2)
+            .map(|kv| (kv[0], kv[1]))
+            .collect();
+
+        {
+            let buf = &self.buf;
+            pairs.sort_by(|a, b| key_bytes(buf, a.0).cmp(key_bytes(buf, b.0)));
+
+            let dup = pairs.windows(2).find(|w| key_bytes(buf, w[0].0) == key_bytes(buf, w[1].0));
+
+            if let Some(w) = dup {
+                panic!("the key `{}` is in a map more than once",
+                       String::from_utf8_lossy(key_bytes(buf, w[0].0)));
+            }
+        }
+
+        for (i, (k, v)) in pairs.into_iter().enumerate() {
+            self.stack[start + 2 * i]     = k;
+            self.stack[start + 2 * i + 1] = v;
+        }
+
+        let len  = len / 2;
+        let keys = self.create_vector(start, len, 2, true, false, None);
+        let map  = self.create_vector(start + 1, len, 2, false, false, Some(keys));
+
+        self.stack.truncate(start);
+        self.push(map);
+    }
+
+    /// Write out the root value, which must be the only one that's been pushed (apart from
+    /// the contents of vectors and maps). Once the buffer is finished, this does nothing.
+    pub fn finish(&mut self) {
+        if self.finished {
+            return
+        }
+
+        assert!(self.stack.len() == 1, "a buffer must have exactly one root value");
+
+        let root  = self.stack[0];
+        let width = root.elem_width(self.buf.len(), 0);
+
+        self.align(width);
+        self.write_value(root, width);
+        self.buf.push(root.packed_type(1));
+        self.buf.push(width as u8);
+
+        self.finished = true;
+    }
+
+    // Pad the buffer to a multiple of `width`.
+    fn align(&mut self, width: usize) {
+        let len = self.buf.len() + padding(self.buf.len(), width);
+        self.buf.resize(len, 0);
+    }
+
+    fn write_uint(&mut self, u: u64, width: usize) {
+        self.buf.extend_from_slice(&u.to_le_bytes()[..width]);
+    }
+
+    // Write `v` into a slot which is `width` bytes wide.
+    fn write_value(&mut self, v: Value, width: usize) {
+        match v.ty {
+            Type::Float if width == 4 => {
+                let f = f64::from_bits(v.data) as f32;
+                self.write_uint(f.to_bits() as u64, 4);
+            }
+            t if t.is_inline()        => self.write_uint(v.data, width),
+            _                         => {
+                let offset = self.buf.len() as u64 - v.data;
+                self.write_uint(offset, width);
+            }
+        }
+    }
+
+    // Write the `len` values at `start`, `start + step`, ..., of the stack as a vector, and return
+    // it as a value. If `keys` is given, the vector is written as the values of a map.
+    fn create_vector(&mut self, start: usize, len: usize, step: usize, typed: bool, fixed: bool,
+                     keys: Option<Value>) -> Value {
+
+        let mut width  = width_u(len as u64);
+        let mut prefix = 1;
+
+        if let Some(k) = keys {
+            width   = cmp::max(width, k.elem_width(self.buf.len(), 0));
+            prefix += 2;
+        }
+
+        let elems: Vec<Value> = self.stack.iter()
+            .skip(start)
+            .step_by(step)
+            .take(len)
+            .cloned()
+            .collect();
+
+        let mut elem_type = Type::Key;
+
+        for (i, e) in elems.iter().enumerate() {
+            width = cmp::max(width, e.elem_width(self.buf.len(), i + prefix));
+
+            if typed {
+                if i == 0 {
+                    elem_type = e.ty;
+                } else {
+                    assert!(e.ty == elem_type, "the elements of a typed vector must all have the \
+                                                same type");
+                }
+            }
+        }
+
+        let ty = if keys.is_some() {
+            Type::Map
+        } else if typed {
+            let fixed_len = if fixed { len } else { 0 };
+
+            match elem_type.to_typed_vector(fixed_len) {
+                Some(t) if t != Type::VectorString => t,
+                _ => panic!("a {}typed vector can't have {} elements of type {:?}",
+                            if fixed { "fixed length " } else { "" }, len, elem_type),
+            }
+        } else {
+            Type::Vector
+        };
+
+        self.align(width);
+
+        if let Some(k) = keys {
+            let offset = self.buf.len() as u64 - k.data;
+            self.write_uint(offset, width);
+            self.write_uint(k.width as u64, width);
+        }
+
+        if !fixed {
+            self.write_uint(len as u64, width);
+        }
+
+        let pos = self.buf.len();
+
+        for e in elems.iter() {
+            self.write_value(*e, width);
+        }
+
+        if !typed {
+            for e in elems.iter() {
+                self.buf.push(e.packed_type(width));
+            }
+        }
+
+        Value::new(pos as u64, ty, width)
+    }
+}
+
+// The bytes of the key `k`, without its terminator.
+fn key_bytes(buf: &[u8], k: Value) -> &[u8] {
+    let key = &buf[k.data as usize..];
+    &key[..key.iter().position(|&b| b == 0).unwrap()]
+}
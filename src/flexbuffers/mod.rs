@@ -0,0 +1,588 @@
+// Copyright 2015 Sam Payson. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! FlexBuffers, the schemaless variant of FlatBuffers.
+//!
+//! A FlexBuffer holds a single value, which may be a scalar, a string, a blob, or a vector or map
+//! of other values. Each value records its own type, so buffers can be read without a schema.
+//! Values are stored in as few bytes as they fit in, so small numbers take up one byte, and
+//! vectors whose elements are all of the same type only store that type once.
+//!
+//! A `Builder` writes values front to back, and `get_root` returns a `Reference` to the root
+//! value of a finished buffer, from which vectors and maps can be read in place.
+//!
+//! Unlike the readers for FlatBuffers, these never panic on a malformed buffer: values which can't
+//! be read are returned as `None`. They don't bound how much is read, though. Vectors and maps can
+//! share their elements, so a buffer of a few hundred bytes can hold a tree with more values than
+//! could ever be visited: code which walks every value of an untrusted buffer should give up after
+//! some limit, as `Debug` does.
+
+mod builder;
+mod reader;
+
+pub use self::builder::Builder;
+pub use self::reader::{Map, MapIter, Reference, Vector, VectorIter, get_root};
+
+use std::convert::TryFrom;
+
+use Endian;
+
+/// The type of a value in a FlexBuffer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Type {
+    Null          = 0,
+    Int           = 1,
+    UInt          = 2,
+    Float         = 3,
+
+    /// A map key: a string which is terminated by a 0 byte, rather than prefixed with its length.
+    Key           = 4,
+    String        = 5,
+
+    /// Scalars which are stored out of line, so that a large value doesn't force the vector or
+    /// map which contains it to use a larger width for all of its elements.
+    IndirectInt   = 6,
+    IndirectUInt  = 7,
+    IndirectFloat = 8,
+    Map           = 9,
+    Vector        = 10,
+
+    /// Vectors whose elements all have the same type, which is stored once.
+    VectorInt     = 11,
+    VectorUInt    = 12,
+    VectorFloat   = 13,
+    VectorKey     = 14,
+
+    /// Typed vectors of strings can't be read reliably, so they're never written.
+    VectorString  = 15,
+
+    /// Typed vectors with a fixed length of 2, 3 or 4, whose length isn't stored.
+    VectorInt2    = 16,
+    VectorUInt2   = 17,
+    VectorFloat2  = 18,
+    VectorInt3    = 19,
+    VectorUInt3   = 20,
+    VectorFloat3  = 21,
+    VectorInt4    = 22,
+    VectorUInt4   = 23,
+    VectorFloat4  = 24,
+    Blob          = 25,
+    Bool          = 26,
+    VectorBool    = 36,
+}
+
+impl Type {
+    pub fn from_u8(t: u8) -> Option<Type> {
+        Some(match t {
+            0  => Type::Null,
+            1  => Type::Int,
+            2  => Type::UInt,
+            3  => Type::Float,
+            4  => Type::Key,
+            5  => Type::String,
+            6  => Type::IndirectInt,
+            7  => Type::IndirectUInt,
+            8  => Type::IndirectFloat,
+            9  => Type::Map,
+            10 => Type::Vector,
+            11 => Type::VectorInt,
+            12 => Type::VectorUInt,
+            13 => Type::VectorFloat,
+            14 => Type::VectorKey,
+            15 => Type::VectorString,
+            16 => Type::VectorInt2,
+            17 => Type::VectorUInt2,
+            18 => Type::VectorFloat2,
+            19 => Type::VectorInt3,
+            20 => Type::VectorUInt3,
+            21 => Type::VectorFloat3,
+            22 => Type::VectorInt4,
+            23 => Type::VectorUInt4,
+            24 => Type::VectorFloat4,
+            25 => Type::Blob,
+            26 => Type::Bool,
+            36 => Type::VectorBool,
+            _  => return None,
+        })
+    }
+
+    /// Whether values of this type are stored in place, rather than at an offset.
+    pub fn is_inline(self) -> bool {
+        matches!(self, Type::Null | Type::Int | Type::UInt | Type::Float | Type::Bool)
+    }
+
+    /// Whether values of this type can be the elements of a typed vector.
+    pub fn is_typed_vector_element(self) -> bool {
+        matches!(self, Type::Int | Type::UInt | Type::Float | Type::Key | Type::String | Type::Bool)
+    }
+
+    /// The type of a typed vector whose elements are of this type, and which has the fixed length
+    /// `len` (or a length which is stored, if `len` is 0).
+    fn to_typed_vector(self, len: usize) -> Option<Type> {
+        let base = match len {
+            0 => Type::VectorInt as u8,
+            2 => Type::VectorInt2 as u8,
+            3 => Type::VectorInt3 as u8,
+            4 => Type::VectorInt4 as u8,
+            _ => return None,
+        };
+
+        let numeric = matches!(self, Type::Int | Type::UInt | Type::Float);
+
+        if !numeric && (len > 0 || !self.is_typed_vector_element()) {
+            return None
+        }
+
+        Type::from_u8(self as u8 - Type::Int as u8 + base)
+    }
+
+    /// For typed vectors, the type of the elements and the fixed length (or 0 if the length is
+    /// stored).
+    fn typed_vector_element(self) -> Option<(Type, usize)> {
+        let t = self as u8;
+
+        if self == Type::VectorBool {
+            Some((Type::Bool, 0))
+        } else if t >= Type::VectorInt as u8 && t <= Type::VectorString as u8 {
+            Some((Type::from_u8(t - Type::VectorInt as u8 + Type::Int as u8)?, 0))
+        } else if t >= Type::VectorInt2 as u8 && t <= Type::VectorFloat4 as u8 {
+            let fixed = t - Type::VectorInt2 as u8;
+            Some((Type::from_u8(fixed % 3 + Type::Int as u8)?, fixed as usize / 3 + 2))
+        } else {
+            None
+        }
+    }
+}
+
+// The number of bytes needed to store `u`.
+fn width_u(u: u64) -> usize {
+    if u <= u8::MAX as u64 {
+        1
+    } else if u <= u16::MAX as u64 {
+        2
+    } else if u <= u32::MAX as u64 {
+        4
+    } else {
+        8
+    }
+}
+
+// The number of bytes needed to store `i`.
+fn width_i(i: i64) -> usize {
+    let u = (i as u64) << 1;
+    width_u(if i >= 0 { u } else { !u })
+}
+
+// The number of bytes needed to store `f` without losing precision.
+fn width_f(f: f64) -> usize {
+    if f as f32 as f64 == f { 4 } else { 8 }
+}
+
+// Read an unsigned integer which is `width` bytes wide.
+fn read_u64(buf: &[u8], pos: usize, width: usize) -> Option<u64> {
+    let b = buf.get(pos..pos.checked_add(width)?)?;
+
+    match width {
+        1 => Some(u8::read_le(b) as u64),
+        2 => Some(u16::read_le(b) as u64),
+        4 => Some(u32::read_le(b) as u64),
+        8 => Some(u64::read_le(b)),
+        _ => None,
+    }
+}
+
+// Read a signed integer which is `width` bytes wide.
+fn read_i64(buf: &[u8], pos: usize, width: usize) -> Option<i64> {
+    let b = buf.get(pos..pos.checked_add(width)?)?;
+
+    match width {
+        1 => Some(i8::read_le(b) as i64),
+        2 => Some(i16::read_le(b) as i64),
+        4 => Some(i32::read_le(b) as i64),
+        8 => Some(i64::read_le(b)),
+        _ => None,
+    }
+}
+
+// Read a float which is `width` bytes wide.
+fn read_f64(buf: &[u8], pos: usize, width: usize) -> Option<f64> {
+    let b = buf.get(pos..pos.checked_add(width)?)?;
+
+    match width {
+        4 => Some(f32::read_le(b) as f64),
+        8 => Some(f64::read_le(b)),
+        _ => None,
+    }
+}
+
+// Read a size or offset which is `width` bytes wide.
+fn read_usize(buf: &[u8], pos: usize, width: usize) -> Option<usize> {
+    usize::try_from(read_u64(buf, pos, width)?).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use test_support::{for_each_corruption, for_each_truncation};
+
+    // Build a buffer whose root is whatever `push` pushes.
+    fn build<F: FnOnce(&mut Builder)>(push: F) -> Vec<u8> {
+        let mut b = Builder::new();
+        push(&mut b);
+        b.finish();
+        b.take_buffer()
+    }
+
+    // Read everything that `r` refers to, in every way that it can be read, and return the number
+    // of values found. Values can be shared, so this gives up after reading 100,000 of them.
+    fn walk(r: Reference) -> usize {
+        let _ = format!("{:?}", r);
+        visit(r, &mut 100_000)
+    }
+
+    fn visit(r: Reference, left: &mut usize) -> usize {
+        if *left == 0 {
+            return 0
+        }
+
+        *left -= 1;
+
+        let _ = (r.is_null(), r.as_bool(), r.as_i64(), r.as_u64(), r.as_f64(), r.as_str(),
+                 r.as_blob());
+
+        let mut n = 1;
+
+        if let Some(m) = r.as_map() {
+            // Keys in a corrupt map may be out of order, so they can't all be expected to be found.
+            for (k, v) in m.iter() {
+                let _ = m.get(k);
+                n += visit(v, left);
+            }
+
+            n += m.keys().iter().map(|k| visit(k, left)).sum::<usize>();
+        }
+
+        if let Some(v) = r.as_vector() {
+            n += v.iter().map(|e| visit(e, left)).sum::<usize>();
+        }
+
+        n
+    }
+
+    // Everything this module can write, in one buffer.
+    fn everything() -> Vec<u8> {
+        build(|b| {
+            let map = b.start_map();
+
+            b.key("ints");
+            let v = b.start_vector();
+            for &i in [0, -1, 127, -128, 128, 40000, i64::MIN].iter() {
+                b.push_int(i);
+            }
+            b.end_vector(v, false, false);
+
+            b.key("typed");
+            let v = b.start_vector();
+            for &u in [1, 2, 300].iter() {
+                b.push_uint(u);
+            }
+            b.end_vector(v, true, false);
+
+            b.key("point");
+            let v = b.start_vector();
+            for &f in [0.5, -1.0, 2.25].iter() {
+                b.push_float(f);
+            }
+            b.end_vector(v, true, true);
+
+            b.key("flags");
+            let v = b.start_vector();
+            b.push_bool(true);
+            b.push_bool(false);
+            b.end_vector(v, true, false);
+
+            b.key("mixed");
+            let v = b.start_vector();
+            b.push_null();
+            b.push_string("str");
+            b.push_blob(&[1, 2, 3]);
+            b.push_indirect_int(-70000);
+            b.push_indirect_uint(u64::MAX);
+            b.push_indirect_double(0.1);
+            b.push_double(1e100);
+            b.end_vector(v, false, false);
+
+            b.key("children");
+            let v = b.start_vector();
+            for name in ["a", "b"].iter() {
+                let m = b.start_map();
+                b.key("name");
+                b.push_string(name);
+                b.key("ints");
+                b.push_int(1);
+                b.end_map(m);
+            }
+            b.end_vector(v, false, false);
+
+            b.end_map(map);
+        })
+    }
+
+    #[test]
+    fn scalars_use_the_smallest_width() {
+        assert_eq!(build(|b| b.push_int(1)), [1, (Type::Int as u8) << 2, 1]);
+        assert_eq!(build(|b| b.push_int(-300)), [0xd4, 0xfe, (Type::Int as u8) << 2 | 1, 2]);
+        assert_eq!(build(|b| b.push_uint(1 << 20)), [0, 0, 0x10, 0, (Type::UInt as u8) << 2 | 2, 4]);
+        assert_eq!(build(|b| b.push_bool(true)), [1, (Type::Bool as u8) << 2, 1]);
+        assert_eq!(build(|b| b.push_null()), [0, 0, 1]);
+
+        let widths = [(0, 1), (127, 1), (-128, 1), (128, 2), (-32769, 4), (1 << 40, 8),
+                      (i64::MIN, 8), (i64::MAX, 8)];
+
+        for &(i, width) in widths.iter() {
+            let buf = build(|b| b.push_int(i));
+            assert_eq!(*buf.last().unwrap() as usize, width, "{}", i);
+            assert_eq!(get_root(&buf).unwrap().as_i64(), Some(i));
+        }
+
+        for &u in [0, 255, 256, 65536, u64::MAX].iter() {
+            let buf = build(|b| b.push_uint(u));
+            assert_eq!(*buf.last().unwrap() as usize, width_u(u));
+            assert_eq!(get_root(&buf).unwrap().as_u64(), Some(u));
+        }
+
+        for &(f, width) in [(0.5, 4), (0.1, 8), (f64::INFINITY, 4)].iter() {
+            let buf = build(|b| b.push_double(f));
+            assert_eq!(*buf.last().unwrap() as usize, width);
+            assert_eq!(get_root(&buf).unwrap().as_f64(), Some(f));
+        }
+
+        let buf  = build(|b| b.push_int(-1));
+        let root = get_root(&buf).unwrap();
+        assert_eq!((root.as_i64(), root.as_u64(), root.as_f64()), (Some(-1), None, Some(-1.0)));
+        assert_eq!(root.as_str(), None);
+        assert_eq!(root.as_vector().map(|v| v.len()), None);
+    }
+
+    #[test]
+    fn vector_elements_share_a_width() {
+        let buf = build(|b| {
+            let v = b.start_vector();
+            b.push_int(1);
+            b.push_int(1000);
+            b.end_vector(v, false, false);
+        });
+
+        let int2 = (Type::Int as u8) << 2 | 1;
+        assert_eq!(buf, [2, 0, 1, 0, 0xe8, 3, int2, int2, 6, (Type::Vector as u8) << 2 | 1, 1]);
+
+        let v = get_root(&buf).unwrap().as_vector().unwrap();
+        assert_eq!(v.element_type(), None);
+        assert_eq!(v.iter().map(|r| r.as_i64().unwrap()).collect::<Vec<_>>(), [1, 1000]);
+        assert!(v.get(2).is_none());
+    }
+
+    #[test]
+    fn indirect_scalars_keep_vectors_narrow() {
+        let buf = build(|b| {
+            let v = b.start_vector();
+            b.push_int(1);
+            b.push_indirect_uint(u64::MAX);
+            b.push_indirect_int(i64::MIN);
+            b.push_indirect_double(0.1);
+            b.end_vector(v, false, false);
+        });
+
+        let root = get_root(&buf).unwrap();
+        assert_eq!(root.flex_type(), Type::Vector);
+
+        let v = root.as_vector().unwrap();
+        let types: Vec<_> = v.iter().map(|r| r.flex_type()).collect();
+        assert_eq!(types, [Type::Int, Type::IndirectUInt, Type::IndirectInt, Type::IndirectFloat]);
+        assert_eq!(v.get(1).unwrap().as_u64(), Some(u64::MAX));
+        assert_eq!(v.get(2).unwrap().as_i64(), Some(i64::MIN));
+        assert_eq!(v.get(3).unwrap().as_f64(), Some(0.1));
+
+        // The elements are offsets to the scalars, which fit in a byte.
+        let (packed, _) = buf.split_at(buf.len() - 2);
+        assert_eq!(packed[packed.len() - 1] & 3, 0);
+    }
+
+    #[test]
+    fn typed_vectors_store_their_type_once() {
+        let buf  = everything();
+        let root = get_root(&buf).unwrap().as_map().unwrap();
+
+        let typed = root.get("typed").unwrap();
+        assert_eq!(typed.flex_type(), Type::VectorUInt);
+        let typed = typed.as_vector().unwrap();
+        assert_eq!(typed.element_type(), Some(Type::UInt));
+        assert_eq!(typed.iter().map(|r| r.as_u64().unwrap()).collect::<Vec<_>>(), [1, 2, 300]);
+
+        let point = root.get("point").unwrap();
+        assert_eq!(point.flex_type(), Type::VectorFloat3);
+        let xyz: Vec<_> = point.as_vector().unwrap().iter().map(|r| r.as_f64().unwrap()).collect();
+        assert_eq!(xyz, [0.5, -1.0, 2.25]);
+
+        let flags = root.get("flags").unwrap();
+        assert_eq!(flags.flex_type(), Type::VectorBool);
+        let flags: Vec<_> = flags.as_vector().unwrap().iter().map(|r| r.as_bool().unwrap()).collect();
+        assert_eq!(flags, [true, false]);
+
+        let buf  = build(|b| {
+            let v = b.start_vector();
+            b.key("x");
+            b.key("y");
+            b.end_vector(v, true, false);
+        });
+        let keys = get_root(&buf).unwrap();
+        assert_eq!(keys.flex_type(), Type::VectorKey);
+        let keys: Vec<_> = keys.as_vector().unwrap().iter().map(|r| r.as_str().unwrap()).collect();
+        assert_eq!(keys, ["x", "y"]);
+    }
+
+    #[test]
+    fn strings_and_blobs_round_trip() {
+        let long = "x".repeat(300);
+        let buf  = build(|b| {
+            let v = b.start_vector();
+            b.push_string("");
+            b.push_string("hello");
+            b.push_string(&long);
+            b.push_blob(&[]);
+            b.push_blob(&[0, 1, 255]);
+            b.end_vector(v, false, false);
+        });
+
+        let v = get_root(&buf).unwrap().as_vector().unwrap();
+        assert_eq!(v.get(0).unwrap().as_str(), Some(""));
+        assert_eq!(v.get(1).unwrap().as_str(), Some("hello"));
+        assert_eq!(v.get(2).unwrap().as_str(), Some(&long[..]));
+        assert_eq!(v.get(3).unwrap().as_blob(), Some(&[][..]));
+        assert_eq!(v.get(4).unwrap().as_blob(), Some(&[0, 1, 255][..]));
+        assert_eq!(v.get(4).unwrap().as_str(), None);
+        assert_eq!(v.get(1).unwrap().as_blob(), None);
+    }
+
+    #[test]
+    fn maps_are_sorted_and_share_keys() {
+        let buf  = everything();
+        let root = get_root(&buf).unwrap().as_map().unwrap();
+
+        let keys: Vec<_> = root.iter().map(|(k, _)| k).collect();
+        assert_eq!(keys, ["children", "flags", "ints", "mixed", "point", "typed"]);
+        assert!(root.get("missing").is_none());
+
+        let ints: Vec<_> = root.get("ints").unwrap().as_vector().unwrap().iter()
+            .map(|r| r.as_i64().unwrap())
+            .collect();
+        assert_eq!(ints, [0, -1, 127, -128, 128, 40000, i64::MIN]);
+
+        let children = root.get("children").unwrap().as_vector().unwrap();
+        let names: Vec<_> = children.iter()
+            .map(|c| c.as_map().unwrap().get("name").unwrap().as_str().unwrap())
+            .collect();
+        assert_eq!(names, ["a", "b"]);
+        assert_eq!(children.get(1).unwrap().as_map().unwrap().get("ints").unwrap().as_i64(), Some(1));
+
+        // Each key is only written once, however many maps it's used in.
+        let count = |needle: &[u8]| buf.windows(needle.len()).filter(|w| *w == needle).count();
+        assert_eq!(count(b"name\0"), 1);
+        assert_eq!(count(b"ints\0"), 1);
+
+        let mixed = root.get("mixed").unwrap().as_vector().unwrap();
+        assert!(mixed.get(0).unwrap().is_null());
+        assert_eq!(mixed.get(1).unwrap().as_str(), Some("str"));
+        assert_eq!(mixed.get(2).unwrap().as_blob(), Some(&[1, 2, 3][..]));
+        assert_eq!(mixed.get(3).unwrap().as_i64(), Some(-70000));
+        assert_eq!(mixed.get(4).unwrap().as_u64(), Some(u64::MAX));
+        assert_eq!(mixed.get(5).unwrap().as_f64(), Some(0.1));
+        assert_eq!(mixed.get(6).unwrap().as_f64(), Some(1e100));
+
+        assert!(walk(get_root(&buf).unwrap()) > 40);
+    }
+
+    #[test]
+    fn malformed_buffers_are_none() {
+        assert!(get_root(&[]).is_none());
+        assert!(get_root(&[1]).is_none());
+        assert!(get_root(&[1, 4, 3]).is_none());
+        assert!(get_root(&[1, 4, 2]).is_none());
+
+        // A vector which claims to be longer than the buffer.
+        let mut buf = build(|b| {
+            let v = b.start_vector();
+            b.push_int(1);
+            b.end_vector(v, false, false);
+        });
+        buf[0] = 200;
+        assert!(get_root(&buf).unwrap().as_vector().is_none());
+
+        // An offset of 0 would make a vector its own element.
+        let vector = (Type::Vector as u8) << 2;
+        let buf    = [1, 0, vector, 2, vector, 1];
+        assert!(get_root(&buf).unwrap().as_vector().unwrap().get(0).unwrap().as_vector().is_none());
+
+        let buf = everything();
+
+        for_each_truncation(&buf, |b| {
+            if let Some(root) = get_root(b) {
+                walk(root);
+            }
+        });
+
+        for_each_corruption(&buf, |b| {
+            if let Some(root) = get_root(b) {
+                walk(root);
+            }
+        });
+    }
+
+    #[test]
+    fn shared_values_are_printed_in_bounded_time() {
+        // Each vector holds the one before it twice, so the root is a tree with 2^41 - 1 values.
+        let vector   = (Type::Vector as u8) << 2;
+        let mut buf  = vec![0];
+        let mut prev = 1;
+
+        for _ in 0..40 {
+            let elems = buf.len() + 1;
+            buf.extend_from_slice(&[2, (elems - prev) as u8, (elems + 1 - prev) as u8, vector,
+                                    vector]);
+            prev = elems;
+        }
+
+        let root = buf.len();
+        buf.extend_from_slice(&[(root - prev) as u8, vector, 1]);
+
+        let root    = get_root(&buf).unwrap();
+        let printed = format!("{:?}", root);
+        assert!(printed.starts_with("[[[[[[[[[[[[[[[[[[[[[[[[[[[[[[[[[[[[[[[[[]"));
+        assert!(printed.ends_with(", ..], ..]") && printed.len() < 1 << 20);
+        assert_eq!(walk(root), 100_000);
+    }
+
+    #[test]
+    fn finishing_twice_does_nothing() {
+        let mut b = Builder::new();
+        b.push_int(7);
+        b.finish();
+
+        let once = b.get_buffer().to_vec();
+        b.finish();
+        assert_eq!(b.get_buffer(), &once[..]);
+        assert_eq!(get_root(&once).unwrap().as_i64(), Some(7));
+    }
+}
@@ -0,0 +1,418 @@
+// Copyright 2015 Sam Payson. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Reading FlexBuffers in place.
+
+use std::cell::Cell;
+use std::cmp::Ordering;
+use std::fmt;
+use std::str;
+
+use super::{Type, read_f64, read_i64, read_u64, read_usize};
+
+/// Return the root value of the FlexBuffer `buf`, or `None` if it's too short to be one.
+pub fn get_root<'buf>(buf: &'buf [u8]) -> Option<Reference<'buf>> {
+    let len   = buf.len();
+    let width = *buf.last()? as usize;
+    let pos   = len.checked_sub(2 + width)?;
+
+    if !is_width(width) {
+        return None
+    }
+
+    Some(Reference::new(buf, pos, width, buf[len - 2]))
+}
+
+fn is_width(w: usize) -> bool {
+    w == 1 || w == 2 || w == 4 || w == 8
+}
+
+/// A value in a FlexBuffer. Accessors return `None` if the value isn't of the right type, or
+/// can't be read.
+#[derive(Clone, Copy)]
+pub struct Reference<'buf> {
+    buf:          &'buf [u8],
+    pos:          usize,
+
+    // The width of the slot that the value (or offset to it) is stored in.
+    parent_width: usize,
+
+    // For values which are stored at an offset, the width of their elements (or size).
+    width:        usize,
+    ty:           Type,
+}
+
+impl<'buf> Reference<'buf> {
+    // A reference to the value at `pos` which has the packed type `packed`. Unknown types are read
+    // as null.
+    fn new(buf: &'buf [u8], pos: usize, parent_width: usize, packed: u8) -> Reference<'buf> {
+        Reference {
+            buf,
+            pos,
+            parent_width,
+            width: 1 << (packed & 3),
+            ty:    Type::from_u8(packed >> 2).unwrap_or(Type::Null),
+        }
+    }
+
+    pub fn flex_type(&self) -> Type { self.ty }
+
+    pub fn is_null(&self) -> bool { self.ty == Type::Null }
+
+    // The position of the value that this refers to, for values which are stored at an offset.
+    // Offsets always point backwards, so a buffer can't make a reader go round in circles.
+    fn indirect(&self) -> Option<usize> {
+        match read_usize(self.buf, self.pos, self.parent_width)? {
+            0   => None,
+            off => self.pos.checked_sub(off),
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self.ty {
+            Type::Bool => read_u64(self.buf, self.pos, self.parent_width).map(|b| b != 0),
+            _          => None,
+        }
+    }
+
+    /// The value as an `i64`, if it's an integer which fits in one.
+    pub fn as_i64(&self) -> Option<i64> {
+        match self.ty {
+            Type::Int                       => read_i64(self.buf, self.pos, self.parent_width),
+            Type::IndirectInt               => read_i64(self.buf, self.indirect()?, self.width),
+            Type::UInt | Type::IndirectUInt => {
+                self.as_u64().filter(|&u| u <= i64::MAX as u64).map(|u| u as i64)
+            }
+            _                               => None,
+        }
+    }
+
+    /// The value as a `u64`, if it's an integer which fits in one.
+    pub fn as_u64(&self) -> Option<u64> {
+        match self.ty {
+            Type::UInt                    => read_u64(self.buf, self.pos, self.parent_width),
+            Type::IndirectUInt            => read_u64(self.buf, self.indirect()?, self.width),
+            Type::Int | Type::IndirectInt => self.as_i64().filter(|&i| i >= 0).map(|i| i as u64),
+            _                             => None,
+        }
+    }
+
+    /// The value as an `f64`, if it's a number.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self.ty {
+            Type::Float                     => read_f64(self.buf, self.pos, self.parent_width),
+            Type::IndirectFloat             => read_f64(self.buf, self.indirect()?, self.width),
+            Type::Int | Type::IndirectInt   => self.as_i64().map(|i| i as f64),
+            Type::UInt | Type::IndirectUInt => self.as_u64().map(|u| u as f64),
+            _                               => None,
+        }
+    }
+
+    /// The value as a string, if it's a string or a key which is valid UTF-8.
+    pub fn as_str(&self) -> Option<&'buf str> {
+        match self.ty {
+            Type::String => str::from_utf8(self.sized()?).ok(),
+            Type::Key    => {
+                let key = self.buf.get(self.indirect()?..)?;
+                str::from_utf8(&key[..key.iter().position(|&b| b == 0)?]).ok()
+            }
+            _            => None,
+        }
+    }
+
+    /// The bytes of a blob.
+    pub fn as_blob(&self) -> Option<&'buf [u8]> {
+        match self.ty {
+            Type::Blob => self.sized(),
+            _          => None,
+        }
+    }
+
+    // The contents of a string or blob, which are preceded by their length.
+    fn sized(&self) -> Option<&'buf [u8]> {
+        let pos = self.indirect()?;
+        let len = read_usize(self.buf, pos.checked_sub(self.width)?, self.width)?;
+
+        self.buf.get(pos..pos.checked_add(len)?)
+    }
+
+    /// The value as a vector, if it's any kind of vector. The values of a map can also be read as
+    /// a vector, in the order of their keys.
+    pub fn as_vector(&self) -> Option<Vector<'buf>> {
+        let pos = self.indirect()?;
+
+        match self.ty {
+            Type::Vector | Type::Map => Vector::new(self.buf, pos, self.width, None, None),
+            t                        => {
+                let (elem, len) = t.typed_vector_element()?;
+                Vector::new(self.buf, pos, self.width, Some(elem), Some(len).filter(|&l| l > 0))
+            }
+        }
+    }
+
+    pub fn as_map(&self) -> Option<Map<'buf>> {
+        if self.ty != Type::Map {
+            return None
+        }
+
+        let values = self.as_vector()?;
+        let w      = self.width;
+
+        // The offset to the keys, and their width, come before the length of the values.
+        let keys_field = values.pos.checked_sub(3 * w)?;
+        let keys_pos   = keys_field.checked_sub(read_usize(self.buf, keys_field, w)?)?;
+        let keys_width = read_usize(self.buf, keys_field + w, w)?;
+
+        if !is_width(keys_width) {
+            return None
+        }
+
+        let keys = Vector::new(self.buf, keys_pos, keys_width, Some(Type::Key), None)?;
+
+        if keys.len() != values.len() {
+            return None
+        }
+
+        Some(Map { keys, values })
+    }
+}
+
+// The most values that `Debug` prints. Vectors and maps can share their elements, so a small
+// buffer can hold a tree with more values than could ever be printed.
+const DEBUG_LIMIT: usize = 1 << 16;
+
+impl<'buf> fmt::Debug for Reference<'buf> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        Limited { r: *self, left: &Cell::new(DEBUG_LIMIT) }.fmt(f)
+    }
+}
+
+// A value which is printed by `Debug` while there are `left` values still to print, and as `..`
+// once they've run out.
+struct Limited<'a, 'buf> {
+    r:    Reference<'buf>,
+    left: &'a Cell<usize>,
+}
+
+impl<'a, 'buf> fmt::Debug for Limited<'a, 'buf> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (r, left) = (self.r, self.left);
+
+        match left.get() {
+            0 => return f.write_str(".."),
+            n => left.set(n - 1),
+        }
+
+        match r.ty {
+            Type::Null                        => write!(f, "null"),
+            Type::Bool                        => write!(f, "{:?}", r.as_bool()),
+            Type::Int | Type::IndirectInt     => write!(f, "{:?}", r.as_i64()),
+            Type::UInt | Type::IndirectUInt   => write!(f, "{:?}", r.as_u64()),
+            Type::Float | Type::IndirectFloat => write!(f, "{:?}", r.as_f64()),
+            Type::String | Type::Key          => write!(f, "{:?}", r.as_str()),
+            Type::Blob                        => write!(f, "{:?}", r.as_blob()),
+            Type::Map                         => match r.as_map() {
+                Some(m) => {
+                    f.debug_map().entries(m.iter().map(|(k, r)| (k, Limited { r, left }))).finish()
+                }
+                None    => write!(f, "None"),
+            },
+            _                                 => match r.as_vector() {
+                Some(v) => f.debug_list().entries(v.iter().map(|r| Limited { r, left })).finish(),
+                None    => write!(f, "None"),
+            },
+        }
+    }
+}
+
+/// A vector in a FlexBuffer.
+#[derive(Clone, Copy)]
+pub struct Vector<'buf> {
+    buf:   &'buf [u8],
+    pos:   usize,
+    width: usize,
+    len:   usize,
+
+    // For typed vectors, the type of the elements. Otherwise, each element's type is stored in a
+    // byte after the elements.
+    elem:  Option<Type>,
+}
+
+impl<'buf> Vector<'buf> {
+    // The vector whose elements start at `pos`. The length is read from before the elements,
+    // unless it's fixed. Returns `None` if the vector doesn't fit in `buf`.
+    fn new(buf: &'buf [u8], pos: usize, width: usize, elem: Option<Type>, fixed: Option<usize>)
+        -> Option<Vector<'buf>> {
+
+        let len = match fixed {
+            Some(len) => len,
+            None      => read_usize(buf, pos.checked_sub(width)?, width)?,
+        };
+
+        let types = if elem.is_none() { len } else { 0 };
+        let end   = len.checked_mul(width)?.checked_add(types)?.checked_add(pos)?;
+
+        if end > buf.len() {
+            return None
+        }
+
+        Some(Vector { buf, pos, width, len, elem })
+    }
+
+    pub fn len(&self) -> usize { self.len }
+
+    pub fn is_empty(&self) -> bool { self.len == 0 }
+
+    /// The type of the elements, if this is a typed vector.
+    pub fn element_type(&self) -> Option<Type> { self.elem }
+
+    pub fn get(&self, idx: usize) -> Option<Reference<'buf>> {
+        if idx >= self.len {
+            return None
+        }
+
+        let pos = self.pos + idx * self.width;
+
+        Some(match self.elem {
+            Some(ty) => Reference { buf: self.buf, pos, parent_width: self.width, width: 1, ty },
+            None     => {
+                let packed = self.buf[self.pos + self.len * self.width + idx];
+                Reference::new(self.buf, pos, self.width, packed)
+            }
+        })
+    }
+
+    pub fn iter(&self) -> VectorIter<'buf> {
+        VectorIter {
+            vec: *self,
+            idx: 0,
+        }
+    }
+}
+
+impl<'buf> IntoIterator for Vector<'buf> {
+    type Item     = Reference<'buf>;
+    type IntoIter = VectorIter<'buf>;
+
+    fn into_iter(self) -> VectorIter<'buf> {
+        self.iter()
+    }
+}
+
+/// An iterator over the elements of a `Vector`.
+pub struct VectorIter<'buf> {
+    vec: Vector<'buf>,
+    idx: usize,
+}
+
+impl<'buf> Iterator for VectorIter<'buf> {
+    type Item = Reference<'buf>;
+
+    fn next(&mut self) -> Option<Reference<'buf>> {
+        let r = self.vec.get(self.idx)?;
+        self.idx += 1;
+
+        Some(r)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let left = self.vec.len() - self.idx;
+        (left, Some(left))
+    }
+}
+
+/// A map in a FlexBuffer. Its entries are sorted by key.
+#[derive(Clone, Copy)]
+pub struct Map<'buf> {
+    keys:   Vector<'buf>,
+    values: Vector<'buf>,
+}
+
+impl<'buf> Map<'buf> {
+    pub fn len(&self) -> usize { self.values.len() }
+
+    pub fn is_empty(&self) -> bool { self.values.is_empty() }
+
+    /// The keys, in sorted order.
+    pub fn keys(&self) -> Vector<'buf> { self.keys }
+
+    /// The values, in the order of their keys.
+    pub fn values(&self) -> Vector<'buf> { self.values }
+
+    // The bytes of the key at `idx`, without its terminator.
+    fn key_bytes(&self, idx: usize) -> Option<&'buf [u8]> {
+        let k   = self.keys.get(idx)?;
+        let key = self.keys.buf.get(k.indirect()?..)?;
+
+        Some(&key[..key.iter().position(|&b| b == 0)?])
+    }
+
+    /// Look up the value for `key`, by binary search.
+    pub fn get(&self, key: &str) -> Option<Reference<'buf>> {
+        let (mut lo, mut hi) = (0, self.len());
+
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+
+            match self.key_bytes(mid)?.cmp(key.as_bytes()) {
+                Ordering::Less    => lo = mid + 1,
+                Ordering::Greater => hi = mid,
+                Ordering::Equal   => return self.values.get(mid),
+            }
+        }
+
+        None
+    }
+
+    /// Iterate over the keys and values, in order of key. Entries whose keys aren't valid UTF-8
+    /// are skipped.
+    pub fn iter(&self) -> MapIter<'buf> {
+        MapIter {
+            map: *self,
+            idx: 0,
+        }
+    }
+}
+
+impl<'buf> IntoIterator for Map<'buf> {
+    type Item     = (&'buf str, Reference<'buf>);
+    type IntoIter = MapIter<'buf>;
+
+    fn into_iter(self) -> MapIter<'buf> {
+        self.iter()
+    }
+}
+
+/// An iterator over the entries of a `Map`.
+pub struct MapIter<'buf> {
+    map: Map<'buf>,
+    idx: usize,
+}
+
+impl<'buf> Iterator for MapIter<'buf> {
+    type Item = (&'buf str, Reference<'buf>);
+
+    fn next(&mut self) -> Option<(&'buf str, Reference<'buf>)> {
+        while self.idx < self.map.len() {
+            let idx = self.idx;
+            self.idx += 1;
+
+            if let Some(key) = self.map.keys.get(idx).and_then(|k| k.as_str()) {
+                return Some((key, self.map.values.get(idx)?))
+            }
+        }
+
+        None
+    }
+}
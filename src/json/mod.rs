@@ -0,0 +1,579 @@
+// Copyright 2015 Sam Payson. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Conversion between buffers and the FlatBuffers JSON text format.
+//!
+//! `parse` reads JSON which describes a table of a schema's `root_type`, and builds a buffer from
+//! it. The JSON is matched up with the schema, then written by a `DynamicBuilder`, so fields which
+//! are equal to their defaults are left out unless the `FlatBufferBuilder` is forcing defaults.
+//!
+//! Enum values can be given by name or by number, and the values of a `bit_flags` enum can be
+//! combined by separating their names with spaces. The member of a union is chosen by its
+//! `<field>_type` field, which can appear before or after the union itself, but not without it
+//! (unless the type is `NONE`).
+//!
+//! Going the other way, a `Printer` prints any buffer as JSON, given its schema.
+
+mod parser;
+mod printer;
+
+pub use self::printer::Printer;
+
+use std::convert::TryFrom;
+use std::error;
+use std::fmt;
+
+use reflection::{BuildError, BuildValue, DynamicBuilder};
+use schema::{BaseType, Enum, Field, Object, Schema, Type};
+use FlatBufferBuilder;
+
+use self::parser::{Json, Member, Node};
+
+/// The ways in which turning JSON into a buffer can fail.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// The JSON isn't valid, or doesn't match the schema. `line` and `col` are 1-based.
+    Parse { line: usize, col: usize, message: String },
+
+    /// The JSON matches the schema, but the buffer couldn't be built, e.g. because a required
+    /// field is missing or a number is out of range for its field.
+    Build(BuildError),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::Parse { line, col, ref message } => write!(f, "{}:{}: {}", line, col, message),
+            Error::Build(ref e)                     => e.fmt(f),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            Error::Parse { .. } => None,
+            Error::Build(ref e) => Some(e),
+        }
+    }
+}
+
+impl From<BuildError> for Error {
+    fn from(e: BuildError) -> Error {
+        Error::Build(e)
+    }
+}
+
+/// Parse `json`, which describes a table of the schema's `root_type`, and build it into `fbb`. The
+/// buffer is finished with the schema's `file_identifier`, if it has one.
+pub fn parse(schema: &Schema, json: &str, fbb: &mut FlatBufferBuilder) -> Result<(), Error> {
+    let root = schema.root_table().ok_or(BuildError::NoRootType)?;
+
+    let fields = parser::parse(json)
+        .and_then(|node| object_fields(schema, root, &node))
+        .map_err(|(pos, message)| {
+            let before = &json[..pos];
+            let line   = before.matches('\n').count() + 1;
+            let col    = before.rfind('\n').map_or(before.len(), |n| before.len() - n - 1) + 1;
+
+            Error::Parse { line, col, message }
+        })?;
+
+    DynamicBuilder::new(schema, fbb).finish(&fields)?;
+
+    Ok(())
+}
+
+// The name of a field in errors.
+fn field_path(object: &Object, field: &Field) -> String {
+    format!("{}.{}", object.name, field.name)
+}
+
+fn expected(node: &Node, what: &str, object: &Object, field: &Field) -> (usize, String) {
+    (node.pos, format!("expected {} for `{}`, found {}", what, field_path(object, field),
+                       node.value.kind()))
+}
+
+// Match the members of the JSON object `node` up with the fields of the table or struct `object`.
+fn object_fields(schema: &Schema, object: &Object, node: &Node)
+    -> Result<Vec<(String, BuildValue)>, (usize, String)> {
+
+    let members = match node.value {
+        Json::Object(ref members) => members,
+        _ => return Err((node.pos, format!("expected an object for `{}`, found {}", object.name,
+                                           node.value.kind()))),
+    };
+
+    let mut fields = Vec::with_capacity(members.len());
+
+    for (i, m) in members.iter().enumerate() {
+        let field = object.field_by_name(&m.name).ok_or_else(|| {
+            (m.pos, format!("`{}` doesn't have a field called `{}`", object.name, m.name))
+        })?;
+
+        if members[..i].iter().any(|n| n.name == m.name) {
+            return Err((m.pos, format!("`{}` is given more than once", m.name)))
+        }
+
+        if field.deprecated {
+            return Err((m.pos, format!("`{}` is deprecated", field_path(object, field))))
+        }
+
+        // Union type fields are read along with the union they belong to, so the union has to be
+        // given as well, unless its type is `NONE`.
+        if field.ty.base_type == BaseType::UType || field.ty.element == BaseType::UType {
+            let union = object.fields.iter().find(|u| u.id == field.id + 1);
+            let given = members.iter().any(|n| {
+                union.is_some_and(|u| n.name == u.name) && n.value.value != Json::Null
+            });
+
+            match union {
+                Some(u) if !given && !is_none_type(&m.value) => {
+                    return Err((m.pos, format!("`{}` is given without `{}`", m.name, u.name)))
+                }
+                _ => continue,
+            }
+        }
+
+        if m.value.value == Json::Null {
+            continue
+        }
+
+        let value = match field.ty.base_type {
+            BaseType::Union => {
+                let tag = union_tag(object, field, members, m)?;
+
+                match union_value(schema, object, field, tag, &m.value)? {
+                    Some(value) => value,
+                    None        => continue,
+                }
+            }
+            BaseType::Vector if field.ty.element == BaseType::Union => {
+                union_vector(schema, object, field, members, m)?
+            }
+            _ => value(schema, object, field, field.ty, &m.value)?,
+        };
+
+        fields.push((m.name.clone(), value));
+    }
+
+    Ok(fields)
+}
+
+// Convert `node` to a value of type `ty`, for the field `field` of `object` (or an element of it).
+fn value(schema: &Schema, object: &Object, field: &Field, ty: Type, node: &Node)
+    -> Result<BuildValue, (usize, String)> {
+
+    match (ty.base_type, &node.value) {
+        (b, _) if b.is_scalar() => scalar(schema, object, field, ty, node),
+        (BaseType::String, Json::Str(s)) => Ok(BuildValue::Str(s.clone())),
+        (BaseType::String, _) => Err(expected(node, "a string", object, field)),
+        (BaseType::Vector, Json::Array(elems)) => {
+            let elem = ty.element_type();
+            let mut values = Vec::with_capacity(elems.len());
+
+            for e in elems.iter() {
+                values.push(value(schema, object, field, elem, e)?);
+            }
+
+            Ok(BuildValue::Vector(values))
+        }
+        (BaseType::Vector, _) => Err(expected(node, "an array", object, field)),
+        (BaseType::Obj, _) => {
+            Ok(BuildValue::Object(object_fields(schema, &schema.objects[ty.index.unwrap()], node)?))
+        }
+        _ => Err((node.pos, format!("`{}` can't be given in JSON", field_path(object, field)))),
+    }
+}
+
+// Convert `node` to the scalar type `ty`. Numbers can also be given as strings, and enums by the
+// names of their values.
+fn scalar(schema: &Schema, object: &Object, field: &Field, ty: Type, node: &Node)
+    -> Result<BuildValue, (usize, String)> {
+
+    let base = ty.base_type;
+    let what = base.schema_name();
+
+    let text = match node.value {
+        Json::Bool(b)                         => return Ok(BuildValue::Bool(b)),
+        Json::Number(ref s)                   => s,
+        Json::Ident(ref s) | Json::Str(ref s) => s,
+        _ => return Err(expected(node, &format!("a value of type {}", what), object, field)),
+    };
+
+    if let Some(v) = number(base, text) {
+        return Ok(v)
+    }
+
+    match ty.index {
+        Some(i) if !matches!(node.value, Json::Number(_)) => {
+            let en = &schema.enums[i];
+
+            enum_value(en, base, text).ok_or_else(|| {
+                (node.pos, format!("`{}` isn't a value of `{}`", text, en.name))
+            })
+        }
+        _ => Err((node.pos, format!("`{}` isn't a valid {} for `{}`", text, what,
+                                    field_path(object, field)))),
+    }
+}
+
+// Parse `text` as a number of type `base`. Integers which are out of range are caught by the
+// `DynamicBuilder`, as long as they fit in an `i64` or a `u64`.
+fn number(base: BaseType, text: &str) -> Option<BuildValue> {
+    let n = integer(text);
+
+    if base.is_float() {
+        return match n {
+            Some(n) => Some(BuildValue::Float(n as f64)),
+            None    => text.parse().ok().map(BuildValue::Float),
+        }
+    }
+
+    let n = n?;
+
+    i64::try_from(n).map(BuildValue::Int)
+        .or_else(|_| u64::try_from(n).map(BuildValue::UInt))
+        .ok()
+}
+
+// Parse a decimal or hexadecimal integer.
+fn integer(text: &str) -> Option<i128> {
+    let (neg, digits) = match text.as_bytes().first() {
+        Some(b'-') => (true, &text[1..]),
+        Some(b'+') => (false, &text[1..]),
+        _          => (false, text),
+    };
+
+    if !digits.bytes().next().is_some_and(|c| c.is_ascii_digit()) {
+        return None
+    }
+
+    let n = if digits.starts_with("0x") || digits.starts_with("0X") {
+        u64::from_str_radix(&digits[2..], 16).ok()?
+    } else {
+        digits.parse::<u64>().ok()?
+    } as i128;
+
+    Some(if neg { -n } else { n })
+}
+
+// Look up the names in `text`, which are separated by spaces if there are more than one, and
+// combine their values. Numbers may be mixed in with the names. Only `bit_flags` enums can have
+// more than one.
+fn enum_value(en: &Enum, base: BaseType, text: &str) -> Option<BuildValue> {
+    let mut bits  = 0;
+    let mut found = false;
+
+    if en.attribute("bit_flags").is_none() && text.split_whitespace().nth(1).is_some() {
+        return None
+    }
+
+    for name in text.split_whitespace() {
+        bits |= match en.value_by_name(name) {
+            Some(v) => v.value,
+            None    => integer(name).and_then(|n| i64::try_from(n).ok())?,
+        };
+
+        found = true;
+    }
+
+    match base {
+        _ if !found      => None,
+        BaseType::ULong  => Some(BuildValue::UInt(bits as u64)),
+        _                => Some(BuildValue::Int(bits)),
+    }
+}
+
+// Find the `<field>_type` member which goes with the union (or vector of unions) `m`.
+fn union_tag<'a>(object: &Object, field: &Field, members: &'a [Member], m: &Member)
+    -> Result<&'a Node, (usize, String)> {
+
+    let name = format!("{}_type", field.name);
+
+    members.iter().find(|t| t.name == name).map(|t| &t.value).ok_or_else(|| {
+        (m.pos, format!("`{}` needs `{}` to say which type it is", field_path(object, field), name))
+    })
+}
+
+// Whether `node` is the type of a union which isn't set.
+fn is_none_type(node: &Node) -> bool {
+    match node.value {
+        Json::Ident(ref s) | Json::Str(ref s) | Json::Number(ref s) => {
+            s == "NONE" || integer(s) == Some(0)
+        }
+        Json::Null => true,
+        _          => false,
+    }
+}
+
+// Convert `node` to a member of the union `field`, chosen by the value of `tag`. Returns `None` if
+// the tag is `NONE`.
+fn union_value(schema: &Schema, object: &Object, field: &Field, tag: &Node, node: &Node)
+    -> Result<Option<BuildValue>, (usize, String)> {
+
+    let en = &schema.enums[field.ty.index.unwrap()];
+
+    let member = match tag.value {
+        Json::Ident(ref s) | Json::Str(ref s) | Json::Number(ref s) => {
+            en.value_by_name(s).or_else(|| {
+                integer(s).and_then(|n| i64::try_from(n).ok()).and_then(|n| en.value_by_value(n))
+            }).ok_or_else(|| (tag.pos, format!("`{}` isn't a member of `{}`", s, en.name)))?
+        }
+        _ => return Err(expected(tag, "a union type", object, field)),
+    };
+
+    if member.value == 0 {
+        return match node.value {
+            Json::Null => Ok(None),
+            _          => Err((node.pos, format!("`{}` has type `{}`, so it can't have a value",
+                                                 field_path(object, field), member.name))),
+        }
+    }
+
+    match member.union_type {
+        Some(ty) if ty.base_type == BaseType::Obj => {
+            let fields = object_fields(schema, &schema.objects[ty.index.unwrap()], node)?;
+            Ok(Some(BuildValue::Union(member.name.clone(), fields)))
+        }
+        _ => Err((tag.pos, format!("`{}` members of `{}` can't be given in JSON", member.name,
+                                   en.name))),
+    }
+}
+
+// Convert the vector of unions `m`, pairing each element with one from its vector of types.
+fn union_vector(schema: &Schema, object: &Object, field: &Field, members: &[Member], m: &Member)
+    -> Result<BuildValue, (usize, String)> {
+
+    let tags = union_tag(object, field, members, m)?;
+
+    let (tags, elems) = match (&tags.value, &m.value.value) {
+        (Json::Array(tags), Json::Array(elems)) => (tags, elems),
+        (Json::Array(_), _)                     => {
+            return Err(expected(&m.value, "an array", object, field))
+        }
+        _ => return Err(expected(tags, "an array of union types", object, field)),
+    };
+
+    if tags.len() != elems.len() {
+        return Err((m.pos, format!("`{}` and `{}_type` have different lengths",
+                                   field_path(object, field), field.name)))
+    }
+
+    let mut values = Vec::with_capacity(elems.len());
+
+    for (tag, elem) in tags.iter().zip(elems.iter()) {
+        match union_value(schema, object, field, tag, elem)? {
+            Some(v) => values.push(v),
+            None    => {
+                return Err((tag.pos, format!("the elements of `{}` can't be `NONE`",
+                                             field_path(object, field))))
+            }
+        }
+    }
+
+    Ok(BuildValue::Vector(values))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use schema;
+    use test_support::{self, read_root};
+
+    const SCHEMA: &str = r#"
+enum Color : byte { Red = 1, Green, Blue }
+
+enum Flags : ushort (bit_flags) { Fast, Loud, Shiny }
+
+struct Inner {
+  a: short;
+  b: byte;
+}
+
+struct Outer {
+  inner: Inner;
+  c: double;
+}
+
+table Leaf { name: string; }
+
+table Bud { size: int = 4; }
+
+union Part { Leaf, Bud }
+
+table Root {
+  name: string;
+  hp: short = 100;
+  color: Color = Green;
+  colors: [Color];
+  flags: Flags;
+  outer: Outer;
+  part: Part;
+  parts: [Part];
+  old: int (deprecated);
+}
+
+root_type Root;
+"#;
+
+    fn try_build(schema: &Schema, json: &str, force_defaults: bool) -> Result<Vec<u8>, Error> {
+        test_support::try_build(force_defaults, |fbb| parse(schema, json, fbb))
+    }
+
+    fn build(schema: &Schema, json: &str) -> Vec<u8> {
+        try_build(schema, json, false).unwrap()
+    }
+
+    fn error(schema: &Schema, json: &str) -> String {
+        try_build(schema, json, false).unwrap_err().to_string()
+    }
+
+    fn get_i64(schema: &Schema, json: &str, field: &str) -> i64 {
+        read_root(schema, &build(schema, json)).get(field).unwrap().as_i64().unwrap()
+    }
+
+    #[test]
+    fn enums_are_given_by_name_or_number() {
+        let schema = schema::compile_str(SCHEMA).unwrap();
+
+        assert_eq!(get_i64(&schema, "{ color: Blue }", "color"), 3);
+        assert_eq!(get_i64(&schema, r#"{ "color": "Red" }"#, "color"), 1);
+        assert_eq!(get_i64(&schema, "{ color: 3 }", "color"), 3);
+        assert_eq!(get_i64(&schema, "{ color: \"3\" }", "color"), 3);
+        assert_eq!(get_i64(&schema, "{}", "color"), 2);
+
+        let buf    = build(&schema, "{ colors: [Red, 2, \"Blue\"] }");
+        let colors = read_root(&schema, &buf).get("colors").unwrap().as_vector().unwrap();
+        assert_eq!(colors.iter().map(|c| c.as_i64().unwrap()).collect::<Vec<_>>(), [1, 2, 3]);
+
+        assert_eq!(error(&schema, "{ color: Purple }"), "1:10: `Purple` isn't a value of `Color`");
+        assert_eq!(error(&schema, "{ color: \"Red Blue\" }"),
+                   "1:10: `Red Blue` isn't a value of `Color`");
+        assert_eq!(error(&schema, "{ colors: [\"Red 2\"] }"),
+                   "1:12: `Red 2` isn't a value of `Color`");
+        assert_eq!(error(&schema, "{ color: 1.5 }"), "1:10: `1.5` isn't a valid byte for `Root.color`");
+    }
+
+    #[test]
+    fn bit_flags_are_combined() {
+        let schema = schema::compile_str(SCHEMA).unwrap();
+
+        assert_eq!(get_i64(&schema, "{ flags: Loud }", "flags"), 2);
+        assert_eq!(get_i64(&schema, "{ flags: \"Fast Shiny\" }", "flags"), 5);
+        assert_eq!(get_i64(&schema, "{ flags: \"Shiny Fast Loud\" }", "flags"), 7);
+        assert_eq!(get_i64(&schema, "{ flags: \"Fast 8\" }", "flags"), 9);
+
+        assert_eq!(error(&schema, "{ flags: \"Fast Slow\" }"),
+                   "1:10: `Fast Slow` isn't a value of `Flags`");
+    }
+
+    #[test]
+    fn union_types_can_come_before_or_after_the_union() {
+        let schema = schema::compile_str(SCHEMA).unwrap();
+
+        let before = build(&schema, "{ part_type: Leaf, part: { name: \"x\" } }");
+        let after  = build(&schema, "{ part: { name: \"x\" }, part_type: Leaf }");
+        assert_eq!(before, after);
+        assert_eq!(build(&schema, "{ part_type: 1, part: { name: \"x\" } }"), before);
+
+        let root = read_root(&schema, &before);
+        let part = root.get("part").unwrap().as_union().unwrap();
+        assert_eq!(part.variant().unwrap().name, "Leaf");
+        assert_eq!(part.value().unwrap().get("name").unwrap().as_str(), Some("x"));
+
+        let buf   = build(&schema, "{ parts: [{ size: 1 }, { name: \"y\" }], parts_type: [Bud, Leaf] }");
+        let parts = read_root(&schema, &buf).get("parts").unwrap().as_vector().unwrap();
+        let tags: Vec<_> = parts.iter().map(|p| p.as_union().unwrap().tag()).collect();
+        assert_eq!(tags, [2, 1]);
+
+        // A union which isn't set can be left out, or given with the type `NONE`.
+        assert_eq!(build(&schema, "{ part_type: NONE }"), build(&schema, "{}"));
+        assert_eq!(build(&schema, "{ part_type: NONE, part: null }"), build(&schema, "{}"));
+    }
+
+    #[test]
+    fn unions_need_both_halves() {
+        let schema = schema::compile_str(SCHEMA).unwrap();
+
+        assert_eq!(error(&schema, "{ part: {} }"),
+                   "1:3: `Root.part` needs `part_type` to say which type it is");
+        assert_eq!(error(&schema, "{ part_type: Leaf }"), "1:3: `part_type` is given without `part`");
+        assert_eq!(error(&schema, "{ part_type: Leaf, part: null }"),
+                   "1:3: `part_type` is given without `part`");
+        assert_eq!(error(&schema, "{\n  parts_type: [Leaf]\n}"),
+                   "2:3: `parts_type` is given without `parts`");
+        assert_eq!(error(&schema, "{ part_type: Stem, part: {} }"),
+                   "1:14: `Stem` isn't a member of `Part`");
+        assert_eq!(error(&schema, "{ part_type: NONE, part: {} }"),
+                   "1:26: `Root.part` has type `NONE`, so it can't have a value");
+        assert_eq!(error(&schema, "{ parts_type: [Leaf], parts: [] }"),
+                   "1:23: `Root.parts` and `parts_type` have different lengths");
+    }
+
+    #[test]
+    fn structs_can_be_nested() {
+        let schema = schema::compile_str(SCHEMA).unwrap();
+        let buf    = build(&schema, "{ outer: { inner: { a: -3, b: 4 }, c: 0.5 } }");
+
+        let outer = read_root(&schema, &buf).get("outer").unwrap().as_struct().unwrap();
+        let inner = outer.get("inner").unwrap().as_struct().unwrap();
+        assert_eq!(inner.get("a").unwrap().as_i64(), Some(-3));
+        assert_eq!(inner.get("b").unwrap().as_i64(), Some(4));
+        assert_eq!(outer.get("c").unwrap().as_f64(), Some(0.5));
+
+        assert_eq!(error(&schema, "{ outer: { inner: { a: 1 }, c: 0 } }"), "`Inner.b` must be set");
+        assert_eq!(error(&schema, "{ outer: { inner: 1, c: 0 } }"),
+                   "1:19: expected an object for `Inner`, found a number");
+    }
+
+    #[test]
+    fn defaults_are_left_out_unless_forced() {
+        let schema = schema::compile_str(SCHEMA).unwrap();
+        let json   = "{ hp: 100, color: Green, flags: 0 }";
+
+        let buf = build(&schema, json);
+        assert_eq!(read_root(&schema, &buf).fields().count(), 0);
+        assert_eq!(buf, build(&schema, "{}"));
+
+        let buf     = try_build(&schema, json, true).unwrap();
+        let root    = read_root(&schema, &buf);
+        let present: Vec<_> = root.fields().map(|(f, _)| &f.name[..]).collect();
+        assert_eq!(present, ["hp", "color", "flags"]);
+        assert_eq!(root.get("hp").unwrap().as_i64(), Some(100));
+    }
+
+    #[test]
+    fn errors_have_lines_and_columns() {
+        let schema = schema::compile_str(SCHEMA).unwrap();
+
+        assert_eq!(error(&schema, "{\n  name: \"x\",\n  size: 3\n}"),
+                   "3:3: `Root` doesn't have a field called `size`");
+        assert_eq!(error(&schema, "{\n  hp: 1,\n  hp: 2\n}"), "3:3: `hp` is given more than once");
+        assert_eq!(error(&schema, "{\n  old: 1\n}"), "2:3: `Root.old` is deprecated");
+        assert_eq!(error(&schema, "{\n\n    name: [1]\n}"),
+                   "3:11: expected a string for `Root.name`, found an array");
+        assert_eq!(error(&schema, "[]"), "1:1: expected an object for `Root`, found an array");
+        assert_eq!(error(&schema, "{ hp: 100000 }"), "`Root.hp` must be a valid short");
+
+        match try_build(&schema, "{\n  name: \"x\"\n  hp: 1 }", false) {
+            Err(Error::Parse { line: 3, col, .. }) => assert_eq!(col, 3),
+            r                                      => panic!("{:?}", r),
+        }
+
+        let rootless = schema::compile_str("table T { a: int; }").unwrap();
+        assert_eq!(try_build(&rootless, "{}", false), Err(Error::Build(BuildError::NoRootType)));
+    }
+}
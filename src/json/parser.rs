@@ -0,0 +1,364 @@
+// Copyright 2015 Sam Payson. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Parsing of JSON text, before it's matched up with a schema.
+//!
+//! This accepts the same relaxed JSON as `flatc`: keys don't need to be quoted, commas may trail,
+//! and there can be `//` and `/* */` comments. Numbers are kept as they were written, since
+//! whether they're read as integers or floats depends on the field they're for.
+
+use std::char;
+
+// How deeply arrays and objects may be nested.
+const MAX_DEPTH: usize = 64;
+
+/// A JSON value.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+
+    /// A number, exactly as written, including its sign.
+    Number(String),
+
+    /// An unquoted word other than `true`, `false` and `null`, e.g. an enum value or `nan`.
+    Ident(String),
+    Str(String),
+    Array(Vec<Node>),
+    Object(Vec<Member>),
+}
+
+/// A JSON value, and the byte position in the text where it starts.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Node {
+    pub pos:   usize,
+    pub value: Json,
+}
+
+/// One of the key/value pairs of an object. `pos` is where the key starts.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Member {
+    pub name:  String,
+    pub pos:   usize,
+    pub value: Node,
+}
+
+impl Json {
+    /// A description of this kind of value, for errors.
+    pub fn kind(&self) -> &'static str {
+        match *self {
+            Json::Null      => "null",
+            Json::Bool(_)   => "a boolean",
+            Json::Number(_) => "a number",
+            Json::Ident(_)  => "an identifier",
+            Json::Str(_)    => "a string",
+            Json::Array(_)  => "an array",
+            Json::Object(_) => "an object",
+        }
+    }
+}
+
+/// Parse `text`, which must hold exactly one value. Errors give the byte position they were found
+/// at.
+pub fn parse(text: &str) -> Result<Node, (usize, String)> {
+    let mut p = Parser { text, pos: 0, depth: 0 };
+
+    let node = p.value()?;
+    p.skip_space()?;
+
+    if p.pos < text.len() {
+        return Err((p.pos, "expected the end of the text".to_string()))
+    }
+
+    Ok(node)
+}
+
+struct Parser<'a> {
+    text:  &'a str,
+    pos:   usize,
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<u8> {
+        self.text.as_bytes().get(self.pos).cloned()
+    }
+
+    // Skip whitespace and comments.
+    fn skip_space(&mut self) -> Result<(), (usize, String)> {
+        loop {
+            let rest = &self.text[self.pos..];
+
+            if rest.starts_with("//") {
+                self.pos += rest.find('\n').unwrap_or(rest.len());
+            } else if let Some(body) = rest.strip_prefix("/*") {
+                match body.find("*/") {
+                    Some(n) => self.pos += n + 4,
+                    None    => return Err((self.pos, "unterminated block comment".to_string())),
+                }
+            } else if self.peek().is_some_and(|c| c.is_ascii_whitespace()) {
+                self.pos += 1;
+            } else {
+                return Ok(())
+            }
+        }
+    }
+
+    // Skip whitespace, then consume `c` if it's next.
+    fn eat(&mut self, c: u8) -> Result<bool, (usize, String)> {
+        self.skip_space()?;
+
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn expect(&mut self, c: u8) -> Result<(), (usize, String)> {
+        if self.eat(c)? {
+            Ok(())
+        } else {
+            Err((self.pos, format!("expected `{}`", c as char)))
+        }
+    }
+
+    fn value(&mut self) -> Result<Node, (usize, String)> {
+        self.skip_space()?;
+
+        let pos = self.pos;
+
+        let value = match self.peek() {
+            Some(b'{')                    => self.nested(Parser::object)?,
+            Some(b'[')                    => self.nested(Parser::array)?,
+            Some(b'"') | Some(b'\'')      => Json::Str(self.string()?),
+            Some(c) if is_number_start(c) => Json::Number(self.number()),
+            Some(c) if is_word_char(c)    => match self.word() {
+                "true"  => Json::Bool(true),
+                "false" => Json::Bool(false),
+                "null"  => Json::Null,
+                w       => Json::Ident(w.to_string()),
+            },
+            Some(_)                       => {
+                let c = self.text[pos..].chars().next().unwrap();
+                return Err((pos, format!("unexpected character `{}`", c)))
+            }
+            None                          => return Err((pos, "expected a value".to_string())),
+        };
+
+        Ok(Node { pos, value })
+    }
+
+    // Parse an array or object with `f`, keeping track of how deeply they're nested.
+    fn nested<F>(&mut self, f: F) -> Result<Json, (usize, String)>
+        where F: FnOnce(&mut Parser<'a>) -> Result<Json, (usize, String)> {
+
+        if self.depth == MAX_DEPTH {
+            return Err((self.pos, "arrays and objects are nested too deeply".to_string()))
+        }
+
+        self.depth += 1;
+        let value = f(self);
+        self.depth -= 1;
+
+        value
+    }
+
+    fn array(&mut self) -> Result<Json, (usize, String)> {
+        self.expect(b'[')?;
+
+        let mut elems = Vec::new();
+
+        while !self.eat(b']')? {
+            elems.push(self.value()?);
+
+            if !self.eat(b',')? {
+                self.expect(b']')?;
+                break
+            }
+        }
+
+        Ok(Json::Array(elems))
+    }
+
+    fn object(&mut self) -> Result<Json, (usize, String)> {
+        self.expect(b'{')?;
+
+        let mut members = Vec::new();
+
+        while !self.eat(b'}')? {
+            let pos = self.pos;
+
+            let name = match self.peek() {
+                Some(b'"') | Some(b'\'')   => self.string()?,
+                Some(c) if is_word_char(c) => self.word().to_string(),
+                _                          => return Err((pos, "expected a key".to_string())),
+            };
+
+            self.expect(b':')?;
+
+            let value = self.value()?;
+            members.push(Member { name, pos, value });
+
+            if !self.eat(b',')? {
+                self.expect(b'}')?;
+                break
+            }
+        }
+
+        Ok(Json::Object(members))
+    }
+
+    fn word(&mut self) -> &'a str {
+        let start = self.pos;
+
+        while self.peek().is_some_and(is_word_char) {
+            self.pos += 1;
+        }
+
+        &self.text[start..self.pos]
+    }
+
+    // Scan a number, allowing anything that could be part of one. Whether it's valid is checked
+    // once it's known what type it should be.
+    fn number(&mut self) -> String {
+        let start = self.pos;
+
+        if self.peek() == Some(b'-') || self.peek() == Some(b'+') {
+            self.pos += 1;
+        }
+
+        let rest = &self.text[self.pos..];
+        let hex  = rest.starts_with("0x") || rest.starts_with("0X");
+
+        while let Some(c) = self.peek() {
+            let exponent_sign = (c == b'-' || c == b'+') && !hex &&
+                                (self.text.as_bytes()[self.pos - 1] | 0x20) == b'e';
+
+            if !(is_word_char(c) || c == b'.' || exponent_sign) {
+                break
+            }
+
+            self.pos += 1;
+        }
+
+        self.text[start..self.pos].to_string()
+    }
+
+    fn string(&mut self) -> Result<String, (usize, String)> {
+        let start = self.pos;
+        let quote = self.text.as_bytes()[start] as char;
+        let mut out = String::new();
+        let mut chars = self.text[start + 1..].char_indices();
+
+        loop {
+            let (off, c) = match chars.next() {
+                Some(x) => x,
+                None    => return Err((start, "unterminated string".to_string())),
+            };
+
+            let pos = start + 1 + off;
+
+            match c {
+                '\n' => return Err((start, "unterminated string".to_string())),
+                c if c == quote => {
+                    self.pos = pos + 1;
+                    return Ok(out)
+                }
+                '\\' => {
+                    let esc = match chars.next() {
+                        Some((_, e)) => e,
+                        None         => return Err((start, "unterminated string".to_string())),
+                    };
+
+                    match esc {
+                        'n'  => out.push('\n'),
+                        't'  => out.push('\t'),
+                        'r'  => out.push('\r'),
+                        'b'  => out.push('\u{8}'),
+                        'f'  => out.push('\u{c}'),
+                        '"'  => out.push('"'),
+                        '\'' => out.push('\''),
+                        '\\' => out.push('\\'),
+                        '/'  => out.push('/'),
+                        'x'  => {
+                            let hex: String = chars.by_ref().take(2).map(|(_, h)| h).collect();
+
+                            match u8::from_str_radix(&hex, 16) {
+                                Ok(b) if is_hex(&hex, 2) && b < 0x80 => out.push(b as char),
+                                _ => return Err((pos, format!("invalid escape `\\x{}`", hex))),
+                            }
+                        }
+                        'u'  => {
+                            let hi = match hex4(&mut chars) {
+                                Some(hi) => hi,
+                                None     => {
+                                    return Err((pos, "invalid `\\u` escape".to_string()))
+                                }
+                            };
+
+                            // Characters outside the basic multilingual plane are written as a
+                            // pair of UTF-16 surrogates.
+                            let c = if (0xd800..0xdc00).contains(&hi) {
+                                let lo = match (chars.next(), chars.next()) {
+                                    (Some((_, '\\')), Some((_, 'u'))) => hex4(&mut chars),
+                                    _                                 => None,
+                                };
+
+                                lo.filter(|lo| (0xdc00..0xe000).contains(lo)).and_then(|lo| {
+                                    char::from_u32(0x10000 + ((hi - 0xd800) << 10) + lo - 0xdc00)
+                                })
+                            } else {
+                                char::from_u32(hi)
+                            };
+
+                            match c {
+                                Some(c) => out.push(c),
+                                None    => {
+                                    return Err((pos, "invalid `\\u` escape".to_string()))
+                                }
+                            }
+                        }
+                        e => return Err((pos, format!("unknown escape sequence `\\{}`", e))),
+                    }
+                }
+                c => out.push(c),
+            }
+        }
+    }
+}
+
+// Read the four hex digits of a `\u` escape.
+fn hex4(chars: &mut ::std::str::CharIndices) -> Option<u32> {
+    let hex: String = chars.take(4).map(|(_, h)| h).collect();
+
+    if is_hex(&hex, 4) {
+        u32::from_str_radix(&hex, 16).ok()
+    } else {
+        None
+    }
+}
+
+fn is_hex(s: &str, len: usize) -> bool {
+    s.len() == len && s.bytes().all(|c| c.is_ascii_hexdigit())
+}
+
+fn is_number_start(c: u8) -> bool {
+    c.is_ascii_digit() || c == b'-' || c == b'+' || c == b'.'
+}
+
+fn is_word_char(c: u8) -> bool {
+    c == b'_' || c.is_ascii_alphanumeric()
+}
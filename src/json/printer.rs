@@ -0,0 +1,570 @@
+// Copyright 2015 Sam Payson. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Printing buffers as JSON.
+
+use std::fmt::Write;
+
+use reflection::{self, DynStruct, DynTable, DynVector, Value};
+use schema::{BaseType, Enum, Field, Schema, Type};
+use verifier::VerifyError;
+
+/// Prints buffers as JSON, according to a schema. The output can be read back in by
+/// `json::parse`, or by `flatc`.
+#[derive(Clone, Debug)]
+pub struct Printer {
+    pretty:     bool,
+    defaults:   bool,
+    enum_names: bool,
+    strict:     bool,
+}
+
+impl Default for Printer {
+    fn default() -> Printer {
+        Printer::new()
+    }
+}
+
+impl Printer {
+    /// Create a printer which indents its output, prints enum values by name, and leaves out
+    /// fields which aren't in the buffer.
+    pub fn new() -> Printer {
+        Printer {
+            pretty:     true,
+            defaults:   false,
+            enum_names: true,
+            strict:     false,
+        }
+    }
+
+    /// Put each field and element on its own line, indented (the default), or print everything on
+    /// one line without any spaces.
+    pub fn pretty(&mut self, pretty: bool) {
+        self.pretty = pretty;
+    }
+
+    /// Print scalar fields which aren't in the buffer, with their default values.
+    pub fn defaults(&mut self, defaults: bool) {
+        self.defaults = defaults;
+    }
+
+    /// Print enum values by name (the default), or as numbers. Values which don't have a name are
+    /// always printed as numbers.
+    pub fn enum_names(&mut self, names: bool) {
+        self.enum_names = names;
+    }
+
+    /// Print strict JSON, which quotes keys and enum names, and writes `nan` and `inf` as strings.
+    /// Otherwise they're left unquoted, as `flatc` does by default.
+    pub fn strict_json(&mut self, strict: bool) {
+        self.strict = strict;
+    }
+
+    /// Print the root table of `buf`, read as the schema's `root_type`, or return `None` if the
+    /// schema doesn't have one. The buffer is checked against the schema first, so malformed
+    /// buffers are reported as errors rather than causing a panic.
+    ///
+    /// Unions whose type isn't in the schema (e.g. because the buffer was built with a newer
+    /// version of it) are left out, along with their type fields, since their values can't be
+    /// printed.
+    pub fn print(&self, schema: &Schema, buf: &[u8]) -> Result<Option<String>, VerifyError> {
+        Ok(reflection::get_root_verified(schema, buf)?.map(|t| self.print_table(t)))
+    }
+
+    /// Print a table, and everything it refers to. Unlike `print`, this trusts the buffer that the
+    /// table is in, so it should come from `reflection::get_root_verified`.
+    pub fn print_table(&self, table: DynTable) -> String {
+        let mut w = Writer {
+            printer: self,
+            schema:  table.schema(),
+            out:     String::new(),
+            depth:   0,
+        };
+
+        w.table(table);
+
+        if self.pretty {
+            w.out.push('\n');
+        }
+
+        w.out
+    }
+}
+
+struct Writer<'p> {
+    printer: &'p Printer,
+    schema:  &'p Schema,
+    out:     String,
+    depth:   usize,
+}
+
+impl<'p> Writer<'p> {
+    fn newline(&mut self) {
+        if self.printer.pretty {
+            self.out.push('\n');
+
+            for _ in 0..self.depth {
+                self.out.push_str("  ");
+            }
+        }
+    }
+
+    // Start the next member of an object or array which is printed one member per line.
+    fn next_line(&mut self, first: bool) {
+        if !first {
+            self.out.push(',');
+        }
+
+        self.newline();
+    }
+
+    // Close an object or array which was printed one member per line.
+    fn close(&mut self, c: char, empty: bool) {
+        self.depth -= 1;
+
+        if !empty {
+            self.newline();
+        }
+
+        self.out.push(c);
+    }
+
+    fn key(&mut self, name: &str) {
+        if self.printer.strict {
+            self.string(name);
+        } else {
+            self.out.push_str(name);
+        }
+
+        self.out.push(':');
+
+        if self.printer.pretty {
+            self.out.push(' ');
+        }
+    }
+
+    // Write `s` as a quoted string.
+    fn string(&mut self, s: &str) {
+        self.out.push('"');
+
+        for c in s.chars() {
+            match c {
+                '"'                    => self.out.push_str("\\\""),
+                '\\'                   => self.out.push_str("\\\\"),
+                '\n'                   => self.out.push_str("\\n"),
+                '\t'                   => self.out.push_str("\\t"),
+                '\r'                   => self.out.push_str("\\r"),
+                '\u{8}'                => self.out.push_str("\\b"),
+                '\u{c}'                => self.out.push_str("\\f"),
+                c if (c as u32) < 0x20 => {
+                    let _ = write!(self.out, "\\u{:04x}", c as u32);
+                }
+                c                      => self.out.push(c),
+            }
+        }
+
+        self.out.push('"');
+    }
+
+    // Write an enum name, or anything else which isn't a number or string. Names of `bit_flags`
+    // combinations have spaces in them, so they're always quoted.
+    fn word(&mut self, word: &str) {
+        if self.printer.strict || word.contains(' ') {
+            self.string(word);
+        } else {
+            self.out.push_str(word);
+        }
+    }
+
+    fn table(&mut self, table: DynTable) {
+        self.out.push('{');
+        self.depth += 1;
+
+        let mut first = true;
+
+        for field in table.object().fields.iter() {
+            if field.deprecated || unprintable_union(table, field) {
+                continue
+            }
+
+            let value = if field.ty.base_type.is_scalar() && !self.printer.defaults &&
+                           !table.is_present(field) {
+                None
+            } else {
+                table.value(field)
+            };
+
+            if let Some(v) = value {
+                self.next_line(first);
+                self.key(&field.name);
+                self.value(field.ty, v);
+
+                first = false;
+            }
+        }
+
+        self.close('}', first);
+    }
+
+    fn structure(&mut self, st: DynStruct) {
+        self.out.push('{');
+        self.depth += 1;
+
+        for (i, (field, v)) in st.fields().enumerate() {
+            self.next_line(i == 0);
+            self.key(&field.name);
+            self.value(field.ty, v);
+        }
+
+        self.close('}', st.object().fields.is_empty());
+    }
+
+    fn vector(&mut self, vec: DynVector) {
+        let elem = vec.element_type();
+
+        self.out.push('[');
+
+        // Vectors of scalars are kept on one line, since they can be long.
+        if elem.base_type.is_scalar() {
+            for (i, v) in vec.iter().enumerate() {
+                if i > 0 {
+                    self.out.push_str(if self.printer.pretty { ", " } else { "," });
+                }
+
+                self.value(elem, v);
+            }
+
+            self.out.push(']');
+            return
+        }
+
+        self.depth += 1;
+
+        for (i, v) in vec.iter().enumerate() {
+            self.next_line(i == 0);
+            self.value(elem, v);
+        }
+
+        self.close(']', vec.is_empty());
+    }
+
+    // Write `v`, which has type `ty` (or is an element of a vector of `ty`).
+    fn value(&mut self, ty: Type, v: Value) {
+        match v {
+            Value::Bool(b)   => self.out.push_str(if b { "true" } else { "false" }),
+            Value::Int(i)    => self.integer(ty, i, &i.to_string()),
+            Value::UInt(u)   => self.integer(ty, u as i64, &u.to_string()),
+            Value::Float(f)  => self.float(ty.base_type, f),
+            Value::Str(s)    => self.string(&String::from_utf8_lossy(s.as_bytes())),
+            Value::Vector(v) => self.vector(v),
+            Value::Table(t)  => self.table(t),
+            Value::Struct(s) => self.structure(s),
+            Value::Union(u)  => match u.value() {
+                Some(t) => self.table(t),
+                None    => self.out.push_str("null"),
+            },
+        }
+    }
+
+    // Write the integer `v`, which is `text` in decimal. If it's of an enum type, its name is
+    // written instead if it has one.
+    fn integer(&mut self, ty: Type, v: i64, text: &str) {
+        let name = match ty.index {
+            Some(i) if self.printer.enum_names => enum_name(&self.schema.enums[i], v),
+            _                                  => None,
+        };
+
+        match name {
+            Some(name) => self.word(&name),
+            None       => self.out.push_str(text),
+        }
+    }
+
+    fn float(&mut self, base: BaseType, f: f64) {
+        if !f.is_finite() {
+            let word = if f.is_nan() { "nan" } else if f > 0.0 { "inf" } else { "-inf" };
+            return self.word(word)
+        }
+
+        // Print `float`s at their own precision, so that e.g. 0.1 isn't printed as
+        // 0.10000000149011612.
+        let _ = if base == BaseType::Float {
+            write!(self.out, "{}", f as f32)
+        } else {
+            write!(self.out, "{}", f)
+        };
+    }
+}
+
+// Whether `field` of `table` is a union (or vector of unions) with a member whose value can't be
+// printed, or the type field of one. Printing `null` in its place wouldn't parse.
+fn unprintable_union(table: DynTable, field: &Field) -> bool {
+    let union = match (field.ty.base_type, field.ty.element) {
+        (BaseType::UType, _) | (BaseType::Vector, BaseType::UType) => {
+            match table.object().fields.iter().find(|u| u.id == field.id + 1) {
+                Some(u) => u,
+                None    => return false,
+            }
+        }
+        _ => field,
+    };
+
+    let unprintable = |v: Value| matches!(v, Value::Union(u) if u.value().is_none());
+
+    match (union.ty.base_type, union.ty.element, table.value(union)) {
+        (BaseType::Union, _, Some(v))                               => unprintable(v),
+        (BaseType::Vector, BaseType::Union, Some(Value::Vector(v))) => v.iter().any(unprintable),
+        _                                                           => false,
+    }
+}
+
+// The name of the value `v` of `en`. The values of `bit_flags` enums may also be a combination of
+// flags, whose names are separated by spaces.
+fn enum_name(en: &Enum, v: i64) -> Option<String> {
+    if let Some(val) = en.value_by_value(v) {
+        return Some(val.name.clone())
+    }
+
+    if v == 0 || en.attribute("bit_flags").is_none() {
+        return None
+    }
+
+    let flags: Vec<_> = en.values.iter()
+        .filter(|f| f.value != 0 && v & f.value == f.value)
+        .collect();
+
+    if flags.iter().fold(0, |bits, f| bits | f.value) != v {
+        return None
+    }
+
+    Some(flags.iter().map(|f| f.name.as_str()).collect::<Vec<_>>().join(" "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use json;
+    use schema;
+    use test_support::{self, for_each_corruption, for_each_truncation};
+
+    const SCHEMA: &str = r#"
+enum Color : ubyte { Red, Green, Blue }
+
+enum Flags : ubyte (bit_flags) { Fast, Loud, Shiny }
+
+struct Vec2 {
+  x: float;
+  y: float;
+}
+
+table Item {
+  name: string;
+  count: int = 1;
+}
+
+union Thing { Item }
+
+table Root {
+  name: string;
+  hp: short = 100;
+  color: Color = Blue;
+  flags: Flags;
+  pos: Vec2;
+  items: [Item];
+  nums: [int];
+  thing: Thing;
+  ratio: double;
+  old: int (deprecated);
+  things: [Thing];
+}
+
+root_type Root;
+"#;
+
+    const JSON: &str = r#"{
+  name: "a \"quoted\"\nname",
+  hp: 50,
+  color: Red,
+  flags: "Fast Shiny",
+  pos: { x: 1.5, y: -2 },
+  items: [{ name: "sword" }, { count: 3 }],
+  nums: [1, 2, 3],
+  thing_type: Item,
+  thing: { name: "gem", count: 0 },
+  ratio: 0.1
+}"#;
+
+    // Build `json` into a buffer.
+    fn build(schema: &Schema, json: &str) -> Vec<u8> {
+        test_support::try_build(false, |fbb| json::parse(schema, json, fbb)).unwrap()
+    }
+
+    fn print(schema: &Schema, buf: &[u8], setup: fn(&mut Printer)) -> String {
+        let mut printer = Printer::new();
+        setup(&mut printer);
+        printer.print(schema, buf).unwrap().unwrap()
+    }
+
+    #[test]
+    fn output_is_pretty_by_default() {
+        let schema = schema::compile_str(SCHEMA).unwrap();
+        let buf    = build(&schema, JSON);
+
+        assert_eq!(print(&schema, &buf, |_| {}), r#"{
+  name: "a \"quoted\"\nname",
+  hp: 50,
+  color: Red,
+  flags: "Fast Shiny",
+  pos: {
+    x: 1.5,
+    y: -2
+  },
+  items: [
+    {
+      name: "sword"
+    },
+    {
+      count: 3
+    }
+  ],
+  nums: [1, 2, 3],
+  thing_type: Item,
+  thing: {
+    name: "gem",
+    count: 0
+  },
+  ratio: 0.1
+}
+"#);
+    }
+
+    #[test]
+    fn output_can_be_compact() {
+        let schema = schema::compile_str(SCHEMA).unwrap();
+        let buf    = build(&schema, JSON);
+
+        assert_eq!(print(&schema, &buf, |p| p.pretty(false)),
+                   r#"{name:"a \"quoted\"\nname",hp:50,color:Red,flags:"Fast Shiny",pos:{x:1.5,y:-2},items:[{name:"sword"},{count:3}],nums:[1,2,3],thing_type:Item,thing:{name:"gem",count:0},ratio:0.1}"#);
+        assert_eq!(print(&schema, &build(&schema, "{ items: [] }"), |_| {}), "{\n  items: []\n}\n");
+        assert_eq!(print(&schema, &build(&schema, "{}"), |_| {}), "{}\n");
+    }
+
+    #[test]
+    fn defaults_can_be_printed() {
+        let schema = schema::compile_str(SCHEMA).unwrap();
+        let buf    = build(&schema, "{ name: \"x\" }");
+
+        assert_eq!(print(&schema, &buf, |p| p.pretty(false)), r#"{name:"x"}"#);
+        assert_eq!(print(&schema, &buf, |p| { p.pretty(false); p.defaults(true) }),
+                   r#"{name:"x",hp:100,color:Blue,flags:0,thing_type:NONE,ratio:0}"#);
+    }
+
+    #[test]
+    fn enums_can_be_printed_as_numbers() {
+        let schema = schema::compile_str(SCHEMA).unwrap();
+        let buf    = build(&schema, "{ color: Green, flags: Loud, thing_type: Item, thing: {} }");
+
+        assert_eq!(print(&schema, &buf, |p| p.pretty(false)),
+                   "{color:Green,flags:Loud,thing_type:Item,thing:{}}");
+        assert_eq!(print(&schema, &buf, |p| { p.pretty(false); p.enum_names(false) }),
+                   "{color:1,flags:2,thing_type:1,thing:{}}");
+
+        // Values without a name are printed as numbers either way.
+        let buf = build(&schema, "{ color: 7, flags: 9 }");
+        assert_eq!(print(&schema, &buf, |p| p.pretty(false)), "{color:7,flags:9}");
+    }
+
+    #[test]
+    fn strict_json_is_quoted() {
+        let schema = schema::compile_str(SCHEMA).unwrap();
+        let buf    = build(&schema, JSON);
+
+        assert_eq!(print(&schema, &buf, |p| { p.pretty(false); p.strict_json(true) }),
+                   r#"{"name":"a \"quoted\"\nname","hp":50,"color":"Red","flags":"Fast Shiny","pos":{"x":1.5,"y":-2},"items":[{"name":"sword"},{"count":3}],"nums":[1,2,3],"thing_type":"Item","thing":{"name":"gem","count":0},"ratio":0.1}"#);
+
+        let buf = build(&schema, "{ ratio: -inf }");
+        assert_eq!(print(&schema, &buf, |p| p.pretty(false)), "{ratio:-inf}");
+        assert_eq!(print(&schema, &buf, |p| { p.pretty(false); p.strict_json(true) }),
+                   r#"{"ratio":"-inf"}"#);
+    }
+
+    #[test]
+    fn printed_json_can_be_parsed_back() {
+        let schema = schema::compile_str(SCHEMA).unwrap();
+        let buf    = build(&schema, JSON);
+
+        let setups: [fn(&mut Printer); 4] = [
+            |_| {},
+            |p| p.pretty(false),
+            |p| { p.strict_json(true); p.enum_names(false) },
+            |p| p.defaults(true),
+        ];
+
+        for setup in setups.iter() {
+            let json = print(&schema, &buf, *setup);
+            assert_eq!(print(&schema, &build(&schema, &json), *setup), json);
+        }
+
+        assert_eq!(build(&schema, &print(&schema, &buf, |_| {})), buf);
+    }
+
+    #[test]
+    fn unknown_union_types_are_left_out() {
+        let schema = schema::compile_str(SCHEMA).unwrap();
+        let newer  = schema::compile_str(&SCHEMA.replace("{ Item }", "{ Item, Root }")).unwrap();
+
+        let buf = build(&newer, "{ thing_type: Root, thing: { hp: 1 }, ratio: 0.5 }");
+        assert_eq!(print(&schema, &buf, |p| p.pretty(false)), "{ratio:0.5}");
+
+        // A vector of unions is left out if any of its elements can't be printed.
+        let json = "{ things_type: [Item, Root], things: [{ count: 2 }, {}], ratio: 0.5 }";
+        assert_eq!(print(&schema, &build(&newer, json), |p| p.pretty(false)), "{ratio:0.5}");
+
+        let json = "{ things_type: [Item, Item], things: [{ count: 2 }, {}] }";
+        let buf  = build(&newer, json);
+        assert_eq!(print(&schema, &buf, |p| p.pretty(false)),
+                   "{things_type:[Item,Item],things:[{count:2},{}]}");
+        assert_eq!(build(&schema, &print(&schema, &buf, |_| {})), buf);
+    }
+
+    #[test]
+    fn recursive_structs_are_rejected_before_printing() {
+        // A struct holding itself would be printed forever, so such schemas can't be loaded.
+        let mut recursive = schema::compile_str(SCHEMA).unwrap();
+        let vec2          = recursive.object_by_name("Vec2").unwrap();
+        recursive.objects[vec2].fields[0].ty.base_type = BaseType::Obj;
+        recursive.objects[vec2].fields[0].ty.index = Some(vec2);
+
+        let err = reflection::load_schema(&reflection::write_bfbs(&recursive)).unwrap_err();
+        assert_eq!(err.to_string(), "struct `Vec2` contains itself");
+    }
+
+    #[test]
+    fn malformed_buffers_are_errors() {
+        let schema = schema::compile_str(SCHEMA).unwrap();
+        let buf    = build(&schema, JSON);
+
+        for_each_truncation(&buf, |b| {
+            assert!(Printer::new().print(&schema, b).is_err(), "{} bytes", b.len());
+        });
+
+        for_each_corruption(&buf, |b| {
+            let _ = Printer::new().print(&schema, b);
+        });
+
+        let rootless = schema::compile_str("table T { a: int; }").unwrap();
+        assert_eq!(Printer::new().print(&rootless, &buf), Ok(None));
+    }
+}
@@ -12,34 +12,65 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-extern crate num;
-
-use std::cmp::Eq;
-
-use std::cmp;
-use std::marker;
-use std::mem;
-use std::slice;
-use std::str;
-
-// Return a byte slice which refers to the same region of memory as `v`.
-fn view_slice_bytes<T>(v: &[T]) -> &[u8] {
-    unsafe {
-        let ptr = mem::transmute::<&T, *const u8>(&v[0]);
-        let len = mem::size_of::<T>() * v.len();
-
-        slice::from_raw_parts(ptr, len)
-    }
-}
-
-// Return a byte slice which refers to the same region of memory as `t`.
-fn view_bytes<T>(t: &T) -> &[u8] {
-    unsafe {
-        let ptr = mem::transmute::<&T, *const u8>(t);
-        let len = mem::size_of::<T>();
-
-        slice::from_raw_parts(ptr, len)
-    }
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(feature = "std")]
+extern crate core;
+
+// Public so that generated code can name `alloc` types without the crate that includes it needing
+// an `extern crate alloc`.
+#[cfg(feature = "alloc")]
+#[doc(hidden)]
+pub extern crate alloc;
+
+#[cfg(feature = "serde")]
+extern crate serde as serde_crate;
+
+#[cfg(feature = "alloc")]
+mod builder;
+#[cfg(feature = "std")]
+pub mod codegen;
+#[cfg(feature = "std")]
+pub mod flexbuffers;
+#[cfg(feature = "std")]
+pub mod json;
+#[cfg(feature = "std")]
+pub mod reflection;
+#[cfg(feature = "std")]
+pub mod schema;
+#[cfg(feature = "serde")]
+pub mod serde;
+#[cfg(all(test, feature = "std"))]
+mod test_support;
+pub mod verifier;
+
+#[cfg(feature = "alloc")]
+pub use builder::{Allocator, BuilderError, FinishedBuffer, FlatBufferBuilder, UnionVectorOffset};
+
+pub use verifier::{Verifier, Verify, VerifyError, get_root_verified,
+                   get_root_verified_with_identifier, get_size_prefixed_root_verified,
+                   get_size_prefixed_root_verified_with_identifier};
+
+use core::cmp;
+use core::marker;
+use core::mem;
+use core::ptr;
+use core::str;
+
+// Read a copy of the `T` stored at `pos` in `buf`. Panics if it's out of bounds. Buffers aren't
+// necessarily aligned in memory, so the struct is copied out rather than referenced in place.
+fn read_struct<T: Struct>(buf: &[u8], pos: usize) -> T {
+    let bytes = &buf[pos..][..mem::size_of::<T>()];
+
+    // Any bit pattern is a valid `Struct`, and we've checked the bounds.
+    unsafe { ptr::read_unaligned(bytes.as_ptr() as *const T) }
+}
+
+// Write `val` over the `T` stored at `pos` in `buf`. Panics if it's out of bounds.
+fn write_struct<T: Struct>(buf: &mut [u8], pos: usize, val: T) {
+    let bytes = &mut buf[pos..][..mem::size_of::<T>()];
+
+    unsafe { ptr::write_unaligned(bytes.as_mut_ptr() as *mut T, val) }
 }
 
 /// An unsigned offset
@@ -51,47 +82,38 @@ pub type SOffset = i32;
 /// A vtable offset, used for indexing the fields of a Table
 pub type VOffset = u16;
 
-/// This is a trait for primitives which can be loaded and stored as aligned little-endian values.
+/// This is a trait for primitives which can be loaded and stored as little-endian values.
 pub trait Endian: Copy + PartialEq {
-    unsafe fn read_le(buf: *const u8) -> Self;
-    unsafe fn write_le(self, buf: *mut u8);
+    /// Read a value from the start of `buf`. Panics if `buf` is too short.
+    fn read_le(buf: &[u8]) -> Self;
+
+    /// Write the value to the start of `buf`. Panics if `buf` is too short.
+    fn write_le(self, buf: &mut [u8]);
 
+    /// Convert a value which was stored in little-endian order (e.g. in a struct) to native order.
+    #[allow(clippy::wrong_self_convention)]
     fn from_le(self) -> Self;
+
+    /// Convert a value to little-endian order, so that it can be stored in a struct.
     fn to_le(self) -> Self;
 }
 
-// What we really want here is:
-//
-//     impl<T: num::PrimInt> Endian for T {
-//         fn read_le(buf: &[u8]) -> T {
-//             let ptr: &T = unsafe { mem::transmute(&buf[0]) };
-//             num::PrimInt::from_le(*ptr)
-//         }
-//
-//         fn write_le(self, buf: &mut [u8]) {
-//             let ptr: &mut T = unsafe { mem::transmute(&mut buf[0]) };
-//             *ptr = self.to_le();
-//         }
-//     }
-//
-// but the blanket impl causes errors if we try to implement it for any other type, so this macro
-// will have to do.
 macro_rules! impl_endian_for {
     ($t:ty) => {
         impl Endian for $t {
-            unsafe fn read_le(buf: *const u8) -> $t {
-                let ptr = mem::transmute::<*const u8, &$t>(buf);
-                num::PrimInt::from_le(*ptr)
+            fn read_le(buf: &[u8]) -> $t {
+                let mut bytes = [0; mem::size_of::<$t>()];
+                bytes.copy_from_slice(&buf[..mem::size_of::<$t>()]);
+                <$t>::from_le_bytes(bytes)
             }
 
-            unsafe fn write_le(self, buf: *mut u8) {
-                let ptr = mem::transmute::<*mut u8, &mut $t>(buf);
-                *ptr = num::PrimInt::to_le(self);
+            fn write_le(self, buf: &mut [u8]) {
+                buf[..mem::size_of::<$t>()].copy_from_slice(&self.to_le_bytes());
             }
 
-            fn from_le(self) -> $t { num::PrimInt::from_le(self) }
+            fn from_le(self) -> $t { <$t>::from_le(self) }
 
-            fn to_le(self) -> $t { num::PrimInt::to_le(self) }
+            fn to_le(self) -> $t { <$t>::to_le(self) }
         }
     }
 }
@@ -109,139 +131,92 @@ impl_endian_for!(isize);
 
 /// This implementation assumes that the endianness of the FPU is the same as for integers.
 impl Endian for f32 {
-    fn from_le(self) -> f32 {
-        unsafe {
-            let u = mem::transmute::<f32, u32>(self);
-            mem::transmute::<u32, f32>(num::PrimInt::from_le(u))
-        }
-    }
+    fn from_le(self) -> f32 { f32::from_bits(u32::from_le(self.to_bits())) }
 
-    fn to_le(self) -> f32 {
-        unsafe {
-            let u = mem::transmute::<f32, u32>(self);
-            mem::transmute::<u32, f32>(num::PrimInt::to_le(u))
-        }
-    }
+    fn to_le(self) -> f32 { f32::from_bits(u32::to_le(self.to_bits())) }
 
-    unsafe fn read_le(buf: *const u8) -> f32 {
-        let ptr = mem::transmute::<*const u8, &u32>(buf);
-        mem::transmute::<u32, f32>(num::PrimInt::from_le(*ptr))
+    fn read_le(buf: &[u8]) -> f32 {
+        f32::from_bits(u32::read_le(buf))
     }
 
-    unsafe fn write_le(self, buf: *mut u8) {
-        let ptr = mem::transmute::<*mut u8, &mut u32>(buf);
-        *ptr = num::PrimInt::to_le(mem::transmute::<f32, u32>(self));
+    fn write_le(self, buf: &mut [u8]) {
+        self.to_bits().write_le(buf)
     }
 }
 
 /// This implementation assumes that the endianness of the FPU is the same as for integers.
 impl Endian for f64 {
-    fn from_le(self) -> f64 {
-        unsafe {
-            let u = mem::transmute::<f64, u64>(self);
-            mem::transmute::<u64, f64>(num::PrimInt::from_le(u))
-        }
-    }
+    fn from_le(self) -> f64 { f64::from_bits(u64::from_le(self.to_bits())) }
 
-    fn to_le(self) -> f64 {
-        unsafe {
-            let u = mem::transmute::<f64, u64>(self);
-            mem::transmute::<u64, f64>(num::PrimInt::to_le(u))
-        }
-    }
+    fn to_le(self) -> f64 { f64::from_bits(u64::to_le(self.to_bits())) }
 
-    unsafe fn read_le(buf: *const u8) -> f64 {
-        let ptr = mem::transmute::<*const u8, &u64>(buf);
-        mem::transmute::<u64, f64>(num::PrimInt::from_le(*ptr))
+    fn read_le(buf: &[u8]) -> f64 {
+        f64::from_bits(u64::read_le(buf))
     }
 
-    unsafe fn write_le(self, buf: *mut u8) {
-        let ptr = mem::transmute::<*mut u8, &mut u64>(buf);
-        *ptr = num::PrimInt::to_le(mem::transmute::<f64, u64>(self));
+    fn write_le(self, buf: &mut [u8]) {
+        self.to_bits().write_le(buf)
     }
 }
 
-impl<T> Endian for Offset<T> {
-    fn from_le(self) -> Offset<T> {
-        Offset::new(num::PrimInt::from_le(self.inner))
-    }
+impl Endian for bool {
+    fn from_le(self) -> bool { self }
 
-    fn to_le(self) -> Offset<T> {
-        Offset::new(num::PrimInt::to_le(self.inner))
-    }
+    fn to_le(self) -> bool { self }
 
-    unsafe fn read_le(buf: *const u8) -> Offset<T> {
-        let ptr = mem::transmute::<*const u8, &UOffset>(buf);
-        Offset::new(num::PrimInt::from_le(*ptr))
+    fn read_le(buf: &[u8]) -> bool {
+        buf[0] != 0
     }
 
-    unsafe fn write_le(self, buf: *mut u8) {
-        let ptr = mem::transmute::<*mut u8, &mut UOffset>(buf);
-        *ptr = num::PrimInt::to_le(self.inner)
+    fn write_le(self, buf: &mut [u8]) {
+        buf[0] = self as u8;
     }
 }
 
-// If `base` were a pointer to an array of type T, return a pointer to element `idx` of that array.
-unsafe fn index<T>(base: *const u8, idx: usize) -> *const u8 {
-    let base_us = mem::transmute::<*const u8, usize>(base);
-
-    mem::transmute::<usize, *const u8>(base_us + idx * mem::size_of::<T>())
+// Read the `UOffset` at `pos` in `buf`, and return the position that it refers to.
+fn follow_offset(buf: &[u8], pos: usize) -> usize {
+    pos + UOffset::read_le(&buf[pos..]) as usize
 }
 
-// Return a pointer to a byte whose address is `off` bytes beyond `base`.
-unsafe fn offset(base: *const u8, off: usize) -> *const u8 {
-    let base_us = mem::transmute::<*const u8, usize>(base);
-
-    mem::transmute::<usize, *const u8>(base_us + off)
-}
-
-// This is like `offset`, except it returns a mutable pointer.
-unsafe fn offset_mut(base: *mut u8, off: usize) -> *mut u8 {
-    let base_us = mem::transmute::<*mut u8, usize>(base);
-
-    mem::transmute::<usize, *mut u8>(base_us + off)
+/// A trait for types which are views of an object (a table, string or vector) in a buffer. A view
+/// borrows the buffer, so it can't outlive it.
+pub trait View<'buf>: Sized {
+    /// View the object which begins at byte `pos` of `buf`.
+    fn view(buf: &'buf [u8], pos: usize) -> Self;
 }
 
-// This is like `offset` except it allows negative offsets.
-unsafe fn soffset(base: *const u8, off: isize) -> *const u8 {
-    let base_is = mem::transmute::<*const u8, isize>(base);
-
-    mem::transmute::<isize, *const u8>(base_is + off)
-}
+/// A trait which determines how the elements of a `Vector` are retrieved from a flatbuffer. See
+/// the implementations for `T`, `Offset<T>`, and `ByRef<T>` for examples.
+pub trait Indirect<'buf> {
+    /// The type of value produced by reading an element.
+    type Read;
 
-// Read a little endian `T` pointed to by `buf`. `buf` must point to a properly aligned value.
-unsafe fn read_scalar<T: Endian>(buf: *const u8) -> T {
-    Endian::read_le(buf)
-}
+    /// The number of bytes each element takes up in the vector.
+    const SIZE: usize;
 
-// Write a little endian `T` to the buffer pointer to by `buf`. `buf` must point to a properly
-// aligned buffer.
-unsafe fn write_scalar<T: Endian>(buf: *mut u8, val: T) {
-    val.write_le(buf)
+    /// Read the element stored at `pos` in `buf`.
+    fn read(buf: &'buf [u8], pos: usize) -> Self::Read;
 }
 
-/// A trait which determines how a type is retrieved from a flatbuffer. See the implementations for
-/// `T`, `Offset<T>`, and `ByRef<T>` for examples.
-pub trait Indirect<I> {
-    unsafe fn read(buf: *const u8, idx: usize) -> I;
-}
+impl<'buf, T: Endian> Indirect<'buf> for T {
+    type Read = T;
 
-impl<T: Copy> Indirect<T> for T {
-    unsafe fn read(buf: *const u8, idx: usize) -> T {
-        let off = idx * mem::size_of::<T>();
-        let ptr = mem::transmute::<*const u8, &T>(offset(buf, off));
+    const SIZE: usize = mem::size_of::<T>();
 
-        *ptr
+    fn read(buf: &'buf [u8], pos: usize) -> T {
+        T::read_le(&buf[pos..])
     }
 }
 
+/// The location of an object in a buffer which is being built, measured from the end of the
+/// buffer.
 pub struct Offset<T> {
     inner: UOffset,
     _t:    marker::PhantomData<T>,
 }
 
 impl<T> Clone for Offset<T> {
-    fn clone(&self) -> Self { Offset::new(self.inner) }
+    fn clone(&self) -> Self { *self }
 }
 
 impl<T> Copy for Offset<T> {}
@@ -250,18 +225,27 @@ impl<T> PartialEq for Offset<T> {
     fn eq(&self, other: &Self) -> bool { self.inner == other.inner }
 }
 
+/// Vectors of `ByRef<T>` hold structs of type `T` inline. Elements are read by copying them out of
+/// the buffer, since it may not be aligned in memory.
 pub struct ByRef<T>(marker::PhantomData<T>);
 
-impl <'x, T> Indirect<&'x T> for ByRef<T> {
-    unsafe fn read(buf: *const u8, idx: usize) -> &'x T {
-        mem::transmute::<*const u8, &'x T>(index::<T>(buf, idx))
+impl<'buf, T: Struct> Indirect<'buf> for ByRef<T> {
+    type Read = T;
+
+    const SIZE: usize = mem::size_of::<T>();
+
+    fn read(buf: &'buf [u8], pos: usize) -> T {
+        read_struct(buf, pos)
     }
 }
 
-impl<'x, T> Indirect<&'x T> for Offset<T> {
-    unsafe fn read(buf: *const u8, idx: usize) -> &'x T {
-        let off: UOffset = read_scalar(index::<UOffset>(buf, idx));
-        mem::transmute::<*const u8, &'x T>(offset(buf, off as usize))
+impl<'buf, T: View<'buf>> Indirect<'buf> for Offset<T> {
+    type Read = T;
+
+    const SIZE: usize = mem::size_of::<UOffset>();
+
+    fn read(buf: &'buf [u8], pos: usize) -> T {
+        T::view(buf, follow_offset(buf, pos))
     }
 }
 
@@ -272,637 +256,608 @@ impl<T> Offset<T> {
             _t:    marker::PhantomData,
         }
     }
+
+    /// The raw offset, measured from the end of the buffer.
+    pub fn value(&self) -> UOffset {
+        self.inner
+    }
 }
 
 /// A helper type for accessing vectors in flatbuffers.
-pub struct Vector<T, I = T> where T: Indirect<I> {
-    length: UOffset,
-    _t:     marker::PhantomData<T>,
-    _i:     marker::PhantomData<I>,
+pub struct Vector<'buf, T> {
+    buf: &'buf [u8],
+    pos: usize,
+    _t:  marker::PhantomData<T>,
+}
+
+impl<'buf, T> Clone for Vector<'buf, T> {
+    fn clone(&self) -> Self { *self }
+}
+
+impl<'buf, T> Copy for Vector<'buf, T> {}
+
+impl<'buf, T> View<'buf> for Vector<'buf, T> {
+    fn view(buf: &'buf [u8], pos: usize) -> Vector<'buf, T> {
+        Vector {
+            buf,
+            pos,
+            _t: marker::PhantomData,
+        }
+    }
 }
 
 /// An iterator to a Vector in a flatbuffer.
-pub struct VecIter<'x, I: 'x, T: Indirect<I> + 'x> {
-    vec: &'x Vector<T, I>,
+pub struct VecIter<'buf, T> {
+    vec: Vector<'buf, T>,
     idx: usize,
 }
 
-impl<'x, I, T: Indirect<I>> Iterator for VecIter<'x, I, T> {
-    type Item = I;
+impl<'buf, T: Indirect<'buf>> Iterator for VecIter<'buf, T> {
+    type Item = T::Read;
 
-    fn next(&mut self) -> Option<I> {
+    fn next(&mut self) -> Option<T::Read> {
         let idx = self.idx;
         self.idx = idx + 1;
 
         self.vec.get(idx)
     }
-}
 
-impl<I, T: Indirect<I>> Vector<T, I> {
-    unsafe fn data(&self) -> *const u8 {
-        index::<UOffset>(mem::transmute::<&Vector<T, I>, *const u8>(self), 1)
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let left = self.vec.len().saturating_sub(self.idx);
+        (left, Some(left))
     }
+}
 
+impl<'buf, T> Vector<'buf, T> {
     pub fn len(&self) -> usize {
-        self.length as usize
+        UOffset::read_le(&self.buf[self.pos..]) as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<'buf, T: Indirect<'buf>> Vector<'buf, T> {
+    // The bytes which make up the elements of the vector.
+    fn data(&self) -> &'buf [u8] {
+        &self.buf[self.pos + mem::size_of::<UOffset>()..][..self.len() * T::SIZE]
     }
 
-    pub fn get(&self, idx: usize) -> Option<I> {
+    pub fn get(&self, idx: usize) -> Option<T::Read> {
         if idx < self.len() {
-            Some(unsafe { <T as Indirect<I>>::read(self.data(), idx) })
+            Some(T::read(self.buf, self.pos + mem::size_of::<UOffset>() + idx * T::SIZE))
         } else {
             None
         }
     }
 
-    pub fn iter<'x>(&'x self) -> VecIter<'x, I, T> {
+    pub fn iter(&self) -> VecIter<'buf, T> {
         VecIter {
-            vec: self,
+            vec: *self,
             idx: 0,
         }
     }
 }
 
-pub type Str = Vector<i8>;
+impl<'buf, T: Indirect<'buf>> IntoIterator for Vector<'buf, T> {
+    type Item     = T::Read;
+    type IntoIter = VecIter<'buf, T>;
 
-impl AsRef<str> for Str {
-    fn as_ref(&self) -> &str {
-        let slc = unsafe {
-            let ptr = self.data();
-            let len = self.len();
-
-            slice::from_raw_parts(ptr, len)
-        };
-
-        // TODO: Should this be the checked version? If so, do we want to panic if it's not utf-8?
-        //
-        //       This (unchecked) version certainly reflects the performance characteristics in the
-        //       spirit of the format. Maybe the `AsRef<str>` implementation should be checked, and
-        //       there should be an unsafe fast method?
-        //
-        //       I'll think about it later...
-        unsafe { str::from_utf8_unchecked(slc) }
+    fn into_iter(self) -> VecIter<'buf, T> {
+        self.iter()
     }
 }
 
-impl PartialEq for Str {
-    fn eq(&self, other: &Str) -> bool {
-        let (a, b): (&str, &str) = (self.as_ref(), other.as_ref());
-        a.eq(b)
+impl<'buf, T: OrdTable + View<'buf>> Vector<'buf, Offset<T>> {
+    /// Find the table whose key is equal to `key`'s with a binary search. The vector must have
+    /// been built with `create_vector_of_sorted_tables`.
+    pub fn lookup_by_key(&self, key: &T) -> Option<T> {
+        let key = key.table();
+        self.binary_search_by(|t| T::key_cmp(t, key))
     }
-}
 
-impl PartialOrd for Str {
-    fn partial_cmp(&self, other: &Str) -> Option<cmp::Ordering> {
-        let (a, b): (&str, &str) = (self.as_ref(), other.as_ref());
-        a.partial_cmp(b)
+    /// Like `lookup_by_key`, but compare with the value of the key field itself.
+    pub fn lookup_by_key_value(&self, key: &T::Key) -> Option<T> {
+        self.binary_search_by(|t| T::key_cmp_with(t, key))
     }
-}
-
-impl Eq for Str {}
-
-impl Ord for Str {
-    fn cmp(&self, other: &Str) -> cmp::Ordering {
-        let (a, b): (&str, &str) = (self.as_ref(), other.as_ref());
-        a.cmp(b)
-    }
-}
-
-pub struct Table;
 
-impl Table {
-    fn get_optional_field_offset(&self, field: VOffset) -> Option<VOffset> {
-        unsafe {
-            let base = mem::transmute::<&Table, *const u8>(self);
+    // Find the element for which `f` returns `Equal`, given that it returns `Less` for every
+    // element before it and `Greater` for every element after it.
+    fn binary_search_by<F>(&self, mut f: F) -> Option<T>
+        where F: FnMut(Table<'buf>) -> cmp::Ordering {
 
-            // I'm not suire why it's subtraction, instead of addition, but this is what they have in
-            // the C++ code.
-            let vtable = soffset(base, -read_scalar::<SOffset>(base) as isize);
+        let (mut lo, mut hi) = (0, self.len());
 
-            let vtsize: VOffset = read_scalar(vtable);
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            let pos = follow_offset(self.buf, self.pos + mem::size_of::<UOffset>() * (mid + 1));
 
-            if field < vtsize {
-                let voff = read_scalar(offset(vtable, field as usize));
-                if voff != 0 {
-                    return Some(voff)
-                }
+            match f(Table::view(self.buf, pos)) {
+                cmp::Ordering::Less    => lo = mid + 1,
+                cmp::Ordering::Greater => hi = mid,
+                cmp::Ordering::Equal   => return Some(T::view(self.buf, pos)),
             }
-
-            None
         }
-    }
-
-    pub fn get_field<T: Endian>(&self, field: VOffset, def: T) -> T {
-
-        self.get_optional_field_offset(field)
-            .map_or(def, |voffs| unsafe {
-                let base = mem::transmute::<&Table, *const u8>(self);
-                read_scalar(offset(base, voffs as usize))
-            } )
-    }
 
-    pub fn get_ref<T>(&self, field: VOffset) -> Option<&T> {
-        self.get_optional_field_offset(field)
-            .map(|voffs| unsafe {
-                let base = mem::transmute::<&Table, *const u8>(self);
-                let p    = offset(base, voffs as usize);
-                let offs: UOffset = read_scalar(p);
-                mem::transmute::<*const u8, &T>(offset(p, offs as usize))
-            })
+        None
     }
+}
 
-    pub fn get_ref_mut<T>(&mut self, field: VOffset) -> Option<&mut T> {
-        self.get_optional_field_offset(field)
-            .map(|voffs| unsafe {
-                let base = mem::transmute::<&mut Table, *mut u8>(self);
-                let p    = offset_mut(base, voffs as usize);
-                let offs: UOffset = read_scalar(p);
-                mem::transmute::<*mut u8, &mut T>(offset_mut(p, offs as usize))
-            })
-    }
+pub type Str<'buf> = Vector<'buf, i8>;
 
-    pub fn get_struct<T>(&self, field: VOffset) -> Option<&T> {
-        self.get_optional_field_offset(field)
-            .map(|voffs| unsafe {
-                let base = mem::transmute::<&Table, *const u8>(self);
-                mem::transmute::<*const u8, &T>(offset(base, voffs as usize))
-            })
+impl<'buf> Str<'buf> {
+    /// The bytes of the string, not including the *NUL* terminator.
+    pub fn as_bytes(&self) -> &'buf [u8] {
+        self.data()
     }
 
-    pub fn get_struct_mut<T>(&mut self, field: VOffset) -> Option<&mut T> {
-        self.get_optional_field_offset(field)
-            .map(|voffs| unsafe {
-                let base = mem::transmute::<&mut Table, *mut u8> (self);
-                mem::transmute::<*mut u8, &mut T>(offset_mut(base, voffs as usize))
-            })
+    /// The string, or an error if it isn't valid UTF-8. Buffers from an untrusted source can hold
+    /// anything, even if they pass verification.
+    pub fn to_str(&self) -> Result<&'buf str, str::Utf8Error> {
+        str::from_utf8(self.as_bytes())
     }
 
-    pub fn set_field<T: Endian>(&mut self, field: VOffset, val: T) {
-        unsafe {
-            // We `unwrap` here because the caller is expected to verify that the field exists
-            // beforehand by calling `check_field`.
-            let voffs = self.get_optional_field_offset(field).unwrap();
-
-            let base = mem::transmute::<&mut Table, *mut u8>(self);
-
-            write_scalar(offset_mut(base, voffs as usize), val);
-        }
+    /// The string, without checking that it's valid UTF-8.
+    ///
+    /// # Safety
+    ///
+    /// The string must be valid UTF-8, e.g. because the buffer was built locally or has already
+    /// been checked with `to_str`.
+    pub unsafe fn to_str_unchecked(&self) -> &'buf str {
+        str::from_utf8_unchecked(self.as_bytes())
     }
+}
 
-    pub fn check_field(&self, field: VOffset) -> bool {
-        self.get_optional_field_offset(field).is_some()
+impl<'buf> AsRef<[u8]> for Str<'buf> {
+    fn as_ref(&self) -> &[u8] {
+        self.as_bytes()
     }
 }
 
-/// A trait for Tables which can be compared for order (i.e. which have a field with the `key`
-/// attribute).
-pub trait OrdTable {
-    fn key_cmp(&self, rhs: &Self) -> cmp::Ordering;
+impl<'buf> PartialEq for Str<'buf> {
+    fn eq(&self, other: &Str<'buf>) -> bool {
+        self.as_bytes() == other.as_bytes()
+    }
 }
 
-/// This type is used internally by the generated types for flatbuffer structs. Its methods allow
-/// access to various different types of struct fields.
-pub struct Struct;
-
-impl Struct {
-    /// Return a scalar field, reading it directly from the buffer.
-    pub fn get_field<T: Endian>(&self, off: UOffset) -> T {
-        unsafe {
-            let base = mem::transmute::<&Struct, *const u8>(self);
-            read_scalar(offset(base, off as usize))
-        }
+impl<'buf> PartialOrd for Str<'buf> {
+    fn partial_cmp(&self, other: &Str<'buf>) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
     }
+}
 
-    /// Return a reference to a field which is stored as a `UOffset`.
-    ///
-    /// # Notes
-    ///
-    /// Is this function ever used? Aren't structs supposed to be fixed-size?
-    pub fn get_ref<T>(&self, off: UOffset) -> &T {
-        unsafe {
-            let base = mem::transmute::<&Struct, *const u8>(self);
-            let p    = offset(base, off as usize);
+impl<'buf> Eq for Str<'buf> {}
 
-            mem::transmute::<*const u8, &T>(offset(p, read_scalar::<UOffset>(p) as usize))
-        }
+/// Strings are ordered by their bytes, which is the same as ordering by `str` for valid UTF-8.
+impl<'buf> Ord for Str<'buf> {
+    fn cmp(&self, other: &Str<'buf>) -> cmp::Ordering {
+        self.as_bytes().cmp(other.as_bytes())
     }
+}
 
-    /// Like `get_ref`, but the reference is mutable.
-    pub fn get_ref_mut<T>(&mut self, off: UOffset) -> &mut T {
-        unsafe {
-            let base = mem::transmute::<&mut Struct, *mut u8>(self);
-            let p    = offset_mut(base, off as usize);
-
-            mem::transmute::<*mut u8, &mut T>(offset_mut(p, read_scalar::<UOffset>(p) as usize))
-        }
-    }
+// Find the position of `field` in the table at `pos`, if it's present.
+fn field_pos(buf: &[u8], pos: usize, field: VOffset) -> Option<usize> {
+    // I'm not suire why it's subtraction, instead of addition, but this is what they have in the
+    // C++ code.
+    let vtable = (pos as isize).wrapping_sub(SOffset::read_le(&buf[pos..]) as isize) as usize;
 
-    /// Get a pointer to a struct field.
-    pub fn get_struct<T>(&self, off: UOffset) -> &T {
-        unsafe {
-            let base = mem::transmute::<&Struct, *const u8>(self);
+    let vtsize = VOffset::read_le(&buf[vtable..]);
 
-            mem::transmute::<*const u8, &T>(offset(base, off as usize))
+    if field < vtsize {
+        let voff = VOffset::read_le(&buf[vtable + field as usize..]);
+        if voff != 0 {
+            return Some(pos + voff as usize)
         }
     }
 
-    /// Like `get_struct`, but the reference is mutable.
-    pub fn get_struct_mut<T>(&mut self, off: UOffset) -> &mut T {
-        unsafe {
-            let base = mem::transmute::<&mut Struct, *mut u8>(self);
-
-            mem::transmute::<*mut u8, &mut T>(offset_mut(base, off as usize))
-        }
-    }
+    None
 }
 
-/// Return a pointer to the root object stored in this buffer, interpreting it as type `T`.
-pub fn get_root<T>(buf: &[u8]) -> &T {
-    unsafe {
-        let base         = buf.as_ptr();
-        let off: UOffset = Endian::read_le(base);
+/// A table in a buffer. The generated types for tables wrap this, and use its methods to read
+/// their fields.
+#[derive(Clone, Copy)]
+pub struct Table<'buf> {
+    buf: &'buf [u8],
+    pos: usize,
+}
 
-        mem::transmute::<*const u8, &T>(offset(base, off as usize))
+impl<'buf> View<'buf> for Table<'buf> {
+    fn view(buf: &'buf [u8], pos: usize) -> Table<'buf> {
+        Table { buf, pos }
     }
 }
 
-// Reverse-growing vector which piggy-backs on std::vec::Vec.
-struct VecDownward {
-    inner: Vec<u8>,
-    next:  usize,
-}
+impl<'buf> Table<'buf> {
+    /// The buffer which holds this table.
+    pub fn buf(&self) -> &'buf [u8] { self.buf }
 
-impl VecDownward {
-    fn new(initial_capacity: usize) -> VecDownward {
-        let mut vec = Vec::with_capacity(initial_capacity);
-        unsafe { vec.set_len(initial_capacity) }
+    /// The position of this table in its buffer.
+    pub fn pos(&self) -> usize { self.pos }
 
-        VecDownward {
-            inner: vec,
-            next:  initial_capacity,
-        }
+    /// The position in the buffer of `field`, if it's present in this table.
+    pub fn field_pos(&self, field: VOffset) -> Option<usize> {
+        field_pos(self.buf, self.pos, field)
     }
 
-    fn data(&self) -> &[u8] { &self.inner[self.next..] }
-
-    fn data_mut(&mut self) -> &mut [u8] { &mut self.inner[self.next..] }
-
-    fn data_at(&self, offset: usize) -> &[u8] {
-        &self.inner[self.inner.len() - offset..]
+    pub fn get_field<T: Endian>(&self, field: VOffset, def: T) -> T {
+        self.field_pos(field).map_or(def, |p| T::read_le(&self.buf[p..]))
     }
 
-    fn data_at_mut(&mut self, offset: usize) -> &mut [u8] {
-        let len = self.inner.len();
-        &mut self.inner[len - offset..]
+    /// Follow the offset stored in `field` to a table, string or vector.
+    pub fn get_ref<T: View<'buf>>(&self, field: VOffset) -> Option<T> {
+        self.field_pos(field).map(|p| T::view(self.buf, follow_offset(self.buf, p)))
     }
 
-    fn len(&self) -> usize { self.inner.len() - self.next }
-
-    fn clear(&mut self) {
-        self.next = self.inner.len();
+    pub fn get_struct<T: Struct>(&self, field: VOffset) -> Option<T> {
+        self.field_pos(field).map(|p| read_struct(self.buf, p))
     }
 
-    // Adds space to the front of the vector, growing towards lower addresses. The returned `usize`
-    // is the offset from the end of the buffer (e.g. the highest address).
-    fn make_space(&mut self, len: usize) -> usize {
-        if len > self.next {
-            let mut new = Vec::with_capacity(2*self.len() + len);
+    pub fn check_field(&self, field: VOffset) -> bool {
+        self.field_pos(field).is_some()
+    }
 
-            unsafe { new.set_len(2*self.len() + len) }
+    /// Read a union made up of a `u8` type tag in `type_field` and an offset to the value in
+    /// `value_field`. Returns `None` if the union isn't set.
+    pub fn get_union<U: Union<'buf>>(&self, type_field: VOffset, value_field: VOffset)
+        -> Option<U> {
 
-            let new_next = new.len() - self.len();
+        let tag: u8 = self.get_field(type_field, 0);
 
-            for i in 0..self.len() {
-                new[new_next + i] = self.inner[self.next + i]
-            }
-
-            self.inner = new;
-            self.next = new_next;
+        if tag == 0 {
+            return None
         }
 
-        self.next -= len;
-
-        self.next
+        self.get_ref(value_field).map(|t| U::from_table(tag, t))
     }
 
-    // Append some raw bytes to the front of the buffer.
-    fn push(&mut self, dat: &[u8]) {
-        let off = self.make_space(dat.len());
+    /// Read a vector of unions, made up of a vector of type tags in `types_field` and a vector of
+    /// offsets in `values_field`. Panics if the two have different lengths, which the verifier
+    /// rejects.
+    pub fn get_union_vector<U: Union<'buf>>(&self, types_field: VOffset, values_field: VOffset)
+        -> Option<UnionVector<'buf, U>> {
 
-        for i in 0..dat.len() {
-            self.inner[off + i] = dat[i];
-        }
-    }
+        let types  = self.get_ref::<Vector<'buf, u8>>(types_field);
+        let values = self.get_ref::<Vector<'buf, Offset<Table<'buf>>>>(values_field);
 
-    // Add `len` *NUL* bytes to the front of the buffer.
-    fn fill(&mut self, len: usize) {
-        let off = self.make_space(len);
+        match (types, values) {
+            (Some(types), Some(values)) => {
+                check_union_lengths(types.len(), values.len());
 
-        for i in 0..len {
-            self.inner[off + i] = 0;
+                Some(UnionVector {
+                    types,
+                    values,
+                    _u: marker::PhantomData,
+                })
+            }
+            _ => None,
         }
     }
-
-    // Remove `len` bytes from the front of the buffer.
-    fn pop(&mut self, len: usize) {
-        self.next += len;
-    }
-}
-
-// Given a field's ID number, convert it to a VOffset
-fn field_index_to_offset(field_id: VOffset) -> VOffset {
-    let fixed_fields = 2; // VTable size and Object size.
-    (field_id + fixed_fields) * (mem::size_of::<VOffset>() as VOffset)
-}
-
-// Return the number of bytes needed to pad a scalar for alignment (see usage in e.g.
-// `FlatBufferBuilder::align(..)`).
-fn padding_bytes(buf_size: usize, scalar_size: usize) -> usize {
-    (!buf_size).wrapping_add(1) & (scalar_size - 1)
-}
-
-// The location of a field, stored as a UOffset from the end of the buffer and a field ID.
-struct FieldLoc {
-    off: UOffset,
-    id:  VOffset,
 }
 
-/// This type is used by the generated `.*Builder` types for Tables. A `FlatBufferBuilder` can be
-/// re-used if the `clear()` method is called between uses; this will avoid some allocations.
-pub struct FlatBufferBuilder {
-    buf:            VecDownward,
-    offset_buf:     Vec<FieldLoc>,
-    vtables:        Vec<UOffset>,
-    min_align:      usize,
-    force_defaults: bool,
+/// A table in a mutable buffer, whose fields can be changed in place. The generated `*Mut` types
+/// for tables wrap this.
+pub struct TableMut<'buf> {
+    buf: &'buf mut [u8],
+    pos: usize,
 }
 
-impl FlatBufferBuilder {
-    pub fn new(initial_capacity: usize) -> FlatBufferBuilder {
-        FlatBufferBuilder {
-            buf:            VecDownward::new(initial_capacity),
-            offset_buf:     Vec::with_capacity(16),
-            vtables:        Vec::with_capacity(16),
-            min_align:      1,
-            force_defaults: false,
-        }
-    }
-
-    /// Prepare to build another FlatBuffer from scratch (forgetting everything about any previous
-    /// FlatBuffer), but reuse the memory from the internal buffers to avoid extra reallocations.
-    pub fn clear(&mut self) {
-        self.buf.clear();
-        self.offset_buf.clear();
-        self.vtables.clear();
-        self.min_align = 1;
+impl<'buf> TableMut<'buf> {
+    pub fn new(buf: &'buf mut [u8], pos: usize) -> TableMut<'buf> {
+        TableMut { buf, pos }
     }
 
-    pub fn get_size(&self) -> usize {
-        self.buf.len()
+    /// A read-only view of the table.
+    pub fn as_table(&self) -> Table<'_> {
+        Table::view(self.buf, self.pos)
     }
 
-    pub fn get_buffer(&self) -> &[u8] { self.buf.data() }
-
-    /// Determines whether or not default values should be hard-coded into the wire representation.
-    pub fn force_defaults(&mut self, fd: bool) {
-        self.force_defaults = fd;
+    /// Set a scalar field. Only fields which are present in the buffer can be changed, so this
+    /// returns `false` (and does nothing) if the field isn't.
+    pub fn set_field<T: Endian>(&mut self, field: VOffset, val: T) -> bool {
+        match field_pos(self.buf, self.pos, field) {
+            Some(p) => {
+                val.write_le(&mut self.buf[p..]);
+                true
+            }
+            None => false,
+        }
     }
 
-    pub fn pad(&mut self, num_bytes: usize) {
-        self.buf.fill(num_bytes);
+    /// Set a struct field. Like `set_field`, this returns `false` (and does nothing) if the field
+    /// isn't present in the buffer.
+    pub fn set_struct<T: Struct>(&mut self, field: VOffset, val: T) -> bool {
+        match field_pos(self.buf, self.pos, field) {
+            Some(p) => {
+                write_struct(self.buf, p, val);
+                true
+            }
+            None => false,
+        }
     }
 
-    pub fn align(&mut self, elem_size: usize) {
-        if elem_size > self.min_align {
-            self.min_align = elem_size;
+    /// A vector field, whose elements can be changed in place.
+    pub fn get_vector_mut<T>(&mut self, field: VOffset) -> Option<VectorMut<'_, T>> {
+        match field_pos(self.buf, self.pos, field) {
+            Some(p) => Some(VectorMut::new(self.buf, follow_offset(self.buf, p))),
+            None    => None,
         }
+    }
+}
 
-        let len = self.buf.len();
+/// A vector in a mutable buffer, whose elements can be changed in place. Only scalars and structs
+/// can be changed, since changing anything else would mean moving things around in the buffer.
+pub struct VectorMut<'buf, T> {
+    buf: &'buf mut [u8],
+    pos: usize,
+    _t:  marker::PhantomData<T>,
+}
 
-        self.buf.fill(padding_bytes(len, elem_size));
+impl<'buf, T> VectorMut<'buf, T> {
+    pub fn new(buf: &'buf mut [u8], pos: usize) -> VectorMut<'buf, T> {
+        VectorMut {
+            buf,
+            pos,
+            _t: marker::PhantomData,
+        }
     }
 
-    pub fn push_bytes(&mut self, dat: &[u8]) {
-        self.buf.push(dat);
+    /// A read-only view of the vector.
+    pub fn as_vector(&self) -> Vector<'_, T> {
+        Vector::view(self.buf, self.pos)
     }
 
-    pub fn pop_bytes(&mut self, len: usize) {
-        self.buf.pop(len)
+    pub fn len(&self) -> usize {
+        UOffset::read_le(&self.buf[self.pos..]) as usize
     }
 
-    pub fn push_scalar<T: Endian>(&mut self, elem: T) -> usize {
-        let little = elem.to_le();
-
-        self.align(mem::size_of::<T>());
-
-        self.buf.push(view_bytes(&little));
-
-        self.get_size()
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
     }
 
-    pub fn push_offset<T>(&mut self, off: Offset<T>) -> usize {
-        let adjusted = self.refer_to(off.inner);
-        self.push_scalar(adjusted)
+    // The position of the `idx`th element, if there is one, for elements which are `size` bytes.
+    fn elem_pos(&self, idx: usize, size: usize) -> Option<usize> {
+        if idx < self.len() {
+            Some(self.pos + mem::size_of::<UOffset>() + idx * size)
+        } else {
+            None
+        }
     }
+}
 
-    pub fn refer_to(&mut self, off: UOffset) -> UOffset {
-        self.align(mem::size_of::<UOffset>());
-        let buf_size = self.get_size() as UOffset;
-
-        assert!(off <= buf_size);
-
-        buf_size - off + (mem::size_of::<UOffset>() as UOffset)
+impl<T: Endian> VectorMut<'_, T> {
+    /// Set the element at `idx`. This returns `false` (and does nothing) if `idx` is out of
+    /// bounds.
+    pub fn set(&mut self, idx: usize, val: T) -> bool {
+        match self.elem_pos(idx, mem::size_of::<T>()) {
+            Some(p) => {
+                val.write_le(&mut self.buf[p..]);
+                true
+            }
+            None => false,
+        }
     }
+}
 
-    pub fn track_field(&mut self, field: VOffset, off: UOffset) {
-        self.offset_buf.push(FieldLoc{off: off, id: field})
+impl<T: Struct> VectorMut<'_, ByRef<T>> {
+    /// Set the struct at `idx`. This returns `false` (and does nothing) if `idx` is out of bounds.
+    pub fn set(&mut self, idx: usize, val: T) -> bool {
+        match self.elem_pos(idx, mem::size_of::<T>()) {
+            Some(p) => {
+                write_struct(self.buf, p, val);
+                true
+            }
+            None => false,
+        }
     }
 
-    pub fn add_scalar<T: Endian>(&mut self, field: VOffset, e: T, def: T) {
-        if e == def && !self.force_defaults { return }
-
-        let off = self.push_scalar(e) as UOffset;
+    /// A mutable view of the struct at `idx`, or `None` if `idx` is out of bounds.
+    pub fn get_mut(&mut self, idx: usize) -> Option<StructMut<'_, T>> {
+        let pos = self.elem_pos(idx, mem::size_of::<T>())?;
 
-        self.track_field(field, off);
+        Some(StructMut {
+            buf: self.buf,
+            pos,
+            _t: marker::PhantomData,
+        })
     }
+}
 
-    pub fn add_offset<T>(&mut self, field: VOffset, off: Offset<T>) {
-        if off.inner == 0 { return }
+/// A struct inside a mutable buffer. Buffers needn't be aligned, so this can't be a `&mut T`:
+/// the struct is copied out with `get` and written back with `set` (or both, with `update`).
+pub struct StructMut<'buf, T> {
+    buf: &'buf mut [u8],
+    pos: usize,
+    _t:  marker::PhantomData<T>,
+}
 
-        let adjusted = self.refer_to(off.inner);
-        self.add_scalar(field, adjusted, 0);
+impl<T: Struct> StructMut<'_, T> {
+    pub fn get(&self) -> T {
+        read_struct(self.buf, self.pos)
     }
 
-    pub fn add_struct<T>(&mut self, field: VOffset, ptr: &T) {
-        self.align(mem::align_of::<T>());
-        self.push_bytes(view_bytes(ptr));
-
-        let off = self.get_size() as UOffset;
-        self.track_field(field, off);
+    pub fn set(&mut self, val: T) {
+        write_struct(self.buf, self.pos, val)
     }
 
-    pub fn add_struct_offset(&mut self, field: VOffset, off: UOffset) {
-        self.track_field(field, off);
+    /// Change the struct in place with `f`, e.g. `s.update(|v| v.set_x(1.0))`.
+    pub fn update<F: FnOnce(&mut T)>(&mut self, f: F) {
+        let mut val = self.get();
+        f(&mut val);
+        self.set(val)
     }
+}
 
-    pub fn not_nested(&self) {
-        assert_eq!(self.offset_buf.len(), 0);
-    }
+impl VectorMut<'_, u8> {
+    /// The elements of a `[ubyte]` vector, as bytes.
+    pub fn as_bytes_mut(&mut self) -> &mut [u8] {
+        let start = self.pos + mem::size_of::<UOffset>();
+        let len   = self.len();
 
-    pub fn start_table(&self) -> UOffset {
-        self.not_nested();
-        self.get_size() as UOffset
+        &mut self.buf[start..][..len]
     }
+}
 
-    pub fn end_table(&mut self, start: UOffset, num_fields: VOffset) -> UOffset {
-        let vtable_offset_loc = self.push_scalar::<SOffset>(0);
+/// A trait for Tables which can be compared for order (i.e. which have a field with the `key`
+/// attribute).
+pub trait OrdTable {
+    /// The type of the key field, as it's passed to `Vector::lookup_by_key_value` (`str` for
+    /// string keys).
+    type Key: ?Sized;
 
-        self.buf.fill((num_fields as usize) * mem::size_of::<VOffset>());
+    /// The table which holds this value.
+    fn table(&self) -> Table<'_>;
 
-        let table_object_size = vtable_offset_loc - (start as usize);
+    /// Compare the keys of two tables of this type.
+    fn key_cmp(a: Table, b: Table) -> cmp::Ordering;
 
-        assert!(table_object_size < 0x10000); // 16-bit offsets
+    /// Compare the key of a table of this type with `key`.
+    fn key_cmp_with(a: Table, key: &Self::Key) -> cmp::Ordering;
+}
 
-        self.push_scalar(table_object_size as VOffset);
-        self.push_scalar(field_index_to_offset(num_fields));
+/// A trait for the types generated for unions, which hold a view of one of the union's member
+/// tables.
+pub trait Union<'buf>: Sized {
+    /// Interpret `table` as the member of the union with type tag `tag`.
+    fn from_table(tag: u8, table: Table<'buf>) -> Self;
+}
 
-        for field_loc in self.offset_buf.iter() {
-            let pos = (vtable_offset_loc as VOffset) - (field_loc.off as VOffset);
+/// A trait for the types generated for building unions, which hold an offset to one of the union's
+/// member tables.
+pub trait UnionOffset: Copy {
+    /// The type tag of the member.
+    fn tag(&self) -> u8;
 
-            unsafe {
-                let buf_ref = &mut self.buf.data_mut()[field_loc.id as usize];
-                let buf_ptr = mem::transmute::<&mut u8, *mut u8>(buf_ref);
-                assert_eq!(read_scalar::<VOffset>(buf_ptr), 0);
-                write_scalar(buf_ptr, pos);
-            }
-        }
+    /// The offset of the member table.
+    fn value(&self) -> Offset<Table<'static>>;
+}
 
-        self.offset_buf.clear();
+/// `None` is the union's `NONE` member, which has a type tag of 0 and no value.
+impl<U: UnionOffset> UnionOffset for Option<U> {
+    fn tag(&self) -> u8 { self.map_or(0, |u| u.tag()) }
 
-        // What follows is the de-duping code. Might be able to speed this up with some kind of
-        // hash-table or something if it becomes a bottleneck since this implementation will take
-        // quadratic time WRT the number of distinct tables in the flatbuffer.
+    fn value(&self) -> Offset<Table<'static>> { self.map_or(Offset::new(0), |u| u.value()) }
+}
 
-        let vt1: &[VOffset] = unsafe {
-            let vt_ptr = mem::transmute::<&u8, *const VOffset>(&self.buf.data()[0]);
-            let vt_len = *vt_ptr as usize;
-            slice::from_raw_parts(vt_ptr, vt_len)
-        };
+/// A vector of unions, which is stored as a vector of type tags and a parallel vector of offsets to
+/// the values.
+pub struct UnionVector<'buf, U> {
+    types:  Vector<'buf, u8>,
+    values: Vector<'buf, Offset<Table<'buf>>>,
+    _u:     marker::PhantomData<U>,
+}
 
-        let mut vt_use = self.get_size() as UOffset;
+impl<'buf, U> Clone for UnionVector<'buf, U> {
+    fn clone(&self) -> Self { *self }
+}
 
-        for &off in self.vtables.iter() {
-            let vt2: &[VOffset] = unsafe {
-                let vt_ptr = mem::transmute::<&u8, *const VOffset>(&self.buf.data_at(off as usize)[0]);
-                let vt_len = *vt_ptr as usize;
-                slice::from_raw_parts(vt_ptr, vt_len)
-            };
+impl<'buf, U> Copy for UnionVector<'buf, U> {}
 
-            if vt1 == vt2 {
-                vt_use = off;
-                let to_pop = self.get_size() - vtable_offset_loc;
-                self.buf.pop(to_pop);
-                break;
-            }
-        }
+/// An iterator over a `UnionVector`. Elements whose type is `NONE` are returned as `None`.
+pub struct UnionVecIter<'buf, U> {
+    vec: UnionVector<'buf, U>,
+    idx: usize,
+}
 
-        if vt_use == self.get_size() as UOffset {
-            self.vtables.push(vt_use);
-        }
+impl<'buf, U: Union<'buf>> Iterator for UnionVecIter<'buf, U> {
+    type Item = Option<U>;
 
+    fn next(&mut self) -> Option<Option<U>> {
+        let idx = self.idx;
 
-        unsafe {
-            let vt_buf = &mut self.buf.data_at_mut(vtable_offset_loc as usize)[0];
-            write_scalar(mem::transmute::<*mut u8, &mut u8>(vt_buf),
-                (vt_use as SOffset) - (vtable_offset_loc as SOffset));
+        if idx < self.vec.len() {
+            self.idx = idx + 1;
+            Some(self.vec.get(idx))
+        } else {
+            None
         }
-
-        vtable_offset_loc as UOffset
-    }
-
-    pub fn pre_align(&mut self, len: usize, align: usize) {
-        let size = self.get_size();
-        self.buf.fill(padding_bytes(size + len, align));
     }
+}
 
-    pub fn create_string(&mut self, s: &str) -> Offset<Str> {
-        self.not_nested();
-
-        self.pre_align(s.len() + 1, mem::size_of::<UOffset>());
-        self.buf.fill(1);
-        self.push_bytes(s.as_bytes());
-        self.push_scalar(s.len() as UOffset);
-
-        Offset::new(self.get_size() as UOffset)
+impl<'buf, U: Union<'buf>> UnionVector<'buf, U> {
+    pub fn len(&self) -> usize {
+        self.types.len()
     }
 
-    pub fn start_vector(&mut self, len: usize, elem_size: usize) {
-        self.pre_align(len * elem_size, mem::size_of::<UOffset>());
-        self.pre_align(len * elem_size, elem_size);
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
     }
 
-    pub fn reserve_elements(&mut self, len: usize, elem_size: usize) -> usize {
-        self.buf.make_space(len * elem_size)
+    /// The type tag of the element at `idx`.
+    pub fn get_type(&self, idx: usize) -> Option<u8> {
+        self.types.get(idx)
     }
 
-    pub fn end_vector(&mut self, len: usize) -> UOffset {
-        self.push_scalar(len as UOffset) as UOffset
+    /// The element at `idx`, or `None` if it's out of range or its type is `NONE`.
+    pub fn get(&self, idx: usize) -> Option<U> {
+        match self.get_type(idx) {
+            Some(0) | None => None,
+            Some(tag)      => self.values.get(idx).map(|t| U::from_table(tag, t)),
+        }
     }
 
-    pub fn create_vector<T: Endian>(&mut self, v: &[T]) -> Offset<Vector<T>> {
-        self.not_nested();
-        self.start_vector(v.len(), mem::size_of::<T>());
-        for &elem in v.iter().rev() {
-            self.push_scalar(elem);
+    pub fn iter(&self) -> UnionVecIter<'buf, U> {
+        UnionVecIter {
+            vec: *self,
+            idx: 0,
         }
-
-        Offset::new(self.end_vector(v.len()))
     }
+}
 
-    pub fn create_vector_of_structs<T>(&mut self, v: &[T]) -> Offset<Vector<ByRef<T>, &T>> {
-        self.not_nested();
-
-        self.start_vector(v.len() * mem::size_of::<T>() / mem::align_of::<T>(),
-                          mem::align_of::<T>());
-        self.push_bytes(view_slice_bytes(v));
-
-        Offset::new(self.end_vector(v.len()))
-    }
+// A vector of unions whose type and value vectors have different lengths is malformed, so reading
+// one panics like reading any other malformed buffer, rather than quietly dropping elements.
+fn check_union_lengths(types: usize, values: usize) {
+    assert!(types == values, "a vector of unions has {} types but {} values", types, values);
+}
 
-    pub fn create_vector_of_sorted_tables<T: OrdTable>(&mut self, v: &mut [Offset<T>])
-        -> Offset<Vector<Offset<T>>> {
+/// A marker trait for the types generated for flatbuffer structs, which are stored in the buffer
+/// exactly as they're laid out in memory.
+///
+/// # Safety
+///
+/// Implementors must be `#[repr(C)]`, have no padding bytes, and be valid for any bit pattern. The
+/// generated code ensures this by storing every field as an integer, a float or another struct,
+/// and by making padding explicit.
+pub unsafe trait Struct: Copy {}
 
-        v.sort_by(|&a_off, &b_off| {
-            unsafe {
-                let a = mem::transmute::<&u8, &T>(&self.buf.data_at(a_off.inner as usize)[0]);
-                let b = mem::transmute::<&u8, &T>(&self.buf.data_at(b_off.inner as usize)[0]);
+/// Return the root object stored in this buffer, interpreting it as type `T`.
+pub fn get_root<'buf, T: View<'buf>>(buf: &'buf [u8]) -> T {
+    T::view(buf, follow_offset(buf, 0))
+}
 
-                a.key_cmp(b)
-            }
-        });
+/// Return the root table of a mutable buffer, so that its fields can be changed in place.
+pub fn get_root_mut(buf: &mut [u8]) -> TableMut<'_> {
+    let pos = follow_offset(buf, 0);
+    TableMut::new(buf, pos)
+}
 
-        self.create_vector(v)
-    }
+/// Like `get_root`, for buffers which were finished with `finish_size_prefixed`.
+pub fn get_size_prefixed_root<'buf, T: View<'buf>>(buf: &'buf [u8]) -> T {
+    get_root(&buf[mem::size_of::<UOffset>()..])
+}
 
-    pub fn create_uninitialized_vector<T>(&mut self, len: usize) -> (UOffset, &mut [T]) {
-        self.not_nested();
-        self.start_vector(len, mem::size_of::<T>());
-        let buf = self.buf.make_space(len * mem::size_of::<T>());
-        let off = self.end_vector(len);
+/// Read the size prefix of a buffer which was finished with `finish_size_prefixed`. This is the
+/// number of bytes which follow the prefix.
+pub fn read_size_prefix(buf: &[u8]) -> usize {
+    UOffset::read_le(buf) as usize
+}
 
-        let slc = unsafe {
-            let ptr = mem::transmute::<&mut u8, *mut T>(&mut self.buf.data_at_mut(buf)[0]);
+/// The length of a `file_identifier`, which is stored just after the root offset.
+pub const FILE_IDENTIFIER_LENGTH: usize = 4;
 
-            slice::from_raw_parts_mut(ptr, len)
-        };
+/// Check whether the buffer was finished with the file identifier `ident`.
+pub fn buffer_has_identifier(buf: &[u8], ident: &str) -> bool {
+    let start = mem::size_of::<UOffset>();
+    let end   = start + FILE_IDENTIFIER_LENGTH;
 
-        (off, slc)
-    }
+    buf.len() >= end && &buf[start..end] == ident.as_bytes()
+}
 
-    pub fn finish<T>(&mut self, root: Offset<T>) {
-        let min_align = self.min_align;
-        self.pre_align(mem::size_of::<UOffset>(), min_align);
-        let refer = self.refer_to(root.inner);
-        self.push_scalar(refer);
+/// Like `get_root`, but return `None` if the buffer doesn't have the file identifier `ident`.
+pub fn get_root_with_identifier<'buf, T: View<'buf>>(buf: &'buf [u8], ident: &str) -> Option<T> {
+    if buffer_has_identifier(buf, ident) {
+        Some(get_root(buf))
+    } else {
+        None
     }
 }
@@ -0,0 +1,950 @@
+// Copyright 2015 Sam Payson. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Building buffers from a schema which is loaded at runtime.
+
+use std::cmp;
+use std::convert::TryFrom;
+use std::error;
+use std::fmt;
+use std::mem;
+
+use schema::{BaseType, Field, Object, Schema, Type};
+use {BuilderError, Endian, FlatBufferBuilder, Offset, Table, UOffset, VOffset};
+
+use super::union_tag_field;
+
+/// A value for a `DynamicBuilder` to write. The schema decides how each value is stored, so tables
+/// and structs are both given as a list of fields, and integers can be written to fields of any
+/// numeric type which they fit in.
+#[derive(Clone, Debug, PartialEq)]
+pub enum BuildValue {
+    Bool(bool),
+    Int(i64),
+    UInt(u64),
+    Float(f64),
+
+    /// A string, or the name of one of the values of an enum.
+    Str(String),
+    Vector(Vec<BuildValue>),
+
+    /// A table or struct, as the names and values of its fields.
+    Object(Vec<(String, BuildValue)>),
+
+    /// A member of a union: the name of the member, and the fields of its table. This can't be
+    /// `NONE`, even in a vector of unions: leave a union field out to not set it.
+    Union(String, Vec<(String, BuildValue)>),
+}
+
+impl From<bool> for BuildValue {
+    fn from(b: bool) -> BuildValue { BuildValue::Bool(b) }
+}
+
+impl From<i64> for BuildValue {
+    fn from(i: i64) -> BuildValue { BuildValue::Int(i) }
+}
+
+impl From<u64> for BuildValue {
+    fn from(u: u64) -> BuildValue { BuildValue::UInt(u) }
+}
+
+impl From<f64> for BuildValue {
+    fn from(f: f64) -> BuildValue { BuildValue::Float(f) }
+}
+
+impl<'a> From<&'a str> for BuildValue {
+    fn from(s: &'a str) -> BuildValue { BuildValue::Str(s.to_string()) }
+}
+
+impl From<String> for BuildValue {
+    fn from(s: String) -> BuildValue { BuildValue::Str(s) }
+}
+
+/// The ways in which a `DynamicBuilder` can fail. Fields are named as `Object.field`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BuildError {
+    /// The schema doesn't have a `root_type`.
+    NoRootType,
+
+    /// A table or struct was given a field which it doesn't have.
+    UnknownField { object: String, field: String },
+
+    /// A field was given more than once. Setting a union also sets its type field.
+    DuplicateField { field: String },
+
+    /// A field was given which is `deprecated`, so mustn't be written any more.
+    DeprecatedField { field: String },
+
+    /// A required field of a table, or any field of a struct, wasn't given.
+    MissingField { field: String },
+
+    /// A value doesn't match the type of its field, or is out of range for it.
+    WrongType { field: String, expected: &'static str },
+
+    /// A string was given for an enum or union field, but isn't the name of one of its values.
+    UnknownEnumValue { field: String, value: String },
+
+    /// The `FlatBufferBuilder` failed, e.g. because it ran out of space.
+    Builder(BuilderError),
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            BuildError::NoRootType =>
+                write!(f, "the schema doesn't have a root type"),
+            BuildError::UnknownField { ref object, ref field } =>
+                write!(f, "`{}` doesn't have a field called `{}`", object, field),
+            BuildError::DuplicateField { ref field } =>
+                write!(f, "`{}` is set more than once", field),
+            BuildError::DeprecatedField { ref field } =>
+                write!(f, "`{}` is deprecated", field),
+            BuildError::MissingField { ref field } =>
+                write!(f, "`{}` must be set", field),
+            BuildError::WrongType { ref field, expected } =>
+                write!(f, "`{}` must be a valid {}", field, expected),
+            BuildError::UnknownEnumValue { ref field, ref value } =>
+                write!(f, "`{}` isn't a valid value for `{}`", value, field),
+            BuildError::Builder(e) =>
+                write!(f, "{}", e),
+        }
+    }
+}
+
+impl error::Error for BuildError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            BuildError::Builder(ref e) => Some(e),
+            _                          => None,
+        }
+    }
+}
+
+impl From<BuilderError> for BuildError {
+    fn from(e: BuilderError) -> BuildError {
+        BuildError::Builder(e)
+    }
+}
+
+// The name of a field in errors.
+fn field_path(object: &Object, field: &Field) -> String {
+    format!("{}.{}", object.name, field.name)
+}
+
+fn wrong_type(object: &Object, field: &Field, expected: &'static str) -> BuildError {
+    BuildError::WrongType { field: field_path(object, field), expected }
+}
+
+// Describe the type `ty` in errors.
+fn type_name(schema: &Schema, ty: Type) -> &'static str {
+    match ty.base_type {
+        BaseType::String                                             => "string",
+        BaseType::Vector                                             => "vector",
+        BaseType::Union                                              => "union",
+        BaseType::Obj if schema.objects[ty.index.unwrap()].is_struct => "struct",
+        BaseType::Obj                                                => "table",
+        b                                                            => b.schema_name(),
+    }
+}
+
+// The value of an enum, or the default of an integer field, as an `i128`. Values of type `ulong`
+// are stored in an `i64`, so they have to be reinterpreted.
+fn integer_value(base: BaseType, v: i64) -> i128 {
+    if base == BaseType::ULong { v as u64 as i128 } else { v as i128 }
+}
+
+// A scalar which has been converted to the type of the field it's being written to.
+#[derive(Clone, Copy, PartialEq)]
+enum Scalar {
+    Bool(bool),
+    I8(i8),
+    U8(u8),
+    I16(i16),
+    U16(u16),
+    I32(i32),
+    U32(u32),
+    I64(i64),
+    U64(u64),
+    F32(f32),
+    F64(f64),
+}
+
+// Evaluate `$body` with `$x` bound to the value held by the scalar `$s`, whatever its type.
+macro_rules! with_scalar {
+    ($s:expr, $x:ident => $body:expr) => {
+        match $s {
+            Scalar::Bool($x) => $body,
+            Scalar::I8($x)   => $body,
+            Scalar::U8($x)   => $body,
+            Scalar::I16($x)  => $body,
+            Scalar::U16($x)  => $body,
+            Scalar::I32($x)  => $body,
+            Scalar::U32($x)  => $body,
+            Scalar::I64($x)  => $body,
+            Scalar::U64($x)  => $body,
+            Scalar::F32($x)  => $body,
+            Scalar::F64($x)  => $body,
+        }
+    }
+}
+
+impl Scalar {
+    // Convert `n` to type `base`, if it fits.
+    fn from_int(base: BaseType, n: i128) -> Option<Scalar> {
+        Some(match base {
+            BaseType::Bool                    => match n {
+                0 => Scalar::Bool(false),
+                1 => Scalar::Bool(true),
+                _ => return None,
+            },
+            BaseType::Byte                    => Scalar::I8(i8::try_from(n).ok()?),
+            BaseType::UType | BaseType::UByte => Scalar::U8(u8::try_from(n).ok()?),
+            BaseType::Short                   => Scalar::I16(i16::try_from(n).ok()?),
+            BaseType::UShort                  => Scalar::U16(u16::try_from(n).ok()?),
+            BaseType::Int                     => Scalar::I32(i32::try_from(n).ok()?),
+            BaseType::UInt                    => Scalar::U32(u32::try_from(n).ok()?),
+            BaseType::Long                    => Scalar::I64(i64::try_from(n).ok()?),
+            BaseType::ULong                   => Scalar::U64(u64::try_from(n).ok()?),
+            BaseType::Float                   => Scalar::F32(n as f32),
+            BaseType::Double                  => Scalar::F64(n as f64),
+            _                                 => return None,
+        })
+    }
+
+    // Convert `f` to type `base`, if it's a floating point type.
+    fn from_float(base: BaseType, f: f64) -> Option<Scalar> {
+        match base {
+            BaseType::Float  => Some(Scalar::F32(f as f32)),
+            BaseType::Double => Some(Scalar::F64(f)),
+            _                => None,
+        }
+    }
+
+    // The default value of the scalar field `field`.
+    fn default_for(field: &Field) -> Option<Scalar> {
+        let base = field.ty.base_type;
+
+        if base.is_float() {
+            Scalar::from_float(base, field.default_real)
+        } else {
+            Scalar::from_int(base, integer_value(base, field.default_integer))
+        }
+    }
+
+    fn size(self) -> usize {
+        with_scalar!(self, x => mem::size_of_val(&x))
+    }
+
+    fn write(self, buf: &mut [u8]) {
+        with_scalar!(self, x => x.write_le(buf))
+    }
+
+    fn push(self, fbb: &mut FlatBufferBuilder<'_>) -> Result<usize, BuilderError> {
+        with_scalar!(self, x => fbb.try_push_scalar(x))
+    }
+
+    // Add this to the table being built as `field`, unless it's equal to the default `def`.
+    fn add(self, fbb: &mut FlatBufferBuilder<'_>, field: VOffset, def: Option<Scalar>)
+        -> Result<(), BuilderError> {
+
+        match (self, def) {
+            (Scalar::Bool(x), Some(Scalar::Bool(d))) => fbb.try_add_scalar(field, x, d),
+            (Scalar::I8(x), Some(Scalar::I8(d)))     => fbb.try_add_scalar(field, x, d),
+            (Scalar::U8(x), Some(Scalar::U8(d)))     => fbb.try_add_scalar(field, x, d),
+            (Scalar::I16(x), Some(Scalar::I16(d)))   => fbb.try_add_scalar(field, x, d),
+            (Scalar::U16(x), Some(Scalar::U16(d)))   => fbb.try_add_scalar(field, x, d),
+            (Scalar::I32(x), Some(Scalar::I32(d)))   => fbb.try_add_scalar(field, x, d),
+            (Scalar::U32(x), Some(Scalar::U32(d)))   => fbb.try_add_scalar(field, x, d),
+            (Scalar::I64(x), Some(Scalar::I64(d)))   => fbb.try_add_scalar(field, x, d),
+            (Scalar::U64(x), Some(Scalar::U64(d)))   => fbb.try_add_scalar(field, x, d),
+            (Scalar::F32(x), Some(Scalar::F32(d)))   => fbb.try_add_scalar(field, x, d),
+            (Scalar::F64(x), Some(Scalar::F64(d)))   => fbb.try_add_scalar(field, x, d),
+            _                                        => {
+                let off = self.push(fbb)? as UOffset;
+                fbb.try_track_field(field, off)
+            }
+        }
+    }
+}
+
+// The inline part of a field of a table which is being built, ready to be added to it.
+enum Inline {
+    Scalar(Scalar, Option<Scalar>),
+    Offset(UOffset),
+    Struct(Vec<u8>, usize),
+}
+
+impl Inline {
+    fn size(&self) -> usize {
+        match *self {
+            Inline::Scalar(s, _)         => s.size(),
+            Inline::Offset(_)            => mem::size_of::<UOffset>(),
+            Inline::Struct(ref bytes, _) => bytes.len(),
+        }
+    }
+}
+
+/// Builds buffers from field names and `BuildValue`s, using a schema to decide how each value is
+/// stored. This is for tools which need to make buffers for schemas they weren't compiled with;
+/// the generated builders are both faster and easier to use.
+pub struct DynamicBuilder<'s, 'fbb, 'buf> {
+    schema: &'s Schema,
+    fbb:    &'fbb mut FlatBufferBuilder<'buf>,
+}
+
+impl<'s, 'fbb, 'buf> DynamicBuilder<'s, 'fbb, 'buf> {
+    pub fn new(schema: &'s Schema, fbb: &'fbb mut FlatBufferBuilder<'buf>)
+        -> DynamicBuilder<'s, 'fbb, 'buf> {
+
+        DynamicBuilder { schema, fbb }
+    }
+
+    /// Build a table of type `object`, which must be one of the schema's tables, and return its
+    /// offset. Fields which aren't given are left out, so readers will see their defaults.
+    pub fn create_table(&mut self, object: &'s Object, fields: &[(String, BuildValue)])
+        -> Result<Offset<Table<'static>>, BuildError> {
+
+        if object.is_struct {
+            return Err(BuildError::WrongType { field: object.name.clone(), expected: "table" })
+        }
+
+        // Everything that the table refers to has to be built before the table is started.
+        let mut inline: Vec<(&'s Field, Inline)> = Vec::with_capacity(fields.len());
+
+        for (name, value) in fields.iter() {
+            let field = object.field_by_name(name).ok_or_else(|| {
+                BuildError::UnknownField { object: object.name.clone(), field: name.clone() }
+            })?;
+
+            if field.deprecated {
+                return Err(BuildError::DeprecatedField { field: field_path(object, field) })
+            }
+
+            for (f, i) in self.prepare_field(object, field, value)? {
+                if inline.iter().any(|&(g, _)| g.id == f.id) {
+                    return Err(BuildError::DuplicateField { field: field_path(object, f) })
+                }
+
+                inline.push((f, i));
+            }
+        }
+
+        let missing = object.fields.iter().find(|f| {
+            f.required && !inline.iter().any(|&(g, _)| g.id == f.id)
+        });
+
+        if let Some(f) = missing {
+            return Err(BuildError::MissingField { field: field_path(object, f) })
+        }
+
+        // Add the biggest fields first, so that there's as little padding between them as possible.
+        inline.sort_by_key(|i| cmp::Reverse(i.1.size()));
+
+        let start = self.fbb.try_start_table()?;
+
+        for (field, i) in inline {
+            match i {
+                Inline::Scalar(s, def)       => s.add(self.fbb, field.offset, def)?,
+                Inline::Offset(off)          => {
+                    self.fbb.try_add_offset(field.offset, Offset::<()>::new(off))?
+                }
+                Inline::Struct(bytes, align) => {
+                    self.fbb.try_align(align)?;
+                    self.fbb.try_push_bytes(&bytes)?;
+
+                    let off = self.fbb.get_size() as UOffset;
+                    self.fbb.try_add_struct_offset(field.offset, off)?;
+                }
+            }
+        }
+
+        Ok(Offset::new(self.fbb.try_end_table(start, object.num_slots())?))
+    }
+
+    /// Build a table of the schema's `root_type`, and finish the buffer with it. The schema's
+    /// `file_identifier` is used, if it has one.
+    pub fn finish(&mut self, fields: &[(String, BuildValue)]) -> Result<(), BuildError> {
+        let schema = self.schema;
+        let root   = schema.root_table().ok_or(BuildError::NoRootType)?;
+        let off    = self.create_table(root, fields)?;
+
+        match schema.file_ident {
+            Some(ref ident) => self.fbb.try_finish_with_identifier(off, ident)?,
+            None            => self.fbb.try_finish(off)?,
+        }
+
+        Ok(())
+    }
+
+    // Build anything that the field `field` of `object` refers to, and return what needs to be
+    // added to the table. Unions are added as two fields: the type and the value.
+    fn prepare_field(&mut self, object: &'s Object, field: &'s Field, value: &BuildValue)
+        -> Result<Vec<(&'s Field, Inline)>, BuildError> {
+
+        let schema = self.schema;
+        let ty     = field.ty;
+
+        let inline = match ty.base_type {
+            b if b.is_scalar() => {
+                Inline::Scalar(self.scalar(object, field, ty, value)?, Scalar::default_for(field))
+            }
+            BaseType::Vector if ty.element == BaseType::Union => {
+                let values = match *value {
+                    BuildValue::Vector(ref values) => values,
+                    _ => return Err(wrong_type(object, field, "vector")),
+                };
+
+                let mut tags = Vec::with_capacity(values.len());
+                let mut offs = Vec::with_capacity(values.len());
+
+                for v in values.iter() {
+                    let (tag, off) = self.union_value(object, field, v)?;
+                    tags.push(tag);
+                    offs.push(Offset::<()>::new(off));
+                }
+
+                let tag_field = self.tag_field(object, field)?;
+                let offs      = self.fbb.try_create_vector_of_offsets(&offs)?.value();
+                let tags      = self.fbb.try_create_vector(&tags)?.value();
+
+                return Ok(vec![(tag_field, Inline::Offset(tags)), (field, Inline::Offset(offs))])
+            }
+            BaseType::Union => {
+                let (tag, off) = self.union_value(object, field, value)?;
+                let tag_field  = self.tag_field(object, field)?;
+
+                return Ok(vec![(tag_field, Inline::Scalar(Scalar::U8(tag), None)),
+                               (field, Inline::Offset(off))])
+            }
+            BaseType::Obj if schema.objects[ty.index.unwrap()].is_struct => {
+                let st = &schema.objects[ty.index.unwrap()];
+                Inline::Struct(self.struct_bytes(object, field, st, value)?, st.minalign)
+            }
+            _ => Inline::Offset(self.offset_value(object, field, ty, value)?),
+        };
+
+        Ok(vec![(field, inline)])
+    }
+
+    fn tag_field(&self, object: &'s Object, field: &Field) -> Result<&'s Field, BuildError> {
+        union_tag_field(object, field).ok_or_else(|| {
+            BuildError::UnknownField {
+                object: object.name.clone(),
+                field:  format!("{}_type", field.name),
+            }
+        })
+    }
+
+    // Convert `value` to a scalar of type `ty`, for the field `field` of `object` (or an element of
+    // it, if it's a vector).
+    fn scalar(&self, object: &Object, field: &Field, ty: Type, value: &BuildValue)
+        -> Result<Scalar, BuildError> {
+
+        let base = ty.base_type;
+
+        let s = match *value {
+            BuildValue::Bool(b)  => Scalar::from_int(base, b as i128),
+            BuildValue::Int(i)   => Scalar::from_int(base, i as i128),
+            BuildValue::UInt(u)  => Scalar::from_int(base, u as i128),
+            BuildValue::Float(f) => Scalar::from_float(base, f),
+            BuildValue::Str(ref s) if base.is_integer() && ty.index.is_some() => {
+                match self.schema.enums[ty.index.unwrap()].value_by_name(s) {
+                    Some(v) => Scalar::from_int(base, integer_value(base, v.value)),
+                    None    => {
+                        return Err(BuildError::UnknownEnumValue { field: field_path(object, field),
+                                                                  value: s.clone() })
+                    }
+                }
+            }
+            _                    => None,
+        };
+
+        s.ok_or_else(|| wrong_type(object, field, type_name(self.schema, ty)))
+    }
+
+    // Build the member table of a union, and return its type tag and offset.
+    fn union_value(&mut self, object: &Object, field: &Field, value: &BuildValue)
+        -> Result<(u8, UOffset), BuildError> {
+
+        let schema = self.schema;
+        let en     = &schema.enums[field.ty.index.unwrap()];
+
+        let (name, fields) = match *value {
+            BuildValue::Union(ref name, ref fields) => (name, fields),
+            _ => return Err(wrong_type(object, field, "union")),
+        };
+
+        let unknown = || BuildError::UnknownEnumValue { field: field_path(object, field),
+                                                        value: name.clone() };
+
+        let member = en.value_by_name(name).ok_or_else(unknown)?;
+
+        match member.union_type {
+            Some(ty) if ty.base_type == BaseType::Obj && member.value > 0 && member.value < 256 => {
+                let off = self.create_table(&schema.objects[ty.index.unwrap()], fields)?;
+                Ok((member.value as u8, off.value()))
+            }
+            _ => Err(unknown()),
+        }
+    }
+
+    // Build a string, vector or table of type `ty` and return its offset.
+    fn offset_value(&mut self, object: &Object, field: &Field, ty: Type, value: &BuildValue)
+        -> Result<UOffset, BuildError> {
+
+        let schema = self.schema;
+
+        match (ty.base_type, value) {
+            (BaseType::String, BuildValue::Str(s)) => Ok(self.fbb.try_create_string(s)?.value()),
+            (BaseType::Vector, BuildValue::Vector(values)) => {
+                self.vector_value(object, field, ty.element_type(), values)
+            }
+            (BaseType::Obj, BuildValue::Object(fields)) => {
+                Ok(self.create_table(&schema.objects[ty.index.unwrap()], fields)?.value())
+            }
+            _ => Err(wrong_type(object, field, type_name(self.schema, ty))),
+        }
+    }
+
+    // Build a vector whose elements are of type `elem`, and return its offset.
+    fn vector_value(&mut self, object: &Object, field: &Field, elem: Type, values: &[BuildValue])
+        -> Result<UOffset, BuildError> {
+
+        let schema = self.schema;
+
+        match elem.base_type {
+            b if b.is_scalar() => {
+                let mut scalars = Vec::with_capacity(values.len());
+
+                for v in values.iter() {
+                    scalars.push(self.scalar(object, field, elem, v)?);
+                }
+
+                self.fbb.try_start_vector(values.len(), b.size())?;
+
+                for s in scalars.iter().rev() {
+                    s.push(self.fbb)?;
+                }
+
+                Ok(self.fbb.try_end_vector(values.len())?)
+            }
+            BaseType::Obj if schema.objects[elem.index.unwrap()].is_struct => {
+                let st = &schema.objects[elem.index.unwrap()];
+
+                let mut bytes = Vec::with_capacity(values.len() * st.bytesize);
+
+                for v in values.iter() {
+                    bytes.extend(self.struct_bytes(object, field, st, v)?);
+                }
+
+                self.fbb.try_not_nested()?;
+                self.fbb.try_start_vector_aligned(values.len(), st.bytesize, st.minalign)?;
+                self.fbb.try_push_bytes(&bytes)?;
+
+                Ok(self.fbb.try_end_vector(values.len())?)
+            }
+            BaseType::String | BaseType::Obj => {
+                let mut offs = Vec::with_capacity(values.len());
+
+                for v in values.iter() {
+                    offs.push(Offset::<()>::new(self.offset_value(object, field, elem, v)?));
+                }
+
+                Ok(self.fbb.try_create_vector_of_offsets(&offs)?.value())
+            }
+            _ => Err(wrong_type(object, field, "vector")),
+        }
+    }
+
+    // Lay out the struct `st`, which is the value of `field` of `object`.
+    fn struct_bytes(&self, object: &Object, field: &Field, st: &Object, value: &BuildValue)
+        -> Result<Vec<u8>, BuildError> {
+
+        let mut bytes = vec![0; st.bytesize];
+
+        match *value {
+            BuildValue::Object(ref fields) => self.write_struct(st, fields, &mut bytes)?,
+            _ => return Err(wrong_type(object, field, "struct")),
+        }
+
+        Ok(bytes)
+    }
+
+    // Write the fields of the struct `st` to `out`. Every field has to be given.
+    fn write_struct(&self, st: &Object, fields: &[(String, BuildValue)], out: &mut [u8])
+        -> Result<(), BuildError> {
+
+        for (i, (name, _)) in fields.iter().enumerate() {
+            let field = st.field_by_name(name).ok_or_else(|| {
+                BuildError::UnknownField { object: st.name.clone(), field: name.clone() }
+            })?;
+
+            if fields[..i].iter().any(|f| f.0 == *name) {
+                return Err(BuildError::DuplicateField { field: field_path(st, field) })
+            }
+        }
+
+        for field in st.fields.iter() {
+            let value = match fields.iter().find(|f| f.0 == field.name) {
+                Some((_, value)) => value,
+                None                  => {
+                    return Err(BuildError::MissingField { field: field_path(st, field) })
+                }
+            };
+
+            let out = &mut out[field.offset as usize..];
+
+            match (field.ty.base_type, value) {
+                (BaseType::Obj, BuildValue::Object(inner)) => {
+                    self.write_struct(&self.schema.objects[field.ty.index.unwrap()], inner, out)?
+                }
+                (BaseType::Obj, _) => return Err(wrong_type(st, field, "struct")),
+                _ => self.scalar(st, field, field.ty, value)?.write(out),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use schema;
+    use test_support::{self, read_root};
+
+    const SCHEMA: &str = r#"
+enum Color : short { Red = -1, Green, Blue }
+
+struct Small {
+  a: byte;
+  b: short;
+}
+
+struct Big {
+  x: double;
+  s: Small;
+}
+
+struct Vec3 {
+  x: float;
+  y: float;
+  z: float;
+}
+
+table Leaf {
+  name: string;
+  size: int = 3;
+}
+
+table Bud { open: bool; }
+
+union Part { Leaf, Bud }
+
+table Root {
+  name: string (required);
+  b: bool;
+  i8: byte = -1;
+  u8: ubyte;
+  i16: short;
+  u16: ushort;
+  i32: int;
+  u32: uint;
+  i64: long;
+  u64: ulong;
+  f32: float = 0.5;
+  f64: double;
+  color: Color = Green;
+  vec: Vec3;
+  big: Big;
+  ints: [int];
+  names: [string];
+  leaves: [Leaf];
+  points: [Vec3];
+  bigs: [Big];
+  child: Leaf;
+  part: Part;
+  parts: [Part];
+  old: int (deprecated);
+}
+
+root_type Root;
+file_identifier "ROOT";
+"#;
+
+    fn fields(fields: &[(&str, BuildValue)]) -> Vec<(String, BuildValue)> {
+        fields.iter().map(|(n, v)| (n.to_string(), v.clone())).collect()
+    }
+
+    fn object(f: &[(&str, BuildValue)]) -> BuildValue {
+        BuildValue::Object(fields(f))
+    }
+
+    fn vec3(x: f64, y: f64, z: f64) -> BuildValue {
+        object(&[("x", x.into()), ("y", y.into()), ("z", z.into())])
+    }
+
+    fn big(x: f64, a: i64, b: i64) -> BuildValue {
+        object(&[("x", x.into()), ("s", object(&[("a", a.into()), ("b", b.into())]))])
+    }
+
+    // Build a root table from `fields`, with the name filled in.
+    fn try_build(schema: &Schema, force_defaults: bool, f: &[(&str, BuildValue)])
+        -> Result<Vec<u8>, BuildError> {
+
+        let mut all = fields(&[("name", "root".into())]);
+        all.extend(fields(f));
+
+        test_support::try_build(force_defaults, |fbb| DynamicBuilder::new(schema, fbb).finish(&all))
+    }
+
+    fn build(schema: &Schema, f: &[(&str, BuildValue)]) -> Vec<u8> {
+        try_build(schema, false, f).unwrap()
+    }
+
+    fn error(schema: &Schema, f: &[(&str, BuildValue)]) -> BuildError {
+        try_build(schema, false, f).unwrap_err()
+    }
+
+    #[test]
+    fn scalars_round_trip() {
+        let schema = schema::compile_str(SCHEMA).unwrap();
+        let buf    = build(&schema, &[
+            ("b",     true.into()),
+            ("i8",    BuildValue::Int(-128)),
+            ("u8",    BuildValue::Int(255)),
+            ("i16",   BuildValue::Int(-32768)),
+            ("u16",   BuildValue::UInt(65535)),
+            ("i32",   BuildValue::Int(i32::MIN as i64)),
+            ("u32",   BuildValue::UInt(u32::MAX as u64)),
+            ("i64",   BuildValue::Int(i64::MIN)),
+            ("u64",   BuildValue::UInt(u64::MAX)),
+            ("f32",   BuildValue::Int(2)),
+            ("f64",   BuildValue::Float(0.1)),
+            ("color", "Red".into()),
+        ]);
+        let root = read_root(&schema, &buf);
+
+        assert_eq!(root.get("b").unwrap().as_bool(), Some(true));
+        assert_eq!(root.get("i8").unwrap().as_i64(), Some(-128));
+        assert_eq!(root.get("u8").unwrap().as_u64(), Some(255));
+        assert_eq!(root.get("i16").unwrap().as_i64(), Some(-32768));
+        assert_eq!(root.get("u16").unwrap().as_u64(), Some(65535));
+        assert_eq!(root.get("i32").unwrap().as_i64(), Some(i32::MIN as i64));
+        assert_eq!(root.get("u32").unwrap().as_u64(), Some(u32::MAX as u64));
+        assert_eq!(root.get("i64").unwrap().as_i64(), Some(i64::MIN));
+        assert_eq!(root.get("u64").unwrap().as_u64(), Some(u64::MAX));
+        assert_eq!(root.get("f32").unwrap().as_f64(), Some(2.0));
+        assert_eq!(root.get("f64").unwrap().as_f64(), Some(0.1));
+        assert_eq!(root.get("color").unwrap().as_i64(), Some(-1));
+    }
+
+    #[test]
+    fn structs_round_trip() {
+        let schema = schema::compile_str(SCHEMA).unwrap();
+        let buf    = build(&schema, &[
+            ("vec",    vec3(1.0, 2.0, 3.0)),
+            ("big",    big(0.25, -5, 300)),
+            ("points", BuildValue::Vector(vec![vec3(1.0, 0.0, 0.0), vec3(0.0, 1.0, 0.0),
+                                              vec3(0.0, 0.0, 1.0)])),
+            ("bigs",   BuildValue::Vector(vec![big(1.0, 1, 2), big(2.0, 3, 4)])),
+        ]);
+        let root = read_root(&schema, &buf);
+
+        let vec = root.get("vec").unwrap().as_struct().unwrap();
+        let xyz: Vec<_> = vec.fields().map(|(_, v)| v.as_f64().unwrap()).collect();
+        assert_eq!(xyz, [1.0, 2.0, 3.0]);
+
+        let b = root.get("big").unwrap().as_struct().unwrap();
+        assert_eq!(b.get("x").unwrap().as_f64(), Some(0.25));
+        let s = b.get("s").unwrap().as_struct().unwrap();
+        assert_eq!((s.get("a").unwrap().as_i64(), s.get("b").unwrap().as_i64()), (Some(-5), Some(300)));
+
+        // Vectors of structs hold exactly their elements, whatever their size.
+        let points = root.get("points").unwrap().as_vector().unwrap();
+        assert_eq!(points.len(), 3);
+        let diagonal: Vec<_> = points.iter().enumerate().map(|(i, p)| {
+            p.as_struct().unwrap().fields().nth(i).unwrap().1.as_f64().unwrap()
+        }).collect();
+        assert_eq!(diagonal, [1.0, 1.0, 1.0]);
+
+        // ... and are aligned to the alignment of the struct.
+        let bigs = root.get("bigs").unwrap().as_vector().unwrap();
+        assert_eq!(bigs.len(), 2);
+        for (i, b) in bigs.iter().enumerate() {
+            let b = b.as_struct().unwrap();
+            assert_eq!((b.bytes().as_ptr() as usize - buf.as_ptr() as usize) % 8, 0);
+            assert_eq!(b.get("x").unwrap().as_f64(), Some(i as f64 + 1.0));
+        }
+    }
+
+    #[test]
+    fn references_round_trip() {
+        let schema = schema::compile_str(SCHEMA).unwrap();
+        let leaf   = |name: &str| object(&[("name", name.into())]);
+        let part   = |name: &str, f: &[(&str, BuildValue)]| BuildValue::Union(name.to_string(), fields(f));
+        let buf    = build(&schema, &[
+            ("ints",   BuildValue::Vector(vec![BuildValue::Int(-1), BuildValue::UInt(7)])),
+            ("names",  BuildValue::Vector(vec!["a".into(), "bc".into()])),
+            ("leaves", BuildValue::Vector(vec![leaf("x"), leaf("y")])),
+            ("child",  leaf("z")),
+            ("part",   part("Bud", &[("open", true.into())])),
+            ("parts",  BuildValue::Vector(vec![part("Leaf", &[("size", 9i64.into())]),
+                                              part("Bud", &[])])),
+        ]);
+        let root = read_root(&schema, &buf);
+
+        assert_eq!(root.get("name").unwrap().as_str(), Some("root"));
+
+        let ints: Vec<_> = root.get("ints").unwrap().as_vector().unwrap().iter()
+            .map(|v| v.as_i64().unwrap()).collect();
+        assert_eq!(ints, [-1, 7]);
+
+        let names: Vec<_> = root.get("names").unwrap().as_vector().unwrap().iter()
+            .map(|v| v.as_str().unwrap()).collect();
+        assert_eq!(names, ["a", "bc"]);
+
+        let leaves: Vec<_> = root.get("leaves").unwrap().as_vector().unwrap().iter()
+            .map(|v| v.as_table().unwrap().get("name").unwrap().as_str().unwrap()).collect();
+        assert_eq!(leaves, ["x", "y"]);
+
+        let child = root.get("child").unwrap().as_table().unwrap();
+        assert_eq!(child.get("name").unwrap().as_str(), Some("z"));
+
+        let part = root.get("part").unwrap().as_union().unwrap();
+        assert_eq!(part.variant().unwrap().name, "Bud");
+        assert_eq!(part.value().unwrap().get("open").unwrap().as_bool(), Some(true));
+
+        let parts: Vec<_> = root.get("parts").unwrap().as_vector().unwrap().iter().map(|v| {
+            let u = v.as_union().unwrap();
+            (u.tag(), u.value().unwrap().fields().count())
+        }).collect();
+        assert_eq!(parts, [(1, 1), (2, 0)]);
+    }
+
+    #[test]
+    fn defaults_are_left_out() {
+        let schema = schema::compile_str(SCHEMA).unwrap();
+        let given  = [
+            ("i8",    BuildValue::Int(-1)),
+            ("u8",    BuildValue::Int(0)),
+            ("f32",   BuildValue::Float(0.5)),
+            ("color", "Green".into()),
+            ("part",  BuildValue::Union("Bud".to_string(), fields(&[("open", false.into())]))),
+        ];
+
+        let buf  = build(&schema, &given);
+        let root = read_root(&schema, &buf);
+
+        let present: Vec<_> = root.fields().map(|(f, _)| &f.name[..]).collect();
+        assert_eq!(present, ["name", "part_type", "part"]);
+        assert_eq!(root.get("i8").unwrap().as_i64(), Some(-1));
+        assert_eq!(root.get("f32").unwrap().as_f64(), Some(0.5));
+        assert_eq!(root.get("part").unwrap().as_union().unwrap().value().unwrap().fields().count(), 0);
+
+        let buf  = try_build(&schema, true, &given).unwrap();
+        let root = read_root(&schema, &buf);
+
+        let present: Vec<_> = root.fields().map(|(f, _)| &f.name[..]).collect();
+        assert_eq!(present, ["name", "i8", "u8", "f32", "color", "part_type", "part"]);
+        assert_eq!(root.get("part").unwrap().as_union().unwrap().value().unwrap().fields().count(), 1);
+    }
+
+    #[test]
+    fn errors_name_the_field() {
+        let schema = schema::compile_str(SCHEMA).unwrap();
+
+        let mut fbb = FlatBufferBuilder::new(64);
+        assert_eq!(DynamicBuilder::new(&Schema::default(), &mut fbb).finish(&[]),
+                   Err(BuildError::NoRootType));
+
+        assert_eq!(error(&schema, &[("size", 1i64.into())]),
+                   BuildError::UnknownField { object: "Root".to_string(), field: "size".to_string() });
+        assert_eq!(error(&schema, &[("vec", object(&[("w", 1.0.into())]))]),
+                   BuildError::UnknownField { object: "Vec3".to_string(), field: "w".to_string() });
+
+        assert_eq!(error(&schema, &[("name", "again".into())]),
+                   BuildError::DuplicateField { field: "Root.name".to_string() });
+        assert_eq!(error(&schema, &[("part_type", 1i64.into()),
+                                    ("part", BuildValue::Union("Bud".to_string(), vec![]))]),
+                   BuildError::DuplicateField { field: "Root.part_type".to_string() });
+
+        let mut fbb = FlatBufferBuilder::new(64);
+        assert_eq!(DynamicBuilder::new(&schema, &mut fbb).finish(&[]),
+                   Err(BuildError::MissingField { field: "Root.name".to_string() }));
+        assert_eq!(error(&schema, &[("vec", object(&[("x", 1.0.into())]))]),
+                   BuildError::MissingField { field: "Vec3.y".to_string() });
+        assert_eq!(error(&schema, &[("vec", object(&[("x", 1.0.into()), ("y", 2.0.into()),
+                                                     ("z", 3.0.into()), ("x", 4.0.into())]))]),
+                   BuildError::DuplicateField { field: "Vec3.x".to_string() });
+
+        assert_eq!(error(&schema, &[("old", 1i64.into())]),
+                   BuildError::DeprecatedField { field: "Root.old".to_string() });
+
+        let wrong = |field: &str, expected| BuildError::WrongType { field: field.to_string(), expected };
+        assert_eq!(error(&schema, &[("i32", "1".into())]), wrong("Root.i32", "int"));
+        assert_eq!(error(&schema, &[("u8", BuildValue::Int(256))]), wrong("Root.u8", "ubyte"));
+        assert_eq!(error(&schema, &[("u64", BuildValue::Int(-1))]), wrong("Root.u64", "ulong"));
+        assert_eq!(error(&schema, &[("i16", BuildValue::Float(1.0))]), wrong("Root.i16", "short"));
+        assert_eq!(error(&schema, &[("b", BuildValue::Int(2))]), wrong("Root.b", "bool"));
+        assert_eq!(error(&schema, &[("names", "a".into())]), wrong("Root.names", "vector"));
+        assert_eq!(error(&schema, &[("names", BuildValue::Vector(vec![1i64.into()]))]),
+                   wrong("Root.names", "string"));
+        assert_eq!(error(&schema, &[("vec", vec3(0.0, 0.0, 0.0)), ("big", vec3(0.0, 0.0, 0.0))]),
+                   BuildError::UnknownField { object: "Big".to_string(), field: "y".to_string() });
+        assert_eq!(error(&schema, &[("vec", 1.0.into())]), wrong("Root.vec", "struct"));
+        assert_eq!(error(&schema, &[("child", "leaf".into())]), wrong("Root.child", "table"));
+        assert_eq!(error(&schema, &[("part", object(&[]))]), wrong("Root.part", "union"));
+
+        let mut fbb = FlatBufferBuilder::new(64);
+        let vec3    = &schema.objects[schema.object_by_name("Vec3").unwrap()];
+        assert_eq!(DynamicBuilder::new(&schema, &mut fbb).create_table(vec3, &[]).err(),
+                   Some(wrong("Vec3", "table")));
+
+        assert_eq!(error(&schema, &[("color", "Purple".into())]),
+                   BuildError::UnknownEnumValue { field: "Root.color".to_string(),
+                                                  value: "Purple".to_string() });
+        assert_eq!(error(&schema, &[("part", BuildValue::Union("Stem".to_string(), vec![]))]),
+                   BuildError::UnknownEnumValue { field: "Root.part".to_string(),
+                                                  value: "Stem".to_string() });
+        assert_eq!(error(&schema, &[("part", BuildValue::Union("NONE".to_string(), vec![]))]),
+                   BuildError::UnknownEnumValue { field: "Root.part".to_string(),
+                                                  value: "NONE".to_string() });
+
+        let parts = vec![BuildValue::Union("Bud".to_string(), vec![]),
+                         BuildValue::Union("NONE".to_string(), vec![])];
+        assert_eq!(error(&schema, &[("parts", BuildValue::Vector(parts))]),
+                   BuildError::UnknownEnumValue { field: "Root.parts".to_string(),
+                                                  value: "NONE".to_string() });
+    }
+
+    #[test]
+    fn builder_errors_are_returned() {
+        let schema = schema::compile_str(SCHEMA).unwrap();
+        let name   = fields(&[("name", "a long enough name to not fit".into())]);
+
+        let mut buf = [0; 32];
+        let mut fbb = FlatBufferBuilder::from_slice(&mut buf);
+        assert_eq!(DynamicBuilder::new(&schema, &mut fbb).finish(&name),
+                   Err(BuildError::Builder(BuilderError::OutOfSpace)));
+
+        let mut buf = [0; 128];
+        let mut fbb = FlatBufferBuilder::from_slice(&mut buf);
+        assert_eq!(DynamicBuilder::new(&schema, &mut fbb).finish(&name), Ok(()));
+        assert_eq!(read_root(&schema, fbb.finished_data().unwrap().as_bytes())
+                       .get("name").unwrap().as_str(),
+                   Some("a long enough name to not fit"));
+    }
+}
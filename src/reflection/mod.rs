@@ -0,0 +1,1589 @@
+// Copyright 2015 Sam Payson. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Reading buffers without generated code.
+//!
+//! `load_schema` reads a binary schema (a `.bfbs` file, which is a flatbuffer of the `Schema` table
+//! in `reflection.fbs`) into a `schema::Schema`. Given a schema, from there or from
+//! `schema::compile`, `get_root` returns a `DynTable`, whose fields can be listed and read by name
+//! as `Value`s. Vectors, structs, sub-tables and unions can be descended into the same way. Like
+//! `::get_root`, `get_root` trusts the buffer; `get_root_verified` checks it against the schema
+//! first.
+//!
+//! Going the other way, a `DynamicBuilder` builds buffers from field names and `BuildValue`s.
+
+mod builder;
+
+pub use self::builder::{BuildError, BuildValue, DynamicBuilder};
+
+use std::error;
+use std::fmt;
+use std::mem;
+use std::slice;
+
+use schema::{BaseType, Enum, EnumVal, Field, KeyValue, Object, Schema, Type};
+use verifier::{Verifier, Verify, VerifyError};
+use {Endian, Offset, Str, Table, UOffset, VOffset, Vector, View, follow_offset};
+
+/// The file identifier of binary schemas.
+pub const BFBS_IDENTIFIER: &str = "BFBS";
+
+/// The ways in which loading a binary schema can fail.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LoadError {
+    /// The buffer isn't a valid `reflection.fbs` `Schema`.
+    Verify(VerifyError),
+
+    /// The buffer is well-formed, but the schema it describes is inconsistent, or uses something
+    /// that this crate doesn't support (like fixed-length arrays).
+    Invalid(String),
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            LoadError::Verify(ref e)  => write!(f, "invalid binary schema: {}", e),
+            LoadError::Invalid(ref m) => f.write_str(m),
+        }
+    }
+}
+
+impl error::Error for LoadError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            LoadError::Verify(ref e) => Some(e),
+            LoadError::Invalid(_)    => None,
+        }
+    }
+}
+
+impl From<VerifyError> for LoadError {
+    fn from(e: VerifyError) -> LoadError {
+        LoadError::Verify(e)
+    }
+}
+
+// Declare a view of one of the tables in `reflection.fbs`, along with the vtable offsets of the
+// fields which we read from it.
+macro_rules! reflection_table {
+    ($name:ident { $($field:ident = $id:expr),* }) => {
+        #[derive(Clone, Copy)]
+        struct $name<'buf>(Table<'buf>);
+
+        impl<'buf> View<'buf> for $name<'buf> {
+            fn view(buf: &'buf [u8], pos: usize) -> $name<'buf> {
+                $name(Table::view(buf, pos))
+            }
+        }
+
+        impl<'buf> $name<'buf> {
+            $(const $field: VOffset = 4 + 2 * $id;)*
+        }
+    }
+}
+
+reflection_table!(RSchema { OBJECTS = 0, ENUMS = 1, FILE_IDENT = 2, FILE_EXT = 3, ROOT_TABLE = 4 });
+
+reflection_table!(RObject { NAME = 0, FIELDS = 1, IS_STRUCT = 2, MINALIGN = 3, BYTESIZE = 4,
+                            ATTRIBUTES = 5, DOCUMENTATION = 6 });
+
+reflection_table!(RField { NAME = 0, TYPE = 1, ID = 2, OFFSET = 3, DEFAULT_INTEGER = 4,
+                           DEFAULT_REAL = 5, DEPRECATED = 6, REQUIRED = 7, KEY = 8,
+                           ATTRIBUTES = 9, DOCUMENTATION = 10 });
+
+reflection_table!(RType { BASE_TYPE = 0, ELEMENT = 1, INDEX = 2 });
+
+reflection_table!(RKeyValue { KEY = 0, VALUE = 1 });
+
+reflection_table!(REnumVal { NAME = 0, VALUE = 1, UNION_TYPE = 3, DOCUMENTATION = 4,
+                             ATTRIBUTES = 5 });
+
+reflection_table!(REnum { NAME = 0, VALUES = 1, IS_UNION = 2, UNDERLYING_TYPE = 3, ATTRIBUTES = 4,
+                          DOCUMENTATION = 5 });
+
+impl<'buf> Verify for RSchema<'buf> {
+    fn verify(v: &mut Verifier, pos: usize) -> Result<(), VerifyError> {
+        let mut t = v.verify_table(pos)?;
+        t.visit_vector_of_tables::<RObject>(RSchema::OBJECTS, true)?;
+        t.visit_vector_of_tables::<REnum>(RSchema::ENUMS, true)?;
+        t.visit_string(RSchema::FILE_IDENT, false)?;
+        t.visit_string(RSchema::FILE_EXT, false)?;
+        t.visit_table::<RObject>(RSchema::ROOT_TABLE, false)?;
+        t.end()
+    }
+}
+
+impl<'buf> Verify for RObject<'buf> {
+    fn verify(v: &mut Verifier, pos: usize) -> Result<(), VerifyError> {
+        let mut t = v.verify_table(pos)?;
+        t.visit_string(RObject::NAME, true)?;
+        t.visit_vector_of_tables::<RField>(RObject::FIELDS, true)?;
+        t.visit_field::<bool>(RObject::IS_STRUCT, false)?;
+        t.visit_field::<i32>(RObject::MINALIGN, false)?;
+        t.visit_field::<i32>(RObject::BYTESIZE, false)?;
+        t.visit_vector_of_tables::<RKeyValue>(RObject::ATTRIBUTES, false)?;
+        t.visit_vector_of_strings(RObject::DOCUMENTATION, false)?;
+        t.end()
+    }
+}
+
+impl<'buf> Verify for RField<'buf> {
+    fn verify(v: &mut Verifier, pos: usize) -> Result<(), VerifyError> {
+        let mut t = v.verify_table(pos)?;
+        t.visit_string(RField::NAME, true)?;
+        t.visit_table::<RType>(RField::TYPE, true)?;
+        t.visit_field::<u16>(RField::ID, false)?;
+        t.visit_field::<u16>(RField::OFFSET, false)?;
+        t.visit_field::<i64>(RField::DEFAULT_INTEGER, false)?;
+        t.visit_field::<f64>(RField::DEFAULT_REAL, false)?;
+        t.visit_field::<bool>(RField::DEPRECATED, false)?;
+        t.visit_field::<bool>(RField::REQUIRED, false)?;
+        t.visit_field::<bool>(RField::KEY, false)?;
+        t.visit_vector_of_tables::<RKeyValue>(RField::ATTRIBUTES, false)?;
+        t.visit_vector_of_strings(RField::DOCUMENTATION, false)?;
+        t.end()
+    }
+}
+
+impl<'buf> Verify for RType<'buf> {
+    fn verify(v: &mut Verifier, pos: usize) -> Result<(), VerifyError> {
+        let mut t = v.verify_table(pos)?;
+        t.visit_field::<u8>(RType::BASE_TYPE, false)?;
+        t.visit_field::<u8>(RType::ELEMENT, false)?;
+        t.visit_field::<i32>(RType::INDEX, false)?;
+        t.end()
+    }
+}
+
+impl<'buf> Verify for RKeyValue<'buf> {
+    fn verify(v: &mut Verifier, pos: usize) -> Result<(), VerifyError> {
+        let mut t = v.verify_table(pos)?;
+        t.visit_string(RKeyValue::KEY, true)?;
+        t.visit_string(RKeyValue::VALUE, false)?;
+        t.end()
+    }
+}
+
+impl<'buf> Verify for REnumVal<'buf> {
+    fn verify(v: &mut Verifier, pos: usize) -> Result<(), VerifyError> {
+        let mut t = v.verify_table(pos)?;
+        t.visit_string(REnumVal::NAME, true)?;
+        t.visit_field::<i64>(REnumVal::VALUE, false)?;
+        t.visit_table::<RType>(REnumVal::UNION_TYPE, false)?;
+        t.visit_vector_of_strings(REnumVal::DOCUMENTATION, false)?;
+        t.visit_vector_of_tables::<RKeyValue>(REnumVal::ATTRIBUTES, false)?;
+        t.end()
+    }
+}
+
+impl<'buf> Verify for REnum<'buf> {
+    fn verify(v: &mut Verifier, pos: usize) -> Result<(), VerifyError> {
+        let mut t = v.verify_table(pos)?;
+        t.visit_string(REnum::NAME, true)?;
+        t.visit_vector_of_tables::<REnumVal>(REnum::VALUES, true)?;
+        t.visit_field::<bool>(REnum::IS_UNION, false)?;
+        t.visit_table::<RType>(REnum::UNDERLYING_TYPE, true)?;
+        t.visit_vector_of_tables::<RKeyValue>(REnum::ATTRIBUTES, false)?;
+        t.visit_vector_of_strings(REnum::DOCUMENTATION, false)?;
+        t.end()
+    }
+}
+
+// Read a string field. Binary schemas can come from anywhere, so it has to be checked for UTF-8.
+fn load_string(t: Table, field: VOffset) -> Result<Option<String>, LoadError> {
+    match t.get_ref::<Str>(field) {
+        Some(s) => match s.to_str() {
+            Ok(s)  => Ok(Some(s.to_string())),
+            Err(_) => Err(LoadError::Invalid(format!("string at {} is not valid UTF-8", s.pos))),
+        },
+        None    => Ok(None),
+    }
+}
+
+fn load_documentation(t: Table, field: VOffset) -> Result<Vec<String>, LoadError> {
+    let mut lines = Vec::new();
+
+    for s in t.get_ref::<Vector<Offset<Str>>>(field).into_iter().flatten() {
+        match s.to_str() {
+            Ok(s)  => lines.push(s.to_string()),
+            Err(_) => {
+                return Err(LoadError::Invalid(format!("string at {} is not valid UTF-8", s.pos)))
+            }
+        }
+    }
+
+    Ok(lines)
+}
+
+fn load_attributes(t: Table, field: VOffset) -> Result<Vec<KeyValue>, LoadError> {
+    let mut attrs = Vec::new();
+
+    for kv in t.get_ref::<Vector<Offset<RKeyValue>>>(field).into_iter().flatten() {
+        attrs.push(KeyValue {
+            key:   load_string(kv.0, RKeyValue::KEY)?.unwrap_or_default(),
+            value: load_string(kv.0, RKeyValue::VALUE)?,
+        });
+    }
+
+    Ok(attrs)
+}
+
+fn load_base_type(b: u8, owner: &str) -> Result<BaseType, LoadError> {
+    BaseType::from_u8(b).ok_or_else(|| {
+        LoadError::Invalid(format!("`{}` has unsupported base type {}", owner, b))
+    })
+}
+
+// Read a `Type`. `owner` names the thing which has the type, for error messages.
+fn load_type(t: RType, owner: &str) -> Result<Type, LoadError> {
+    let index: i32 = t.0.get_field(RType::INDEX, -1);
+
+    Ok(Type {
+        base_type: load_base_type(t.0.get_field(RType::BASE_TYPE, 0), owner)?,
+        element:   load_base_type(t.0.get_field(RType::ELEMENT, 0), owner)?,
+        index:     if index < 0 { None } else { Some(index as usize) },
+    })
+}
+
+fn load_field(f: RField, object: &str) -> Result<Field, LoadError> {
+    let t    = f.0;
+    let name = load_string(t, RField::NAME)?.unwrap_or_default();
+    let ty   = match t.get_ref::<RType>(RField::TYPE) {
+        Some(ty) => load_type(ty, &format!("{}.{}", object, name))?,
+        None     => Type::new(BaseType::None),
+    };
+
+    Ok(Field {
+        ty,
+        id:              t.get_field(RField::ID, 0),
+        offset:          t.get_field(RField::OFFSET, 0),
+        default_integer: t.get_field(RField::DEFAULT_INTEGER, 0),
+        default_real:    t.get_field(RField::DEFAULT_REAL, 0.0),
+        deprecated:      t.get_field(RField::DEPRECATED, false),
+        required:        t.get_field(RField::REQUIRED, false),
+        key:             t.get_field(RField::KEY, false),
+        attributes:      load_attributes(t, RField::ATTRIBUTES)?,
+        documentation:   load_documentation(t, RField::DOCUMENTATION)?,
+        name,
+    })
+}
+
+fn load_object(o: RObject) -> Result<Object, LoadError> {
+    let t    = o.0;
+    let name = load_string(t, RObject::NAME)?.unwrap_or_default();
+
+    let mut fields = Vec::new();
+
+    for f in t.get_ref::<Vector<Offset<RField>>>(RObject::FIELDS).into_iter().flatten() {
+        fields.push(load_field(f, &name)?);
+    }
+
+    // Binary schemas sort fields by name, but `Object` keeps them in order of id.
+    fields.sort_by_key(|f| f.id);
+
+    Ok(Object {
+        fields,
+        is_struct:     t.get_field(RObject::IS_STRUCT, false),
+        minalign:      t.get_field::<i32>(RObject::MINALIGN, 0).max(0) as usize,
+        bytesize:      t.get_field::<i32>(RObject::BYTESIZE, 0).max(0) as usize,
+        attributes:    load_attributes(t, RObject::ATTRIBUTES)?,
+        documentation: load_documentation(t, RObject::DOCUMENTATION)?,
+        name,
+    })
+}
+
+fn load_enum(e: REnum) -> Result<Enum, LoadError> {
+    let t    = e.0;
+    let name = load_string(t, REnum::NAME)?.unwrap_or_default();
+
+    let mut values = Vec::new();
+
+    for v in t.get_ref::<Vector<Offset<REnumVal>>>(REnum::VALUES).into_iter().flatten() {
+        let vname = load_string(v.0, REnumVal::NAME)?.unwrap_or_default();
+
+        // Values of plain enums, and the `NONE` member of unions, have a type of `None`.
+        let union_type = match v.0.get_ref::<RType>(REnumVal::UNION_TYPE) {
+            Some(ty) => Some(load_type(ty, &format!("{}.{}", name, vname))?),
+            None     => None,
+        };
+
+        values.push(EnumVal {
+            value:         v.0.get_field(REnumVal::VALUE, 0),
+            union_type:    union_type.filter(|ty| ty.base_type != BaseType::None),
+            attributes:    load_attributes(v.0, REnumVal::ATTRIBUTES)?,
+            documentation: load_documentation(v.0, REnumVal::DOCUMENTATION)?,
+            name:          vname,
+        });
+    }
+
+    values.sort_by_key(|v| v.value);
+
+    let underlying_type = match t.get_ref::<RType>(REnum::UNDERLYING_TYPE) {
+        Some(ty) => load_type(ty, &name)?,
+        None     => Type::new(BaseType::None),
+    };
+
+    Ok(Enum {
+        values,
+        is_union:      t.get_field(REnum::IS_UNION, false),
+        underlying_type,
+        attributes:    load_attributes(t, REnum::ATTRIBUTES)?,
+        documentation: load_documentation(t, REnum::DOCUMENTATION)?,
+        name,
+    })
+}
+
+// The field of `object` which holds the type tag (or vector of tags) for the union `field`.
+fn union_tag_field<'s>(object: &'s Object, field: &Field) -> Option<&'s Field> {
+    object.fields.iter().find(|f| {
+        let is_tag = f.ty.base_type == BaseType::UType || f.ty.element == BaseType::UType;
+        is_tag && f.id + 1 == field.id
+    })
+}
+
+// Whether the object or enum that `ty` refers to (if any) exists, and is the right kind of thing.
+fn type_is_valid(schema: &Schema, ty: Type) -> bool {
+    let base = if ty.base_type == BaseType::Vector { ty.element } else { ty.base_type };
+
+    match base {
+        BaseType::Obj                     => ty.index.is_some_and(|i| i < schema.objects.len()),
+        BaseType::Union | BaseType::UType => {
+            ty.index.is_some_and(|i| i < schema.enums.len() && schema.enums[i].is_union)
+        }
+        BaseType::String                  => true,
+        BaseType::None | BaseType::Vector => false,
+        _                                 => ty.index.is_none_or(|i| i < schema.enums.len()),
+    }
+}
+
+// Check the things that `DynTable` and friends rely on, which the verifier can't: that every type
+// refers to something which exists, that table fields sit where their ids say, and that structs
+// only hold scalars and structs which fit, and don't hold themselves.
+fn check_schema(schema: &Schema) -> Result<(), LoadError> {
+    for o in schema.objects.iter() {
+        for f in o.fields.iter() {
+            if !type_is_valid(schema, f.ty) {
+                return Err(LoadError::Invalid(format!("field `{}` of `{}` has an invalid type",
+                                                      f.name, o.name)))
+            }
+
+            let is_union = f.ty.base_type == BaseType::Union ||
+                           (f.ty.base_type == BaseType::Vector && f.ty.element == BaseType::Union);
+
+            if is_union && union_tag_field(o, f).is_none() {
+                return Err(LoadError::Invalid(format!("union `{}` of `{}` doesn't have a type \
+                                                       field", f.name, o.name)))
+            }
+
+            if !o.is_struct {
+                if f.offset as usize != 4 + 2 * f.id as usize {
+                    return Err(LoadError::Invalid(format!("field `{}` of `{}` has the wrong vtable \
+                                                           offset", f.name, o.name)))
+                }
+
+                continue
+            }
+
+            let size = match f.ty.base_type {
+                b if b.is_scalar() => b.size(),
+                BaseType::Obj if schema.objects[f.ty.index.unwrap()].is_struct => {
+                    schema.objects[f.ty.index.unwrap()].bytesize
+                }
+                _ => {
+                    return Err(LoadError::Invalid(format!("field `{}` of struct `{}` isn't a \
+                                                           scalar or a struct", f.name, o.name)))
+                }
+            };
+
+            if f.offset as usize + size > o.bytesize {
+                return Err(LoadError::Invalid(format!("field `{}` doesn't fit in struct `{}`",
+                                                      f.name, o.name)))
+            }
+        }
+    }
+
+    for o in schema.objects.iter().filter(|o| o.is_struct) {
+        if !o.minalign.is_power_of_two() || !o.bytesize.is_multiple_of(o.minalign) {
+            return Err(LoadError::Invalid(format!("struct `{}` has an invalid size or alignment",
+                                                  o.name)))
+        }
+    }
+
+    // A struct fits inside the structs that hold it, but that doesn't stop one holding itself at
+    // offset 0, and walking that would never end. Peel off the structs whose fields are all
+    // scalars or structs already peeled off: any left over are part of a cycle.
+    let mut pending = vec![0usize; schema.objects.len()];
+    let mut holders = vec![Vec::new(); schema.objects.len()];
+    let mut ready   = Vec::new();
+
+    for (i, o) in schema.objects.iter().enumerate().filter(|&(_, o)| o.is_struct) {
+        for f in o.fields.iter().filter(|f| f.ty.base_type == BaseType::Obj) {
+            pending[i] += 1;
+            holders[f.ty.index.unwrap()].push(i);
+        }
+
+        if pending[i] == 0 {
+            ready.push(i);
+        }
+    }
+
+    while let Some(i) = ready.pop() {
+        for &h in holders[i].iter() {
+            pending[h] -= 1;
+            if pending[h] == 0 {
+                ready.push(h);
+            }
+        }
+    }
+
+    if let Some(i) = pending.iter().position(|&n| n > 0) {
+        return Err(LoadError::Invalid(format!("struct `{}` contains itself",
+                                              schema.objects[i].name)))
+    }
+
+    if schema.file_ident.as_ref().is_some_and(|i| i.len() != ::FILE_IDENTIFIER_LENGTH) {
+        return Err(LoadError::Invalid("file identifier must be exactly 4 bytes".to_string()))
+    }
+
+    for e in schema.enums.iter() {
+        let valid = e.values.iter().all(|v| {
+            v.union_type.is_none_or(|ty| type_is_valid(schema, ty))
+        });
+
+        if !valid || !e.underlying_type.base_type.is_integer() {
+            return Err(LoadError::Invalid(format!("enum `{}` has an invalid type", e.name)))
+        }
+    }
+
+    Ok(())
+}
+
+/// Load a binary schema, as written by `flatc --binary --schema`. The buffer is verified first, so
+/// it's safe to load schemas from an untrusted source.
+pub fn load_schema(bfbs: &[u8]) -> Result<Schema, LoadError> {
+    Verifier::new(bfbs).verify_root_with_identifier::<RSchema>(BFBS_IDENTIFIER)?;
+
+    let root = ::get_root::<RSchema>(bfbs).0;
+
+    let mut objects = Vec::new();
+
+    for o in root.get_ref::<Vector<Offset<RObject>>>(RSchema::OBJECTS).into_iter().flatten() {
+        objects.push(load_object(o)?);
+    }
+
+    let mut enums = Vec::new();
+
+    for e in root.get_ref::<Vector<Offset<REnum>>>(RSchema::ENUMS).into_iter().flatten() {
+        enums.push(load_enum(e)?);
+    }
+
+    // The root table is stored as a table of its own, rather than an index, so find it by name.
+    let root_table = match root.get_ref::<RObject>(RSchema::ROOT_TABLE) {
+        Some(o) => {
+            let name = load_string(o.0, RObject::NAME)?.unwrap_or_default();
+
+            match objects.iter().position(|o| o.name == name && !o.is_struct) {
+                Some(i) => Some(i),
+                None    => {
+                    return Err(LoadError::Invalid(format!("root table `{}` isn't one of the \
+                                                           schema's tables", name)))
+                }
+            }
+        }
+        None    => None,
+    };
+
+    let schema = Schema {
+        objects,
+        enums,
+        root_table,
+        file_ident: load_string(root, RSchema::FILE_IDENT)?,
+        file_ext:   load_string(root, RSchema::FILE_EXT)?,
+    };
+
+    check_schema(&schema)?;
+
+    Ok(schema)
+}
+
+/// A value read from a buffer according to a schema. Integers are widened to 64 bits, and enums
+/// are read as their underlying integer type.
+#[derive(Clone, Copy)]
+pub enum Value<'s, 'buf> {
+    Bool(bool),
+
+    /// A signed integer.
+    Int(i64),
+
+    /// An unsigned integer. Union type tags are read as these.
+    UInt(u64),
+    Float(f64),
+    Str(Str<'buf>),
+    Vector(DynVector<'s, 'buf>),
+    Table(DynTable<'s, 'buf>),
+    Struct(DynStruct<'s, 'buf>),
+    Union(DynUnion<'s, 'buf>),
+}
+
+impl<'s, 'buf> Value<'s, 'buf> {
+    pub fn as_bool(&self) -> Option<bool> {
+        match *self {
+            Value::Bool(b) => Some(b),
+            _              => None,
+        }
+    }
+
+    /// The value as an `i64`, if it's an integer which fits in one.
+    pub fn as_i64(&self) -> Option<i64> {
+        match *self {
+            Value::Int(i)                          => Some(i),
+            Value::UInt(u) if u <= i64::MAX as u64 => Some(u as i64),
+            _                                      => None,
+        }
+    }
+
+    /// The value as a `u64`, if it's an integer which fits in one.
+    pub fn as_u64(&self) -> Option<u64> {
+        match *self {
+            Value::UInt(u)          => Some(u),
+            Value::Int(i) if i >= 0 => Some(i as u64),
+            _                       => None,
+        }
+    }
+
+    /// The value as an `f64`, if it's a number.
+    pub fn as_f64(&self) -> Option<f64> {
+        match *self {
+            Value::Float(f) => Some(f),
+            Value::Int(i)   => Some(i as f64),
+            Value::UInt(u)  => Some(u as f64),
+            _               => None,
+        }
+    }
+
+    /// The value as a `str`, if it's a string which is valid UTF-8.
+    pub fn as_str(&self) -> Option<&'buf str> {
+        match *self {
+            Value::Str(s) => s.to_str().ok(),
+            _             => None,
+        }
+    }
+
+    pub fn as_vector(&self) -> Option<DynVector<'s, 'buf>> {
+        match *self {
+            Value::Vector(v) => Some(v),
+            _                => None,
+        }
+    }
+
+    pub fn as_table(&self) -> Option<DynTable<'s, 'buf>> {
+        match *self {
+            Value::Table(t) => Some(t),
+            _               => None,
+        }
+    }
+
+    pub fn as_struct(&self) -> Option<DynStruct<'s, 'buf>> {
+        match *self {
+            Value::Struct(s) => Some(s),
+            _                => None,
+        }
+    }
+
+    pub fn as_union(&self) -> Option<DynUnion<'s, 'buf>> {
+        match *self {
+            Value::Union(u) => Some(u),
+            _               => None,
+        }
+    }
+}
+
+// Read the scalar of type `base` which is stored at `pos` in `buf`.
+fn read_scalar<'s, 'buf>(buf: &[u8], pos: usize, base: BaseType) -> Value<'s, 'buf> {
+    let b = &buf[pos..];
+
+    match base {
+        BaseType::Bool                    => Value::Bool(bool::read_le(b)),
+        BaseType::Byte                    => Value::Int(i8::read_le(b) as i64),
+        BaseType::Short                   => Value::Int(i16::read_le(b) as i64),
+        BaseType::Int                     => Value::Int(i32::read_le(b) as i64),
+        BaseType::Long                    => Value::Int(i64::read_le(b)),
+        BaseType::UType | BaseType::UByte => Value::UInt(u8::read_le(b) as u64),
+        BaseType::UShort                  => Value::UInt(u16::read_le(b) as u64),
+        BaseType::UInt                    => Value::UInt(u32::read_le(b) as u64),
+        BaseType::ULong                   => Value::UInt(u64::read_le(b)),
+        BaseType::Float                   => Value::Float(f32::read_le(b) as f64),
+        BaseType::Double                  => Value::Float(f64::read_le(b)),
+        _                                 => panic!("{:?} isn't a scalar type", base),
+    }
+}
+
+// The value of a scalar field which isn't present in its table.
+fn default_value<'s, 'buf>(field: &Field) -> Value<'s, 'buf> {
+    match field.ty.base_type {
+        BaseType::Bool     => Value::Bool(field.default_integer != 0),
+        b if b.is_float()  => Value::Float(field.default_real),
+        b if b.is_signed() => Value::Int(field.default_integer),
+        _                  => Value::UInt(field.default_integer as u64),
+    }
+}
+
+fn is_struct(schema: &Schema, ty: Type) -> bool {
+    ty.base_type == BaseType::Obj && schema.objects[ty.index.unwrap()].is_struct
+}
+
+// The number of bytes that a value of type `ty` takes up in a struct or vector.
+fn inline_size(schema: &Schema, ty: Type) -> usize {
+    match ty.base_type {
+        BaseType::Obj if is_struct(schema, ty) => schema.objects[ty.index.unwrap()].bytesize,
+        b                                      => b.size(),
+    }
+}
+
+// The alignment of a value of type `ty` in a struct or vector.
+fn inline_align(schema: &Schema, ty: Type) -> usize {
+    match ty.base_type {
+        BaseType::Obj if is_struct(schema, ty) => schema.objects[ty.index.unwrap()].minalign,
+        b                                      => b.size(),
+    }
+}
+
+// Read a value of type `ty` whose inline part (the value itself, or an offset to it) is at `pos`.
+// Unions are handled by the callers, since their type tags are stored separately.
+fn read_value<'s, 'buf>(schema: &'s Schema, buf: &'buf [u8], pos: usize, ty: Type)
+    -> Value<'s, 'buf> {
+
+    match ty.base_type {
+        BaseType::String => Value::Str(Str::view(buf, follow_offset(buf, pos))),
+        BaseType::Vector => Value::Vector(DynVector {
+            schema,
+            element: ty.element_type(),
+            buf,
+            pos:     follow_offset(buf, pos),
+            types:   None,
+        }),
+        BaseType::Obj    => {
+            let object = &schema.objects[ty.index.unwrap()];
+
+            if object.is_struct {
+                Value::Struct(DynStruct { schema, object, buf, pos })
+            } else {
+                let table = Table::view(buf, follow_offset(buf, pos));
+                Value::Table(DynTable { schema, object, table })
+            }
+        }
+        b                => read_scalar(buf, pos, b),
+    }
+}
+
+/// A table in a buffer, read according to a schema rather than by generated code.
+#[derive(Clone, Copy)]
+pub struct DynTable<'s, 'buf> {
+    schema: &'s Schema,
+    object: &'s Object,
+    table:  Table<'buf>,
+}
+
+impl<'s, 'buf> DynTable<'s, 'buf> {
+    /// Read `table` as an instance of `object`, which must be one of `schema`'s tables.
+    pub fn new(schema: &'s Schema, object: &'s Object, table: Table<'buf>) -> DynTable<'s, 'buf> {
+        DynTable { schema, object, table }
+    }
+
+    pub fn schema(&self) -> &'s Schema { self.schema }
+
+    /// The schema's description of this table.
+    pub fn object(&self) -> &'s Object { self.object }
+
+    /// The underlying table.
+    pub fn table(&self) -> Table<'buf> { self.table }
+
+    /// Whether `field` is present in the buffer.
+    pub fn is_present(&self, field: &Field) -> bool {
+        self.table.check_field(field.offset)
+    }
+
+    /// Read the field called `name`. This returns `None` if there's no such field, or if it's a
+    /// string, vector, table or union which isn't set. Scalars which aren't present have their
+    /// default value.
+    pub fn get(&self, name: &str) -> Option<Value<'s, 'buf>> {
+        self.object.field_by_name(name).and_then(|f| self.value(f))
+    }
+
+    /// Like `get`, for one of the fields of `object()`.
+    pub fn value(&self, field: &Field) -> Option<Value<'s, 'buf>> {
+        let buf = self.table.buf();
+        let pos = self.table.field_pos(field.offset);
+
+        match field.ty.base_type {
+            b if b.is_scalar() => {
+                Some(pos.map_or_else(|| default_value(field), |p| read_scalar(buf, p, b)))
+            }
+            BaseType::Union    => {
+                let tag: u8 = self.table.get_field(union_tag_field(self.object, field)?.offset, 0);
+
+                if tag == 0 {
+                    return None
+                }
+
+                Some(Value::Union(DynUnion {
+                    schema:   self.schema,
+                    enum_def: &self.schema.enums[field.ty.index.unwrap()],
+                    tag,
+                    table:    Some(Table::view(buf, follow_offset(buf, pos?))),
+                }))
+            }
+            BaseType::Vector if field.ty.element == BaseType::Union => {
+                let types: Vector<u8> =
+                    self.table.get_ref(union_tag_field(self.object, field)?.offset)?;
+                let pos = follow_offset(buf, pos?);
+
+                ::check_union_lengths(types.len(), UOffset::read_le(&buf[pos..]) as usize);
+
+                Some(Value::Vector(DynVector {
+                    schema:  self.schema,
+                    element: field.ty.element_type(),
+                    buf,
+                    pos,
+                    types:   Some(types),
+                }))
+            }
+            _                  => pos.map(|p| read_value(self.schema, buf, p, field.ty)),
+        }
+    }
+
+    /// Iterate over the fields which are present in the buffer, along with their values, in order
+    /// of id. Deprecated fields are skipped.
+    pub fn fields(&self) -> FieldIter<'s, 'buf> {
+        FieldIter {
+            fields:    self.object.fields.iter(),
+            container: Container::Table(*self),
+        }
+    }
+}
+
+/// A struct in a buffer, read according to a schema.
+#[derive(Clone, Copy)]
+pub struct DynStruct<'s, 'buf> {
+    schema: &'s Schema,
+    object: &'s Object,
+    buf:    &'buf [u8],
+    pos:    usize,
+}
+
+impl<'s, 'buf> DynStruct<'s, 'buf> {
+    /// The schema's description of this struct.
+    pub fn object(&self) -> &'s Object { self.object }
+
+    /// The bytes which make up the struct.
+    pub fn bytes(&self) -> &'buf [u8] {
+        &self.buf[self.pos..][..self.object.bytesize]
+    }
+
+    /// Read the field called `name`, or return `None` if there's no such field.
+    pub fn get(&self, name: &str) -> Option<Value<'s, 'buf>> {
+        self.object.field_by_name(name).map(|f| self.value(f))
+    }
+
+    /// Like `get`, for one of the fields of `object()`.
+    pub fn value(&self, field: &Field) -> Value<'s, 'buf> {
+        read_value(self.schema, self.buf, self.pos + field.offset as usize, field.ty)
+    }
+
+    /// Iterate over the struct's fields, along with their values.
+    pub fn fields(&self) -> FieldIter<'s, 'buf> {
+        FieldIter {
+            fields:    self.object.fields.iter(),
+            container: Container::Struct(*self),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Container<'s, 'buf> {
+    Table(DynTable<'s, 'buf>),
+    Struct(DynStruct<'s, 'buf>),
+}
+
+/// An iterator over the fields of a `DynTable` or `DynStruct`, and their values.
+pub struct FieldIter<'s, 'buf> {
+    fields:    slice::Iter<'s, Field>,
+    container: Container<'s, 'buf>,
+}
+
+impl<'s, 'buf> Iterator for FieldIter<'s, 'buf> {
+    type Item = (&'s Field, Value<'s, 'buf>);
+
+    fn next(&mut self) -> Option<(&'s Field, Value<'s, 'buf>)> {
+        for f in self.fields.by_ref() {
+            match self.container {
+                Container::Table(t) if !f.deprecated && t.is_present(f) => {
+                    if let Some(v) = t.value(f) {
+                        return Some((f, v))
+                    }
+                }
+                Container::Table(_)  => {}
+                Container::Struct(s) => return Some((f, s.value(f))),
+            }
+        }
+
+        None
+    }
+}
+
+/// A union in a buffer, read according to a schema.
+#[derive(Clone, Copy)]
+pub struct DynUnion<'s, 'buf> {
+    schema:   &'s Schema,
+    enum_def: &'s Enum,
+    tag:      u8,
+    table:    Option<Table<'buf>>,
+}
+
+impl<'s, 'buf> DynUnion<'s, 'buf> {
+    /// The schema's description of the union.
+    pub fn enum_def(&self) -> &'s Enum { self.enum_def }
+
+    /// The union's type tag.
+    pub fn tag(&self) -> u8 { self.tag }
+
+    /// The member of the union that the type tag refers to, or `None` if it's unknown (e.g.
+    /// because the buffer was built with a newer version of the schema).
+    pub fn variant(&self) -> Option<&'s EnumVal> {
+        self.enum_def.value_by_value(self.tag as i64)
+    }
+
+    /// The member's table, or `None` if the type is `NONE`.
+    pub fn table(&self) -> Option<Table<'buf>> { self.table }
+
+    /// The member's table read as the type that the tag refers to, or `None` if the type is
+    /// `NONE`, unknown, or not a table.
+    pub fn value(&self) -> Option<DynTable<'s, 'buf>> {
+        let ty = self.variant()?.union_type?;
+
+        if ty.base_type != BaseType::Obj || is_struct(self.schema, ty) {
+            return None
+        }
+
+        self.table.map(|t| DynTable::new(self.schema, &self.schema.objects[ty.index.unwrap()], t))
+    }
+}
+
+/// A vector in a buffer, read according to a schema.
+#[derive(Clone, Copy)]
+pub struct DynVector<'s, 'buf> {
+    schema:  &'s Schema,
+    element: Type,
+    buf:     &'buf [u8],
+    pos:     usize,
+
+    // For vectors of unions, the parallel vector of type tags.
+    types:   Option<Vector<'buf, u8>>,
+}
+
+impl<'s, 'buf> DynVector<'s, 'buf> {
+    /// The type of the vector's elements.
+    pub fn element_type(&self) -> Type { self.element }
+
+    pub fn len(&self) -> usize {
+        UOffset::read_le(&self.buf[self.pos..]) as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The element at `idx`. Elements of vectors of unions are always returned as
+    /// `Value::Union`, even if their type is `NONE`.
+    pub fn get(&self, idx: usize) -> Option<Value<'s, 'buf>> {
+        if idx >= self.len() {
+            return None
+        }
+
+        let size = inline_size(self.schema, self.element);
+        let pos  = self.pos + mem::size_of::<UOffset>() + idx * size;
+
+        match self.types {
+            Some(types) => {
+                let tag = types.get(idx)?;
+
+                Some(Value::Union(DynUnion {
+                    schema:   self.schema,
+                    enum_def: &self.schema.enums[self.element.index.unwrap()],
+                    tag,
+                    table:    if tag == 0 { None } else {
+                        Some(Table::view(self.buf, follow_offset(self.buf, pos)))
+                    },
+                }))
+            }
+            None        => Some(read_value(self.schema, self.buf, pos, self.element)),
+        }
+    }
+
+    pub fn iter(&self) -> DynVecIter<'s, 'buf> {
+        DynVecIter {
+            vec: *self,
+            idx: 0,
+        }
+    }
+}
+
+impl<'s, 'buf> IntoIterator for DynVector<'s, 'buf> {
+    type Item     = Value<'s, 'buf>;
+    type IntoIter = DynVecIter<'s, 'buf>;
+
+    fn into_iter(self) -> DynVecIter<'s, 'buf> {
+        self.iter()
+    }
+}
+
+/// An iterator over the elements of a `DynVector`.
+pub struct DynVecIter<'s, 'buf> {
+    vec: DynVector<'s, 'buf>,
+    idx: usize,
+}
+
+impl<'s, 'buf> Iterator for DynVecIter<'s, 'buf> {
+    type Item = Value<'s, 'buf>;
+
+    fn next(&mut self) -> Option<Value<'s, 'buf>> {
+        let idx = self.idx;
+        self.idx = idx + 1;
+
+        self.vec.get(idx)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let left = self.vec.len().saturating_sub(self.idx);
+        (left, Some(left))
+    }
+}
+
+/// Return the root table of `buf`, read as the schema's `root_type`, or `None` if the schema
+/// doesn't have one. Like `::get_root`, this does no checking of the buffer.
+pub fn get_root<'s, 'buf>(schema: &'s Schema, buf: &'buf [u8]) -> Option<DynTable<'s, 'buf>> {
+    schema.root_table().map(|o| DynTable::new(schema, o, ::get_root(buf)))
+}
+
+/// Like `get_root`, but check the buffer first, so that reading it can't panic.
+pub fn get_root_verified<'s, 'buf>(schema: &'s Schema, buf: &'buf [u8])
+    -> Result<Option<DynTable<'s, 'buf>>, VerifyError> {
+
+    let object = match schema.root_table() {
+        Some(o) => o,
+        None    => return Ok(None),
+    };
+
+    let mut v = Verifier::new(buf);
+
+    let root = v.verify_offset(0)?;
+    verify_table(&mut v, schema, object, root)?;
+
+    Ok(Some(DynTable::new(schema, object, ::get_root(buf))))
+}
+
+/// Check that `buf` can be read as a table of the schema's `root_type`, as `get_root_verified`
+/// does.
+///
+/// # Panics
+///
+/// If the schema doesn't have a `root_type`.
+pub fn verify(schema: &Schema, buf: &[u8]) -> Result<(), VerifyError> {
+    assert!(schema.root_table.is_some(), "the schema doesn't have a root type");
+    get_root_verified(schema, buf).map(|_| ())
+}
+
+/// Check the table at `pos` in the verifier's buffer as an instance of `object`, which must be one
+/// of `schema`'s tables. This does for any table what the generated `Verify` impls do for theirs,
+/// for use with a `Verifier` whose limits have been changed.
+pub fn verify_table(v: &mut Verifier, schema: &Schema, object: &Object, pos: usize)
+    -> Result<(), VerifyError> {
+
+    let mut t = v.verify_table(pos)?;
+
+    for f in object.fields.iter() {
+        let is_union_vector = f.ty.base_type == BaseType::Vector && f.ty.element == BaseType::Union;
+
+        if f.ty.base_type == BaseType::Union || is_union_vector {
+            let tag = match union_tag_field(object, f) {
+                Some(tag) => tag.offset,
+                None      => continue,
+            };
+
+            let en = &schema.enums[f.ty.index.unwrap()];
+            let member = |v: &mut Verifier, tag, pos| verify_union_member(v, schema, en, tag, pos);
+
+            if is_union_vector {
+                t.visit_union_vector(tag, f.offset, f.required, member)?;
+            } else {
+                t.visit_union(tag, f.offset, f.required, member)?;
+            }
+
+            continue
+        }
+
+        match t.field_pos(f.offset) {
+            Some(p)            => verify_value(t.verifier(), schema, f.ty, p)?,
+            None if f.required => {
+                return Err(VerifyError::MissingRequiredField { pos, field: f.offset })
+            }
+            None               => {}
+        }
+    }
+
+    t.end()
+}
+
+// Check a value of type `ty` whose inline part is at `pos`, following everything that `read_value`
+// would follow.
+fn verify_value(v: &mut Verifier, schema: &Schema, ty: Type, pos: usize) -> Result<(), VerifyError> {
+    match ty.base_type {
+        BaseType::String => {
+            let target = v.verify_offset(pos)?;
+            v.verify_string(target)
+        }
+        BaseType::Vector => {
+            let target = v.verify_offset(pos)?;
+            let elem   = ty.element_type();
+            let size   = inline_size(schema, elem);
+            let len    = v.verify_vector_header(target, size)?;
+            let data   = target + mem::size_of::<UOffset>();
+
+            // Elements which are stored inline only need to be aligned, since the header check
+            // covers their bytes. Strings and tables have to be followed.
+            if elem.base_type.is_scalar() || is_struct(schema, elem) {
+                return v.is_aligned(data, inline_align(schema, elem))
+            }
+
+            for i in 0..len {
+                verify_value(v, schema, elem, data + i * size)?;
+            }
+
+            Ok(())
+        }
+        BaseType::Obj if is_struct(schema, ty) => {
+            let object = &schema.objects[ty.index.unwrap()];
+            v.is_aligned(pos, object.minalign)?;
+            v.in_bounds(pos, object.bytesize)
+        }
+        BaseType::Obj    => {
+            let target = v.verify_offset(pos)?;
+            verify_table(v, schema, &schema.objects[ty.index.unwrap()], target)
+        }
+        b                => {
+            v.is_aligned(pos, b.size())?;
+            v.in_bounds(pos, b.size())
+        }
+    }
+}
+
+// Check the member of the union `en` whose type tag is `tag`. Only tables are read through a
+// `DynUnion`, so other kinds of member, and unknown tags, are accepted as they are.
+fn verify_union_member(v: &mut Verifier, schema: &Schema, en: &Enum, tag: u8, pos: usize)
+    -> Result<(), VerifyError> {
+
+    match en.value_by_value(tag as i64).and_then(|m| m.union_type) {
+        Some(ty) if ty.base_type == BaseType::Obj && !is_struct(schema, ty) => {
+            verify_table(v, schema, &schema.objects[ty.index.unwrap()], pos)
+        }
+        _ => Ok(()),
+    }
+}
+
+// Write `schema` as a `.bfbs`, laid out the way `flatc` lays them out, with each object's
+// fields sorted by name. This is for tests which need to load a schema that `schema` can't
+// compile, because it's broken.
+#[cfg(test)]
+pub(crate) fn write_bfbs(schema: &Schema) -> Vec<u8> {
+    use FlatBufferBuilder;
+
+    type Strings = Vector<'static, Offset<Str<'static>>>;
+    type Tables  = Vector<'static, Offset<Table<'static>>>;
+
+    fn string_vector(fbb: &mut FlatBufferBuilder, lines: &[String]) -> Offset<Strings> {
+        let lines: Vec<_> = lines.iter().map(|l| fbb.create_string(l)).collect();
+        fbb.create_vector_of_offsets(&lines)
+    }
+
+    fn attributes(fbb: &mut FlatBufferBuilder, attrs: &[KeyValue]) -> Offset<Tables> {
+        let mut kvs = Vec::new();
+
+        for kv in attrs.iter() {
+            let key   = fbb.create_string(&kv.key);
+            let value = kv.value.as_ref().map(|v| fbb.create_string(v));
+            let start = fbb.start_table();
+
+            fbb.add_offset(RKeyValue::KEY, key);
+            if let Some(v) = value {
+                fbb.add_offset(RKeyValue::VALUE, v);
+            }
+
+            kvs.push(Offset::new(fbb.end_table(start, 2)));
+        }
+
+        fbb.create_vector_of_offsets(&kvs)
+    }
+
+    fn ty(fbb: &mut FlatBufferBuilder, ty: Type) -> Offset<Table<'static>> {
+        let start = fbb.start_table();
+        fbb.add_scalar(RType::BASE_TYPE, ty.base_type as u8, 0);
+        fbb.add_scalar(RType::ELEMENT, ty.element as u8, 0);
+        fbb.add_scalar(RType::INDEX, ty.index.map_or(-1, |i| i as i32), -1);
+        Offset::new(fbb.end_table(start, 3))
+    }
+
+    let mut fbb = FlatBufferBuilder::new(1024);
+
+    let mut objects = Vec::new();
+
+    for o in schema.objects.iter() {
+        let mut sorted: Vec<&Field> = o.fields.iter().collect();
+        sorted.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let mut fields = Vec::new();
+
+        for f in sorted {
+            let name  = fbb.create_string(&f.name);
+            let fty   = ty(&mut fbb, f.ty);
+            let attrs = attributes(&mut fbb, &f.attributes);
+            let docs  = string_vector(&mut fbb, &f.documentation);
+            let start = fbb.start_table();
+
+            fbb.add_offset(RField::NAME, name);
+            fbb.add_offset(RField::TYPE, fty);
+            fbb.add_scalar(RField::ID, f.id, 0);
+            fbb.add_scalar(RField::OFFSET, f.offset, 0);
+            fbb.add_scalar(RField::DEFAULT_INTEGER, f.default_integer, 0);
+            fbb.add_scalar(RField::DEFAULT_REAL, f.default_real, 0.0);
+            fbb.add_scalar(RField::DEPRECATED, f.deprecated, false);
+            fbb.add_scalar(RField::REQUIRED, f.required, false);
+            fbb.add_scalar(RField::KEY, f.key, false);
+            fbb.add_offset(RField::ATTRIBUTES, attrs);
+            fbb.add_offset(RField::DOCUMENTATION, docs);
+
+            fields.push(Offset::<Table>::new(fbb.end_table(start, 11)));
+        }
+
+        let name   = fbb.create_string(&o.name);
+        let fields = fbb.create_vector_of_offsets(&fields);
+        let attrs  = attributes(&mut fbb, &o.attributes);
+        let docs   = string_vector(&mut fbb, &o.documentation);
+        let start  = fbb.start_table();
+
+        fbb.add_offset(RObject::NAME, name);
+        fbb.add_offset(RObject::FIELDS, fields);
+        fbb.add_scalar(RObject::IS_STRUCT, o.is_struct, false);
+        fbb.add_scalar(RObject::MINALIGN, o.minalign as i32, 0);
+        fbb.add_scalar(RObject::BYTESIZE, o.bytesize as i32, 0);
+        fbb.add_offset(RObject::ATTRIBUTES, attrs);
+        fbb.add_offset(RObject::DOCUMENTATION, docs);
+
+        objects.push(Offset::<Table>::new(fbb.end_table(start, 7)));
+    }
+
+    let mut enums = Vec::new();
+
+    for e in schema.enums.iter() {
+        let mut values = Vec::new();
+
+        for v in e.values.iter() {
+            let name  = fbb.create_string(&v.name);
+            let uty   = v.union_type.map(|t| ty(&mut fbb, t));
+            let docs  = string_vector(&mut fbb, &v.documentation);
+            let attrs = attributes(&mut fbb, &v.attributes);
+            let start = fbb.start_table();
+
+            fbb.add_offset(REnumVal::NAME, name);
+            fbb.add_scalar(REnumVal::VALUE, v.value, 0);
+            if let Some(t) = uty {
+                fbb.add_offset(REnumVal::UNION_TYPE, t);
+            }
+            fbb.add_offset(REnumVal::DOCUMENTATION, docs);
+            fbb.add_offset(REnumVal::ATTRIBUTES, attrs);
+
+            values.push(Offset::<Table>::new(fbb.end_table(start, 6)));
+        }
+
+        let name   = fbb.create_string(&e.name);
+        let values = fbb.create_vector_of_offsets(&values);
+        let under  = ty(&mut fbb, e.underlying_type);
+        let attrs  = attributes(&mut fbb, &e.attributes);
+        let docs   = string_vector(&mut fbb, &e.documentation);
+        let start  = fbb.start_table();
+
+        fbb.add_offset(REnum::NAME, name);
+        fbb.add_offset(REnum::VALUES, values);
+        fbb.add_scalar(REnum::IS_UNION, e.is_union, false);
+        fbb.add_offset(REnum::UNDERLYING_TYPE, under);
+        fbb.add_offset(REnum::ATTRIBUTES, attrs);
+        fbb.add_offset(REnum::DOCUMENTATION, docs);
+
+        enums.push(Offset::<Table>::new(fbb.end_table(start, 6)));
+    }
+
+    let root_table = schema.root_table.map(|i| objects[i]);
+    let objects    = fbb.create_vector_of_offsets(&objects);
+    let enums      = fbb.create_vector_of_offsets(&enums);
+    let ident      = schema.file_ident.as_ref().map(|i| fbb.create_string(i));
+    let start      = fbb.start_table();
+
+    fbb.add_offset(RSchema::OBJECTS, objects);
+    fbb.add_offset(RSchema::ENUMS, enums);
+    if let Some(i) = ident {
+        fbb.add_offset(RSchema::FILE_IDENT, i);
+    }
+    if let Some(o) = root_table {
+        fbb.add_offset(RSchema::ROOT_TABLE, o);
+    }
+
+    let root = fbb.end_table(start, 5);
+    fbb.finish_with_identifier(Offset::<Table>::new(root), BFBS_IDENTIFIER);
+
+    fbb.finished_data().unwrap().as_bytes().to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use reflection::{BuildValue, DynamicBuilder};
+    use schema;
+    use test_support::{for_each_corruption, for_each_truncation};
+    use FlatBufferBuilder;
+
+    const MONSTER: &str = r#"
+namespace Game;
+
+enum Color : ubyte { Red, Green, Blue }
+
+struct Vec3 {
+  x: float;
+  y: float;
+  z: float;
+}
+
+/// A path between two points.
+struct Line {
+  from: Vec3;
+  to: Vec3;
+  weight: short;
+}
+
+table Weapon {
+  name: string;
+  damage: short = 10;
+}
+
+table Shield { defense: int; }
+
+union Equipment { Weapon, Shield }
+
+table Monster {
+  name: string (required);
+  hp: short = 100;
+  color: Color = Blue;
+  pos: Vec3;
+  route: [Line];
+  inventory: [ubyte];
+  tags: [string];
+  weapons: [Weapon];
+  equipped: Equipment;
+  loot: [Equipment];
+  friend: Monster;
+  speed: double = 1.5 (deprecated);
+}
+
+root_type Monster;
+file_identifier "MONS";
+"#;
+
+    fn load_monster() -> Schema {
+        load_schema(&write_bfbs(&schema::compile_str(MONSTER).unwrap())).unwrap()
+    }
+
+    fn fields(fields: &[(&str, BuildValue)]) -> Vec<(String, BuildValue)> {
+        fields.iter().map(|(n, v)| (n.to_string(), v.clone())).collect()
+    }
+
+    fn vec3(x: f64, y: f64, z: f64) -> BuildValue {
+        BuildValue::Object(fields(&[("x", x.into()), ("y", y.into()), ("z", z.into())]))
+    }
+
+    fn build_monster(schema: &Schema) -> Vec<u8> {
+        let line = |x: f64, weight: i64| BuildValue::Object(fields(&[
+            ("from", vec3(x, 0.0, 0.0)), ("to", vec3(x + 1.0, 0.0, 0.0)), ("weight", weight.into()),
+        ]));
+
+        let sword = fields(&[("name", "sword".into()), ("damage", 3i64.into())]);
+
+        let monster = fields(&[
+            ("name",      "orc".into()),
+            ("hp",        80i64.into()),
+            ("color",     "Green".into()),
+            ("pos",       vec3(1.0, 2.0, 3.0)),
+            ("route",     BuildValue::Vector(vec![line(0.0, 5), line(1.0, 7)])),
+            ("inventory", BuildValue::Vector(vec![1i64.into(), 2i64.into(), 3i64.into()])),
+            ("tags",      BuildValue::Vector(vec!["big".into(), "green".into()])),
+            ("weapons",   BuildValue::Vector(vec![BuildValue::Object(sword.clone()),
+                                                  BuildValue::Object(fields(&[]))])),
+            ("equipped",  BuildValue::Union("Weapon".to_string(), sword)),
+            ("loot",      BuildValue::Vector(vec![
+                BuildValue::Union("Shield".to_string(), fields(&[("defense", 4i64.into())])),
+                BuildValue::Union("Weapon".to_string(), fields(&[("name", "axe".into())])),
+            ])),
+            ("friend",    BuildValue::Object(fields(&[("name", "goblin".into())]))),
+        ]);
+
+        let mut fbb = FlatBufferBuilder::new(1024);
+        DynamicBuilder::new(schema, &mut fbb).finish(&monster).unwrap();
+        fbb.finished_data().unwrap().as_bytes().to_vec()
+    }
+
+    // Read everything that `v` refers to, returning the number of values read.
+    fn walk(v: Value) -> usize {
+        1 + match v {
+            Value::Vector(v) => v.iter().map(walk).sum(),
+            Value::Table(t)  => t.fields().map(|(_, v)| walk(v)).sum(),
+            Value::Struct(s) => s.fields().map(|(_, v)| walk(v)).sum(),
+            Value::Union(u)  => u.value().map_or(0, |t| walk(Value::Table(t))),
+            Value::Str(s)    => s.as_bytes().len(),
+            _                => 0,
+        }
+    }
+
+    #[test]
+    fn binary_schemas_are_loaded() {
+        let schema = schema::compile_str(MONSTER).unwrap();
+        assert_eq!(load_schema(&write_bfbs(&schema)).unwrap(), schema);
+
+        let monster = schema.root_table().unwrap();
+        assert_eq!(monster.name, "Game.Monster");
+        assert_eq!(monster.fields[0].name, "name");
+        assert!(monster.fields[0].required);
+
+        let line = &schema.objects[schema.object_by_name("Game.Line").unwrap()];
+        assert_eq!(line.documentation, [" A path between two points."]);
+        assert_eq!((line.bytesize, line.minalign), (28, 4));
+    }
+
+    #[test]
+    fn binary_schemas_are_checked() {
+        let schema = schema::compile_str(MONSTER).unwrap();
+        let bfbs   = write_bfbs(&schema);
+
+        let mut wrong_ident = bfbs.clone();
+        wrong_ident[4] = b'X';
+        assert_eq!(load_schema(&wrong_ident), Err(LoadError::Verify(VerifyError::BadIdentifier { pos: 4 })));
+
+        // The last few bytes may only be padding, but cutting off more than that must be caught.
+        for_each_truncation(&bfbs, |b| {
+            let _ = load_schema(b);
+        });
+        assert!(load_schema(&bfbs[..bfbs.len() - 16]).is_err());
+
+        let mut bad_index = schema.clone();
+        bad_index.objects[0].fields[0].ty.index = Some(100);
+        let err = load_schema(&write_bfbs(&bad_index)).unwrap_err();
+        assert_eq!(err.to_string(), format!("field `{}` of `{}` has an invalid type",
+                                            schema.objects[0].fields[0].name, schema.objects[0].name));
+
+        let mut table_in_struct = schema.clone();
+        let vec3 = table_in_struct.object_by_name("Game.Vec3").unwrap();
+        table_in_struct.objects[vec3].fields[0].ty = Type::new(BaseType::String);
+        let err = load_schema(&write_bfbs(&table_in_struct)).unwrap_err();
+        assert_eq!(err.to_string(), "field `x` of struct `Game.Vec3` isn't a scalar or a struct");
+
+        // Vec3 holding itself at offset 0 fits, but walking it would never end.
+        let mut self_holding = schema.clone();
+        self_holding.objects[vec3].fields[0].ty = Type::new(BaseType::Obj);
+        self_holding.objects[vec3].fields[0].ty.index = Some(vec3);
+        let err = load_schema(&write_bfbs(&self_holding)).unwrap_err();
+        assert_eq!(err.to_string(), "struct `Game.Vec3` contains itself");
+
+        // So does Vec3 holding a Line which holds a Vec3, once they're the same size.
+        let line      = schema.object_by_name("Game.Line").unwrap();
+        let mut cycle = schema.clone();
+        let to        = schema.objects[line].fields.iter().position(|f| f.name == "to").unwrap();
+        cycle.objects[vec3].bytesize = 40;
+        cycle.objects[line].bytesize = 40;
+        cycle.objects[line].fields[to].ty = Type::new(BaseType::Float);
+        cycle.objects[vec3].fields[0].ty = Type::new(BaseType::Obj);
+        cycle.objects[vec3].fields[0].ty.index = Some(line);
+        let err = load_schema(&write_bfbs(&cycle)).unwrap_err();
+        assert!(err.to_string().ends_with("contains itself"), "{}", err);
+
+        // An odd offset would let a field be read from past the end of a verified vtable.
+        let monster = schema.object_by_name("Game.Monster").unwrap();
+        let hp      = schema.objects[monster].fields.iter().position(|f| f.name == "hp").unwrap();
+
+        for &offset in [1, 5, 6, 200].iter() {
+            let mut bad_offset = schema.clone();
+            bad_offset.objects[monster].fields[hp].offset += offset;
+            let err = load_schema(&write_bfbs(&bad_offset)).unwrap_err();
+            assert_eq!(err.to_string(), "field `hp` of `Game.Monster` has the wrong vtable offset");
+        }
+    }
+
+    #[test]
+    fn tables_are_walked() {
+        let schema  = load_monster();
+        let buf     = build_monster(&schema);
+        let monster = get_root_verified(&schema, &buf).unwrap().unwrap();
+
+        let names: Vec<&str> = monster.fields().map(|(f, _)| &f.name[..]).collect();
+        assert_eq!(names, ["name", "hp", "color", "pos", "route", "inventory", "tags", "weapons",
+                           "equipped_type", "equipped", "loot_type", "loot", "friend"]);
+
+        assert_eq!(monster.get("name").unwrap().as_str(), Some("orc"));
+        assert_eq!(monster.get("hp").unwrap().as_i64(), Some(80));
+        assert_eq!(monster.get("color").unwrap().as_u64(), Some(1));
+        assert_eq!(monster.get("speed").unwrap().as_f64(), Some(1.5));
+        assert!(monster.get("nothing").is_none());
+
+        let friend = monster.get("friend").unwrap().as_table().unwrap();
+        assert_eq!(friend.object().name, "Game.Monster");
+        assert_eq!(friend.get("name").unwrap().as_str(), Some("goblin"));
+        assert_eq!(friend.get("hp").unwrap().as_i64(), Some(100));
+        assert_eq!(friend.get("color").unwrap().as_u64(), Some(2));
+        assert!(!friend.is_present(friend.object().field_by_name("hp").unwrap()));
+        assert!(friend.get("friend").is_none());
+        assert!(friend.get("equipped").is_none());
+    }
+
+    #[test]
+    fn vectors_are_walked() {
+        let schema  = load_monster();
+        let buf     = build_monster(&schema);
+        let monster = get_root_verified(&schema, &buf).unwrap().unwrap();
+
+        let inventory = monster.get("inventory").unwrap().as_vector().unwrap();
+        assert_eq!(inventory.element_type(), Type::new(BaseType::UByte));
+        assert_eq!(inventory.iter().map(|v| v.as_u64().unwrap()).collect::<Vec<_>>(), [1, 2, 3]);
+        assert!(inventory.get(3).is_none());
+
+        let tags = monster.get("tags").unwrap().as_vector().unwrap();
+        assert_eq!(tags.iter().map(|v| v.as_str().unwrap()).collect::<Vec<_>>(), ["big", "green"]);
+
+        let weapons = monster.get("weapons").unwrap().as_vector().unwrap();
+        assert_eq!(weapons.len(), 2);
+        let damage: Vec<_> = weapons.iter()
+            .map(|w| w.as_table().unwrap().get("damage").unwrap().as_i64().unwrap())
+            .collect();
+        assert_eq!(damage, [3, 10]);
+
+        let route = monster.get("route").unwrap().as_vector().unwrap();
+        let weights: Vec<_> = route.iter()
+            .map(|l| l.as_struct().unwrap().get("weight").unwrap().as_i64().unwrap())
+            .collect();
+        assert_eq!(weights, [5, 7]);
+    }
+
+    #[test]
+    fn structs_are_walked() {
+        let schema  = load_monster();
+        let buf     = build_monster(&schema);
+        let monster = get_root_verified(&schema, &buf).unwrap().unwrap();
+
+        let pos = monster.get("pos").unwrap().as_struct().unwrap();
+        assert_eq!(pos.object().name, "Game.Vec3");
+        assert_eq!(pos.bytes().len(), 12);
+        let coords: Vec<_> = pos.fields().map(|(f, v)| (&f.name[..], v.as_f64().unwrap())).collect();
+        assert_eq!(coords, [("x", 1.0), ("y", 2.0), ("z", 3.0)]);
+
+        let route = monster.get("route").unwrap().as_vector().unwrap();
+        let line  = route.get(1).unwrap().as_struct().unwrap();
+        let to    = line.get("to").unwrap().as_struct().unwrap();
+        assert_eq!(to.get("x").unwrap().as_f64(), Some(2.0));
+        assert!(line.get("length").is_none());
+    }
+
+    #[test]
+    fn unions_are_walked() {
+        let schema  = load_monster();
+        let buf     = build_monster(&schema);
+        let monster = get_root_verified(&schema, &buf).unwrap().unwrap();
+
+        let equipped = monster.get("equipped").unwrap().as_union().unwrap();
+        assert_eq!(equipped.enum_def().name, "Game.Equipment");
+        assert_eq!(equipped.tag(), 1);
+        assert_eq!(equipped.variant().unwrap().name, "Weapon");
+        assert_eq!(equipped.value().unwrap().get("name").unwrap().as_str(), Some("sword"));
+        assert_eq!(monster.get("equipped_type").unwrap().as_u64(), Some(1));
+
+        let loot = monster.get("loot").unwrap().as_vector().unwrap();
+        let members: Vec<_> = loot.iter().map(|u| {
+            let u = u.as_union().unwrap();
+            (&u.variant().unwrap().name[..], u.value().unwrap().fields().count())
+        }).collect();
+        assert_eq!(members, [("Shield", 1), ("Weapon", 1)]);
+    }
+
+    #[test]
+    #[should_panic(expected = "a vector of unions has 2 types but 1 values")]
+    fn union_vectors_with_different_lengths_are_malformed() {
+        let schema  = load_monster();
+        let monster = schema.root_table().unwrap();
+        let field   = |name| monster.field_by_name(name).unwrap().offset;
+
+        let mut fbb = FlatBufferBuilder::new(64);
+        let start   = fbb.start_table();
+        let empty   = Offset::<Table>::new(fbb.end_table(start, 0));
+        let types   = fbb.create_vector(&[1u8, 1]);
+        let values  = fbb.create_vector_of_offsets(&[empty]);
+        let name    = fbb.create_string("orc");
+        let start   = fbb.start_table();
+        fbb.add_offset(field("name"), name);
+        fbb.add_offset(field("loot_type"), types);
+        fbb.add_offset(field("loot"), values);
+        let root    = fbb.end_table(start, monster.num_slots());
+        fbb.finish(Offset::<Table>::new(root));
+
+        let buf = fbb.finished_data().unwrap().as_bytes().to_vec();
+        assert!(verify(&schema, &buf).is_err());
+        get_root(&schema, &buf).unwrap().get("loot");
+    }
+
+    #[test]
+    fn buffers_are_verified_against_the_schema() {
+        let schema = load_monster();
+        let buf    = build_monster(&schema);
+
+        assert_eq!(verify(&schema, &buf), Ok(()));
+        assert!(walk(Value::Table(get_root(&schema, &buf).unwrap())) > 50);
+        assert!(get_root_verified(&Schema::default(), &buf).unwrap().is_none());
+
+        // A monster without its required name.
+        let mut fbb   = FlatBufferBuilder::new(64);
+        let start     = fbb.start_table();
+        fbb.add_scalar::<i16>(6, 1, 100);
+        let nameless  = fbb.end_table(start, 2);
+        fbb.finish(Offset::<Table>::new(nameless));
+        let nameless  = fbb.finished_data().unwrap().as_bytes().to_vec();
+        match verify(&schema, &nameless) {
+            Err(VerifyError::MissingRequiredField { field: 4, .. }) => {}
+            r                                                        => panic!("{:?}", r),
+        }
+
+        for_each_truncation(&buf, |b| {
+            assert!(verify(&schema, b).is_err(), "{} bytes", b.len());
+        });
+
+        // However a byte is corrupted, either the buffer is rejected or it can be read in full.
+        for_each_corruption(&buf, |b| {
+            if let Ok(Some(root)) = get_root_verified(&schema, b) {
+                walk(Value::Table(root));
+            }
+        });
+    }
+}
@@ -0,0 +1,234 @@
+// Copyright 2015 Sam Payson. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The syntax tree for a single `.fbs` file. This mirrors what was written in the file, and no
+//! names have been resolved yet.
+
+use schema::Span;
+
+/// An identifier, along with where it was written.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Ident {
+    pub name: String,
+    pub span: Span,
+}
+
+/// A dotted name such as `MyGame.Sample.Monster`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct QualifiedName {
+    pub parts: Vec<Ident>,
+    pub span:  Span,
+}
+
+impl QualifiedName {
+    /// The name with its components joined by `.`.
+    pub fn to_dotted(&self) -> String {
+        let parts: Vec<&str> = self.parts.iter().map(|p| &p.name[..]).collect();
+        parts.join(".")
+    }
+}
+
+/// A constant which appears as a default value, enum value, or attribute argument.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Literal {
+    Integer(i128),
+    Float(f64),
+    Bool(bool),
+    String(String),
+
+    /// An identifier in value position, e.g. the name of an enum value used as a default.
+    Ident(String),
+}
+
+/// The built-in scalar types.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Scalar {
+    Bool,
+    Byte,
+    UByte,
+    Short,
+    UShort,
+    Int,
+    UInt,
+    Long,
+    ULong,
+    Float,
+    Double,
+}
+
+impl Scalar {
+    /// Look up a scalar type by any of the names the schema language accepts for it.
+    pub fn from_name(name: &str) -> Option<Scalar> {
+        Some(match name {
+            "bool"               => Scalar::Bool,
+            "byte" | "int8"      => Scalar::Byte,
+            "ubyte" | "uint8"    => Scalar::UByte,
+            "short" | "int16"    => Scalar::Short,
+            "ushort" | "uint16"  => Scalar::UShort,
+            "int" | "int32"      => Scalar::Int,
+            "uint" | "uint32"    => Scalar::UInt,
+            "long" | "int64"     => Scalar::Long,
+            "ulong" | "uint64"   => Scalar::ULong,
+            "float" | "float32"  => Scalar::Float,
+            "double" | "float64" => Scalar::Double,
+            _                    => return None,
+        })
+    }
+
+    /// The size of the scalar in bytes.
+    pub fn size(self) -> usize {
+        match self {
+            Scalar::Bool | Scalar::Byte | Scalar::UByte   => 1,
+            Scalar::Short | Scalar::UShort                => 2,
+            Scalar::Int | Scalar::UInt | Scalar::Float    => 4,
+            Scalar::Long | Scalar::ULong | Scalar::Double => 8,
+        }
+    }
+
+    pub fn is_integer(self) -> bool {
+        !matches!(self, Scalar::Bool | Scalar::Float | Scalar::Double)
+    }
+
+    pub fn is_float(self) -> bool {
+        self == Scalar::Float || self == Scalar::Double
+    }
+}
+
+/// The type of a field, as written.
+#[derive(Clone, Debug, PartialEq)]
+pub enum TypeKind {
+    Scalar(Scalar),
+    String,
+    Vector(Box<Type>),
+    Named(QualifiedName),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Type {
+    pub kind: TypeKind,
+    pub span: Span,
+}
+
+/// An entry in a declaration's metadata, e.g. `id: 3` or `deprecated`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Attribute {
+    pub name:  Ident,
+    pub value: Option<Literal>,
+    pub span:  Span,
+}
+
+/// A field of a table or struct.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Field {
+    pub name:    Ident,
+    pub ty:      Type,
+    pub default: Option<(Literal, Span)>,
+    pub attrs:   Vec<Attribute>,
+    pub doc:     Vec<String>,
+    pub span:    Span,
+}
+
+/// Whether an object declaration is a `table` or a `struct`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ObjectKind {
+    Table,
+    Struct,
+}
+
+/// A `table` or `struct` declaration.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Object {
+    pub kind:   ObjectKind,
+    pub name:   Ident,
+    pub fields: Vec<Field>,
+    pub attrs:  Vec<Attribute>,
+    pub doc:    Vec<String>,
+    pub span:   Span,
+}
+
+/// A value of an `enum` or `union`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EnumVal {
+    pub name:  Ident,
+    pub value: Option<(i128, Span)>,
+
+    /// For union members, the table type (if it differs from `name`, e.g. `Pic: Picture` or
+    /// `Game.Monster`).
+    pub ty:    Option<QualifiedName>,
+    pub attrs: Vec<Attribute>,
+    pub doc:   Vec<String>,
+    pub span:  Span,
+}
+
+/// An `enum` or `union` declaration. Unions have no underlying type.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Enum {
+    pub is_union:   bool,
+    pub name:       Ident,
+    pub underlying: Option<Type>,
+    pub values:     Vec<EnumVal>,
+    pub attrs:      Vec<Attribute>,
+    pub doc:        Vec<String>,
+    pub span:       Span,
+}
+
+/// A method of an `rpc_service`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RpcMethod {
+    pub name:     Ident,
+    pub request:  QualifiedName,
+    pub response: QualifiedName,
+    pub attrs:    Vec<Attribute>,
+    pub doc:      Vec<String>,
+    pub span:     Span,
+}
+
+/// An `rpc_service` declaration.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Service {
+    pub name:    Ident,
+    pub methods: Vec<RpcMethod>,
+    pub attrs:   Vec<Attribute>,
+    pub doc:     Vec<String>,
+    pub span:    Span,
+}
+
+/// A top-level declaration. Declarations appear in the order they were written, and a `Namespace`
+/// applies to everything after it up to the next `Namespace`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Item {
+    Namespace(QualifiedName),
+    Object(Object),
+    Enum(Enum),
+    Service(Service),
+    RootType(QualifiedName),
+    FileIdentifier(String, Span),
+    FileExtension(String, Span),
+    Attribute(Ident),
+}
+
+/// An `include` statement.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Include {
+    pub path:   String,
+    pub native: bool,
+    pub span:   Span,
+}
+
+/// A parsed `.fbs` file.
+#[derive(Clone, Debug, PartialEq, Default)]
+pub struct File {
+    pub includes: Vec<Include>,
+    pub items:    Vec<Item>,
+}
@@ -0,0 +1,215 @@
+// Copyright 2015 Sam Payson. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fmt;
+
+use schema::Span;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum TokenKind {
+    Ident(String),
+
+    // Numbers are kept as text, since whether they're integers or floats (and how big they're
+    // allowed to be) depends on where they appear.
+    Number(String),
+    Str(String),
+    Punct(char),
+    Eof,
+}
+
+impl fmt::Display for TokenKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TokenKind::Ident(ref s)  => write!(f, "`{}`", s),
+            TokenKind::Number(ref s) => write!(f, "`{}`", s),
+            TokenKind::Str(ref s)    => write!(f, "\"{}\"", s),
+            TokenKind::Punct(c)      => write!(f, "`{}`", c),
+            TokenKind::Eof           => write!(f, "end of file"),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub span: Span,
+
+    // The `///` comments which immediately precede this token.
+    pub doc:  Vec<String>,
+}
+
+/// Split `src` into tokens. On failure, returns the span of the offending text and a message.
+pub fn tokenize(src: &str) -> Result<Vec<Token>, (Span, String)> {
+    let bytes = src.as_bytes();
+    let mut toks = Vec::new();
+    let mut doc  = Vec::new();
+    let mut i    = 0;
+
+    while i < bytes.len() {
+        let c = bytes[i];
+
+        if c.is_ascii_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        let start = i;
+
+        if src[i..].starts_with("//") {
+            let end = src[i..].find('\n').map_or(bytes.len(), |n| i + n);
+
+            if src[i..].starts_with("///") && !src[i..].starts_with("////") {
+                doc.push(src[i + 3..end].trim_end().to_string());
+            }
+
+            i = end;
+            continue;
+        }
+
+        if src[i..].starts_with("/*") {
+            match src[i + 2..].find("*/") {
+                Some(n) => i += n + 4,
+                None    => return Err((Span::new(start, bytes.len()),
+                                       "unterminated block comment".to_string())),
+            }
+            continue;
+        }
+
+        let kind = if c == b'_' || c.is_ascii_alphabetic() {
+            while i < bytes.len() && (bytes[i] == b'_' || bytes[i].is_ascii_alphanumeric()) {
+                i += 1;
+            }
+            TokenKind::Ident(src[start..i].to_string())
+        } else if c.is_ascii_digit() || (c == b'.' && bytes.get(i + 1).is_some_and(|b| b.is_ascii_digit())) {
+            i = scan_number(bytes, i);
+            TokenKind::Number(src[start..i].to_string())
+        } else if c == b'"' || c == b'\'' {
+            let (s, end) = scan_string(src, i)?;
+            i = end;
+            TokenKind::Str(s)
+        } else if b"{}()[]:;,=.-+<>".contains(&c) {
+            i += 1;
+            TokenKind::Punct(c as char)
+        } else {
+            let ch = src[i..].chars().next().unwrap();
+            return Err((Span::new(i, i + ch.len_utf8()), format!("unexpected character `{}`", ch)))
+        };
+
+        toks.push(Token {
+            kind,
+            span: Span::new(start, i),
+            doc:  doc.split_off(0),
+        });
+    }
+
+    toks.push(Token {
+        kind: TokenKind::Eof,
+        span: Span::new(bytes.len(), bytes.len()),
+        doc,
+    });
+
+    Ok(toks)
+}
+
+// Return the index just past the number which begins at `i`.
+fn scan_number(bytes: &[u8], mut i: usize) -> usize {
+    if bytes[i] == b'0' && (bytes.get(i + 1) == Some(&b'x') || bytes.get(i + 1) == Some(&b'X')) {
+        i += 2;
+        while i < bytes.len() && (bytes[i].is_ascii_hexdigit() || bytes[i] == b'.' ||
+                                  bytes[i] == b'p' || bytes[i] == b'P' ||
+                                  ((bytes[i] == b'-' || bytes[i] == b'+') &&
+                                   (bytes[i - 1] == b'p' || bytes[i - 1] == b'P'))) {
+            i += 1;
+        }
+        return i
+    }
+
+    while i < bytes.len() && (bytes[i].is_ascii_digit() || bytes[i] == b'.') {
+        i += 1;
+    }
+
+    if i < bytes.len() && (bytes[i] == b'e' || bytes[i] == b'E') {
+        let mut j = i + 1;
+        if j < bytes.len() && (bytes[j] == b'-' || bytes[j] == b'+') {
+            j += 1;
+        }
+        if j < bytes.len() && bytes[j].is_ascii_digit() {
+            i = j;
+            while i < bytes.len() && bytes[i].is_ascii_digit() {
+                i += 1;
+            }
+        }
+    }
+
+    i
+}
+
+// Scan a quoted string beginning at `start`, returning its unescaped contents and the index just
+// past the closing quote.
+fn scan_string(src: &str, start: usize) -> Result<(String, usize), (Span, String)> {
+    let quote = src.as_bytes()[start] as char;
+    let mut out  = String::new();
+    let mut chars = src[start + 1..].char_indices();
+
+    loop {
+        let (off, c) = match chars.next() {
+            Some(x) => x,
+            None    => return Err((Span::new(start, src.len()), "unterminated string".to_string())),
+        };
+
+        let pos = start + 1 + off;
+
+        match c {
+            '\n' => return Err((Span::new(start, pos), "unterminated string".to_string())),
+            c if c == quote => return Ok((out, pos + 1)),
+            '\\' => {
+                let esc = match chars.next() {
+                    Some((_, e)) => e,
+                    None         => return Err((Span::new(start, src.len()),
+                                                "unterminated string".to_string())),
+                };
+
+                match esc {
+                    'n'  => out.push('\n'),
+                    't'  => out.push('\t'),
+                    'r'  => out.push('\r'),
+                    'b'  => out.push('\u{8}'),
+                    'f'  => out.push('\u{c}'),
+                    '0'  => out.push('\0'),
+                    '"'  => out.push('"'),
+                    '\'' => out.push('\''),
+                    '\\' => out.push('\\'),
+                    '/'  => out.push('/'),
+                    'x' | 'u' => {
+                        let digits = if esc == 'x' { 2 } else { 4 };
+                        let hex_start = start + 1 + chars.clone().next().map_or(src.len(), |(o, _)| o);
+                        let hex: String = chars.by_ref().take(digits).map(|(_, h)| h).collect();
+                        let val = u32::from_str_radix(&hex, 16).ok()
+                            .filter(|_| hex.len() == digits)
+                            .and_then(::std::char::from_u32);
+
+                        match val {
+                            Some(ch) => out.push(ch),
+                            None     => return Err((Span::new(pos, hex_start + hex.len()),
+                                                    format!("invalid escape `\\{}{}`", esc, hex))),
+                        }
+                    }
+                    e => return Err((Span::new(pos, pos + 1 + e.len_utf8()),
+                                     format!("unknown escape sequence `\\{}`", e))),
+                }
+            }
+            c => out.push(c),
+        }
+    }
+}
@@ -0,0 +1,474 @@
+// Copyright 2015 Sam Payson. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Parsing of `.fbs` schema files.
+//!
+//! `parse` turns the text of a single file into an `ast::File`, and `load` reads a file from disk
+//! along with everything it (transitively) includes. `resolve` checks a set of parsed files and
+//! turns them into a `Schema`, and `compile` does all of the above in one step.
+
+pub mod ast;
+
+mod lexer;
+mod model;
+mod parser;
+mod resolve;
+
+pub use self::model::*;
+pub use self::resolve::resolve;
+
+use std::collections::HashSet;
+use std::error;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A range of bytes in a source file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+pub struct Span {
+    pub start: usize,
+    pub end:   usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Span {
+        Span { start, end }
+    }
+
+    /// The smallest span which covers both `self` and `other`.
+    pub fn to(self, other: Span) -> Span {
+        Span::new(self.start.min(other.start), self.end.max(other.end))
+    }
+}
+
+/// An error in a schema, along with where it was found.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Error {
+    /// The file the error was found in, if the schema came from a file.
+    pub file:    Option<PathBuf>,
+    pub span:    Span,
+
+    /// The 1-based line and column of the start of `span`.
+    pub line:    usize,
+    pub col:     usize,
+    pub message: String,
+}
+
+impl Error {
+    /// Create an error for the text at `span` in `source`.
+    pub fn new(file: Option<&Path>, source: &str, span: Span, message: String) -> Error {
+        let before = &source[..span.start.min(source.len())];
+        let line   = before.matches('\n').count() + 1;
+        let col    = before.rfind('\n').map_or(before.len(), |n| before.len() - n - 1) + 1;
+
+        Error {
+            file: file.map(|f| f.to_path_buf()),
+            span,
+            line,
+            col,
+            message,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.file {
+            Some(ref file) => write!(f, "{}:{}:{}: {}", file.display(), self.line, self.col,
+                                     self.message),
+            None           => write!(f, "{}:{}: {}", self.line, self.col, self.message),
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+/// Parse the text of a single schema file. Any `include`s are recorded in the result, but not
+/// followed.
+pub fn parse(source: &str) -> Result<ast::File, Error> {
+    parse_named(None, source)
+}
+
+fn parse_named(file: Option<&Path>, source: &str) -> Result<ast::File, Error> {
+    lexer::tokenize(source)
+        .and_then(|toks| parser::Parser::new(toks).file())
+        .map_err(|(span, msg)| Error::new(file, source, span, msg))
+}
+
+/// A schema file which has been read and parsed.
+#[derive(Clone, Debug)]
+pub struct SourceFile {
+    pub path:   PathBuf,
+    pub source: String,
+    pub ast:    ast::File,
+}
+
+/// Read and parse the schema at `path`, and every file that it includes. Included files are looked
+/// for relative to the including file first, and then in each of `include_paths`.
+///
+/// Files are returned in dependency order: every file comes after the files it includes, so the
+/// file named by `path` is last.
+pub fn load<P: AsRef<Path>>(path: P, include_paths: &[PathBuf]) -> Result<Vec<SourceFile>, Error> {
+    let mut loader = Loader {
+        include_paths,
+        seen:  HashSet::new(),
+        files: Vec::new(),
+    };
+
+    let path = path.as_ref();
+    let canon = fs::canonicalize(path).map_err(|e| io_error(path, e))?;
+    loader.load(&canon)?;
+
+    Ok(loader.files)
+}
+
+fn io_error(path: &Path, err: ::std::io::Error) -> Error {
+    Error {
+        file:    Some(path.to_path_buf()),
+        span:    Span::default(),
+        line:    0,
+        col:     0,
+        message: format!("couldn't read file: {}", err),
+    }
+}
+
+struct Loader<'a> {
+    include_paths: &'a [PathBuf],
+    seen:          HashSet<PathBuf>,
+    files:         Vec<SourceFile>,
+}
+
+impl<'a> Loader<'a> {
+    // Load `path`, which must be canonical, after all of its includes.
+    fn load(&mut self, path: &Path) -> Result<(), Error> {
+        if !self.seen.insert(path.to_path_buf()) {
+            return Ok(())
+        }
+
+        let source = fs::read_to_string(path).map_err(|e| io_error(path, e))?;
+        let ast    = parse_named(Some(path), &source)?;
+
+        for inc in ast.includes.iter().filter(|inc| !inc.native) {
+            let found = match self.find_include(path, &inc.path) {
+                Some(p) => p,
+                None    => return Err(Error::new(Some(path), &source, inc.span,
+                                                 format!("couldn't find included file \"{}\"",
+                                                         inc.path))),
+            };
+
+            self.load(&found)?;
+        }
+
+        self.files.push(SourceFile {
+            path: path.to_path_buf(),
+            source,
+            ast,
+        });
+
+        Ok(())
+    }
+
+    fn find_include(&self, from: &Path, inc: &str) -> Option<PathBuf> {
+        let dirs = from.parent().into_iter().chain(self.include_paths.iter().map(|p| p.as_path()));
+
+        for dir in dirs {
+            if let Ok(p) = fs::canonicalize(dir.join(inc)) {
+                if p.is_file() {
+                    return Some(p)
+                }
+            }
+        }
+
+        None
+    }
+}
+
+/// Load the schema at `path` (and everything it includes) and resolve it into a `Schema`.
+pub fn compile<P: AsRef<Path>>(path: P, include_paths: &[PathBuf]) -> Result<Schema, Error> {
+    resolve(&load(path, include_paths)?)
+}
+
+/// Parse and resolve a schema held in memory. The schema can't `include` other files.
+pub fn compile_str(source: &str) -> Result<Schema, Error> {
+    let file = SourceFile {
+        path:   PathBuf::new(),
+        source: source.to_string(),
+        ast:    parse(source)?,
+    };
+
+    if let Some(inc) = file.ast.includes.iter().find(|inc| !inc.native) {
+        return Err(Error::new(None, source, inc.span,
+                              "includes aren't allowed in in-memory schemas".to_string()))
+    }
+
+    resolve(&[file])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::env;
+
+    const MONSTER: &str = r#"
+include "weapon.fbs";
+
+namespace Game.Sample;
+
+attribute "priority";
+
+/// The colour of a monster.
+enum Color : byte { Red = 1, Green, Blue = 8 }
+
+struct Vec3 {
+  x: float;
+  y: float;
+  z: double;
+}
+
+union Equipment { Weapon, Shield: Game.Sample.Armour }
+
+table Armour { defense: short = 2; }
+
+table Monster (priority: 1) {
+  pos: Vec3 (id: 6);
+  hp: short = 100 (id: 1);
+  name: string (id: 0, required, key);
+  inventory: [ubyte] (id: 2);
+  color: Color = Blue (id: 3);
+  equipped: Equipment (id: 5);
+}
+
+root_type Monster;
+file_identifier "MONS";
+"#;
+
+    // The error from compiling `source`, as `line:col: message`.
+    fn compile_error(source: &str) -> String {
+        compile_str(source).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn files_are_parsed_into_declarations() {
+        let file = parse(MONSTER).unwrap();
+
+        assert_eq!(file.includes.len(), 1);
+        assert_eq!(file.includes[0].path, "weapon.fbs");
+        assert!(!file.includes[0].native);
+
+        let kinds: Vec<&str> = file.items.iter().map(|item| match *item {
+            ast::Item::Namespace(_)         => "namespace",
+            ast::Item::Object(ref o)        => match o.kind {
+                ast::ObjectKind::Table  => "table",
+                ast::ObjectKind::Struct => "struct",
+            },
+            ast::Item::Enum(ref e)          => if e.is_union { "union" } else { "enum" },
+            ast::Item::Service(_)           => "rpc_service",
+            ast::Item::RootType(_)          => "root_type",
+            ast::Item::FileIdentifier(..)   => "file_identifier",
+            ast::Item::FileExtension(..)    => "file_extension",
+            ast::Item::Attribute(_)         => "attribute",
+        }).collect();
+        assert_eq!(kinds, ["namespace", "attribute", "enum", "struct", "union", "table", "table",
+                           "root_type", "file_identifier"]);
+
+        match file.items[0] {
+            ast::Item::Namespace(ref ns) => assert_eq!(ns.to_dotted(), "Game.Sample"),
+            ref item                     => panic!("{:?}", item),
+        }
+
+        match file.items[2] {
+            ast::Item::Enum(ref e) => {
+                assert_eq!(e.doc, [" The colour of a monster."]);
+                assert_eq!(e.underlying.as_ref().unwrap().kind, ast::TypeKind::Scalar(ast::Scalar::Byte));
+                let values: Vec<_> = e.values.iter().map(|v| (&v.name.name[..], v.value.map(|v| v.0)))
+                                                     .collect();
+                assert_eq!(values, [("Red", Some(1)), ("Green", None), ("Blue", Some(8))]);
+            }
+            ref item => panic!("{:?}", item),
+        }
+
+        match file.items[4] {
+            ast::Item::Enum(ref u) => {
+                assert_eq!(u.values[1].name.name, "Shield");
+                assert_eq!(u.values[1].ty.as_ref().unwrap().to_dotted(), "Game.Sample.Armour");
+            }
+            ref item => panic!("{:?}", item),
+        }
+
+        match file.items[6] {
+            ast::Item::Object(ref o) => {
+                assert_eq!(o.attrs[0].name.name, "priority");
+                assert_eq!(o.attrs[0].value, Some(ast::Literal::Integer(1)));
+
+                let name = &o.fields[2];
+                assert_eq!(name.ty.kind, ast::TypeKind::String);
+                let attrs: Vec<_> = name.attrs.iter().map(|a| (&a.name.name[..], a.value.clone()))
+                                                     .collect();
+                assert_eq!(attrs, [("id", Some(ast::Literal::Integer(0))), ("required", None),
+                                   ("key", None)]);
+
+                assert_eq!(o.fields[1].default.as_ref().unwrap().0, ast::Literal::Integer(100));
+                assert_eq!(o.fields[4].default.as_ref().unwrap().0, ast::Literal::Ident("Blue".to_string()));
+                assert_eq!(&MONSTER[o.fields[3].ty.span.start..o.fields[3].ty.span.end], "[ubyte]");
+            }
+            ref item => panic!("{:?}", item),
+        }
+
+        match file.items[8] {
+            ast::Item::FileIdentifier(ref s, _) => assert_eq!(s, "MONS"),
+            ref item                            => panic!("{:?}", item),
+        }
+    }
+
+    #[test]
+    fn schemas_are_resolved() {
+        let source = MONSTER.replace("include \"weapon.fbs\";", "")
+                            .replace("namespace Game.Sample;",
+                                     "namespace Game.Sample;\ntable Weapon { damage: int; }");
+        let schema = compile_str(&source).unwrap();
+
+        let monster = schema.root_table().unwrap();
+        assert_eq!(monster.name, "Game.Sample.Monster");
+        assert_eq!(schema.file_ident.as_ref().unwrap(), "MONS");
+
+        let fields: Vec<_> = monster.fields.iter().map(|f| (&f.name[..], f.id, f.offset)).collect();
+        assert_eq!(fields, [("name", 0, 4), ("hp", 1, 6), ("inventory", 2, 8), ("color", 3, 10),
+                            ("equipped_type", 4, 12), ("equipped", 5, 14), ("pos", 6, 16)]);
+
+        let name = monster.field_by_name("name").unwrap();
+        assert!(name.required && name.key);
+        assert_eq!(monster.field_by_name("hp").unwrap().default_integer, 100);
+        assert_eq!(monster.field_by_name("color").unwrap().default_integer, 8);
+        assert_eq!(monster.attribute("priority").unwrap().value.as_ref().unwrap(), "1");
+
+        let vec3 = &schema.objects[schema.object_by_name("Game.Sample.Vec3").unwrap()];
+        assert!(vec3.is_struct);
+        assert_eq!((vec3.minalign, vec3.bytesize), (8, 16));
+        let offsets: Vec<_> = vec3.fields.iter().map(|f| f.offset).collect();
+        assert_eq!(offsets, [0, 4, 8]);
+
+        let color = &schema.enums[schema.enum_by_name("Game.Sample.Color").unwrap()];
+        let values: Vec<_> = color.values.iter().map(|v| (&v.name[..], v.value)).collect();
+        assert_eq!(values, [("Red", 1), ("Green", 2), ("Blue", 8)]);
+
+        let equipment = &schema.enums[schema.enum_by_name("Game.Sample.Equipment").unwrap()];
+        assert!(equipment.is_union);
+        let members: Vec<_> = equipment.values.iter().map(|v| (&v.name[..], v.value)).collect();
+        assert_eq!(members, [("NONE", 0), ("Weapon", 1), ("Shield", 2)]);
+        let armour = equipment.values[2].union_type.unwrap().index.unwrap();
+        assert_eq!(schema.objects[armour].name, "Game.Sample.Armour");
+    }
+
+    #[test]
+    fn includes_are_loaded_first() {
+        let dir = env::temp_dir().join(format!("flatbuffers-schema-test-{}", ::std::process::id()));
+        fs::create_dir_all(dir.join("lib")).unwrap();
+        fs::write(dir.join("lib/weapon.fbs"),
+                  "namespace Game.Sample;\ntable Weapon { damage: int; }\n").unwrap();
+        fs::write(dir.join("monster.fbs"), MONSTER).unwrap();
+
+        let err = load(dir.join("monster.fbs"), &[]).unwrap_err();
+        assert_eq!((err.line, err.col), (2, 1));
+        assert_eq!(err.message, "couldn't find included file \"weapon.fbs\"");
+
+        let files = load(dir.join("monster.fbs"), &[dir.join("lib")]).unwrap();
+        let names: Vec<_> = files.iter().map(|f| f.path.file_name().unwrap().to_str().unwrap())
+                                        .collect();
+        assert_eq!(names, ["weapon.fbs", "monster.fbs"]);
+
+        let schema = resolve(&files).unwrap();
+        assert_eq!(schema.root_table().unwrap().name, "Game.Sample.Monster");
+        assert!(schema.object_by_name("Game.Sample.Weapon").is_some());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn syntax_errors_have_spans() {
+        let source = "table T {\n  a: int\n}\n";
+        let err    = parse(source).unwrap_err();
+        assert_eq!((err.line, err.col), (3, 1));
+        assert_eq!(&source[err.span.start..err.span.end], "}");
+        assert_eq!(err.to_string(), "3:1: expected `;`, found `}`");
+
+        assert_eq!(parse("table T { a: [[int]]; }").unwrap_err().to_string(),
+                   "1:15: nested vectors are not allowed; wrap the inner vector in a table");
+        assert_eq!(parse("file_identifier \"MONSTER\";").unwrap_err().to_string(),
+                   "1:17: file_identifier must be exactly 4 bytes, but \"MONSTER\" is 7 bytes");
+        assert_eq!(parse("table T {}\ninclude \"a.fbs\";").unwrap_err().to_string(),
+                   "2:1: includes must come before any declarations");
+        assert_eq!(parse("table T { a: string = \"abc }").unwrap_err().to_string(),
+                   "1:23: unterminated string");
+    }
+
+    #[test]
+    fn semantic_errors_have_spans() {
+        assert_eq!(compile_error("table T { a: U; }"), "1:14: unknown type `U`");
+        assert_eq!(compile_error("table T { a: int; a: int; }"), "1:19: field `a` is already defined");
+        assert_eq!(compile_error("table T { a: int (required); }"),
+                   "1:11: scalar fields can't be `required`");
+        assert_eq!(compile_error("table T { a: int (id: 0); b: int; }"),
+                   "1:27: if any field has an `id`, they all must");
+        assert_eq!(compile_error("table T { a: int (id: 1); }"),
+                   "1:11: field ids must be unique and consecutive starting from 0, but `a` has id 1");
+        assert_eq!(compile_error("table T { a: int (unknown); }"), "1:19: unknown attribute `unknown`; user-defined attributes must be declared \
+                    with `attribute \"unknown\";`");
+        assert_eq!(compile_error("struct S { a: S; }"), "1:8: struct `S` contains itself");
+        assert_eq!(compile_error("table T {}\nroot_type U;"), "2:11: root_type `U` must be a table");
+    }
+
+    #[test]
+    fn union_type_fields_must_not_clash() {
+        let union = "table T {}\nunion U { T }\n";
+        assert_eq!(compile_error(&format!("{}table R {{ a_type: int; a: U; }}", union)),
+                   "3:24: field `a_type` is already defined");
+        assert_eq!(compile_error(&format!("{}table R {{ a: U; a_type: int; }}", union)),
+                   "3:17: field `a_type` is already defined");
+    }
+
+    #[test]
+    fn enum_values_must_fit_in_their_type() {
+        assert_eq!(compile_error("enum E : int { A = 170141183460469231731687303715884105727 }"),
+                   "1:20: value of `A` doesn't fit in the underlying type `int`");
+        assert_eq!(compile_error("enum E : ubyte { A = 255, B }"),
+                   "1:27: value of `B` doesn't fit in the underlying type `ubyte`");
+        assert_eq!(compile_error("enum E : long (bit_flags) { A = 63 }"),
+                   "1:33: bit flag `A` doesn't fit in the underlying type `long`");
+        assert_eq!(compile_error("enum E : ulong (bit_flags) { A = 64 }"),
+                   "1:34: bit flag `A` is out of range for the underlying type");
+
+        // `ulong` values are stored by their bit pattern.
+        let schema = compile_str("enum E : ulong (bit_flags) { A = 0, B = 63 }\n\
+                                  enum F : ulong { A = 18446744073709551615, B = 1 }").unwrap();
+        let values = |e: &Enum| e.values.iter().map(|v| v.value as u64).collect::<Vec<_>>();
+        assert_eq!(values(&schema.enums[0]), [1, 1 << 63]);
+        assert_eq!(values(&schema.enums[1]), [1, u64::MAX]);
+    }
+
+    #[test]
+    fn field_ids_must_fit_in_a_vtable() {
+        assert!(compile_str("table T { a: int (id: 32765); }").unwrap_err().message
+                    .starts_with("field ids must be unique"));
+        assert_eq!(compile_error("table T { a: int (id: 32766); }"), "1:19: field id is out of range");
+        assert_eq!(compile_error("table T { a: int (id: 65535); }"), "1:19: field id is out of range");
+
+        let mut source = "table T {\n".to_string();
+        for i in 0..32767 {
+            source.push_str(&format!("  f{}: int;\n", i));
+        }
+        source.push('}');
+        assert_eq!(compile_error(&source), "32768:3: table `T` has too many fields");
+    }
+}
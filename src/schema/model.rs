@@ -0,0 +1,306 @@
+// Copyright 2015 Sam Payson. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A fully resolved schema. Every type reference has been looked up, table fields have their ids
+//! and vtable offsets, and structs have been laid out. The shape of these types follows the
+//! `reflection.fbs` schema which ships with FlatBuffers.
+
+/// The kinds of value which can be stored in a field. The discriminants match `reflection.fbs`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum BaseType {
+    None   = 0,
+    UType  = 1,
+    Bool   = 2,
+    Byte   = 3,
+    UByte  = 4,
+    Short  = 5,
+    UShort = 6,
+    Int    = 7,
+    UInt   = 8,
+    Long   = 9,
+    ULong  = 10,
+    Float  = 11,
+    Double = 12,
+    String = 13,
+    Vector = 14,
+    Obj    = 15,
+    Union  = 16,
+}
+
+impl BaseType {
+    /// Convert from the numbering used in `reflection.fbs`.
+    pub fn from_u8(b: u8) -> Option<BaseType> {
+        Some(match b {
+            0  => BaseType::None,
+            1  => BaseType::UType,
+            2  => BaseType::Bool,
+            3  => BaseType::Byte,
+            4  => BaseType::UByte,
+            5  => BaseType::Short,
+            6  => BaseType::UShort,
+            7  => BaseType::Int,
+            8  => BaseType::UInt,
+            9  => BaseType::Long,
+            10 => BaseType::ULong,
+            11 => BaseType::Float,
+            12 => BaseType::Double,
+            13 => BaseType::String,
+            14 => BaseType::Vector,
+            15 => BaseType::Obj,
+            16 => BaseType::Union,
+            _  => return None,
+        })
+    }
+
+    /// The number of bytes a value of this type takes up inline in a table, struct or vector.
+    /// Strings, vectors, tables and unions are stored as offsets.
+    pub fn size(self) -> usize {
+        match self {
+            BaseType::None | BaseType::UType | BaseType::Bool |
+            BaseType::Byte | BaseType::UByte                     => 1,
+            BaseType::Short | BaseType::UShort                   => 2,
+            BaseType::Int | BaseType::UInt | BaseType::Float     => 4,
+            BaseType::Long | BaseType::ULong | BaseType::Double  => 8,
+            BaseType::String | BaseType::Vector |
+            BaseType::Obj | BaseType::Union                      => 4,
+        }
+    }
+
+    pub fn is_scalar(self) -> bool {
+        (self as u8) >= (BaseType::UType as u8) && (self as u8) <= (BaseType::Double as u8)
+    }
+
+    pub fn is_integer(self) -> bool {
+        (self as u8) >= (BaseType::UType as u8) && (self as u8) <= (BaseType::ULong as u8)
+    }
+
+    pub fn is_float(self) -> bool {
+        self == BaseType::Float || self == BaseType::Double
+    }
+
+    /// Whether this is an integer type which can hold negative values.
+    pub fn is_signed(self) -> bool {
+        matches!(self, BaseType::Byte | BaseType::Short | BaseType::Int | BaseType::Long)
+    }
+
+    /// The name this type has in a schema file.
+    pub fn schema_name(self) -> &'static str {
+        match self {
+            BaseType::None   => "none",
+            BaseType::UType  => "utype",
+            BaseType::Bool   => "bool",
+            BaseType::Byte   => "byte",
+            BaseType::UByte  => "ubyte",
+            BaseType::Short  => "short",
+            BaseType::UShort => "ushort",
+            BaseType::Int    => "int",
+            BaseType::UInt   => "uint",
+            BaseType::Long   => "long",
+            BaseType::ULong  => "ulong",
+            BaseType::Float  => "float",
+            BaseType::Double => "double",
+            BaseType::String => "string",
+            BaseType::Vector => "vector",
+            BaseType::Obj    => "object",
+            BaseType::Union  => "union",
+        }
+    }
+}
+
+/// The type of a field.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Type {
+    pub base_type: BaseType,
+
+    /// The element type, if `base_type` is `Vector`.
+    pub element:   BaseType,
+
+    /// For `Obj` (or vectors of them), the index of the object in `Schema::objects`. For unions,
+    /// union type tags, and scalars with an enum type, the index of the enum in `Schema::enums`.
+    pub index:     Option<usize>,
+}
+
+impl Type {
+    pub fn new(base_type: BaseType) -> Type {
+        Type { base_type, element: BaseType::None, index: None }
+    }
+
+    pub fn with_index(base_type: BaseType, index: usize) -> Type {
+        Type { base_type, element: BaseType::None, index: Some(index) }
+    }
+
+    pub fn vector_of(element: BaseType, index: Option<usize>) -> Type {
+        Type { base_type: BaseType::Vector, element, index }
+    }
+
+    /// The type of the elements of a vector type.
+    pub fn element_type(&self) -> Type {
+        Type { base_type: self.element, element: BaseType::None, index: self.index }
+    }
+}
+
+/// A single attribute (`key` or `key: value`) from a declaration's metadata.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeyValue {
+    pub key:   String,
+    pub value: Option<String>,
+}
+
+fn find_attribute<'a>(attrs: &'a [KeyValue], key: &str) -> Option<&'a KeyValue> {
+    attrs.iter().find(|kv| kv.key == key)
+}
+
+/// A field of a table or struct.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Field {
+    pub name:            String,
+    pub ty:              Type,
+
+    /// The field's id. For structs this is just the field's position.
+    pub id:              u16,
+
+    /// For tables, the field's offset in the vtable. For structs, the field's byte offset from the
+    /// start of the struct.
+    pub offset:          u16,
+
+    /// The default for integer, bool and enum fields.
+    pub default_integer: i64,
+
+    /// The default for floating point fields.
+    pub default_real:    f64,
+    pub deprecated:      bool,
+    pub required:        bool,
+    pub key:             bool,
+    pub attributes:      Vec<KeyValue>,
+    pub documentation:   Vec<String>,
+}
+
+impl Field {
+    pub fn attribute(&self, key: &str) -> Option<&KeyValue> {
+        find_attribute(&self.attributes, key)
+    }
+}
+
+/// A table or struct.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Object {
+    /// The fully qualified name, e.g. `MyGame.Sample.Monster`.
+    pub name:          String,
+
+    /// The fields, in order of increasing id.
+    pub fields:        Vec<Field>,
+    pub is_struct:     bool,
+
+    /// For structs, the alignment and size of the struct.
+    pub minalign:      usize,
+    pub bytesize:      usize,
+    pub attributes:    Vec<KeyValue>,
+    pub documentation: Vec<String>,
+}
+
+impl Object {
+    pub fn field_by_name(&self, name: &str) -> Option<&Field> {
+        self.fields.iter().find(|f| f.name == name)
+    }
+
+    pub fn attribute(&self, key: &str) -> Option<&KeyValue> {
+        find_attribute(&self.attributes, key)
+    }
+
+    /// The number of vtable slots needed to hold every field of this table.
+    pub fn num_slots(&self) -> u16 {
+        self.fields.iter().map(|f| f.id + 1).max().unwrap_or(0)
+    }
+
+    /// The field marked with the `key` attribute, if there is one.
+    pub fn key_field(&self) -> Option<&Field> {
+        self.fields.iter().find(|f| f.key)
+    }
+}
+
+/// A value of an enum, or a member of a union.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EnumVal {
+    pub name:          String,
+    pub value:         i64,
+
+    /// For union members, the type of the member (`None` for the implicit `NONE` member).
+    pub union_type:    Option<Type>,
+    pub attributes:    Vec<KeyValue>,
+    pub documentation: Vec<String>,
+}
+
+/// An enum or union.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Enum {
+    /// The fully qualified name.
+    pub name:            String,
+
+    /// The values, in order of increasing value.
+    pub values:          Vec<EnumVal>,
+    pub is_union:        bool,
+    pub underlying_type: Type,
+    pub attributes:      Vec<KeyValue>,
+    pub documentation:   Vec<String>,
+}
+
+impl Enum {
+    pub fn value_by_name(&self, name: &str) -> Option<&EnumVal> {
+        self.values.iter().find(|v| v.name == name)
+    }
+
+    pub fn value_by_value(&self, value: i64) -> Option<&EnumVal> {
+        self.values.iter().find(|v| v.value == value)
+    }
+
+    pub fn attribute(&self, key: &str) -> Option<&KeyValue> {
+        find_attribute(&self.attributes, key)
+    }
+}
+
+/// A complete, resolved schema.
+#[derive(Clone, Debug, PartialEq, Default)]
+pub struct Schema {
+    pub objects:    Vec<Object>,
+    pub enums:      Vec<Enum>,
+
+    /// The index into `objects` of the `root_type`.
+    pub root_table: Option<usize>,
+    pub file_ident: Option<String>,
+    pub file_ext:   Option<String>,
+}
+
+impl Schema {
+    pub fn root_table(&self) -> Option<&Object> {
+        self.root_table.map(|i| &self.objects[i])
+    }
+
+    /// Look up a table or struct by its fully qualified name.
+    pub fn object_by_name(&self, name: &str) -> Option<usize> {
+        self.objects.iter().position(|o| o.name == name)
+    }
+
+    /// Look up an enum or union by its fully qualified name.
+    pub fn enum_by_name(&self, name: &str) -> Option<usize> {
+        self.enums.iter().position(|e| e.name == name)
+    }
+}
+
+/// Split a fully qualified name into its namespace and its base name.
+pub fn split_name(name: &str) -> (&str, &str) {
+    match name.rfind('.') {
+        Some(i) => (&name[..i], &name[i + 1..]),
+        None    => ("", name),
+    }
+}
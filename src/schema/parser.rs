@@ -0,0 +1,518 @@
+// Copyright 2015 Sam Payson. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use schema::Span;
+use schema::ast::*;
+use schema::lexer::{Token, TokenKind};
+
+type PResult<T> = Result<T, (Span, String)>;
+
+/// A recursive-descent parser over the tokens of a single file.
+pub struct Parser {
+    toks: Vec<Token>,
+    pos:  usize,
+}
+
+impl Parser {
+    pub fn new(toks: Vec<Token>) -> Parser {
+        Parser { toks, pos: 0 }
+    }
+
+    fn peek(&self) -> &Token { &self.toks[self.pos] }
+
+    fn peek_kind(&self) -> &TokenKind { &self.toks[self.pos].kind }
+
+    fn bump(&mut self) -> Token {
+        let tok = self.toks[self.pos].clone();
+        if self.pos + 1 < self.toks.len() {
+            self.pos += 1;
+        }
+        tok
+    }
+
+    // The span of the most recently consumed token.
+    fn prev_span(&self) -> Span {
+        self.toks[self.pos.saturating_sub(1)].span
+    }
+
+    fn error<T>(&self, expected: &str) -> PResult<T> {
+        let tok = self.peek();
+        Err((tok.span, format!("expected {}, found {}", expected, tok.kind)))
+    }
+
+    fn is_punct(&self, c: char) -> bool {
+        *self.peek_kind() == TokenKind::Punct(c)
+    }
+
+    fn is_keyword(&self, kw: &str) -> bool {
+        match *self.peek_kind() {
+            TokenKind::Ident(ref s) => s == kw,
+            _                       => false,
+        }
+    }
+
+    fn eat_punct(&mut self, c: char) -> bool {
+        if self.is_punct(c) {
+            self.bump();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect_punct(&mut self, c: char) -> PResult<Span> {
+        if self.is_punct(c) {
+            Ok(self.bump().span)
+        } else {
+            self.error(&format!("`{}`", c))
+        }
+    }
+
+    fn expect_ident(&mut self, what: &str) -> PResult<Ident> {
+        if let TokenKind::Ident(ref name) = *self.peek_kind() {
+            let name = name.clone();
+            let span = self.bump().span;
+            return Ok(Ident { name, span })
+        }
+
+        self.error(what)
+    }
+
+    fn expect_string(&mut self, what: &str) -> PResult<(String, Span)> {
+        if let TokenKind::Str(ref s) = *self.peek_kind() {
+            let s = s.clone();
+            return Ok((s, self.bump().span))
+        }
+
+        self.error(what)
+    }
+
+    // Attribute names may be written either bare or quoted.
+    fn attribute_name(&mut self) -> PResult<Ident> {
+        if let TokenKind::Str(ref s) = *self.peek_kind() {
+            let name = s.clone();
+            let span = self.bump().span;
+            return Ok(Ident { name, span })
+        }
+
+        self.expect_ident("an attribute name")
+    }
+
+    fn qualified_name(&mut self, what: &str) -> PResult<QualifiedName> {
+        let first = self.expect_ident(what)?;
+        let start = first.span;
+        let mut parts = vec![first];
+
+        while self.eat_punct('.') {
+            parts.push(self.expect_ident("an identifier after `.`")?);
+        }
+
+        let span = start.to(self.prev_span());
+        Ok(QualifiedName { parts, span })
+    }
+
+    pub fn file(&mut self) -> PResult<File> {
+        let mut file = File::default();
+
+        loop {
+            if self.is_keyword("include") || self.is_keyword("native_include") {
+                let start  = self.peek().span;
+                let native = self.is_keyword("native_include");
+                self.bump();
+
+                let (path, _) = self.expect_string("a quoted file name")?;
+                self.expect_punct(';')?;
+
+                if !file.items.is_empty() {
+                    return Err((start, "includes must come before any declarations".to_string()))
+                }
+
+                file.includes.push(Include { path, native, span: start.to(self.prev_span()) });
+                continue;
+            }
+
+            if *self.peek_kind() == TokenKind::Eof {
+                return Ok(file)
+            }
+
+            let item = self.item()?;
+            file.items.push(item);
+        }
+    }
+
+    fn item(&mut self) -> PResult<Item> {
+        let start = self.peek().span;
+        let doc   = self.peek().doc.clone();
+
+        let kw = match *self.peek_kind() {
+            TokenKind::Ident(ref s) => s.clone(),
+            TokenKind::Punct('{')   => {
+                return Err((start, "JSON data in schema files is not supported".to_string()))
+            }
+            _                       => return self.error("a declaration"),
+        };
+
+        match &kw[..] {
+            "namespace" => {
+                self.bump();
+                let name = self.qualified_name("a namespace name")?;
+                self.expect_punct(';')?;
+                Ok(Item::Namespace(name))
+            }
+            "table" | "struct" => {
+                self.bump();
+                let kind = if kw == "table" { ObjectKind::Table } else { ObjectKind::Struct };
+                self.object(kind, doc, start).map(Item::Object)
+            }
+            "enum" | "union" => {
+                self.bump();
+                self.enum_decl(kw == "union", doc, start).map(Item::Enum)
+            }
+            "rpc_service" => {
+                self.bump();
+                self.service(doc, start).map(Item::Service)
+            }
+            "root_type" => {
+                self.bump();
+                let name = self.qualified_name("a table name")?;
+                self.expect_punct(';')?;
+                Ok(Item::RootType(name))
+            }
+            "file_identifier" | "file_extension" => {
+                self.bump();
+                let (s, span) = self.expect_string("a quoted string")?;
+                self.expect_punct(';')?;
+
+                if kw == "file_identifier" {
+                    if s.len() != 4 {
+                        return Err((span, format!("file_identifier must be exactly 4 bytes, but \
+                                                   \"{}\" is {} bytes", s, s.len())))
+                    }
+                    Ok(Item::FileIdentifier(s, span))
+                } else {
+                    Ok(Item::FileExtension(s, span))
+                }
+            }
+            "attribute" => {
+                self.bump();
+                let ident = self.attribute_name()?;
+                self.expect_punct(';')?;
+                Ok(Item::Attribute(ident))
+            }
+            "include" | "native_include" => {
+                Err((start, "includes must come before any declarations".to_string()))
+            }
+            _ => self.error("a declaration (`table`, `struct`, `enum`, `union`, `namespace`, ...)"),
+        }
+    }
+
+    fn object(&mut self, kind: ObjectKind, doc: Vec<String>, start: Span) -> PResult<Object> {
+        let name  = self.expect_ident("a type name")?;
+        let attrs = self.metadata()?;
+        let mut fields = Vec::new();
+
+        self.expect_punct('{')?;
+
+        while !self.eat_punct('}') {
+            fields.push(self.field()?);
+        }
+
+        let span = start.to(self.prev_span());
+        Ok(Object { kind, name, fields, attrs, doc, span })
+    }
+
+    fn field(&mut self) -> PResult<Field> {
+        let doc  = self.peek().doc.clone();
+        let name = self.expect_ident("a field name or `}`")?;
+
+        self.expect_punct(':')?;
+
+        let ty = self.ty()?;
+
+        let default = if self.eat_punct('=') {
+            let start = self.peek().span;
+            let lit   = self.literal()?;
+            Some((lit, start.to(self.prev_span())))
+        } else {
+            None
+        };
+
+        let attrs = self.metadata()?;
+        self.expect_punct(';')?;
+
+        let span = name.span.to(self.prev_span());
+        Ok(Field { name, ty, default, attrs, doc, span })
+    }
+
+    fn ty(&mut self) -> PResult<Type> {
+        let start = self.peek().span;
+
+        if self.eat_punct('[') {
+            let elem = self.ty()?;
+
+            if self.is_punct(':') {
+                return Err((self.peek().span, "fixed-length arrays are not supported".to_string()))
+            }
+
+            self.expect_punct(']')?;
+
+            if let TypeKind::Vector(_) = elem.kind {
+                return Err((elem.span, "nested vectors are not allowed; wrap the inner vector \
+                                        in a table".to_string()))
+            }
+
+            return Ok(Type { kind: TypeKind::Vector(Box::new(elem)), span: start.to(self.prev_span()) })
+        }
+
+        let name = self.qualified_name("a type")?;
+
+        let kind = if name.parts.len() == 1 {
+            let base = &name.parts[0].name;
+
+            if base == "string" {
+                TypeKind::String
+            } else if let Some(s) = Scalar::from_name(base) {
+                TypeKind::Scalar(s)
+            } else {
+                TypeKind::Named(name.clone())
+            }
+        } else {
+            TypeKind::Named(name.clone())
+        };
+
+        Ok(Type { kind, span: name.span })
+    }
+
+    // Parses an optional `( name [: value], ... )` list.
+    fn metadata(&mut self) -> PResult<Vec<Attribute>> {
+        let mut attrs = Vec::new();
+
+        if !self.eat_punct('(') {
+            return Ok(attrs)
+        }
+
+        loop {
+            let name = self.attribute_name()?;
+
+            let value = if self.eat_punct(':') {
+                Some(self.literal()?)
+            } else {
+                None
+            };
+
+            let span = name.span.to(self.prev_span());
+            attrs.push(Attribute { name, value, span });
+
+            if self.eat_punct(')') {
+                return Ok(attrs)
+            }
+
+            self.expect_punct(',')?;
+        }
+    }
+
+    fn literal(&mut self) -> PResult<Literal> {
+        let negative = if self.eat_punct('-') {
+            true
+        } else {
+            self.eat_punct('+');
+            false
+        };
+
+        let tok = self.peek().clone();
+
+        match tok.kind {
+            TokenKind::Number(ref text) => {
+                self.bump();
+                parse_number(text, negative).ok_or_else(|| {
+                    (tok.span, format!("invalid numeric constant `{}`", text))
+                })
+            }
+            TokenKind::Ident(ref s) => {
+                let special = match &s.to_lowercase()[..] {
+                    "nan"               => Some(f64::NAN),
+                    "inf" | "infinity"  => Some(f64::INFINITY),
+                    _                   => None,
+                };
+
+                if let Some(f) = special {
+                    self.bump();
+                    return Ok(Literal::Float(if negative { -f } else { f }))
+                }
+
+                if negative {
+                    return self.error("a number after `-`")
+                }
+
+                match &s[..] {
+                    "true"  => { self.bump(); Ok(Literal::Bool(true)) }
+                    "false" => { self.bump(); Ok(Literal::Bool(false)) }
+                    _       => {
+                        let name = self.qualified_name("a value")?;
+                        Ok(Literal::Ident(name.to_dotted()))
+                    }
+                }
+            }
+            TokenKind::Str(ref s) if !negative => {
+                self.bump();
+                Ok(Literal::String(s.clone()))
+            }
+            _ => self.error("a constant"),
+        }
+    }
+
+    // Parse an integer constant, which is all that's allowed as an enum value.
+    fn integer(&mut self) -> PResult<(i128, Span)> {
+        let start = self.peek().span;
+
+        match self.literal()? {
+            Literal::Integer(i) => Ok((i, start.to(self.prev_span()))),
+            _                   => Err((start.to(self.prev_span()),
+                                        "expected an integer constant".to_string())),
+        }
+    }
+
+    fn enum_decl(&mut self, is_union: bool, doc: Vec<String>, start: Span) -> PResult<Enum> {
+        let name = self.expect_ident(if is_union { "a union name" } else { "an enum name" })?;
+
+        let underlying = if !is_union {
+            if !self.eat_punct(':') {
+                return self.error("`:` followed by the enum's underlying type")
+            }
+            Some(self.ty()?)
+        } else {
+            None
+        };
+
+        let attrs = self.metadata()?;
+        let mut values = Vec::new();
+
+        self.expect_punct('{')?;
+
+        while !self.eat_punct('}') {
+            let vdoc  = self.peek().doc.clone();
+            let vname = self.qualified_name("a value name or `}`")?;
+
+            let (vname, mut ty) = if vname.parts.len() == 1 {
+                (vname.parts[0].clone(), None)
+            } else if is_union {
+                let joined: Vec<&str> = vname.parts.iter().map(|p| &p.name[..]).collect();
+                (Ident { name: joined.join("_"), span: vname.span }, Some(vname))
+            } else {
+                return Err((vname.span, "enum value names cannot contain `.`".to_string()))
+            };
+
+            if is_union && self.eat_punct(':') {
+                ty = Some(self.qualified_name("a table name")?);
+            }
+
+            let value = if self.eat_punct('=') {
+                Some(self.integer()?)
+            } else {
+                None
+            };
+
+            let vattrs = self.metadata()?;
+            let span   = vname.span.to(self.prev_span());
+
+            values.push(EnumVal { name: vname, value, ty, attrs: vattrs, doc: vdoc, span });
+
+            if !self.eat_punct(',') {
+                self.expect_punct('}')?;
+                break;
+            }
+        }
+
+        let span = start.to(self.prev_span());
+        Ok(Enum { is_union, name, underlying, values, attrs, doc, span })
+    }
+
+    fn service(&mut self, doc: Vec<String>, start: Span) -> PResult<Service> {
+        let name  = self.expect_ident("a service name")?;
+        let attrs = self.metadata()?;
+        let mut methods = Vec::new();
+
+        self.expect_punct('{')?;
+
+        while !self.eat_punct('}') {
+            let mdoc  = self.peek().doc.clone();
+            let mname = self.expect_ident("a method name or `}`")?;
+
+            self.expect_punct('(')?;
+            let request = self.qualified_name("a request table name")?;
+            self.expect_punct(')')?;
+            self.expect_punct(':')?;
+            let response = self.qualified_name("a response table name")?;
+            let mattrs   = self.metadata()?;
+            self.expect_punct(';')?;
+
+            let span = mname.span.to(self.prev_span());
+            methods.push(RpcMethod { name: mname, request, response, attrs: mattrs, doc: mdoc, span });
+        }
+
+        let span = start.to(self.prev_span());
+        Ok(Service { name, methods, attrs, doc, span })
+    }
+}
+
+// Parse the text of a number token. Integers which fit in an `i128` are returned as integers, and
+// everything else as floats.
+fn parse_number(text: &str, negative: bool) -> Option<Literal> {
+    let lower = text.to_lowercase();
+
+    if let Some(digits) = lower.strip_prefix("0x") {
+        if digits.contains('.') || digits.contains('p') {
+            return parse_hex_float(digits).map(|f| Literal::Float(if negative { -f } else { f }))
+        }
+
+        return i128::from_str_radix(digits, 16).ok()
+            .map(|i| Literal::Integer(if negative { -i } else { i }))
+    }
+
+    if !lower.contains('.') && !lower.contains('e') {
+        return lower.parse::<i128>().ok().map(|i| Literal::Integer(if negative { -i } else { i }))
+    }
+
+    lower.parse::<f64>().ok().map(|f| Literal::Float(if negative { -f } else { f }))
+}
+
+// Parse the part of a hexadecimal float after the `0x`, e.g. `1.8p3`.
+fn parse_hex_float(digits: &str) -> Option<f64> {
+    let (mantissa, exp) = match digits.find('p') {
+        Some(i) => (&digits[..i], digits[i + 1..].parse::<i32>().ok()?),
+        None    => (digits, 0),
+    };
+
+    let (int_part, frac_part) = match mantissa.find('.') {
+        Some(i) => (&mantissa[..i], &mantissa[i + 1..]),
+        None    => (mantissa, ""),
+    };
+
+    if int_part.is_empty() && frac_part.is_empty() {
+        return None
+    }
+
+    let mut val = 0f64;
+    for c in int_part.chars() {
+        val = val * 16.0 + c.to_digit(16)? as f64;
+    }
+
+    let mut scale = 1.0 / 16.0;
+    for c in frac_part.chars() {
+        val += c.to_digit(16)? as f64 * scale;
+        scale /= 16.0;
+    }
+
+    Some(val * 2f64.powi(exp))
+}
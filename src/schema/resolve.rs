@@ -0,0 +1,709 @@
+// Copyright 2015 Sam Payson. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Turns the syntax trees of a set of files into a `Schema`.
+
+use std::collections::{HashMap, HashSet};
+
+use schema::{ast, Error, SourceFile, Span};
+use schema::model::*;
+
+// Attributes which the compiler knows about. Any others must be declared with `attribute`.
+const KNOWN_ATTRIBUTES: &[&str] = &[
+    "id", "deprecated", "required", "key", "hash", "force_align", "bit_flags", "original_order",
+    "nested_flatbuffer", "flexbuffer", "shared", "private", "streaming", "idempotent",
+    "native_inline", "native_type", "native_default", "native_custom_alloc", "cpp_type",
+    "cpp_ptr_type", "cpp_ptr_type_get", "cpp_str_type", "cpp_str_flex_ctor", "csharp_partial",
+];
+
+// The largest field id whose vtable offset (`4 + 2 * id`) fits in a `VOffset`.
+const MAX_FIELD_ID: u16 = (u16::MAX - 4) / 2;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Decl {
+    Object(usize),
+    Enum(usize),
+}
+
+// A declaration along with where it was found.
+struct Pending<'a, T: 'a> {
+    file: usize,
+    ns:   Vec<String>,
+    ast:  &'a T,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Layout {
+    NotStarted,
+    InProgress,
+    Done,
+}
+
+type RResult<T> = Result<T, Error>;
+
+struct Resolver<'a> {
+    files:      &'a [SourceFile],
+    objects:    Vec<Pending<'a, ast::Object>>,
+    enums:      Vec<Pending<'a, ast::Enum>>,
+    names:      HashMap<String, Decl>,
+    attributes: HashSet<String>,
+    schema:     Schema,
+    layout:     Vec<Layout>,
+}
+
+/// Resolve a set of parsed files (as returned by `schema::load`) into a single `Schema`. The
+/// `root_type`, `file_identifier` and `file_extension` are taken from the last file.
+pub fn resolve(files: &[SourceFile]) -> Result<Schema, Error> {
+    let mut r = Resolver {
+        files,
+        objects:    Vec::new(),
+        enums:      Vec::new(),
+        names:      HashMap::new(),
+        attributes: KNOWN_ATTRIBUTES.iter().map(|s| s.to_string()).collect(),
+        schema:     Schema::default(),
+        layout:     Vec::new(),
+    };
+
+    r.collect()?;
+
+    for i in 0..r.enums.len() {
+        let e = r.resolve_enum(i)?;
+        r.schema.enums.push(e);
+    }
+
+    for i in 0..r.objects.len() {
+        let o = r.resolve_object(i)?;
+        r.schema.objects.push(o);
+    }
+
+    r.layout = vec![Layout::NotStarted; r.objects.len()];
+    for i in 0..r.objects.len() {
+        if r.schema.objects[i].is_struct {
+            r.layout_struct(i)?;
+        }
+    }
+
+    r.root_decls()?;
+
+    Ok(r.schema)
+}
+
+fn qualify(ns: &[String], name: &str) -> String {
+    if ns.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}.{}", ns.join("."), name)
+    }
+}
+
+fn literal_to_string(lit: &ast::Literal) -> String {
+    match *lit {
+        ast::Literal::Integer(i)     => i.to_string(),
+        ast::Literal::Float(f)       => f.to_string(),
+        ast::Literal::Bool(b)        => b.to_string(),
+        ast::Literal::String(ref s)  => s.clone(),
+        ast::Literal::Ident(ref s)   => s.clone(),
+    }
+}
+
+fn scalar_base_type(s: ast::Scalar) -> BaseType {
+    match s {
+        ast::Scalar::Bool   => BaseType::Bool,
+        ast::Scalar::Byte   => BaseType::Byte,
+        ast::Scalar::UByte  => BaseType::UByte,
+        ast::Scalar::Short  => BaseType::Short,
+        ast::Scalar::UShort => BaseType::UShort,
+        ast::Scalar::Int    => BaseType::Int,
+        ast::Scalar::UInt   => BaseType::UInt,
+        ast::Scalar::Long   => BaseType::Long,
+        ast::Scalar::ULong  => BaseType::ULong,
+        ast::Scalar::Float  => BaseType::Float,
+        ast::Scalar::Double => BaseType::Double,
+    }
+}
+
+// The range of values an integer type can hold.
+fn integer_range(b: BaseType) -> (i128, i128) {
+    match b {
+        BaseType::Bool                    => (0, 1),
+        BaseType::Byte                    => (i8::MIN as i128, i8::MAX as i128),
+        BaseType::UByte | BaseType::UType => (0, u8::MAX as i128),
+        BaseType::Short                   => (i16::MIN as i128, i16::MAX as i128),
+        BaseType::UShort                  => (0, u16::MAX as i128),
+        BaseType::Int                     => (i32::MIN as i128, i32::MAX as i128),
+        BaseType::UInt                    => (0, u32::MAX as i128),
+        BaseType::Long                    => (i64::MIN as i128, i64::MAX as i128),
+        _                                 => (0, u64::MAX as i128),
+    }
+}
+
+fn align_up(n: usize, align: usize) -> usize {
+    (n + align - 1) & !(align - 1)
+}
+
+impl<'a> Resolver<'a> {
+    fn error(&self, file: usize, span: Span, message: String) -> Error {
+        let f    = &self.files[file];
+        let path = if f.path.as_os_str().is_empty() { None } else { Some(f.path.as_path()) };
+
+        Error::new(path, &f.source, span, message)
+    }
+
+    // Gather every declaration, so that types can be referred to before they're declared.
+    fn collect(&mut self) -> RResult<()> {
+        let files = self.files;
+
+        for (fi, file) in files.iter().enumerate() {
+            let mut ns: Vec<String> = Vec::new();
+
+            for item in file.ast.items.iter() {
+                let (name, decl) = match *item {
+                    ast::Item::Namespace(ref q) => {
+                        ns = q.parts.iter().map(|p| p.name.clone()).collect();
+                        continue;
+                    }
+                    ast::Item::Attribute(ref id) => {
+                        self.attributes.insert(id.name.clone());
+                        continue;
+                    }
+                    ast::Item::Object(ref o) => {
+                        self.objects.push(Pending { file: fi, ns: ns.clone(), ast: o });
+                        (&o.name, Decl::Object(self.objects.len() - 1))
+                    }
+                    ast::Item::Enum(ref e) => {
+                        self.enums.push(Pending { file: fi, ns: ns.clone(), ast: e });
+                        (&e.name, Decl::Enum(self.enums.len() - 1))
+                    }
+                    _ => continue,
+                };
+
+                let full = qualify(&ns, &name.name);
+
+                if self.names.insert(full.clone(), decl).is_some() {
+                    return Err(self.error(fi, name.span, format!("`{}` is already defined", full)))
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    // Find a type by name, searching from the innermost namespace outwards.
+    fn lookup(&self, ns: &[String], name: &ast::QualifiedName) -> Option<Decl> {
+        let dotted = name.to_dotted();
+
+        for i in (0..ns.len() + 1).rev() {
+            if let Some(&d) = self.names.get(&qualify(&ns[..i], &dotted)) {
+                return Some(d)
+            }
+        }
+
+        None
+    }
+
+    fn check_attributes(&self, file: usize, attrs: &[ast::Attribute]) -> RResult<Vec<KeyValue>> {
+        let mut out: Vec<KeyValue> = Vec::new();
+
+        for a in attrs.iter() {
+            if !self.attributes.contains(&a.name.name) {
+                return Err(self.error(file, a.name.span,
+                                      format!("unknown attribute `{}`; user-defined attributes must \
+                                               be declared with `attribute \"{}\";`",
+                                              a.name.name, a.name.name)))
+            }
+
+            if out.iter().any(|kv| kv.key == a.name.name) {
+                return Err(self.error(file, a.span,
+                                      format!("attribute `{}` is given more than once", a.name.name)))
+            }
+
+            out.push(KeyValue {
+                key:   a.name.name.clone(),
+                value: a.value.as_ref().map(literal_to_string),
+            });
+        }
+
+        Ok(out)
+    }
+
+    // Return the integer value of an attribute, if it's present.
+    fn int_attribute(&self, file: usize, attrs: &[ast::Attribute], key: &str)
+        -> RResult<Option<(i128, Span)>> {
+
+        match attrs.iter().find(|a| a.name.name == key) {
+            Some(a) => match a.value {
+                Some(ast::Literal::Integer(i)) => Ok(Some((i, a.span))),
+                _ => Err(self.error(file, a.span, format!("attribute `{}` needs an integer value", key))),
+            },
+            None => Ok(None),
+        }
+    }
+
+    fn resolve_enum(&mut self, idx: usize) -> RResult<Enum> {
+        let (file, ast) = (self.enums[idx].file, self.enums[idx].ast);
+        let ns          = self.enums[idx].ns.clone();
+        let attributes  = self.check_attributes(file, &ast.attrs)?;
+        let bit_flags   = ast.attrs.iter().any(|a| a.name.name == "bit_flags");
+
+        let underlying = match ast.underlying {
+            None                                             => Type::with_index(BaseType::UType, idx),
+            Some(ast::Type { kind: ast::TypeKind::Scalar(s), .. }) if s.is_integer() => {
+                Type::with_index(scalar_base_type(s), idx)
+            }
+            Some(ref t) => {
+                return Err(self.error(file, t.span,
+                                      "the underlying type of an enum must be an integer type"
+                                      .to_string()))
+            }
+        };
+
+        let (min, max) = integer_range(underlying.base_type);
+        let mut values: Vec<EnumVal> = Vec::new();
+        let mut next = 0i128;
+
+        if ast.is_union {
+            values.push(EnumVal {
+                name:          "NONE".to_string(),
+                value:         0,
+                union_type:    None,
+                attributes:    Vec::new(),
+                documentation: Vec::new(),
+            });
+            next = 1;
+        }
+
+        for v in ast.values.iter() {
+            let (mut value, vspan) = v.value.unwrap_or((next, v.name.span));
+
+            if bit_flags {
+                let bits = (underlying.base_type.size() * 8) as i128;
+                if value < 0 || value >= bits {
+                    return Err(self.error(file, vspan,
+                                          format!("bit flag `{}` is out of range for the \
+                                                   underlying type", v.name.name)))
+                }
+            } else if value < min || value > max {
+                return Err(self.error(file, vspan,
+                                      format!("value of `{}` doesn't fit in the underlying type \
+                                               `{}`", v.name.name,
+                                              underlying.base_type.schema_name())))
+            }
+
+            next = value.checked_add(1).ok_or_else(|| {
+                self.error(file, vspan, format!("value of `{}` is out of range", v.name.name))
+            })?;
+
+            if bit_flags {
+                value = 1 << value;
+
+                if value < min || value > max {
+                    return Err(self.error(file, vspan,
+                                          format!("bit flag `{}` doesn't fit in the underlying \
+                                                   type `{}`", v.name.name,
+                                                  underlying.base_type.schema_name())))
+                }
+            }
+
+            // Values are stored as an `i64`, as they are in `reflection.fbs`, so a `ulong` value
+            // is stored as its bit pattern. Everything else was range checked above, and fits.
+            let value = if underlying.base_type == BaseType::ULong {
+                value as u64 as i64
+            } else {
+                value as i64
+            };
+
+            if values.iter().any(|o| o.name == v.name.name) {
+                return Err(self.error(file, v.name.span,
+                                      format!("`{}` is already a value of `{}`", v.name.name,
+                                              ast.name.name)))
+            }
+
+            if values.iter().any(|o| o.value == value) {
+                return Err(self.error(file, vspan,
+                                      format!("`{}` has the same value as an earlier member of `{}`",
+                                              v.name.name, ast.name.name)))
+            }
+
+            let union_type = if ast.is_union {
+                let tname = match v.ty {
+                    Some(ref q) => q.clone(),
+                    None        => ast::QualifiedName { parts: vec![v.name.clone()], span: v.name.span },
+                };
+
+                match self.lookup(&ns, &tname) {
+                    Some(Decl::Object(o)) if self.objects[o].ast.kind == ast::ObjectKind::Table => {
+                        Some(Type::with_index(BaseType::Obj, o))
+                    }
+                    _ => return Err(self.error(file, tname.span,
+                                               format!("union member `{}` must be a table",
+                                                       tname.to_dotted()))),
+                }
+            } else {
+                None
+            };
+
+            values.push(EnumVal {
+                name:          v.name.name.clone(),
+                value,
+                union_type,
+                attributes:    self.check_attributes(file, &v.attrs)?,
+                documentation: v.doc.clone(),
+            });
+        }
+
+        if underlying.base_type == BaseType::ULong {
+            values.sort_by_key(|v| v.value as u64);
+        } else {
+            values.sort_by_key(|v| v.value);
+        }
+
+        Ok(Enum {
+            name:            qualify(&ns, &ast.name.name),
+            values,
+            is_union:        ast.is_union,
+            underlying_type: underlying,
+            attributes,
+            documentation:   ast.doc.clone(),
+        })
+    }
+
+    fn resolve_type(&self, file: usize, ns: &[String], ty: &ast::Type) -> RResult<Type> {
+        match ty.kind {
+            ast::TypeKind::Scalar(s)  => Ok(Type::new(scalar_base_type(s))),
+            ast::TypeKind::String     => Ok(Type::new(BaseType::String)),
+            ast::TypeKind::Vector(ref elem) => {
+                let e = self.resolve_type(file, ns, elem)?;
+                Ok(Type::vector_of(e.base_type, e.index))
+            }
+            ast::TypeKind::Named(ref q) => match self.lookup(ns, q) {
+                Some(Decl::Object(o)) => Ok(Type::with_index(BaseType::Obj, o)),
+                Some(Decl::Enum(e))   => {
+                    let en = &self.schema.enums[e];
+                    if en.is_union {
+                        Ok(Type::with_index(BaseType::Union, e))
+                    } else {
+                        Ok(Type::with_index(en.underlying_type.base_type, e))
+                    }
+                }
+                None => Err(self.error(file, q.span, format!("unknown type `{}`", q.to_dotted()))),
+            },
+        }
+    }
+
+    // Work out the default value for a scalar field.
+    fn default_value(&self, file: usize, ty: Type, lit: &ast::Literal, span: Span)
+        -> RResult<(i64, f64)> {
+
+        let b = ty.base_type;
+
+        if b.is_float() {
+            return match *lit {
+                ast::Literal::Integer(i) => Ok((0, i as f64)),
+                ast::Literal::Float(f)   => Ok((0, f)),
+                _ => Err(self.error(file, span, "expected a number".to_string())),
+            }
+        }
+
+        let value = match *lit {
+            ast::Literal::Integer(i) => i,
+            ast::Literal::Bool(v) if b == BaseType::Bool => v as i128,
+            ast::Literal::Ident(ref name) if ty.index.is_some() => {
+                let en   = &self.schema.enums[ty.index.unwrap()];
+                let base = name.rsplit('.').next().unwrap();
+
+                match en.value_by_name(base) {
+                    Some(v) => v.value as i128,
+                    None    => return Err(self.error(file, span,
+                                                     format!("`{}` is not a value of `{}`", name,
+                                                             en.name))),
+                }
+            }
+            _ => return Err(self.error(file, span,
+                                       format!("invalid default for a field of type `{}`",
+                                               b.schema_name()))),
+        };
+
+        let (min, max) = integer_range(b);
+        if value < min || value > max {
+            return Err(self.error(file, span,
+                                  format!("default value doesn't fit in a `{}`", b.schema_name())))
+        }
+
+        Ok((value as i64, 0.0))
+    }
+
+    fn resolve_object(&mut self, idx: usize) -> RResult<Object> {
+        let (file, ast) = (self.objects[idx].file, self.objects[idx].ast);
+        let ns          = self.objects[idx].ns.clone();
+        let is_struct   = ast.kind == ast::ObjectKind::Struct;
+        let attributes  = self.check_attributes(file, &ast.attrs)?;
+        let explicit_ids = ast.fields.iter().any(|f| f.attrs.iter().any(|a| a.name.name == "id"));
+
+        let mut fields: Vec<Field> = Vec::new();
+        let mut names   = HashSet::new();
+        let mut next_id = 0u16;
+
+        for f in ast.fields.iter() {
+            let ty    = self.resolve_type(file, &ns, &f.ty)?;
+            let attrs = self.check_attributes(file, &f.attrs)?;
+            let has   = |key: &str| attrs.iter().any(|kv| kv.key == key);
+
+            if names.contains(&f.name.name) {
+                return Err(self.error(file, f.name.span,
+                                      format!("field `{}` is already defined", f.name.name)))
+            }
+
+            if is_struct {
+                let ok = ty.base_type.is_scalar() ||
+                         (ty.base_type == BaseType::Obj &&
+                          self.objects[ty.index.unwrap()].ast.kind == ast::ObjectKind::Struct);
+                if !ok {
+                    return Err(self.error(file, f.ty.span,
+                                          "struct fields must be scalars, enums or other structs"
+                                          .to_string()))
+                }
+
+                if let Some((_, span)) = f.default {
+                    return Err(self.error(file, span,
+                                          "struct fields can't have default values".to_string()))
+                }
+
+                if has("deprecated") || has("required") || has("id") {
+                    return Err(self.error(file, f.span,
+                                          "`deprecated`, `required` and `id` aren't allowed on \
+                                           struct fields".to_string()))
+                }
+            }
+
+            if has("required") && ty.base_type.is_scalar() {
+                return Err(self.error(file, f.span, "scalar fields can't be `required`".to_string()))
+            }
+
+            if has("key") {
+                if fields.iter().any(|o| o.key) {
+                    return Err(self.error(file, f.span, "only one field may be the `key`".to_string()))
+                }
+                if !ty.base_type.is_scalar() && ty.base_type != BaseType::String {
+                    return Err(self.error(file, f.span,
+                                          "only scalar and string fields can be the `key`"
+                                          .to_string()))
+                }
+            }
+
+            let (default_integer, default_real) = match f.default {
+                Some((ref lit, span)) => {
+                    if !ty.base_type.is_scalar() {
+                        return Err(self.error(file, span,
+                                              "only scalar fields can have default values"
+                                              .to_string()))
+                    }
+                    self.default_value(file, ty, lit, span)?
+                }
+                None => (0, 0.0),
+            };
+
+            let is_union = ty.base_type == BaseType::Union ||
+                           (ty.base_type == BaseType::Vector && ty.element == BaseType::Union);
+
+            let id = if explicit_ids {
+                match self.int_attribute(file, &f.attrs, "id")? {
+                    Some((i, span)) => {
+                        let min = if is_union { 1 } else { 0 };
+                        if i < min || i > MAX_FIELD_ID as i128 {
+                            return Err(self.error(file, span, "field id is out of range".to_string()))
+                        }
+                        i as u16
+                    }
+                    None => return Err(self.error(file, f.span,
+                                                  "if any field has an `id`, they all must"
+                                                  .to_string())),
+                }
+            } else if is_union {
+                next_id + 1
+            } else {
+                next_id
+            };
+
+            if id > MAX_FIELD_ID {
+                return Err(self.error(file, f.span,
+                                      format!("table `{}` has too many fields", ast.name.name)))
+            }
+
+            next_id = id + 1;
+
+            // Unions are stored as two fields: the type tag, and the offset of the value. Vectors of
+            // unions are a vector of tags and a vector of offsets.
+            if is_union {
+                let tag_ty = if ty.base_type == BaseType::Vector {
+                    Type::vector_of(BaseType::UType, ty.index)
+                } else {
+                    Type::with_index(BaseType::UType, ty.index.unwrap())
+                };
+
+                let tag_name = format!("{}_type", f.name.name);
+
+                if names.contains(&tag_name) {
+                    return Err(self.error(file, f.name.span,
+                                          format!("field `{}` is already defined", tag_name)))
+                }
+
+                names.insert(tag_name.clone());
+                fields.push(Field {
+                    name:            tag_name,
+                    ty:              tag_ty,
+                    id:              id - 1,
+                    offset:          0,
+                    default_integer: 0,
+                    default_real:    0.0,
+                    deprecated:      has("deprecated"),
+                    required:        false,
+                    key:             false,
+                    attributes:      Vec::new(),
+                    documentation:   Vec::new(),
+                });
+            }
+
+            names.insert(f.name.name.clone());
+            fields.push(Field {
+                name:          f.name.name.clone(),
+                ty,
+                id,
+                offset:        0,
+                default_integer,
+                default_real,
+                deprecated:    has("deprecated"),
+                required:      has("required"),
+                key:           has("key"),
+                attributes:    attrs.clone(),
+                documentation: f.doc.clone(),
+            });
+        }
+
+        fields.sort_by_key(|f| f.id);
+
+        for (i, f) in fields.iter_mut().enumerate() {
+            if f.id as usize != i {
+                let span = ast.fields.iter()
+                    .find(|af| af.name.name == f.name || format!("{}_type", af.name.name) == f.name)
+                    .map_or(ast.name.span, |af| af.span);
+
+                return Err(self.error(file, span,
+                                      format!("field ids must be unique and consecutive starting \
+                                               from 0, but `{}` has id {}", f.name, f.id)))
+            }
+
+            if !is_struct {
+                f.offset = 4 + 2 * f.id;
+            }
+        }
+
+        Ok(Object {
+            name:          qualify(&ns, &ast.name.name),
+            fields,
+            is_struct,
+            minalign:      1,
+            bytesize:      0,
+            attributes,
+            documentation: ast.doc.clone(),
+        })
+    }
+
+    fn layout_struct(&mut self, idx: usize) -> RResult<()> {
+        let (file, ast) = (self.objects[idx].file, self.objects[idx].ast);
+
+        match self.layout[idx] {
+            Layout::Done       => return Ok(()),
+            Layout::InProgress => {
+                return Err(self.error(file, ast.name.span,
+                                      format!("struct `{}` contains itself", ast.name.name)))
+            }
+            Layout::NotStarted => {}
+        }
+
+        self.layout[idx] = Layout::InProgress;
+
+        let mut size     = 0;
+        let mut minalign = 1;
+
+        for i in 0..self.schema.objects[idx].fields.len() {
+            let ty = self.schema.objects[idx].fields[i].ty;
+
+            let (fsize, falign) = if ty.base_type == BaseType::Obj {
+                let inner = ty.index.unwrap();
+                self.layout_struct(inner)?;
+                (self.schema.objects[inner].bytesize, self.schema.objects[inner].minalign)
+            } else {
+                (ty.base_type.size(), ty.base_type.size())
+            };
+
+            size = align_up(size, falign);
+            self.schema.objects[idx].fields[i].offset = size as u16;
+            size += fsize;
+            minalign = minalign.max(falign);
+        }
+
+        if let Some((force, span)) = self.int_attribute(file, &ast.attrs, "force_align")? {
+            let force = force as usize;
+            if force < minalign || force > 16 || !force.is_power_of_two() {
+                return Err(self.error(file, span,
+                                      format!("force_align must be a power of two between {} and \
+                                               16", minalign)))
+            }
+            minalign = force;
+        }
+
+        if size == 0 {
+            return Err(self.error(file, ast.name.span,
+                                  format!("struct `{}` has no fields", ast.name.name)))
+        }
+
+        let obj = &mut self.schema.objects[idx];
+        obj.minalign = minalign;
+        obj.bytesize = align_up(size, minalign);
+
+        self.layout[idx] = Layout::Done;
+        Ok(())
+    }
+
+    // Handle `root_type`, `file_identifier` and `file_extension` in the last file.
+    fn root_decls(&mut self) -> RResult<()> {
+        let fi = match self.files.len() {
+            0 => return Ok(()),
+            n => n - 1,
+        };
+
+        let mut ns: Vec<String> = Vec::new();
+
+        for item in self.files[fi].ast.items.iter() {
+            match *item {
+                ast::Item::Namespace(ref q) => {
+                    ns = q.parts.iter().map(|p| p.name.clone()).collect();
+                }
+                ast::Item::RootType(ref q) => match self.lookup(&ns, q) {
+                    Some(Decl::Object(o)) if !self.schema.objects[o].is_struct => {
+                        self.schema.root_table = Some(o);
+                    }
+                    _ => return Err(self.error(fi, q.span,
+                                               format!("root_type `{}` must be a table",
+                                                       q.to_dotted()))),
+                },
+                ast::Item::FileIdentifier(ref s, _) => self.schema.file_ident = Some(s.clone()),
+                ast::Item::FileExtension(ref s, _)  => self.schema.file_ext = Some(s.clone()),
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,288 @@
+// Copyright 2015 Sam Payson. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Deserializing values from buffers, through `reflection`.
+
+use std::vec;
+
+use serde_crate::de::value::StrDeserializer;
+use serde_crate::de::{self, DeserializeSeed, IntoDeserializer, Visitor};
+
+use reflection::{DynTable, DynUnion, DynVecIter, Value};
+use schema::{BaseType, Enum, Field, Schema, Type};
+
+use super::Error;
+
+type Result<T> = ::std::result::Result<T, Error>;
+
+/// A serde `Deserializer` which reads a value from a buffer, according to its schema. Strings are
+/// borrowed from the buffer, so types which hold `&str`s can be deserialized without copying.
+pub struct Deserializer<'s, 'buf> {
+    schema:   &'s Schema,
+    value:    Value<'s, 'buf>,
+
+    // The enum which an integer value is a member of, so that it can be read by name.
+    enum_def: Option<&'s Enum>,
+}
+
+impl<'s, 'buf> Deserializer<'s, 'buf> {
+    /// Create a deserializer which reads `table`.
+    pub fn from_table(table: DynTable<'s, 'buf>) -> Deserializer<'s, 'buf> {
+        Deserializer::new(table.schema(), Value::Table(table), None)
+    }
+
+    // Create a deserializer for `value`, which has the type `ty` in the schema.
+    fn new(schema: &'s Schema, value: Value<'s, 'buf>, ty: Option<Type>)
+        -> Deserializer<'s, 'buf> {
+
+        let enum_def = match ty {
+            Some(Type { base_type, index: Some(i), .. }) if base_type.is_integer() => {
+                Some(&schema.enums[i])
+            }
+            _ => None,
+        };
+
+        Deserializer { schema, value, enum_def }
+    }
+
+    // The fields of a table or struct, to be read as a map. Scalar fields of tables are always
+    // included, with their defaults if they aren't present. Union type fields are left out, since
+    // the type is read along with the union.
+    fn fields(&self) -> Option<MapAccess<'s, 'buf>> {
+        let fields: Vec<_> = match self.value {
+            Value::Table(t)  => {
+                t.object().fields.iter()
+                    .filter(|f| !f.deprecated && !is_union_type(f))
+                    .filter_map(|f| t.value(f).map(|v| (f, v)))
+                    .collect()
+            }
+            Value::Struct(s) => s.fields().collect(),
+            _                => return None,
+        };
+
+        Some(MapAccess {
+            schema: self.schema,
+            fields: fields.into_iter(),
+            value:  None,
+        })
+    }
+}
+
+// Whether `field` holds the type tags of a union, or of a vector of unions.
+fn is_union_type(field: &Field) -> bool {
+    field.ty.base_type == BaseType::UType || field.ty.element == BaseType::UType
+}
+
+// A deserializer for the name of a field, enum value or union member.
+fn name(name: &str) -> StrDeserializer<'_, Error> {
+    name.into_deserializer()
+}
+
+// The name of the member of `u`, or an error if the tag isn't one of the union's members.
+fn variant_name<'s>(u: &DynUnion<'s, '_>) -> Result<&'s str> {
+    match u.variant() {
+        Some(v) => Ok(&v.name),
+        None    => Err(Error::Message(format!("{} isn't a member of `{}`", u.tag(),
+                                              u.enum_def().name))),
+    }
+}
+
+impl<'de, 's> de::Deserializer<'de> for Deserializer<'s, 'de> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.value {
+            Value::Bool(b)  => visitor.visit_bool(b),
+            Value::Int(i)   => visitor.visit_i64(i),
+            Value::UInt(u)  => visitor.visit_u64(u),
+            Value::Float(f) => visitor.visit_f64(f),
+            Value::Str(s)   => visitor.visit_borrowed_str(s.to_str()?),
+            Value::Vector(v) => {
+                visitor.visit_seq(SeqAccess {
+                    schema:  self.schema,
+                    element: v.element_type(),
+                    iter:    v.iter(),
+                })
+            }
+            Value::Table(_) | Value::Struct(_) => visitor.visit_map(self.fields().unwrap()),
+            Value::Union(u) if u.tag() == 0    => visitor.visit_unit(),
+            Value::Union(u) => {
+                visitor.visit_enum(UnionAccess { schema: self.schema,
+                                                 name:   variant_name(&u)?,
+                                                 union:  u })
+            }
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.value {
+            Value::Union(u) if u.tag() == 0 => visitor.visit_none(),
+            _                               => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V)
+        -> Result<V::Value> {
+
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V)
+        -> Result<V::Value> {
+
+        visitor.visit_newtype_struct(self)
+    }
+
+    /// Enum values are read by name, and unions as the variant named after their member.
+    fn deserialize_enum<V: Visitor<'de>>(self, _name: &'static str,
+                                         _variants: &'static [&'static str], visitor: V)
+        -> Result<V::Value> {
+
+        let value = match self.value {
+            Value::Int(i)  => i,
+            Value::UInt(u) => u as i64,
+            Value::Str(s)  => return visitor.visit_enum(name(s.to_str()?)),
+            Value::Union(u) => {
+                return visitor.visit_enum(UnionAccess { schema: self.schema,
+                                                        name:   variant_name(&u)?,
+                                                        union:  u })
+            }
+            _ => return self.deserialize_any(visitor),
+        };
+
+        let en = match self.enum_def {
+            Some(en) => en,
+            None     => return self.deserialize_any(visitor),
+        };
+
+        match en.value_by_value(value) {
+            Some(v) => visitor.visit_enum(name(&v.name)),
+            None    => Err(Error::Message(format!("{} isn't a value of `{}`", value, en.name))),
+        }
+    }
+
+    serde_crate::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf seq
+        tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+/// Reads the elements of a vector.
+struct SeqAccess<'s, 'buf> {
+    schema:  &'s Schema,
+    element: Type,
+    iter:    DynVecIter<'s, 'buf>,
+}
+
+impl<'de, 's> de::SeqAccess<'de> for SeqAccess<'s, 'de> {
+    type Error = Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>> {
+        match self.iter.next() {
+            Some(v) => {
+                seed.deserialize(Deserializer::new(self.schema, v, Some(self.element))).map(Some)
+            }
+            None    => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.size_hint().0)
+    }
+}
+
+/// Reads the fields of a table or struct, keyed by name.
+struct MapAccess<'s, 'buf> {
+    schema: &'s Schema,
+    fields: vec::IntoIter<(&'s Field, Value<'s, 'buf>)>,
+
+    // The field whose name was just read, and whose value is next.
+    value:  Option<(&'s Field, Value<'s, 'buf>)>,
+}
+
+impl<'de, 's> de::MapAccess<'de> for MapAccess<'s, 'de> {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>> {
+        match self.fields.next() {
+            Some((f, v)) => {
+                self.value = Some((f, v));
+                seed.deserialize(name(&f.name)).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value> {
+        let (f, v) = self.value.take().expect("next_value_seed called before next_key_seed");
+        seed.deserialize(Deserializer::new(self.schema, v, Some(f.ty)))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.fields.len())
+    }
+}
+
+/// Reads a union as an enum, whose variant is named after the union's member.
+struct UnionAccess<'s, 'buf> {
+    schema: &'s Schema,
+    name:   &'s str,
+    union:  DynUnion<'s, 'buf>,
+}
+
+impl<'s, 'buf> UnionAccess<'s, 'buf> {
+    // The member's table, which newtype and struct variants are read from.
+    fn table(&self) -> Result<Deserializer<'s, 'buf>> {
+        match self.union.value() {
+            Some(t) => Ok(Deserializer::new(self.schema, Value::Table(t), None)),
+            None    => Err(Error::Message(format!("`{}` doesn't have a value", self.name))),
+        }
+    }
+}
+
+impl<'de, 's> de::EnumAccess<'de> for UnionAccess<'s, 'de> {
+    type Error   = Error;
+    type Variant = Self;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, Self)> {
+        let v = seed.deserialize(name(self.name))?;
+        Ok((v, self))
+    }
+}
+
+impl<'de, 's> de::VariantAccess<'de> for UnionAccess<'s, 'de> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<()> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value> {
+        seed.deserialize(self.table()?)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, _visitor: V) -> Result<V::Value> {
+        Err(Error::Unsupported("a tuple variant"))
+    }
+
+    fn struct_variant<V: Visitor<'de>>(self, _fields: &'static [&'static str], visitor: V)
+        -> Result<V::Value> {
+
+        visitor.visit_map(self.table()?.fields().unwrap())
+    }
+}
@@ -0,0 +1,416 @@
+// Copyright 2015 Sam Payson. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Conversion between buffers and types which implement serde's `Serialize` and `Deserialize`.
+//!
+//! `to_builder` serializes a value as a table of a schema's `root_type`. The value is turned into
+//! a `BuildValue` by a `Serializer`, then written by a `DynamicBuilder`, so it's matched up with
+//! the schema by field name. `from_buffer` and `from_table` go the other way, with a `Deserializer`
+//! which reads a table through `reflection`. `from_buffer` verifies the buffer first, so it's safe
+//! to use on untrusted buffers.
+//!
+//! Structs and maps with string keys become tables or structs, sequences become vectors, and unit
+//! variants become enum values, by name. A newtype or struct variant becomes a member of a union,
+//! with the variant's name choosing the member. `None` leaves a field out of the buffer, and when
+//! reading, fields which aren't in the buffer are left out, apart from scalars, which have their
+//! defaults.
+//!
+//! This module is only available with the `serde` feature.
+
+mod de;
+mod ser;
+
+pub use self::de::Deserializer;
+pub use self::ser::Serializer;
+
+use std::error;
+use std::fmt;
+use std::str;
+
+use serde_crate::{de as serde_de, ser as serde_ser, Deserialize, Serialize};
+
+use reflection::{self, BuildError, BuildValue, DynTable, DynamicBuilder};
+use schema::Schema;
+use verifier::VerifyError;
+use FlatBufferBuilder;
+
+/// The ways in which converting between a value and a buffer can fail.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// An error from a `Serialize` or `Deserialize` implementation, e.g. because a field is
+    /// missing or has the wrong type.
+    Message(String),
+
+    /// The value has a shape which can't be stored in a buffer, like a map with keys which aren't
+    /// strings, or a tuple variant.
+    Unsupported(&'static str),
+
+    /// The schema doesn't have a `root_type`.
+    NoRootType,
+
+    /// The value was serialized, but the buffer couldn't be built from it, e.g. because a field
+    /// doesn't exist in the schema.
+    Build(BuildError),
+
+    /// The buffer isn't a valid table of the schema's `root_type`.
+    Verify(VerifyError),
+
+    /// A string in the buffer isn't valid UTF-8.
+    Utf8(str::Utf8Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::Message(ref m)  => f.write_str(m),
+            Error::Unsupported(w)  => write!(f, "{} can't be stored in a flatbuffer", w),
+            Error::NoRootType      => write!(f, "the schema doesn't have a root type"),
+            Error::Build(ref e)    => e.fmt(f),
+            Error::Verify(ref e)   => write!(f, "invalid buffer: {}", e),
+            Error::Utf8(ref e)     => write!(f, "invalid string: {}", e),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            Error::Build(ref e)  => Some(e),
+            Error::Verify(ref e) => Some(e),
+            Error::Utf8(ref e)   => Some(e),
+            _                    => None,
+        }
+    }
+}
+
+impl From<BuildError> for Error {
+    fn from(e: BuildError) -> Error {
+        Error::Build(e)
+    }
+}
+
+impl From<VerifyError> for Error {
+    fn from(e: VerifyError) -> Error {
+        Error::Verify(e)
+    }
+}
+
+impl From<str::Utf8Error> for Error {
+    fn from(e: str::Utf8Error) -> Error {
+        Error::Utf8(e)
+    }
+}
+
+impl serde_ser::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Error {
+        Error::Message(msg.to_string())
+    }
+}
+
+impl serde_de::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Error {
+        Error::Message(msg.to_string())
+    }
+}
+
+/// Serialize `value` as a table of the schema's `root_type`, and finish the buffer with it. The
+/// buffer is finished with the schema's `file_identifier`, if it has one.
+pub fn to_builder<T: Serialize + ?Sized>(schema: &Schema, value: &T, fbb: &mut FlatBufferBuilder)
+    -> Result<(), Error> {
+
+    let fields = to_fields(value)?;
+
+    DynamicBuilder::new(schema, fbb).finish(&fields)?;
+
+    Ok(())
+}
+
+/// Serialize `value` as the fields of a table or struct, which can be given to a `DynamicBuilder`
+/// to build a table of any type.
+pub fn to_fields<T: Serialize + ?Sized>(value: &T) -> Result<Vec<(String, BuildValue)>, Error> {
+    match value.serialize(Serializer)? {
+        Some(BuildValue::Object(fields)) => Ok(fields),
+        _                                => Err(Error::Unsupported("a root which isn't a struct")),
+    }
+}
+
+/// Deserialize the root table of `buf`, read as the schema's `root_type`. The buffer is checked
+/// against the schema first, so malformed buffers are reported as errors rather than causing a
+/// panic.
+pub fn from_buffer<'buf, T: Deserialize<'buf>>(schema: &Schema, buf: &'buf [u8])
+    -> Result<T, Error> {
+
+    from_table(reflection::get_root_verified(schema, buf)?.ok_or(Error::NoRootType)?)
+}
+
+/// Deserialize a table which was read with `reflection`. This trusts the buffer that the table is
+/// in, so it should come from `reflection::get_root_verified`.
+pub fn from_table<'buf, T: Deserialize<'buf>>(table: DynTable<'_, 'buf>) -> Result<T, Error> {
+    T::deserialize(Deserializer::from_table(table))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::BTreeMap;
+
+    use schema;
+    use test_support::{for_each_corruption, for_each_truncation, read_root};
+
+    const SCHEMA: &str = r#"
+        enum Color : byte { Red, Green, Blue = 8 }
+
+        struct Vec3 { x: float; y: float; z: float; }
+
+        table Weapon { name: string; damage: short; }
+
+        table Shield { armor: int; }
+
+        union Equipment { Weapon, Shield }
+
+        table Monster {
+            pos:       Vec3;
+            hp:        short = 100;
+            name:      string (required);
+            inventory: [ubyte];
+            color:     Color = Blue;
+            weapons:   [Weapon];
+            equipped:  Equipment;
+            path:      [Vec3];
+        }
+
+        root_type Monster;
+    "#;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Vec3 { x: f32, y: f32, z: f32 }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    enum Color { Red, Green, Blue }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Weapon<'a> { name: &'a str, damage: i16 }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    enum Equipment<'a> {
+        #[serde(borrow)]
+        Weapon(Weapon<'a>),
+        Shield { armor: i32 },
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Monster<'a> {
+        pos:       Option<Vec3>,
+        hp:        i16,
+        name:      &'a str,
+        inventory: Vec<u8>,
+        color:     Color,
+        #[serde(borrow)]
+        weapons:   Option<Vec<Weapon<'a>>>,
+        #[serde(borrow)]
+        equipped:  Option<Equipment<'a>>,
+        path:      Option<Vec<Vec3>>,
+    }
+
+    fn orc() -> Monster<'static> {
+        Monster {
+            pos:       None,
+            hp:        100,
+            name:      "Orc",
+            inventory: vec![],
+            color:     Color::Blue,
+            weapons:   None,
+            equipped:  None,
+            path:      None,
+        }
+    }
+
+    fn build(monster: &Monster) -> Vec<u8> {
+        let schema  = schema::compile_str(SCHEMA).unwrap();
+        let mut fbb = FlatBufferBuilder::new(1);
+
+        to_builder(&schema, monster, &mut fbb).unwrap();
+        fbb.get_buffer().to_vec()
+    }
+
+    fn round_trip(monster: &Monster) {
+        let schema  = schema::compile_str(SCHEMA).unwrap();
+        let mut fbb = FlatBufferBuilder::new(1);
+
+        to_builder(&schema, monster, &mut fbb).unwrap();
+
+        let read: Monster = from_buffer(&schema, fbb.get_buffer()).unwrap();
+        assert_eq!(&read, monster);
+    }
+
+    #[test]
+    fn round_trip_every_kind_of_field() {
+        round_trip(&Monster {
+            pos:       Some(Vec3 { x: 1.0, y: 2.0, z: 3.0 }),
+            hp:        300,
+            name:      "Orc",
+            inventory: vec![1, 2, 3],
+            color:     Color::Red,
+            weapons:   Some(vec![Weapon { name: "Sword", damage: 3 },
+                                 Weapon { name: "Axe", damage: 5 }]),
+            equipped:  Some(Equipment::Weapon(Weapon { name: "Bow", damage: 2 })),
+            path:      Some(vec![Vec3 { x: 0.0, y: 0.0, z: 0.0 }, Vec3 { x: 1.0, y: 1.0, z: 1.0 }]),
+        });
+
+        round_trip(&Monster {
+            pos:       None,
+            hp:        100,
+            name:      "Goblin",
+            inventory: vec![],
+            color:     Color::Blue,
+            weapons:   None,
+            equipped:  Some(Equipment::Shield { armor: 7 }),
+            path:      None,
+        });
+    }
+
+    #[test]
+    fn defaults_are_read_for_missing_scalars() {
+        let schema  = schema::compile_str(SCHEMA).unwrap();
+        let mut fbb = FlatBufferBuilder::new(1);
+
+        let mut fields = BTreeMap::new();
+        fields.insert("name", "Troll");
+
+        to_builder(&schema, &fields, &mut fbb).unwrap();
+
+        #[derive(Deserialize)]
+        struct Partial { hp: i16, color: Color, pos: Option<Vec3> }
+
+        let read: Partial = from_buffer(&schema, fbb.get_buffer()).unwrap();
+        assert_eq!((read.hp, read.color, read.pos), (100, Color::Blue, None));
+    }
+
+    #[test]
+    fn unknown_fields_are_errors() {
+        let schema  = schema::compile_str(SCHEMA).unwrap();
+        let mut fbb = FlatBufferBuilder::new(1);
+
+        let mut fields = BTreeMap::new();
+        fields.insert("name", "Troll");
+        fields.insert("mana", "lots");
+
+        assert_eq!(to_builder(&schema, &fields, &mut fbb),
+                   Err(Error::Build(BuildError::UnknownField { object: "Monster".into(),
+                                                               field:  "mana".into() })));
+    }
+
+    #[test]
+    fn unions_are_chosen_by_variant_name() {
+        let schema = schema::compile_str(SCHEMA).unwrap();
+        let buf    = build(&Monster { equipped: Some(Equipment::Shield { armor: 7 }), ..orc() });
+
+        let root = read_root(&schema, &buf);
+        assert_eq!(root.get("equipped_type").unwrap().as_u64(), Some(2));
+
+        let read: Monster = from_buffer(&schema, &buf).unwrap();
+        assert_eq!(read.equipped, Some(Equipment::Shield { armor: 7 }));
+
+        // A type which only has some of the union's members can read those, but not the others.
+        #[derive(Deserialize)]
+        enum WeaponOnly<'a> {
+            #[serde(borrow)]
+            Weapon(Weapon<'a>),
+        }
+
+        #[derive(Deserialize)]
+        struct Armed<'a> {
+            #[serde(borrow)]
+            equipped: Option<WeaponOnly<'a>>,
+        }
+
+        let bow   = Equipment::Weapon(Weapon { name: "Bow", damage: 2 });
+        let bow   = build(&Monster { equipped: Some(bow), ..orc() });
+        let armed = from_buffer::<Armed>(&schema, &bow).unwrap();
+        assert!(matches!(armed.equipped, Some(WeaponOnly::Weapon(Weapon { name: "Bow", .. }))));
+
+        match from_buffer::<Armed>(&schema, &buf).err() {
+            Some(Error::Message(m)) => assert!(m.contains("`Shield`"), "{}", m),
+            e                       => panic!("{:?}", e),
+        }
+
+        #[derive(Serialize)]
+        enum Headgear { Helmet { armor: i32 } }
+
+        #[derive(Serialize)]
+        struct Helmeted { name: &'static str, equipped: Headgear }
+
+        let mut fbb = FlatBufferBuilder::new(1);
+        let helmet  = Helmeted { name: "Orc", equipped: Headgear::Helmet { armor: 1 } };
+        assert_eq!(to_builder(&schema, &helmet, &mut fbb),
+                   Err(Error::Build(BuildError::UnknownEnumValue { field: "Monster.equipped".into(),
+                                                                   value: "Helmet".into() })));
+    }
+
+    #[test]
+    fn vectors_of_tables_keep_their_order() {
+        let weapons = vec![Weapon { name: "Sword", damage: 3 }, Weapon { name: "Axe", damage: 0 },
+                           Weapon { name: "Bow", damage: -2 }];
+
+        round_trip(&Monster { weapons: Some(weapons), ..orc() });
+        round_trip(&Monster { weapons: Some(vec![]), ..orc() });
+
+        let schema = schema::compile_str(SCHEMA).unwrap();
+        let buf    = build(&Monster { weapons: Some(vec![Weapon { name: "Club", damage: 0 }]),
+                                      ..orc() });
+
+        // Fields which are left out of a table are read as their defaults, in each element.
+        let weapons = read_root(&schema, &buf).get("weapons").unwrap().as_vector().unwrap();
+        let club    = weapons.get(0).unwrap().as_table().unwrap();
+        assert!(!club.is_present(club.object().field_by_name("damage").unwrap()));
+
+        #[derive(Deserialize)]
+        struct Damage { damage: i16 }
+
+        #[derive(Deserialize)]
+        struct Damages { weapons: Vec<Damage> }
+
+        let read: Damages = from_buffer(&schema, &buf).unwrap();
+        assert_eq!(read.weapons.iter().map(|w| w.damage).collect::<Vec<_>>(), [0]);
+    }
+
+    #[test]
+    fn malformed_buffers_are_errors() {
+        let schema = schema::compile_str(SCHEMA).unwrap();
+        let buf    = build(&Monster {
+            pos:       Some(Vec3 { x: 1.0, y: 2.0, z: 3.0 }),
+            inventory: vec![1, 2, 3],
+            weapons:   Some(vec![Weapon { name: "Sword", damage: 3 }]),
+            equipped:  Some(Equipment::Weapon(Weapon { name: "Bow", damage: 2 })),
+            path:      Some(vec![Vec3 { x: 0.0, y: 0.0, z: 0.0 }]),
+            ..orc()
+        });
+
+        for_each_truncation(&buf, |b| {
+            match from_buffer::<Monster>(&schema, b) {
+                Err(Error::Verify(_)) => {}
+                r                     => panic!("{} bytes: {:?}", b.len(), r),
+            }
+        });
+
+        // However a byte is corrupted, either an error is returned or the monster is read in full.
+        for_each_corruption(&buf, |b| {
+            let _ = from_buffer::<Monster>(&schema, b);
+        });
+
+        let rootless = schema::compile_str("table T { a: int; }").unwrap();
+        assert_eq!(from_buffer::<Monster>(&rootless, &buf), Err(Error::NoRootType));
+    }
+}
@@ -0,0 +1,291 @@
+// Copyright 2015 Sam Payson. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Serializing values as `BuildValue`s.
+
+use serde_crate::ser::{self, Serialize};
+
+use reflection::BuildValue;
+
+use super::Error;
+
+/// A serde `Serializer` which turns a value into a `BuildValue`, for a `DynamicBuilder` to write.
+/// Values which should be left out of the buffer, like `None` and `()`, are serialized as `None`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Serializer;
+
+type Result<T> = ::std::result::Result<T, Error>;
+
+// Serialize an element of a vector, which can't be left out.
+fn element<T: Serialize + ?Sized>(value: &T) -> Result<BuildValue> {
+    value.serialize(Serializer)?.ok_or(Error::Unsupported("a vector element which is `None`"))
+}
+
+impl ser::Serializer for Serializer {
+    type Ok                     = Option<BuildValue>;
+    type Error                  = Error;
+    type SerializeSeq           = SerializeVector;
+    type SerializeTuple         = SerializeVector;
+    type SerializeTupleStruct   = SerializeVector;
+    type SerializeTupleVariant  = ser::Impossible<Option<BuildValue>, Error>;
+    type SerializeMap           = SerializeObject;
+    type SerializeStruct        = SerializeObject;
+    type SerializeStructVariant = SerializeObject;
+
+    fn serialize_bool(self, v: bool) -> Result<Option<BuildValue>> {
+        Ok(Some(BuildValue::Bool(v)))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Option<BuildValue>> { self.serialize_i64(v as i64) }
+
+    fn serialize_i16(self, v: i16) -> Result<Option<BuildValue>> { self.serialize_i64(v as i64) }
+
+    fn serialize_i32(self, v: i32) -> Result<Option<BuildValue>> { self.serialize_i64(v as i64) }
+
+    fn serialize_i64(self, v: i64) -> Result<Option<BuildValue>> {
+        Ok(Some(BuildValue::Int(v)))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Option<BuildValue>> { self.serialize_u64(v as u64) }
+
+    fn serialize_u16(self, v: u16) -> Result<Option<BuildValue>> { self.serialize_u64(v as u64) }
+
+    fn serialize_u32(self, v: u32) -> Result<Option<BuildValue>> { self.serialize_u64(v as u64) }
+
+    fn serialize_u64(self, v: u64) -> Result<Option<BuildValue>> {
+        Ok(Some(BuildValue::UInt(v)))
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Option<BuildValue>> { self.serialize_f64(v as f64) }
+
+    fn serialize_f64(self, v: f64) -> Result<Option<BuildValue>> {
+        Ok(Some(BuildValue::Float(v)))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Option<BuildValue>> {
+        Ok(Some(BuildValue::Str(v.to_string())))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Option<BuildValue>> {
+        Ok(Some(BuildValue::from(v)))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Option<BuildValue>> {
+        Ok(Some(BuildValue::Vector(v.iter().map(|&b| BuildValue::UInt(b as u64)).collect())))
+    }
+
+    fn serialize_none(self) -> Result<Option<BuildValue>> {
+        Ok(None)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Option<BuildValue>> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Option<BuildValue>> {
+        Ok(None)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Option<BuildValue>> {
+        Ok(None)
+    }
+
+    /// Unit variants are enum values, which are written by name.
+    fn serialize_unit_variant(self, _name: &'static str, _index: u32, variant: &'static str)
+        -> Result<Option<BuildValue>> {
+
+        Ok(Some(BuildValue::from(variant)))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(self, _name: &'static str, value: &T)
+        -> Result<Option<BuildValue>> {
+
+        value.serialize(self)
+    }
+
+    /// Newtype variants are members of a union, and must hold a struct or map for the member's
+    /// table.
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(self, _name: &'static str, _index: u32,
+                                                        variant: &'static str, value: &T)
+        -> Result<Option<BuildValue>> {
+
+        match value.serialize(self)? {
+            Some(BuildValue::Object(fields)) => {
+                Ok(Some(BuildValue::Union(variant.to_string(), fields)))
+            }
+            _ => Err(Error::Unsupported("a newtype variant which doesn't hold a struct")),
+        }
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<SerializeVector> {
+        Ok(SerializeVector(Vec::with_capacity(len.unwrap_or(0))))
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SerializeVector> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> Result<SerializeVector> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(self, _name: &'static str, _index: u32, _variant: &'static str,
+                               _len: usize)
+        -> Result<ser::Impossible<Option<BuildValue>, Error>> {
+
+        Err(Error::Unsupported("a tuple variant"))
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<SerializeObject> {
+        Ok(SerializeObject {
+            fields:  Vec::with_capacity(len.unwrap_or(0)),
+            key:     None,
+            variant: None,
+        })
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<SerializeObject> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(self, _name: &'static str, _index: u32, variant: &'static str,
+                                len: usize) -> Result<SerializeObject> {
+
+        let mut obj = self.serialize_map(Some(len))?;
+        obj.variant = Some(variant);
+        Ok(obj)
+    }
+}
+
+/// Collects the elements of a sequence or tuple into a `BuildValue::Vector`.
+pub struct SerializeVector(Vec<BuildValue>);
+
+impl SerializeVector {
+    fn push<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.0.push(element(value)?);
+        Ok(())
+    }
+
+    fn finish(self) -> Result<Option<BuildValue>> {
+        Ok(Some(BuildValue::Vector(self.0)))
+    }
+}
+
+impl ser::SerializeSeq for SerializeVector {
+    type Ok    = Option<BuildValue>;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Option<BuildValue>> { self.finish() }
+}
+
+impl ser::SerializeTuple for SerializeVector {
+    type Ok    = Option<BuildValue>;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Option<BuildValue>> { self.finish() }
+}
+
+impl ser::SerializeTupleStruct for SerializeVector {
+    type Ok    = Option<BuildValue>;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Option<BuildValue>> { self.finish() }
+}
+
+/// Collects the fields of a struct or map into a `BuildValue::Object`, or a `BuildValue::Union`
+/// for a struct variant.
+pub struct SerializeObject {
+    fields:  Vec<(String, BuildValue)>,
+
+    // The key of a map entry whose value hasn't been serialized yet.
+    key:     Option<String>,
+    variant: Option<&'static str>,
+}
+
+impl SerializeObject {
+    fn field<T: Serialize + ?Sized>(&mut self, key: String, value: &T) -> Result<()> {
+        if let Some(v) = value.serialize(Serializer)? {
+            self.fields.push((key, v));
+        }
+
+        Ok(())
+    }
+
+    fn finish(self) -> Result<Option<BuildValue>> {
+        Ok(Some(match self.variant {
+            Some(v) => BuildValue::Union(v.to_string(), self.fields),
+            None    => BuildValue::Object(self.fields),
+        }))
+    }
+}
+
+impl ser::SerializeMap for SerializeObject {
+    type Ok    = Option<BuildValue>;
+    type Error = Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<()> {
+        match key.serialize(Serializer)? {
+            Some(BuildValue::Str(k)) => {
+                self.key = Some(k);
+                Ok(())
+            }
+            _ => Err(Error::Unsupported("a map whose keys aren't strings")),
+        }
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        let key = self.key.take().expect("serialize_value called before serialize_key");
+        self.field(key, value)
+    }
+
+    fn end(self) -> Result<Option<BuildValue>> { self.finish() }
+}
+
+impl ser::SerializeStruct for SerializeObject {
+    type Ok    = Option<BuildValue>;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T)
+        -> Result<()> {
+
+        self.field(key.to_string(), value)
+    }
+
+    fn end(self) -> Result<Option<BuildValue>> { self.finish() }
+}
+
+impl ser::SerializeStructVariant for SerializeObject {
+    type Ok    = Option<BuildValue>;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T)
+        -> Result<()> {
+
+        self.field(key.to_string(), value)
+    }
+
+    fn end(self) -> Result<Option<BuildValue>> { self.finish() }
+}
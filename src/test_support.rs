@@ -0,0 +1,54 @@
+// Copyright 2015 Sam Payson. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Helpers shared by the tests of the modules which work from a schema at runtime.
+
+use reflection::{self, DynTable};
+use schema::Schema;
+use FlatBufferBuilder;
+
+/// Build a buffer with `build`, forcing defaults to be written if asked to, and copy it out.
+pub fn try_build<E, F>(force_defaults: bool, build: F) -> Result<Vec<u8>, E>
+    where F: FnOnce(&mut FlatBufferBuilder) -> Result<(), E> {
+
+    let mut fbb = FlatBufferBuilder::new(256);
+    fbb.force_defaults(force_defaults);
+    build(&mut fbb)?;
+
+    Ok(fbb.finished_data().unwrap().as_bytes().to_vec())
+}
+
+/// Read the root table of `buf`, which must be valid for `schema`.
+pub fn read_root<'s, 'buf>(schema: &'s Schema, buf: &'buf [u8]) -> DynTable<'s, 'buf> {
+    reflection::get_root_verified(schema, buf).unwrap().unwrap()
+}
+
+/// Call `f` with each prefix of `buf` which is missing at least one byte.
+pub fn for_each_truncation<F: FnMut(&[u8])>(buf: &[u8], mut f: F) {
+    for len in 0..buf.len() {
+        f(&buf[..len]);
+    }
+}
+
+/// Call `f` with copies of `buf` which have had one byte corrupted, for each byte, in a few ways:
+/// flipping the lowest, a middle and the highest bit, then all of them.
+pub fn for_each_corruption<F: FnMut(&[u8])>(buf: &[u8], mut f: F) {
+    for i in 0..buf.len() {
+        for &bits in [0x01, 0x04, 0x80, 0xff].iter() {
+            let mut corrupt = buf.to_vec();
+            corrupt[i] ^= bits;
+            f(&corrupt);
+        }
+    }
+}
@@ -0,0 +1,838 @@
+// Copyright 2015 Sam Payson. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Checking of untrusted buffers.
+//!
+//! The accessors in this crate panic when they're asked to read outside of their buffer, so a buffer
+//! which came from somewhere you don't trust should be run through a `Verifier` before anything
+//! reads from it. The generated code for each table implements `Verify`, which describes the
+//! table's fields to a `TableVerifier`.
+
+use core::fmt;
+use core::mem;
+#[cfg(feature = "std")]
+use std::error;
+
+use {Endian, SOffset, UOffset, View, VOffset};
+
+/// The ways in which a buffer can fail verification. Each variant records the position (as a byte
+/// index into the buffer) at which the problem was found.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum VerifyError {
+    /// A read of `len` bytes at `pos` would run past the end of the buffer.
+    OutOfBounds { pos: usize, len: usize },
+
+    /// A value at `pos` isn't aligned to `align` bytes.
+    Unaligned { pos: usize, align: usize },
+
+    /// The offset stored at `pos` is zero, or points outside of the buffer.
+    BadOffset { pos: usize },
+
+    /// The vtable at `pos` has an impossible size.
+    BadVTable { pos: usize },
+
+    /// The string at `pos` isn't terminated by a *NUL* byte.
+    MissingNulTerminator { pos: usize },
+
+    /// The table at `pos` doesn't have the required field `field`.
+    MissingRequiredField { pos: usize, field: VOffset },
+
+    /// The table at `pos` is nested more deeply than the verifier allows.
+    DepthLimitReached { pos: usize },
+
+    /// The table at `pos` pushed the total number of tables past the verifier's limit.
+    TableLimitReached { pos: usize },
+
+    /// The file identifier at `pos` isn't the one that was expected.
+    BadIdentifier { pos: usize },
+
+    /// The vector of union values at `pos` has a different length than its vector of types.
+    UnionLengthMismatch { pos: usize },
+}
+
+impl VerifyError {
+    /// The position in the buffer at which verification failed.
+    pub fn pos(&self) -> usize {
+        match *self {
+            VerifyError::OutOfBounds { pos, .. }          => pos,
+            VerifyError::Unaligned { pos, .. }            => pos,
+            VerifyError::BadOffset { pos }                => pos,
+            VerifyError::BadVTable { pos }                => pos,
+            VerifyError::MissingNulTerminator { pos }     => pos,
+            VerifyError::MissingRequiredField { pos, .. } => pos,
+            VerifyError::DepthLimitReached { pos }        => pos,
+            VerifyError::TableLimitReached { pos }        => pos,
+            VerifyError::BadIdentifier { pos }            => pos,
+            VerifyError::UnionLengthMismatch { pos }      => pos,
+        }
+    }
+}
+
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            VerifyError::OutOfBounds { pos, len } =>
+                write!(f, "reading {} bytes at {} runs past the end of the buffer", len, pos),
+            VerifyError::Unaligned { pos, align } =>
+                write!(f, "value at {} is not aligned to {} bytes", pos, align),
+            VerifyError::BadOffset { pos } =>
+                write!(f, "offset at {} does not point into the buffer", pos),
+            VerifyError::BadVTable { pos } =>
+                write!(f, "vtable at {} has an invalid size", pos),
+            VerifyError::MissingNulTerminator { pos } =>
+                write!(f, "string at {} is not NUL-terminated", pos),
+            VerifyError::MissingRequiredField { pos, field } =>
+                write!(f, "table at {} is missing required field at vtable offset {}", pos, field),
+            VerifyError::DepthLimitReached { pos } =>
+                write!(f, "table at {} exceeds the maximum nesting depth", pos),
+            VerifyError::TableLimitReached { pos } =>
+                write!(f, "table at {} exceeds the maximum number of tables", pos),
+            VerifyError::BadIdentifier { pos } =>
+                write!(f, "file identifier at {} does not match", pos),
+            VerifyError::UnionLengthMismatch { pos } =>
+                write!(f, "union vector at {} does not match the length of its types", pos),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl error::Error for VerifyError {}
+
+/// A trait for types which know how to check their own layout in a buffer. The generated code
+/// implements this for every table type.
+pub trait Verify {
+    /// Check the object which begins at byte `pos` of the verifier's buffer.
+    fn verify(v: &mut Verifier, pos: usize) -> Result<(), VerifyError>;
+}
+
+/// Walks a buffer, checking that every access the generated accessors might make is in-bounds and
+/// properly aligned.
+pub struct Verifier<'buf> {
+    buf:             &'buf [u8],
+    depth:           usize,
+    num_tables:      usize,
+    max_depth:       usize,
+    max_tables:      usize,
+    check_alignment: bool,
+    origin:          usize,
+}
+
+impl<'buf> Verifier<'buf> {
+    pub fn new(buf: &'buf [u8]) -> Verifier<'buf> {
+        Verifier {
+            buf,
+            depth:           0,
+            num_tables:      0,
+            max_depth:       64,
+            max_tables:      1000000,
+            check_alignment: true,
+            origin:          0,
+        }
+    }
+
+    /// Set the maximum depth to which tables may be nested (the default is 64).
+    pub fn max_depth(&mut self, depth: usize) {
+        self.max_depth = depth;
+    }
+
+    /// Set the maximum number of tables which a buffer may contain (the default is 1000000). This
+    /// bounds the amount of work done for buffers where many offsets point at the same table.
+    pub fn max_tables(&mut self, tables: usize) {
+        self.max_tables = tables;
+    }
+
+    /// Determines whether or not values must be aligned relative to the start of the buffer. A
+    /// builder always aligns what it writes, so misaligned values are a sign of a corrupt buffer.
+    /// The buffer itself can be anywhere in memory, since the accessors copy values out of it.
+    pub fn check_alignment(&mut self, check: bool) {
+        self.check_alignment = check;
+    }
+
+    /// The buffer being verified.
+    pub fn buffer(&self) -> &'buf [u8] { self.buf }
+
+    /// Verify the whole buffer, treating its root object as a `T`.
+    pub fn verify_root<T: Verify>(&mut self) -> Result<(), VerifyError> {
+        let root = self.verify_offset(0)?;
+        T::verify(self, root)
+    }
+
+    /// Like `verify_root`, but also check that the buffer has the file identifier `ident`.
+    pub fn verify_root_with_identifier<T: Verify>(&mut self, ident: &str) -> Result<(), VerifyError> {
+        let pos = mem::size_of::<UOffset>();
+
+        if !::buffer_has_identifier(self.buf, ident) {
+            return Err(VerifyError::BadIdentifier { pos })
+        }
+
+        self.verify_root::<T>()
+    }
+
+    /// Check that `len` bytes starting at `pos` are inside the buffer.
+    pub fn in_bounds(&self, pos: usize, len: usize) -> Result<(), VerifyError> {
+        match pos.checked_add(len) {
+            Some(end) if end <= self.buf.len() => Ok(()),
+            _                                  => Err(VerifyError::OutOfBounds { pos, len }),
+        }
+    }
+
+    /// Check that `pos` is aligned to `align` bytes, counting from the start of the buffer (if
+    /// alignment checking is turned on).
+    pub fn is_aligned(&self, pos: usize, align: usize) -> Result<(), VerifyError> {
+        if !self.check_alignment || (self.origin + pos) & (align - 1) == 0 {
+            Ok(())
+        } else {
+            Err(VerifyError::Unaligned { pos, align })
+        }
+    }
+
+    /// Check that a `T` stored inline at `pos` is in-bounds and aligned.
+    pub fn verify_scalar<T>(&self, pos: usize) -> Result<(), VerifyError> {
+        self.is_aligned(pos, mem::align_of::<T>())?;
+        self.in_bounds(pos, mem::size_of::<T>())
+    }
+
+    // Read a little-endian `T` from `pos`. The caller must have checked that it's in-bounds.
+    fn read<T: Endian>(&self, pos: usize) -> T {
+        T::read_le(&self.buf[pos..])
+    }
+
+    /// Check the `UOffset` stored at `pos`, and return the position that it refers to.
+    pub fn verify_offset(&self, pos: usize) -> Result<usize, VerifyError> {
+        self.verify_scalar::<UOffset>(pos)?;
+
+        let off = self.read::<UOffset>(pos) as usize;
+
+        // Offsets are unsigned in the format, but C++ treats them as signed, so a buffer with an
+        // offset above 2^31 couldn't have been produced by any builder.
+        if off == 0 || off > (SOffset::MAX as usize) {
+            return Err(VerifyError::BadOffset { pos })
+        }
+
+        match pos.checked_add(off) {
+            Some(target) if target < self.buf.len() => Ok(target),
+            _                                       => Err(VerifyError::BadOffset { pos }),
+        }
+    }
+
+    /// Check the header of the table at `pos` (its vtable, and that the table's inline data fits
+    /// in the buffer). The returned `TableVerifier` is used to check the table's fields, and
+    /// `TableVerifier::end` must be called when that's done.
+    pub fn verify_table<'v>(&'v mut self, pos: usize)
+        -> Result<TableVerifier<'v, 'buf>, VerifyError> {
+
+        self.depth      += 1;
+        self.num_tables += 1;
+
+        if self.depth > self.max_depth {
+            return Err(VerifyError::DepthLimitReached { pos })
+        }
+
+        if self.num_tables > self.max_tables {
+            return Err(VerifyError::TableLimitReached { pos })
+        }
+
+        self.verify_scalar::<SOffset>(pos)?;
+
+        let vtable = (pos as isize) - (self.read::<SOffset>(pos) as isize);
+
+        if vtable < 0 {
+            return Err(VerifyError::BadOffset { pos })
+        }
+
+        let vtable = vtable as usize;
+
+        self.verify_scalar::<VOffset>(vtable)?;
+
+        let vtsize = self.read::<VOffset>(vtable);
+
+        if vtsize & 1 != 0 || (vtsize as usize) < 2 * mem::size_of::<VOffset>() {
+            return Err(VerifyError::BadVTable { pos: vtable })
+        }
+
+        self.in_bounds(vtable, vtsize as usize)?;
+
+        let tsize = self.read::<VOffset>(vtable + mem::size_of::<VOffset>());
+
+        self.in_bounds(pos, tsize as usize)?;
+
+        Ok(TableVerifier {
+            verifier: self,
+            pos,
+            vtable,
+            vtsize,
+        })
+    }
+
+    /// Check the header of a vector at `pos` whose elements are `elem_size` bytes, and return the
+    /// vector's length. The elements themselves aren't checked, which is left to the caller for
+    /// vectors whose element type is only known at runtime.
+    pub fn verify_vector_header(&self, pos: usize, elem_size: usize) -> Result<usize, VerifyError> {
+        self.verify_scalar::<UOffset>(pos)?;
+
+        let len  = self.read::<UOffset>(pos) as usize;
+        let data = pos + mem::size_of::<UOffset>();
+
+        match len.checked_mul(elem_size) {
+            Some(size) => self.in_bounds(data, size)?,
+            None       => return Err(VerifyError::OutOfBounds { pos: data, len: usize::MAX }),
+        }
+
+        Ok(len)
+    }
+
+    /// Check a vector at `pos` whose elements are inline `T`s (scalars or structs).
+    pub fn verify_vector<T>(&self, pos: usize) -> Result<(), VerifyError> {
+        self.verify_vector_header(pos, mem::size_of::<T>())?;
+        self.is_aligned(pos + mem::size_of::<UOffset>(), mem::align_of::<T>())
+    }
+
+    /// Check a string at `pos`, including its *NUL* terminator.
+    pub fn verify_string(&self, pos: usize) -> Result<(), VerifyError> {
+        let len = self.verify_vector_header(pos, 1)?;
+        let nul = pos + mem::size_of::<UOffset>() + len;
+
+        self.in_bounds(nul, 1)?;
+
+        if self.buf[nul] != 0 {
+            return Err(VerifyError::MissingNulTerminator { pos })
+        }
+
+        Ok(())
+    }
+
+    /// Check a vector at `pos` whose elements are offsets to tables of type `T`.
+    pub fn verify_vector_of_tables<T: Verify>(&mut self, pos: usize) -> Result<(), VerifyError> {
+        let len = self.verify_vector_header(pos, mem::size_of::<UOffset>())?;
+
+        for i in 0..len {
+            let elem = self.verify_offset(pos + (i + 1) * mem::size_of::<UOffset>())?;
+            T::verify(self, elem)?;
+        }
+
+        Ok(())
+    }
+
+    /// Check a vector at `pos` whose elements are offsets to strings.
+    pub fn verify_vector_of_strings(&self, pos: usize) -> Result<(), VerifyError> {
+        let len = self.verify_vector_header(pos, mem::size_of::<UOffset>())?;
+
+        for i in 0..len {
+            let elem = self.verify_offset(pos + (i + 1) * mem::size_of::<UOffset>())?;
+            self.verify_string(elem)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Checks the fields of a single table. Fields are identified by their vtable offset, the same way
+/// that `Table::get_field` and friends identify them.
+pub struct TableVerifier<'v, 'buf: 'v> {
+    verifier: &'v mut Verifier<'buf>,
+    pos:      usize,
+    vtable:   usize,
+    vtsize:   VOffset,
+}
+
+impl<'v, 'buf> TableVerifier<'v, 'buf> {
+    /// The position of the table being checked.
+    pub fn pos(&self) -> usize { self.pos }
+
+    /// The underlying `Verifier`, for checking things this type doesn't know about.
+    pub fn verifier(&mut self) -> &mut Verifier<'buf> { self.verifier }
+
+    /// Return the position of `field`'s inline data, or `None` if it isn't present in the table.
+    pub fn field_pos(&self, field: VOffset) -> Option<usize> {
+        if field < self.vtsize {
+            let voff = self.verifier.read::<VOffset>(self.vtable + field as usize);
+            if voff != 0 {
+                return Some(self.pos + voff as usize)
+            }
+        }
+
+        None
+    }
+
+    fn required_field_pos(&self, field: VOffset, required: bool)
+        -> Result<Option<usize>, VerifyError> {
+
+        match self.field_pos(field) {
+            None if required => Err(VerifyError::MissingRequiredField { pos: self.pos, field }),
+            p                => Ok(p),
+        }
+    }
+
+    // Like `required_field_pos`, but follows the offset stored in the field.
+    fn required_field_target(&self, field: VOffset, required: bool)
+        -> Result<Option<usize>, VerifyError> {
+
+        match self.required_field_pos(field, required)? {
+            Some(p) => self.verifier.verify_offset(p).map(Some),
+            None    => Ok(None),
+        }
+    }
+
+    /// Check a field which is stored inline in the table as a `T` (a scalar or a struct).
+    pub fn visit_field<T>(&mut self, field: VOffset, required: bool) -> Result<(), VerifyError> {
+        match self.required_field_pos(field, required)? {
+            Some(p) => self.verifier.verify_scalar::<T>(p),
+            None    => Ok(()),
+        }
+    }
+
+    /// Check a string field.
+    pub fn visit_string(&mut self, field: VOffset, required: bool) -> Result<(), VerifyError> {
+        match self.required_field_target(field, required)? {
+            Some(p) => self.verifier.verify_string(p),
+            None    => Ok(()),
+        }
+    }
+
+    /// Check a field which is a vector of inline `T`s (scalars or structs).
+    pub fn visit_vector<T>(&mut self, field: VOffset, required: bool) -> Result<(), VerifyError> {
+        match self.required_field_target(field, required)? {
+            Some(p) => self.verifier.verify_vector::<T>(p),
+            None    => Ok(()),
+        }
+    }
+
+    /// Check a field which refers to a sub-table of type `T`.
+    pub fn visit_table<T: Verify>(&mut self, field: VOffset, required: bool)
+        -> Result<(), VerifyError> {
+
+        match self.required_field_target(field, required)? {
+            Some(p) => T::verify(self.verifier, p),
+            None    => Ok(()),
+        }
+    }
+
+    /// Check a field which is a vector of tables of type `T`.
+    pub fn visit_vector_of_tables<T: Verify>(&mut self, field: VOffset, required: bool)
+        -> Result<(), VerifyError> {
+
+        match self.required_field_target(field, required)? {
+            Some(p) => self.verifier.verify_vector_of_tables::<T>(p),
+            None    => Ok(()),
+        }
+    }
+
+    /// Check a field which is a vector of strings.
+    pub fn visit_vector_of_strings(&mut self, field: VOffset, required: bool)
+        -> Result<(), VerifyError> {
+
+        match self.required_field_target(field, required)? {
+            Some(p) => self.verifier.verify_vector_of_strings(p),
+            None    => Ok(()),
+        }
+    }
+
+    /// Check a union, made up of a `u8` type tag in `type_field` and an offset to the value in
+    /// `value_field`. If the union is set, `verify_value` is called with the tag and the position
+    /// of the value, and should check the value according to the tag.
+    pub fn visit_union<F>(&mut self, type_field: VOffset, value_field: VOffset, required: bool,
+                          verify_value: F) -> Result<(), VerifyError>
+        where F: FnOnce(&mut Verifier<'buf>, u8, usize) -> Result<(), VerifyError> {
+
+        self.visit_field::<u8>(type_field, required)?;
+
+        let tag = match self.field_pos(type_field) {
+            Some(p) => self.verifier.read::<u8>(p),
+            None    => 0,
+        };
+
+        match self.required_field_target(value_field, required || tag != 0)? {
+            Some(p) if tag != 0 => verify_value(self.verifier, tag, p),
+            _                   => Ok(()),
+        }
+    }
+
+    /// Check a vector of unions, made up of a vector of `u8` type tags in `types_field` and a
+    /// vector of offsets in `values_field`. `verify_value` is called for each element which is set,
+    /// in the same way as for `visit_union`.
+    pub fn visit_union_vector<F>(&mut self, types_field: VOffset, values_field: VOffset,
+                                 required: bool, mut verify_value: F) -> Result<(), VerifyError>
+        where F: FnMut(&mut Verifier<'buf>, u8, usize) -> Result<(), VerifyError> {
+
+        let types  = self.required_field_target(types_field, required)?;
+        let values = self.required_field_target(values_field, required || types.is_some())?;
+
+        let (types, values) = match (types, values) {
+            (Some(t), Some(v)) => (t, v),
+            (None, None)       => return Ok(()),
+            (None, Some(_))    => {
+                return Err(VerifyError::MissingRequiredField { pos: self.pos, field: types_field })
+            }
+            (Some(_), None)    => unreachable!(),
+        };
+
+        let len = self.verifier.verify_vector_header(types, mem::size_of::<u8>())?;
+
+        if self.verifier.verify_vector_header(values, mem::size_of::<UOffset>())? != len {
+            return Err(VerifyError::UnionLengthMismatch { pos: values })
+        }
+
+        for i in 0..len {
+            let tag = self.verifier.read::<u8>(types + mem::size_of::<UOffset>() + i);
+
+            if tag != 0 {
+                let elem = values + (i + 1) * mem::size_of::<UOffset>();
+                let pos  = self.verifier.verify_offset(elem)?;
+                verify_value(self.verifier, tag, pos)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Finish checking this table.
+    pub fn end(self) -> Result<(), VerifyError> {
+        self.verifier.depth -= 1;
+        Ok(())
+    }
+}
+
+/// Verify `buf`, and if it's valid return its root object, interpreted as a `T`.
+pub fn get_root_verified<'buf, T: Verify + View<'buf>>(buf: &'buf [u8]) -> Result<T, VerifyError> {
+    Verifier::new(buf).verify_root::<T>()?;
+    Ok(::get_root(buf))
+}
+
+/// Like `get_root_verified`, but also check that the buffer has the file identifier `ident`.
+pub fn get_root_verified_with_identifier<'buf, T: Verify + View<'buf>>(buf: &'buf [u8], ident: &str)
+    -> Result<T, VerifyError> {
+
+    Verifier::new(buf).verify_root_with_identifier::<T>(ident)?;
+    Ok(::get_root(buf))
+}
+
+// A verifier for the part of a size-prefixed buffer which follows the prefix. The builder aligned
+// everything from the start of the prefix, so that's where alignment is counted from.
+fn size_prefixed_verifier(buf: &[u8]) -> Result<Verifier<'_>, VerifyError> {
+    let prefix = mem::size_of::<UOffset>();
+    let v      = Verifier::new(buf);
+
+    v.verify_scalar::<UOffset>(0)?;
+
+    let size = v.read::<UOffset>(0) as usize;
+    v.in_bounds(prefix, size)?;
+
+    let mut v = Verifier::new(&buf[prefix..prefix + size]);
+    v.origin  = prefix;
+
+    Ok(v)
+}
+
+/// Like `get_root_verified`, for buffers which were finished with `finish_size_prefixed`. Positions
+/// in errors are relative to the end of the size prefix.
+pub fn get_size_prefixed_root_verified<'buf, T: Verify + View<'buf>>(buf: &'buf [u8])
+    -> Result<T, VerifyError> {
+
+    let mut v = size_prefixed_verifier(buf)?;
+    v.verify_root::<T>()?;
+    Ok(::get_root(v.buffer()))
+}
+
+/// Like `get_size_prefixed_root_verified`, but also check that the buffer has the file identifier
+/// `ident`.
+pub fn get_size_prefixed_root_verified_with_identifier<'buf, T: Verify + View<'buf>>(
+    buf: &'buf [u8], ident: &str) -> Result<T, VerifyError> {
+
+    let mut v = size_prefixed_verifier(buf)?;
+    v.verify_root_with_identifier::<T>(ident)?;
+    Ok(::get_root(v.buffer()))
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+    use super::*;
+
+    use alloc::vec::Vec;
+
+    use {FlatBufferBuilder, Offset, Table, UnionOffset, follow_offset};
+
+    const VT_HP:          VOffset = 4;
+    const VT_NAME:        VOffset = 6;
+    const VT_COUNTS:      VOffset = 8;
+    const VT_CHILD:       VOffset = 10;
+    const VT_THINGS_TYPE: VOffset = 12;
+    const VT_THINGS:      VOffset = 14;
+
+    // A table which looks like
+    //
+    //     table Node { hp: ushort; name: string (required); counts: [uint]; child: Node;
+    //                  things: [Thing]; }
+    //     union Thing { Node }
+    struct Node<'buf>(Table<'buf>);
+
+    impl<'buf> View<'buf> for Node<'buf> {
+        fn view(buf: &'buf [u8], pos: usize) -> Node<'buf> { Node(Table::view(buf, pos)) }
+    }
+
+    impl Verify for Node<'_> {
+        fn verify(v: &mut Verifier, pos: usize) -> Result<(), VerifyError> {
+            let mut t = v.verify_table(pos)?;
+            t.visit_field::<u16>(VT_HP, false)?;
+            t.visit_string(VT_NAME, true)?;
+            t.visit_vector::<u32>(VT_COUNTS, false)?;
+            t.visit_table::<Node>(VT_CHILD, false)?;
+            t.visit_union_vector(VT_THINGS_TYPE, VT_THINGS, false,
+                                 |v, _, pos| Node::verify(v, pos))?;
+            t.end()
+        }
+    }
+
+    #[derive(Clone, Copy)]
+    struct Thing(Offset<Table<'static>>);
+
+    impl UnionOffset for Thing {
+        fn tag(&self) -> u8 { 1 }
+
+        fn value(&self) -> Offset<Table<'static>> { self.0 }
+    }
+
+    // Memory which is aligned well enough for anything in a buffer.
+    #[repr(align(16))]
+    struct Aligned([u8; 512]);
+
+    // Verify `buf` as a `Node`, after copying it to memory which is aligned (or, if `shift` is
+    // non-zero, misaligned by `shift` bytes).
+    fn verify_shifted(buf: &[u8], shift: usize, setup: fn(&mut Verifier))
+        -> Result<(), VerifyError> {
+
+        let mut mem = Aligned([0; 512]);
+        mem.0[shift..][..buf.len()].copy_from_slice(buf);
+
+        let mut v = Verifier::new(&mem.0[shift..][..buf.len()]);
+        setup(&mut v);
+        v.verify_root::<Node>()
+    }
+
+    fn verify(buf: &[u8]) -> Result<(), VerifyError> {
+        verify_shifted(buf, 0, |_| {})
+    }
+
+    // Build a chain of `depth` nodes, each named "abc", where the root has some counts and a
+    // vector of `things` which all refer to the same node.
+    fn build(depth: usize, things: usize) -> Vec<u8> {
+        let mut fbb   = FlatBufferBuilder::new(1);
+        let mut child = None;
+
+        for i in 0..depth {
+            let name   = fbb.create_string("abc");
+            let counts = fbb.create_vector(&[1u32, 2, 3]);
+            let union  = if i + 1 == depth {
+                let leaf = {
+                    let name  = fbb.create_string("leaf");
+                    let start = fbb.start_table();
+                    fbb.add_offset(VT_NAME, name);
+                    Offset::new(fbb.end_table(start, 2))
+                };
+                let things: Vec<Thing> = (0..things).map(|_| Thing(leaf)).collect();
+                Some(fbb.create_union_vector(&things))
+            } else {
+                None
+            };
+
+            let start = fbb.start_table();
+            fbb.add_scalar(VT_HP, 100u16, 0);
+            fbb.add_offset(VT_NAME, name);
+            fbb.add_offset(VT_COUNTS, counts);
+            if let Some(c) = child {
+                fbb.add_offset(VT_CHILD, c);
+            }
+            if let Some(u) = union {
+                fbb.add_union_vector(VT_THINGS_TYPE, VT_THINGS, u);
+            }
+            child = Some(Offset::<Table>::new(fbb.end_table(start, 6)));
+        }
+
+        fbb.finish(child.unwrap());
+        fbb.get_buffer().to_vec()
+    }
+
+    // The position of the object that the root table's `field` refers to.
+    fn root_field_target(buf: &[u8], field: VOffset) -> usize {
+        let root = follow_offset(buf, 0);
+        follow_offset(buf, Table::view(buf, root).field_pos(field).unwrap())
+    }
+
+    #[test]
+    fn valid_buffers_verify() {
+        let buf = build(3, 2);
+        assert_eq!(verify(&buf), Ok(()));
+
+        let mut mem = Aligned([0; 512]);
+        mem.0[..buf.len()].copy_from_slice(&buf);
+        let node = get_root_verified::<Node>(&mem.0[..buf.len()]).unwrap();
+        assert_eq!(node.0.get_field::<u16>(VT_HP, 0), 100);
+    }
+
+    #[test]
+    fn out_of_bounds_reads_are_reported() {
+        assert_eq!(verify(&[4, 0]), Err(VerifyError::OutOfBounds { pos: 0, len: 4 }));
+
+        // Cutting the end off the buffer leaves the root table's inline data hanging off the end.
+        let buf  = build(1, 0);
+        let root = follow_offset(&buf, 0);
+        let err  = verify(&buf[..root + 6]).unwrap_err();
+        assert!(matches!(err, VerifyError::OutOfBounds { .. }), "{:?}", err);
+    }
+
+    #[test]
+    fn bad_offsets_are_reported() {
+        assert_eq!(verify(&[0, 0, 0, 0]), Err(VerifyError::BadOffset { pos: 0 }));
+        assert_eq!(verify(&[8, 0, 0, 0, 0, 0, 0, 0]), Err(VerifyError::BadOffset { pos: 0 }));
+        assert_eq!(verify(&[0, 0, 0, 0x80, 0, 0, 0, 0]), Err(VerifyError::BadOffset { pos: 0 }));
+
+        // A vtable which would be before the start of the buffer.
+        assert_eq!(verify(&[4, 0, 0, 0, 0x10, 0, 0, 0]), Err(VerifyError::BadOffset { pos: 4 }));
+    }
+
+    #[test]
+    fn bad_vtable_sizes_are_reported() {
+        let mut buf = build(1, 0);
+        let root    = follow_offset(&buf, 0);
+        let vtable  = root - i32::read_le(&buf[root..]) as usize;
+
+        for &size in &[0u16, 2, 7] {
+            size.write_le(&mut buf[vtable..]);
+            assert_eq!(verify(&buf), Err(VerifyError::BadVTable { pos: vtable }));
+        }
+    }
+
+    #[test]
+    fn strings_must_be_nul_terminated() {
+        let mut buf = build(1, 0);
+        let name    = root_field_target(&buf, VT_NAME);
+
+        buf[name + 4 + 3] = b'!';
+        assert_eq!(verify(&buf), Err(VerifyError::MissingNulTerminator { pos: name }));
+    }
+
+    #[test]
+    fn vector_lengths_must_fit_in_the_buffer() {
+        let mut buf = build(1, 0);
+        let counts  = root_field_target(&buf, VT_COUNTS);
+
+        for &len in &[0x4000_0000u32, u32::MAX] {
+            len.write_le(&mut buf[counts..]);
+            let err = verify(&buf).unwrap_err();
+            assert!(matches!(err, VerifyError::OutOfBounds { pos, .. } if pos == counts + 4),
+                    "{:?}", err);
+        }
+    }
+
+    #[test]
+    fn misaligned_values_are_reported() {
+        // Alignment is counted from the start of the buffer, so the buffer can be anywhere.
+        let mut buf = build(1, 0);
+        assert_eq!(verify_shifted(&buf, 1, |_| {}), Ok(()));
+
+        let root = follow_offset(&buf, 0);
+        (root as UOffset + 2).write_le(&mut buf);
+        assert_eq!(verify(&buf), Err(VerifyError::Unaligned { pos: root + 2, align: 4 }));
+    }
+
+    #[test]
+    fn size_prefixed_buffers_are_aligned_from_the_prefix() {
+        // A table which looks like `table Wide { x: ulong (required); }`.
+        struct Wide<'buf>(Table<'buf>);
+
+        impl<'buf> View<'buf> for Wide<'buf> {
+            fn view(buf: &'buf [u8], pos: usize) -> Wide<'buf> { Wide(Table::view(buf, pos)) }
+        }
+
+        impl Verify for Wide<'_> {
+            fn verify(v: &mut Verifier, pos: usize) -> Result<(), VerifyError> {
+                let mut t = v.verify_table(pos)?;
+                t.visit_field::<u64>(4, true)?;
+                t.end()
+            }
+        }
+
+        let mut fbb = FlatBufferBuilder::new(1);
+        let start   = fbb.start_table();
+        fbb.add_scalar(4, 7u64, 0);
+        let root    = Offset::<Table>::new(fbb.end_table(start, 1));
+        fbb.finish_size_prefixed(root);
+
+        let buf  = fbb.get_buffer();
+        let wide = get_size_prefixed_root_verified::<Wide>(buf).unwrap();
+        assert_eq!(wide.0.get_field::<u64>(4, 0), 7);
+    }
+
+    #[test]
+    fn nesting_is_limited() {
+        let buf = build(3, 0);
+        assert_eq!(verify_shifted(&buf, 0, |v| v.max_depth(3)), Ok(()));
+
+        let err = verify_shifted(&buf, 0, |v| v.max_depth(2)).unwrap_err();
+        assert!(matches!(err, VerifyError::DepthLimitReached { .. }), "{:?}", err);
+    }
+
+    #[test]
+    fn number_of_tables_is_limited() {
+        // The root, and the same leaf three times.
+        let buf = build(1, 3);
+        assert_eq!(verify_shifted(&buf, 0, |v| v.max_tables(4)), Ok(()));
+
+        let err = verify_shifted(&buf, 0, |v| v.max_tables(3)).unwrap_err();
+        assert!(matches!(err, VerifyError::TableLimitReached { .. }), "{:?}", err);
+    }
+
+    #[test]
+    fn union_vectors_must_have_matching_lengths() {
+        let mut fbb = FlatBufferBuilder::new(1);
+
+        let name   = fbb.create_string("abc");
+        let leaf   = {
+            let start = fbb.start_table();
+            fbb.add_offset(VT_NAME, name);
+            Offset::<Table>::new(fbb.end_table(start, 2))
+        };
+        let types  = fbb.create_vector(&[1u8, 1]);
+        let values = fbb.create_vector_of_offsets(&[leaf]);
+
+        let start = fbb.start_table();
+        fbb.add_offset(VT_NAME, name);
+        fbb.add_offset(VT_THINGS_TYPE, types);
+        fbb.add_offset(VT_THINGS, values);
+        let root = Offset::<Table>::new(fbb.end_table(start, 6));
+        fbb.finish(root);
+
+        let buf    = fbb.get_buffer().to_vec();
+        let values = root_field_target(&buf, VT_THINGS);
+        assert_eq!(verify(&buf), Err(VerifyError::UnionLengthMismatch { pos: values }));
+    }
+
+    #[test]
+    fn required_fields_and_identifiers_are_checked() {
+        let mut fbb = FlatBufferBuilder::new(1);
+        let start   = fbb.start_table();
+        fbb.add_scalar(VT_HP, 1u16, 0);
+        let root    = Offset::<Table>::new(fbb.end_table(start, 1));
+        fbb.finish_with_identifier(root, "NODE");
+
+        let buf  = fbb.get_buffer().to_vec();
+        let root = follow_offset(&buf, 0);
+        assert_eq!(verify(&buf),
+                   Err(VerifyError::MissingRequiredField { pos: root, field: VT_NAME }));
+
+        let mut mem = Aligned([0; 512]);
+        mem.0[..buf.len()].copy_from_slice(&buf);
+        let err = Verifier::new(&mem.0[..buf.len()]).verify_root_with_identifier::<Node>("EDGE");
+        assert_eq!(err, Err(VerifyError::BadIdentifier { pos: 4 }));
+    }
+}
@@ -0,0 +1,386 @@
+// Copyright 2015 Sam Payson. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Builds and reads buffers with the code generated for `codegen/monster.fbs`, which is checked in
+//! as `codegen/monster_generated.rs`. With `std`, this also checks that the checked-in code is what
+//! the generator currently produces; run with `FLATBUFFERS_BLESS=1` to update it.
+//!
+//! The crate is `no_std`, so that the generated code is shown to build without the `std` prelude.
+
+#![cfg(feature = "alloc")]
+#![no_std]
+
+extern crate alloc;
+extern crate flatbuffers;
+#[cfg(feature = "std")]
+extern crate std;
+
+#[allow(clippy::all)]
+mod monster_generated {
+    include!("codegen/monster_generated.rs");
+}
+
+use alloc::boxed::Box;
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use flatbuffers::{BuilderError, FlatBufferBuilder};
+
+use monster_generated::game::sample::*;
+
+#[cfg(feature = "std")]
+#[test]
+fn generated_code_is_up_to_date() {
+    use std::{env, format, fs};
+
+    let dir    = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/codegen");
+    let schema = flatbuffers::schema::compile(format!("{}/monster.fbs", dir), &[]).unwrap();
+    let code   = flatbuffers::codegen::generate(&schema);
+    let path   = format!("{}/monster_generated.rs", dir);
+
+    if env::var_os("FLATBUFFERS_BLESS").is_some() {
+        fs::write(&path, &code).unwrap();
+    }
+
+    assert!(fs::read_to_string(&path).unwrap() == code,
+            "{} is out of date; run the tests with FLATBUFFERS_BLESS=1 to update it", path);
+}
+
+fn vec3(x: f32) -> Vec3 { Vec3::new(x, x + 1.0, x + 2.0) }
+
+// Build a monster which has every field set, with one enemy which has only its name.
+fn build_monster(fbb: &mut FlatBufferBuilder) {
+    let enemy_name = fbb.create_string("Goblin");
+    let enemy = {
+        let mut b = MonsterBuilder::new(fbb);
+        b.add_name(enemy_name);
+        b.finish()
+    };
+
+    let mut weapons = Vec::new();
+    for &(name, damage) in &[("Sword", 3), ("Axe", 5), ("Bow", 2)] {
+        let name  = fbb.create_string(name);
+        let mut b = WeaponBuilder::new(fbb);
+        b.add_name(name);
+        b.add_damage(damage);
+        weapons.push(b.finish());
+    }
+    let weapons = fbb.create_vector_of_sorted_tables(&mut weapons);
+
+    let shield = {
+        let mut b = ShieldBuilder::new(fbb);
+        b.add_defense(7);
+        b.finish()
+    };
+    let loot = fbb.create_union_vector(&[EquipmentOffset::Shield(shield)]);
+
+    let name      = fbb.create_string("Orc");
+    let inventory = fbb.create_vector(&[1u8, 2, 3]);
+    let path      = fbb.create_vector_of_structs(&[vec3(1.0), vec3(4.0)]);
+    let tag_a     = fbb.create_string("big");
+    let tag_b     = fbb.create_string("green");
+    let tags      = fbb.create_vector_of_offsets(&[tag_a, tag_b]);
+
+    let mut b = MonsterBuilder::new(fbb);
+    b.add_pos(&vec3(0.5));
+    b.add_hp(300);
+    b.add_name(name);
+    b.add_inventory(inventory);
+    b.add_color(Color::RED);
+    b.add_flags(Flags(Flags::STRONG.0 | Flags::WISE.0));
+    b.add_weapons(weapons);
+    b.add_path(path);
+    b.add_equipped(EquipmentOffset::Shield(shield));
+    b.add_loot(loot);
+    b.add_tags(tags);
+    b.add_enemy(enemy);
+    b.add_route(&Path::new(&vec3(0.0), &vec3(9.0), 1.5));
+    let root = b.finish();
+
+    finish_monster_buffer(fbb, root);
+}
+
+#[test]
+fn builders_and_accessors_agree() {
+    let mut fbb = FlatBufferBuilder::new(1);
+    build_monster(&mut fbb);
+    let buf = fbb.get_buffer();
+
+    assert!(monster_buffer_has_identifier(buf));
+    let monster = get_root_as_monster_verified(buf).unwrap();
+
+    assert_eq!(monster.pos(), Some(vec3(0.5)));
+    assert_eq!(monster.mana(), 150);
+    assert_eq!(monster.hp(), 300);
+    assert_eq!(monster.name().unwrap().to_str(), Ok("Orc"));
+    assert_eq!(monster.inventory().unwrap().iter().collect::<Vec<_>>(), [1, 2, 3]);
+    assert_eq!(monster.color(), Color::RED);
+    assert_eq!(monster.flags(), Flags(6));
+    assert_eq!(monster.path().unwrap().iter().collect::<Vec<_>>(), [vec3(1.0), vec3(4.0)]);
+    assert_eq!(monster.route().unwrap().end().z(), 11.0);
+    assert_eq!(monster.route().unwrap().speed(), 1.5);
+
+    let tags: Vec<_> = monster.tags().unwrap().iter().map(|s| s.to_str().unwrap()).collect();
+    assert_eq!(tags, ["big", "green"]);
+
+    // The weapons are sorted by their `key`, so they can be looked up by name.
+    let weapons = monster.weapons().unwrap();
+    let names: Vec<_> = weapons.iter().map(|w| w.name().unwrap().to_str().unwrap()).collect();
+    assert_eq!(names, ["Axe", "Bow", "Sword"]);
+    assert_eq!(weapons.lookup_by_key_value("Bow").unwrap().damage(), 2);
+    assert!(weapons.lookup_by_key_value("Club").is_none());
+
+    assert_eq!(monster.equipped_type(), Equipment::SHIELD);
+    assert!(monster.equipped_as_weapon().is_none());
+    assert_eq!(monster.equipped_as_shield().unwrap().defense(), 7);
+    match monster.equipped() {
+        Some(EquipmentRef::Shield(s)) => assert_eq!(s.defense(), 7),
+        _                             => panic!("expected a shield"),
+    }
+
+    let loot = monster.loot().unwrap();
+    assert_eq!(loot.len(), 1);
+    assert_eq!(loot.get(0).unwrap().union_type(), Equipment::SHIELD);
+
+    let enemy = monster.enemy().unwrap();
+    assert_eq!(enemy.name().unwrap().to_str(), Ok("Goblin"));
+    assert_eq!(enemy.hp(), 100);
+    assert!(enemy.weapons().is_none());
+    assert!(enemy.equipped().is_none());
+}
+
+#[test]
+fn sorted_tables_can_be_looked_up_by_key() {
+    let mut fbb = FlatBufferBuilder::new(1);
+    build_monster(&mut fbb);
+    let weapons = get_root_as_monster_verified(fbb.get_buffer()).unwrap().weapons().unwrap();
+
+    // Keys are whole tables, so look the weapons up with the weapons of another monster.
+    let mut kb   = FlatBufferBuilder::new(1);
+    let mut keys = Vec::new();
+    for &name in &["Aardvark", "Axe", "Bow", "Club", "Sword", "Zweihander"] {
+        let name  = kb.create_string(name);
+        let mut b = WeaponBuilder::new(&mut kb);
+        b.add_name(name);
+        keys.push(b.finish());
+    }
+    let keys  = kb.create_vector_of_sorted_tables(&mut keys);
+    let name  = kb.create_string("Keys");
+    let mut b = MonsterBuilder::new(&mut kb);
+    b.add_name(name);
+    b.add_weapons(keys);
+    let root  = b.finish();
+    finish_monster_buffer(&mut kb, root);
+    let keys  = get_root_as_monster(kb.get_buffer()).weapons().unwrap();
+
+    // The first and last weapons are found, as well as the middle one, and keys which fall before,
+    // between or after them aren't.
+    let found: Vec<_> = keys.iter().map(|k| weapons.lookup_by_key(&k).map(|w| w.damage())).collect();
+    assert_eq!(found, [None, Some(5), Some(2), None, Some(3), None]);
+
+    assert_eq!(weapons.lookup_by_key_value("Axe").unwrap().damage(), 5);
+    assert_eq!(weapons.lookup_by_key_value("Sword").unwrap().damage(), 3);
+    assert!(weapons.lookup_by_key_value("").is_none());
+    assert!(weapons.lookup_by_key_value("Zweihander").is_none());
+}
+
+#[test]
+fn fields_can_be_changed_in_place() {
+    let mut fbb = FlatBufferBuilder::new(1);
+    build_monster(&mut fbb);
+    let mut buf = fbb.get_buffer().to_vec();
+
+    {
+        let mut monster = get_root_as_monster_mut(&mut buf);
+        assert!(monster.mutate_hp(10));
+        assert!(!monster.mutate_mana(10));
+        assert!(monster.mutate_pos(vec3(2.0)));
+        monster.inventory_mut().unwrap().as_bytes_mut()[0] = 9;
+        let mut path = monster.path_mut().unwrap();
+        assert!(path.set(1, vec3(7.0)));
+        let mut first = path.get_mut(0).unwrap();
+        assert_eq!(first.get(), vec3(1.0));
+        first.update(|v| v.set_z(0.5));
+        assert!(path.get_mut(2).is_none());
+    }
+
+    let monster = get_root_as_monster(&buf);
+    assert_eq!(monster.hp(), 10);
+    assert_eq!(monster.mana(), 150);
+    assert_eq!(monster.pos(), Some(vec3(2.0)));
+    assert_eq!(monster.inventory().unwrap().get(0), Some(9));
+    assert_eq!(monster.path().unwrap().get(0), Some(Vec3::new(1.0, 2.0, 0.5)));
+    assert_eq!(monster.path().unwrap().get(1), Some(vec3(7.0)));
+}
+
+#[test]
+#[should_panic(expected = "missing required field")]
+fn required_fields_must_be_set() {
+    let mut fbb = FlatBufferBuilder::new(1);
+    let mut b   = MonsterBuilder::new(&mut fbb);
+    b.add_hp(1);
+    b.finish();
+}
+
+#[test]
+fn fallible_builders_report_errors_instead_of_panicking() {
+    let mut fbb = FlatBufferBuilder::new(1);
+    let mut b   = MonsterBuilder::try_new(&mut fbb).unwrap();
+    b.try_add_hp(1).unwrap();
+    assert_eq!(b.try_finish().err(), Some(BuilderError::MissingRequiredField(Monster::VT_NAME)));
+
+    let monster = MonsterT { hp: 1, ..MonsterT::default() };
+    let mut fbb = FlatBufferBuilder::new(1);
+    assert_eq!(monster.try_pack(&mut fbb).err(),
+               Some(BuilderError::MissingRequiredField(Monster::VT_NAME)));
+
+    // A nested table which is too big for the buffer fails the whole object.
+    let monster = MonsterT {
+        name:  Some("Orc".to_string()),
+        enemy: Some(Box::new(MonsterT { name: Some("x".repeat(64)), ..MonsterT::default() })),
+        ..MonsterT::default()
+    };
+    let mut buf = [0; 32];
+    let mut fbb = FlatBufferBuilder::from_slice(&mut buf);
+    assert_eq!(monster.try_pack(&mut fbb).err(), Some(BuilderError::OutOfSpace));
+
+    let mut buf = [0; 256];
+    let mut fbb = FlatBufferBuilder::from_slice(&mut buf);
+    let root    = monster.try_pack(&mut fbb).unwrap();
+    try_finish_monster_buffer(&mut fbb, root).unwrap();
+    assert_eq!(get_root_as_monster_verified(fbb.get_buffer()).unwrap().unpack(), monster);
+}
+
+#[test]
+fn verification_checks_required_fields_and_identifiers() {
+    // `MonsterBuilder` won't leave out the name, so build the table by hand.
+    let mut fbb = FlatBufferBuilder::new(1);
+    let start   = fbb.start_table();
+    fbb.add_scalar(Monster::VT_HP, 1i16, 100);
+    let root    = flatbuffers::Offset::new(fbb.end_table(start, 4));
+    finish_monster_buffer(&mut fbb, root);
+    assert!(get_root_as_monster_verified(fbb.get_buffer()).is_err());
+
+    let mut fbb = FlatBufferBuilder::new(1);
+    build_monster(&mut fbb);
+    let mut buf = fbb.get_buffer().to_vec();
+    buf[4..8].copy_from_slice(b"NOPE");
+    assert!(get_root_as_monster_verified(&buf).is_err());
+}
+
+#[test]
+#[should_panic(expected = "a vector of unions has 2 types but 1 values")]
+fn union_vectors_with_different_lengths_are_malformed() {
+    // `create_union_vector` keeps the types and values the same length, so build them by hand.
+    let mut fbb = FlatBufferBuilder::new(1);
+    let shield  = {
+        let mut b = ShieldBuilder::new(&mut fbb);
+        b.add_defense(7);
+        b.finish()
+    };
+    let types   = fbb.create_vector(&[Equipment::SHIELD.0, Equipment::SHIELD.0]);
+    let values  = fbb.create_vector_of_offsets(&[shield]);
+    let name    = fbb.create_string("Orc");
+    let start   = fbb.start_table();
+    fbb.add_offset(Monster::VT_NAME, name);
+    fbb.add_offset(Monster::VT_LOOT_TYPE, types);
+    fbb.add_offset(Monster::VT_LOOT, values);
+    let root    = flatbuffers::Offset::new(fbb.end_table(start, 13));
+    finish_monster_buffer(&mut fbb, root);
+
+    let buf = fbb.get_buffer();
+    assert!(get_root_as_monster_verified(buf).is_err());
+    get_root_as_monster(buf).loot();
+}
+
+#[test]
+fn objects_can_be_unpacked_and_packed() {
+    let mut fbb = FlatBufferBuilder::new(1);
+    build_monster(&mut fbb);
+    let monster = get_root_as_monster(fbb.get_buffer()).unpack();
+
+    assert_eq!(monster.name.as_ref().unwrap(), "Orc");
+    assert_eq!(monster.weapons.as_ref().unwrap()[0], WeaponT { name: Some("Axe".to_string()), damage: 5 });
+    assert_eq!(monster.equipped, EquipmentT::Shield(Box::new(ShieldT { defense: 7 })));
+    assert_eq!(monster.tags, Some(vec!["big".to_string(), "green".to_string()]));
+    assert_eq!(monster.enemy.as_ref().unwrap().hp, 100);
+
+    let mut fbb = FlatBufferBuilder::new(1);
+    let root    = monster.pack(&mut fbb);
+    finish_monster_buffer(&mut fbb, root);
+
+    let again = get_root_as_monster_verified(fbb.get_buffer()).unwrap().unpack();
+    assert_eq!(again, monster);
+}
+
+#[test]
+fn every_kind_of_field_round_trips_through_the_object_api() {
+    let weapon = |name: &str, damage| WeaponT { name: Some(name.to_string()), damage };
+
+    let monster = MonsterT {
+        pos:       Some(vec3(1.0)),
+        mana:      20,
+        name:      Some("Troll".to_string()),
+        inventory: Some(vec![]),
+        color:     Color::GREEN,
+        weapons:   Some(vec![weapon("Club", 4), weapon("Rock", 1)]),
+        path:      Some(vec![vec3(2.0), vec3(3.0)]),
+        equipped:  EquipmentT::Weapon(Box::new(weapon("Club", 4))),
+        loot:      Some(vec![EquipmentT::NONE,
+                             EquipmentT::Shield(Box::new(ShieldT { defense: 3 })),
+                             EquipmentT::NONE,
+                             EquipmentT::Weapon(Box::default())]),
+        tags:      Some(vec!["".to_string(), "héllo".to_string()]),
+        enemy:     Some(Box::new(MonsterT {
+            name:  Some("Imp".to_string()),
+            enemy: Some(Box::new(MonsterT { name: Some("Rat".to_string()), ..MonsterT::default() })),
+            ..MonsterT::default()
+        })),
+        route:     Some(Path::new(&vec3(5.0), &vec3(6.0), 0.25)),
+        ..MonsterT::default()
+    };
+
+    let mut fbb = FlatBufferBuilder::new(1);
+    let root    = monster.pack(&mut fbb);
+    finish_monster_buffer(&mut fbb, root);
+
+    let view = get_root_as_monster_verified(fbb.get_buffer()).unwrap();
+    assert_eq!(view.unpack(), monster);
+
+    // `NONE` elements of the union vector keep their places.
+    let loot  = view.loot().unwrap();
+    let types = view.loot_type().unwrap().iter().collect::<Vec<_>>();
+    assert_eq!(types, [Equipment::NONE, Equipment::SHIELD, Equipment::NONE, Equipment::WEAPON]);
+    assert!(loot.get(0).is_none() && loot.get(2).is_none());
+    assert_eq!(loot.get(3).unwrap().union_type(), Equipment::WEAPON);
+
+    // Fields which were left at their defaults aren't stored.
+    assert_eq!(view.hp(), 100);
+    let mut buf = fbb.get_buffer().to_vec();
+    assert!(!get_root_as_monster_mut(&mut buf).mutate_hp(1));
+}
+
+#[test]
+fn an_empty_object_round_trips() {
+    let monster = MonsterT { name: Some("Nobody".to_string()), ..MonsterT::default() };
+
+    let mut fbb = FlatBufferBuilder::new(1);
+    let root    = monster.pack(&mut fbb);
+    finish_monster_buffer(&mut fbb, root);
+
+    let view = get_root_as_monster_verified(fbb.get_buffer()).unwrap();
+    assert!(view.pos().is_none() && view.inventory().is_none() && view.loot().is_none());
+    assert_eq!(view.unpack(), monster);
+}
@@ -0,0 +1,1120 @@
+// automatically generated by the FlatBuffers compiler, do not modify
+
+pub mod game {
+    #![allow(dead_code, unused_imports, non_camel_case_types)]
+
+    pub mod sample {
+        #![allow(dead_code, unused_imports, non_camel_case_types)]
+
+        #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+        #[repr(transparent)]
+        pub struct Color(pub u8);
+
+        impl Color {
+            pub const RED: Color = Color(0);
+            pub const GREEN: Color = Color(1);
+            pub const BLUE: Color = Color(2);
+
+            /// Every named value, in increasing order.
+            pub const VALUES: [Color; 3] = [
+                Color::RED,
+                Color::GREEN,
+                Color::BLUE,
+            ];
+
+            /// The name of this value as written in the schema, if it has one.
+            pub fn variant_name(self) -> Option<&'static str> {
+                match self {
+                    Color::RED => Some("Red"),
+                    Color::GREEN => Some("Green"),
+                    Color::BLUE => Some("Blue"),
+                    _ => None,
+                }
+            }
+        }
+
+        impl ::core::fmt::Debug for Color {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+                match self.variant_name() {
+                    Some(n) => f.write_str(n),
+                    None => write!(f, "Color({})", self.0),
+                }
+            }
+        }
+
+        impl ::flatbuffers::Endian for Color {
+            fn read_le(buf: &[u8]) -> Color { Color(::flatbuffers::Endian::read_le(buf)) }
+            fn write_le(self, buf: &mut [u8]) { ::flatbuffers::Endian::write_le(self.0, buf) }
+            fn from_le(self) -> Color { Color(::flatbuffers::Endian::from_le(self.0)) }
+            fn to_le(self) -> Color { Color(::flatbuffers::Endian::to_le(self.0)) }
+        }
+
+        #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+        #[repr(transparent)]
+        pub struct Flags(pub u16);
+
+        impl Flags {
+            pub const FAST: Flags = Flags(1);
+            pub const STRONG: Flags = Flags(2);
+            pub const WISE: Flags = Flags(4);
+
+            /// Every named value, in increasing order.
+            pub const VALUES: [Flags; 3] = [
+                Flags::FAST,
+                Flags::STRONG,
+                Flags::WISE,
+            ];
+
+            /// The name of this value as written in the schema, if it has one.
+            pub fn variant_name(self) -> Option<&'static str> {
+                match self {
+                    Flags::FAST => Some("Fast"),
+                    Flags::STRONG => Some("Strong"),
+                    Flags::WISE => Some("Wise"),
+                    _ => None,
+                }
+            }
+        }
+
+        impl ::core::fmt::Debug for Flags {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+                match self.variant_name() {
+                    Some(n) => f.write_str(n),
+                    None => write!(f, "Flags({})", self.0),
+                }
+            }
+        }
+
+        impl ::flatbuffers::Endian for Flags {
+            fn read_le(buf: &[u8]) -> Flags { Flags(::flatbuffers::Endian::read_le(buf)) }
+            fn write_le(self, buf: &mut [u8]) { ::flatbuffers::Endian::write_le(self.0, buf) }
+            fn from_le(self) -> Flags { Flags(::flatbuffers::Endian::from_le(self.0)) }
+            fn to_le(self) -> Flags { Flags(::flatbuffers::Endian::to_le(self.0)) }
+        }
+
+        #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+        #[repr(transparent)]
+        pub struct Equipment(pub u8);
+
+        impl Equipment {
+            pub const NONE: Equipment = Equipment(0);
+            pub const WEAPON: Equipment = Equipment(1);
+            pub const SHIELD: Equipment = Equipment(2);
+
+            /// Every named value, in increasing order.
+            pub const VALUES: [Equipment; 3] = [
+                Equipment::NONE,
+                Equipment::WEAPON,
+                Equipment::SHIELD,
+            ];
+
+            /// The name of this value as written in the schema, if it has one.
+            pub fn variant_name(self) -> Option<&'static str> {
+                match self {
+                    Equipment::NONE => Some("NONE"),
+                    Equipment::WEAPON => Some("Weapon"),
+                    Equipment::SHIELD => Some("Shield"),
+                    _ => None,
+                }
+            }
+
+            /// Check a value of this union, whose type is given by `self`. Unknown types are
+            /// accepted, since they may have been added in a newer version of the schema.
+            pub fn verify_value(self, v: &mut ::flatbuffers::Verifier, pos: usize)
+                -> Result<(), ::flatbuffers::VerifyError> {
+                match self {
+                    Equipment::WEAPON => <Weapon as ::flatbuffers::Verify>::verify(v, pos),
+                    Equipment::SHIELD => <Shield as ::flatbuffers::Verify>::verify(v, pos),
+                    _ => Ok(()),
+                }
+            }
+        }
+
+        impl ::core::fmt::Debug for Equipment {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+                match self.variant_name() {
+                    Some(n) => f.write_str(n),
+                    None => write!(f, "Equipment({})", self.0),
+                }
+            }
+        }
+
+        impl ::flatbuffers::Endian for Equipment {
+            fn read_le(buf: &[u8]) -> Equipment { Equipment(::flatbuffers::Endian::read_le(buf)) }
+            fn write_le(self, buf: &mut [u8]) { ::flatbuffers::Endian::write_le(self.0, buf) }
+            fn from_le(self) -> Equipment { Equipment(::flatbuffers::Endian::from_le(self.0)) }
+            fn to_le(self) -> Equipment { Equipment(::flatbuffers::Endian::to_le(self.0)) }
+        }
+
+        /// A view of the value of an `Equipment`.
+        #[derive(Clone, Copy)]
+        pub enum EquipmentRef<'buf> {
+            Weapon(Weapon<'buf>),
+            Shield(Shield<'buf>),
+            /// A member which isn't in this version of the schema.
+            Unknown(Equipment, ::flatbuffers::Table<'buf>),
+        }
+
+        impl EquipmentRef<'_> {
+            pub fn union_type(&self) -> Equipment {
+                match *self {
+                    EquipmentRef::Weapon(_) => Equipment::WEAPON,
+                    EquipmentRef::Shield(_) => Equipment::SHIELD,
+                    EquipmentRef::Unknown(t, _) => t,
+                }
+            }
+
+            /// Copy the value out of the buffer. Unknown members become `NONE`.
+            pub fn unpack(&self) -> EquipmentT {
+                match *self {
+                    EquipmentRef::Weapon(ref t) => EquipmentT::Weapon(::flatbuffers::alloc::boxed::Box::new(t.unpack())),
+                    EquipmentRef::Shield(ref t) => EquipmentT::Shield(::flatbuffers::alloc::boxed::Box::new(t.unpack())),
+                    EquipmentRef::Unknown(..) => EquipmentT::NONE,
+                }
+            }
+        }
+
+        impl<'buf> ::flatbuffers::Union<'buf> for EquipmentRef<'buf> {
+            fn from_table(tag: u8, table: ::flatbuffers::Table<'buf>) -> EquipmentRef<'buf> {
+                match Equipment(tag) {
+                    Equipment::WEAPON => EquipmentRef::Weapon(::flatbuffers::View::view(table.buf(), table.pos())),
+                    Equipment::SHIELD => EquipmentRef::Shield(::flatbuffers::View::view(table.buf(), table.pos())),
+                    t => EquipmentRef::Unknown(t, table),
+                }
+            }
+        }
+
+        /// An `Equipment` value which has been built, ready to be added to a table.
+        #[derive(Clone, Copy)]
+        pub enum EquipmentOffset {
+            Weapon(::flatbuffers::Offset<Weapon<'static>>),
+            Shield(::flatbuffers::Offset<Shield<'static>>),
+        }
+
+        impl EquipmentOffset {
+            pub fn union_type(&self) -> Equipment {
+                match *self {
+                    EquipmentOffset::Weapon(_) => Equipment::WEAPON,
+                    EquipmentOffset::Shield(_) => Equipment::SHIELD,
+                }
+            }
+        }
+
+        impl ::flatbuffers::UnionOffset for EquipmentOffset {
+            fn tag(&self) -> u8 { self.union_type().0 }
+
+            fn value(&self) -> ::flatbuffers::Offset<::flatbuffers::Table<'static>> {
+                match *self {
+                    EquipmentOffset::Weapon(o) => ::flatbuffers::Offset::new(o.value()),
+                    EquipmentOffset::Shield(o) => ::flatbuffers::Offset::new(o.value()),
+                }
+            }
+        }
+
+        /// An owned `Equipment` value, for use with the object API.
+        #[derive(Clone, Debug, PartialEq)]
+        pub enum EquipmentT {
+            NONE,
+            Weapon(::flatbuffers::alloc::boxed::Box<WeaponT>),
+            Shield(::flatbuffers::alloc::boxed::Box<ShieldT>),
+        }
+
+        impl Default for EquipmentT {
+            fn default() -> EquipmentT { EquipmentT::NONE }
+        }
+
+        impl EquipmentT {
+            pub fn union_type(&self) -> Equipment {
+                match *self {
+                    EquipmentT::NONE => Equipment(0),
+                    EquipmentT::Weapon(_) => Equipment::WEAPON,
+                    EquipmentT::Shield(_) => Equipment::SHIELD,
+                }
+            }
+
+            /// Build the member table into `fbb`, or return `None` if this is `NONE`.
+            pub fn pack(&self, fbb: &mut ::flatbuffers::FlatBufferBuilder<'_>) -> Option<EquipmentOffset> {
+                match self.try_pack(fbb) {
+                    Ok(o)  => o,
+                    Err(e) => panic!("{}", e),
+                }
+            }
+
+            /// Like `pack`, but return an error instead of panicking.
+            pub fn try_pack(&self, fbb: &mut ::flatbuffers::FlatBufferBuilder<'_>)
+                            -> Result<Option<EquipmentOffset>, ::flatbuffers::BuilderError> {
+                match *self {
+                    EquipmentT::NONE => Ok(None),
+                    EquipmentT::Weapon(ref t) => Ok(Some(EquipmentOffset::Weapon(t.try_pack(fbb)?))),
+                    EquipmentT::Shield(ref t) => Ok(Some(EquipmentOffset::Shield(t.try_pack(fbb)?))),
+                }
+            }
+        }
+
+        #[repr(C)]
+        #[derive(Clone, Copy, PartialEq)]
+        pub struct Vec3 {
+            x: f32,
+            y: f32,
+            z: f32,
+        }
+
+        unsafe impl ::flatbuffers::Struct for Vec3 {}
+
+        impl Vec3 {
+            pub fn new(x: f32, y: f32, z: f32) -> Vec3 {
+                Vec3 {
+                    x: ::flatbuffers::Endian::to_le(x),
+                    y: ::flatbuffers::Endian::to_le(y),
+                    z: ::flatbuffers::Endian::to_le(z),
+                }
+            }
+
+            pub fn x(&self) -> f32 { ::flatbuffers::Endian::from_le(self.x) }
+
+            pub fn set_x(&mut self, x: f32) { self.x = ::flatbuffers::Endian::to_le(x); }
+
+            pub fn y(&self) -> f32 { ::flatbuffers::Endian::from_le(self.y) }
+
+            pub fn set_y(&mut self, y: f32) { self.y = ::flatbuffers::Endian::to_le(y); }
+
+            pub fn z(&self) -> f32 { ::flatbuffers::Endian::from_le(self.z) }
+
+            pub fn set_z(&mut self, z: f32) { self.z = ::flatbuffers::Endian::to_le(z); }
+        }
+
+        impl ::core::fmt::Debug for Vec3 {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+                f.debug_struct("Vec3")
+                    .field("x", &self.x())
+                    .field("y", &self.y())
+                    .field("z", &self.z())
+                    .finish()
+            }
+        }
+
+        #[repr(C)]
+        #[derive(Clone, Copy, PartialEq)]
+        pub struct Path {
+            start: Vec3,
+            end: Vec3,
+            speed: f64,
+        }
+
+        unsafe impl ::flatbuffers::Struct for Path {}
+
+        impl Path {
+            pub fn new(start: &Vec3, end: &Vec3, speed: f64) -> Path {
+                Path {
+                    start: *start,
+                    end: *end,
+                    speed: ::flatbuffers::Endian::to_le(speed),
+                }
+            }
+
+            pub fn start(&self) -> &Vec3 { &self.start }
+
+            pub fn start_mut(&mut self) -> &mut Vec3 { &mut self.start }
+
+            pub fn end(&self) -> &Vec3 { &self.end }
+
+            pub fn end_mut(&mut self) -> &mut Vec3 { &mut self.end }
+
+            pub fn speed(&self) -> f64 { ::flatbuffers::Endian::from_le(self.speed) }
+
+            pub fn set_speed(&mut self, speed: f64) { self.speed = ::flatbuffers::Endian::to_le(speed); }
+        }
+
+        impl ::core::fmt::Debug for Path {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+                f.debug_struct("Path")
+                    .field("start", &self.start())
+                    .field("end", &self.end())
+                    .field("speed", &self.speed())
+                    .finish()
+            }
+        }
+
+        #[derive(Clone, Copy)]
+        pub struct Weapon<'buf>(::flatbuffers::Table<'buf>);
+
+        impl<'buf> ::flatbuffers::View<'buf> for Weapon<'buf> {
+            fn view(buf: &'buf [u8], pos: usize) -> Weapon<'buf> {
+                Weapon(::flatbuffers::View::view(buf, pos))
+            }
+        }
+
+        impl<'buf> Weapon<'buf> {
+            pub const VT_NAME: ::flatbuffers::VOffset = 4;
+            pub const VT_DAMAGE: ::flatbuffers::VOffset = 6;
+
+            pub fn name(&self) -> Option<::flatbuffers::Str<'buf>> {
+                self.0.get_ref(Weapon::VT_NAME)
+            }
+
+            pub fn damage(&self) -> i16 {
+                self.0.get_field(Weapon::VT_DAMAGE, 10)
+            }
+        }
+
+        /// A `Weapon` in a mutable buffer, whose fields can be changed in place.
+        pub struct WeaponMut<'buf>(::flatbuffers::TableMut<'buf>);
+
+        impl<'buf> From<::flatbuffers::TableMut<'buf>> for WeaponMut<'buf> {
+            fn from(t: ::flatbuffers::TableMut<'buf>) -> WeaponMut<'buf> { WeaponMut(t) }
+        }
+
+        impl WeaponMut<'_> {
+            /// A read-only view of the table.
+            pub fn view(&self) -> Weapon<'_> { Weapon(self.0.as_table()) }
+
+            /// Change the value of `damage` in place. This only works if the field is present
+            /// in the buffer, and returns `false` if it isn't.
+            pub fn mutate_damage(&mut self, damage: i16) -> bool {
+                self.0.set_field(Weapon::VT_DAMAGE, damage)
+            }
+        }
+
+        impl ::flatbuffers::Verify for Weapon<'_> {
+            fn verify(v: &mut ::flatbuffers::Verifier, pos: usize) -> Result<(), ::flatbuffers::VerifyError> {
+                let mut t = v.verify_table(pos)?;
+                t.visit_string(Weapon::VT_NAME, false)?;
+                t.visit_field::<i16>(Weapon::VT_DAMAGE, false)?;
+                t.end()
+            }
+        }
+
+        impl ::flatbuffers::OrdTable for Weapon<'_> {
+            type Key = str;
+
+            fn table(&self) -> ::flatbuffers::Table<'_> { self.0 }
+
+            fn key_cmp(a: ::flatbuffers::Table, b: ::flatbuffers::Table) -> ::core::cmp::Ordering {
+                Weapon(a).name().cmp(&Weapon(b).name())
+            }
+
+            fn key_cmp_with(a: ::flatbuffers::Table, key: &str) -> ::core::cmp::Ordering {
+                match Weapon(a).name() {
+                    Some(s) => s.as_bytes().cmp(key.as_bytes()),
+                    None    => ::core::cmp::Ordering::Less,
+                }
+            }
+        }
+
+        pub struct WeaponBuilder<'fbb, 'buf> {
+            fbb: &'fbb mut ::flatbuffers::FlatBufferBuilder<'buf>,
+            start: ::flatbuffers::UOffset,
+        }
+
+        impl<'fbb, 'buf> WeaponBuilder<'fbb, 'buf> {
+            pub fn new(fbb: &'fbb mut ::flatbuffers::FlatBufferBuilder<'buf>) -> WeaponBuilder<'fbb, 'buf> {
+                let start = fbb.start_table();
+                WeaponBuilder { fbb, start }
+            }
+
+            pub fn try_new(fbb: &'fbb mut ::flatbuffers::FlatBufferBuilder<'buf>)
+                           -> Result<WeaponBuilder<'fbb, 'buf>, ::flatbuffers::BuilderError> {
+                let start = fbb.try_start_table()?;
+                Ok(WeaponBuilder { fbb, start })
+            }
+
+            pub fn add_name(&mut self, name: ::flatbuffers::Offset<::flatbuffers::Str<'_>>) {
+                self.fbb.add_offset(Weapon::VT_NAME, name);
+            }
+
+            pub fn try_add_name(&mut self, name: ::flatbuffers::Offset<::flatbuffers::Str<'_>>) -> Result<(), ::flatbuffers::BuilderError> {
+                self.fbb.try_add_offset(Weapon::VT_NAME, name)
+            }
+
+            pub fn add_damage(&mut self, damage: i16) {
+                self.fbb.add_scalar(Weapon::VT_DAMAGE, damage, 10);
+            }
+
+            pub fn try_add_damage(&mut self, damage: i16) -> Result<(), ::flatbuffers::BuilderError> {
+                self.fbb.try_add_scalar(Weapon::VT_DAMAGE, damage, 10)
+            }
+
+            pub fn finish(self) -> ::flatbuffers::Offset<Weapon<'static>> {
+                let o = self.fbb.end_table(self.start, 2);
+                ::flatbuffers::Offset::new(o)
+            }
+
+            /// Like `finish`, but return an error instead of panicking, e.g. if a required field is
+            /// missing.
+            pub fn try_finish(self) -> Result<::flatbuffers::Offset<Weapon<'static>>, ::flatbuffers::BuilderError> {
+                let o = self.fbb.try_end_table(self.start, 2)?;
+                Ok(::flatbuffers::Offset::new(o))
+            }
+        }
+
+        /// An owned `Weapon`, which doesn't borrow a buffer. It can be made from a `Weapon` with
+        /// `unpack`, changed freely, and built into a buffer again with `pack`.
+        #[derive(Clone, Debug, PartialEq)]
+        pub struct WeaponT {
+            pub name: Option<::flatbuffers::alloc::string::String>,
+            pub damage: i16,
+        }
+
+        impl Default for WeaponT {
+            fn default() -> WeaponT {
+                WeaponT {
+                    name: Default::default(),
+                    damage: 10,
+                }
+            }
+        }
+
+        impl Weapon<'_> {
+            /// Copy this table, and everything it refers to, out of the buffer. Strings which aren't
+            /// valid UTF-8 are copied lossily.
+            pub fn unpack(&self) -> WeaponT {
+                WeaponT {
+                    name: self.name().map(|s| ::flatbuffers::alloc::string::String::from_utf8_lossy(s.as_bytes()).into_owned()),
+                    damage: self.damage(),
+                }
+            }
+        }
+
+        impl WeaponT {
+            /// Build this table into `fbb`, along with everything it refers to. Like `WeaponBuilder`,
+            /// this panics if a required field is `None`.
+            pub fn pack(&self, fbb: &mut ::flatbuffers::FlatBufferBuilder<'_>) -> ::flatbuffers::Offset<Weapon<'static>> {
+                match self.try_pack(fbb) {
+                    Ok(o)  => o,
+                    Err(e) => panic!("{}", e),
+                }
+            }
+
+            /// Like `pack`, but return an error instead of panicking.
+            pub fn try_pack(&self, fbb: &mut ::flatbuffers::FlatBufferBuilder<'_>)
+                            -> Result<::flatbuffers::Offset<Weapon<'static>>, ::flatbuffers::BuilderError> {
+                let name = self.name.as_ref().map(|s| fbb.try_create_string(s)).transpose()?;
+
+                let mut builder = WeaponBuilder::try_new(fbb)?;
+                if let Some(x) = name { builder.try_add_name(x)?; }
+                builder.try_add_damage(self.damage)?;
+                builder.try_finish()
+            }
+        }
+
+        #[derive(Clone, Copy)]
+        pub struct Shield<'buf>(::flatbuffers::Table<'buf>);
+
+        impl<'buf> ::flatbuffers::View<'buf> for Shield<'buf> {
+            fn view(buf: &'buf [u8], pos: usize) -> Shield<'buf> {
+                Shield(::flatbuffers::View::view(buf, pos))
+            }
+        }
+
+        impl<'buf> Shield<'buf> {
+            pub const VT_DEFENSE: ::flatbuffers::VOffset = 4;
+
+            pub fn defense(&self) -> i32 {
+                self.0.get_field(Shield::VT_DEFENSE, 0)
+            }
+        }
+
+        /// A `Shield` in a mutable buffer, whose fields can be changed in place.
+        pub struct ShieldMut<'buf>(::flatbuffers::TableMut<'buf>);
+
+        impl<'buf> From<::flatbuffers::TableMut<'buf>> for ShieldMut<'buf> {
+            fn from(t: ::flatbuffers::TableMut<'buf>) -> ShieldMut<'buf> { ShieldMut(t) }
+        }
+
+        impl ShieldMut<'_> {
+            /// A read-only view of the table.
+            pub fn view(&self) -> Shield<'_> { Shield(self.0.as_table()) }
+
+            /// Change the value of `defense` in place. This only works if the field is present
+            /// in the buffer, and returns `false` if it isn't.
+            pub fn mutate_defense(&mut self, defense: i32) -> bool {
+                self.0.set_field(Shield::VT_DEFENSE, defense)
+            }
+        }
+
+        impl ::flatbuffers::Verify for Shield<'_> {
+            fn verify(v: &mut ::flatbuffers::Verifier, pos: usize) -> Result<(), ::flatbuffers::VerifyError> {
+                let mut t = v.verify_table(pos)?;
+                t.visit_field::<i32>(Shield::VT_DEFENSE, false)?;
+                t.end()
+            }
+        }
+
+        pub struct ShieldBuilder<'fbb, 'buf> {
+            fbb: &'fbb mut ::flatbuffers::FlatBufferBuilder<'buf>,
+            start: ::flatbuffers::UOffset,
+        }
+
+        impl<'fbb, 'buf> ShieldBuilder<'fbb, 'buf> {
+            pub fn new(fbb: &'fbb mut ::flatbuffers::FlatBufferBuilder<'buf>) -> ShieldBuilder<'fbb, 'buf> {
+                let start = fbb.start_table();
+                ShieldBuilder { fbb, start }
+            }
+
+            pub fn try_new(fbb: &'fbb mut ::flatbuffers::FlatBufferBuilder<'buf>)
+                           -> Result<ShieldBuilder<'fbb, 'buf>, ::flatbuffers::BuilderError> {
+                let start = fbb.try_start_table()?;
+                Ok(ShieldBuilder { fbb, start })
+            }
+
+            pub fn add_defense(&mut self, defense: i32) {
+                self.fbb.add_scalar(Shield::VT_DEFENSE, defense, 0);
+            }
+
+            pub fn try_add_defense(&mut self, defense: i32) -> Result<(), ::flatbuffers::BuilderError> {
+                self.fbb.try_add_scalar(Shield::VT_DEFENSE, defense, 0)
+            }
+
+            pub fn finish(self) -> ::flatbuffers::Offset<Shield<'static>> {
+                let o = self.fbb.end_table(self.start, 1);
+                ::flatbuffers::Offset::new(o)
+            }
+
+            /// Like `finish`, but return an error instead of panicking, e.g. if a required field is
+            /// missing.
+            pub fn try_finish(self) -> Result<::flatbuffers::Offset<Shield<'static>>, ::flatbuffers::BuilderError> {
+                let o = self.fbb.try_end_table(self.start, 1)?;
+                Ok(::flatbuffers::Offset::new(o))
+            }
+        }
+
+        /// An owned `Shield`, which doesn't borrow a buffer. It can be made from a `Shield` with
+        /// `unpack`, changed freely, and built into a buffer again with `pack`.
+        #[derive(Clone, Debug, PartialEq)]
+        pub struct ShieldT {
+            pub defense: i32,
+        }
+
+        impl Default for ShieldT {
+            fn default() -> ShieldT {
+                ShieldT {
+                    defense: 0,
+                }
+            }
+        }
+
+        impl Shield<'_> {
+            /// Copy this table, and everything it refers to, out of the buffer. Strings which aren't
+            /// valid UTF-8 are copied lossily.
+            pub fn unpack(&self) -> ShieldT {
+                ShieldT {
+                    defense: self.defense(),
+                }
+            }
+        }
+
+        impl ShieldT {
+            /// Build this table into `fbb`, along with everything it refers to. Like `ShieldBuilder`,
+            /// this panics if a required field is `None`.
+            pub fn pack(&self, fbb: &mut ::flatbuffers::FlatBufferBuilder<'_>) -> ::flatbuffers::Offset<Shield<'static>> {
+                match self.try_pack(fbb) {
+                    Ok(o)  => o,
+                    Err(e) => panic!("{}", e),
+                }
+            }
+
+            /// Like `pack`, but return an error instead of panicking.
+            pub fn try_pack(&self, fbb: &mut ::flatbuffers::FlatBufferBuilder<'_>)
+                            -> Result<::flatbuffers::Offset<Shield<'static>>, ::flatbuffers::BuilderError> {
+
+                let mut builder = ShieldBuilder::try_new(fbb)?;
+                builder.try_add_defense(self.defense)?;
+                builder.try_finish()
+            }
+        }
+
+        #[derive(Clone, Copy)]
+        pub struct Monster<'buf>(::flatbuffers::Table<'buf>);
+
+        impl<'buf> ::flatbuffers::View<'buf> for Monster<'buf> {
+            fn view(buf: &'buf [u8], pos: usize) -> Monster<'buf> {
+                Monster(::flatbuffers::View::view(buf, pos))
+            }
+        }
+
+        impl<'buf> Monster<'buf> {
+            pub const VT_POS: ::flatbuffers::VOffset = 4;
+            pub const VT_MANA: ::flatbuffers::VOffset = 6;
+            pub const VT_HP: ::flatbuffers::VOffset = 8;
+            pub const VT_NAME: ::flatbuffers::VOffset = 10;
+            pub const VT_INVENTORY: ::flatbuffers::VOffset = 12;
+            pub const VT_COLOR: ::flatbuffers::VOffset = 14;
+            pub const VT_FLAGS: ::flatbuffers::VOffset = 16;
+            pub const VT_WEAPONS: ::flatbuffers::VOffset = 18;
+            pub const VT_PATH: ::flatbuffers::VOffset = 20;
+            pub const VT_EQUIPPED_TYPE: ::flatbuffers::VOffset = 22;
+            pub const VT_EQUIPPED: ::flatbuffers::VOffset = 24;
+            pub const VT_LOOT_TYPE: ::flatbuffers::VOffset = 26;
+            pub const VT_LOOT: ::flatbuffers::VOffset = 28;
+            pub const VT_TAGS: ::flatbuffers::VOffset = 30;
+            pub const VT_FRIENDLY: ::flatbuffers::VOffset = 32;
+            pub const VT_ENEMY: ::flatbuffers::VOffset = 34;
+            pub const VT_ROUTE: ::flatbuffers::VOffset = 36;
+
+            pub fn pos(&self) -> Option<Vec3> {
+                self.0.get_struct(Monster::VT_POS)
+            }
+
+            pub fn mana(&self) -> i16 {
+                self.0.get_field(Monster::VT_MANA, 150)
+            }
+
+            pub fn hp(&self) -> i16 {
+                self.0.get_field(Monster::VT_HP, 100)
+            }
+
+            pub fn name(&self) -> Option<::flatbuffers::Str<'buf>> {
+                self.0.get_ref(Monster::VT_NAME)
+            }
+
+            pub fn inventory(&self) -> Option<::flatbuffers::Vector<'buf, u8>> {
+                self.0.get_ref(Monster::VT_INVENTORY)
+            }
+
+            pub fn color(&self) -> Color {
+                self.0.get_field(Monster::VT_COLOR, Color(2))
+            }
+
+            pub fn flags(&self) -> Flags {
+                self.0.get_field(Monster::VT_FLAGS, Flags(1))
+            }
+
+            pub fn weapons(&self) -> Option<::flatbuffers::Vector<'buf, ::flatbuffers::Offset<Weapon<'buf>>>> {
+                self.0.get_ref(Monster::VT_WEAPONS)
+            }
+
+            pub fn path(&self) -> Option<::flatbuffers::Vector<'buf, ::flatbuffers::ByRef<Vec3>>> {
+                self.0.get_ref(Monster::VT_PATH)
+            }
+
+            pub fn equipped_type(&self) -> Equipment {
+                self.0.get_field(Monster::VT_EQUIPPED_TYPE, Equipment(0))
+            }
+
+            pub fn equipped(&self) -> Option<EquipmentRef<'buf>> {
+                self.0.get_union(Monster::VT_EQUIPPED_TYPE, Monster::VT_EQUIPPED)
+            }
+
+            pub fn equipped_as_weapon(&self) -> Option<Weapon<'buf>> {
+                if self.equipped_type() != Equipment::WEAPON { return None }
+                self.0.get_ref(Monster::VT_EQUIPPED)
+            }
+
+            pub fn equipped_as_shield(&self) -> Option<Shield<'buf>> {
+                if self.equipped_type() != Equipment::SHIELD { return None }
+                self.0.get_ref(Monster::VT_EQUIPPED)
+            }
+
+            pub fn loot_type(&self) -> Option<::flatbuffers::Vector<'buf, Equipment>> {
+                self.0.get_ref(Monster::VT_LOOT_TYPE)
+            }
+
+            pub fn loot(&self) -> Option<::flatbuffers::UnionVector<'buf, EquipmentRef<'buf>>> {
+                self.0.get_union_vector(Monster::VT_LOOT_TYPE, Monster::VT_LOOT)
+            }
+
+            pub fn tags(&self) -> Option<::flatbuffers::Vector<'buf, ::flatbuffers::Offset<::flatbuffers::Str<'buf>>>> {
+                self.0.get_ref(Monster::VT_TAGS)
+            }
+
+            pub fn enemy(&self) -> Option<Monster<'buf>> {
+                self.0.get_ref(Monster::VT_ENEMY)
+            }
+
+            pub fn route(&self) -> Option<Path> {
+                self.0.get_struct(Monster::VT_ROUTE)
+            }
+        }
+
+        /// A `Monster` in a mutable buffer, whose fields can be changed in place.
+        pub struct MonsterMut<'buf>(::flatbuffers::TableMut<'buf>);
+
+        impl<'buf> From<::flatbuffers::TableMut<'buf>> for MonsterMut<'buf> {
+            fn from(t: ::flatbuffers::TableMut<'buf>) -> MonsterMut<'buf> { MonsterMut(t) }
+        }
+
+        impl MonsterMut<'_> {
+            /// A read-only view of the table.
+            pub fn view(&self) -> Monster<'_> { Monster(self.0.as_table()) }
+
+            /// Change the value of `pos` in place. This only works if the field is present
+            /// in the buffer, and returns `false` if it isn't.
+            pub fn mutate_pos(&mut self, pos: Vec3) -> bool {
+                self.0.set_struct(Monster::VT_POS, pos)
+            }
+
+            /// Change the value of `mana` in place. This only works if the field is present
+            /// in the buffer, and returns `false` if it isn't.
+            pub fn mutate_mana(&mut self, mana: i16) -> bool {
+                self.0.set_field(Monster::VT_MANA, mana)
+            }
+
+            /// Change the value of `hp` in place. This only works if the field is present
+            /// in the buffer, and returns `false` if it isn't.
+            pub fn mutate_hp(&mut self, hp: i16) -> bool {
+                self.0.set_field(Monster::VT_HP, hp)
+            }
+
+            /// The elements of `inventory`, which can be changed in place.
+            pub fn inventory_mut(&mut self) -> Option<::flatbuffers::VectorMut<'_, u8>> {
+                self.0.get_vector_mut(Monster::VT_INVENTORY)
+            }
+
+            /// Change the value of `color` in place. This only works if the field is present
+            /// in the buffer, and returns `false` if it isn't.
+            pub fn mutate_color(&mut self, color: Color) -> bool {
+                self.0.set_field(Monster::VT_COLOR, color)
+            }
+
+            /// Change the value of `flags` in place. This only works if the field is present
+            /// in the buffer, and returns `false` if it isn't.
+            pub fn mutate_flags(&mut self, flags: Flags) -> bool {
+                self.0.set_field(Monster::VT_FLAGS, flags)
+            }
+
+            /// The elements of `path`, which can be changed in place.
+            pub fn path_mut(&mut self) -> Option<::flatbuffers::VectorMut<'_, ::flatbuffers::ByRef<Vec3>>> {
+                self.0.get_vector_mut(Monster::VT_PATH)
+            }
+
+            /// Change the value of `route` in place. This only works if the field is present
+            /// in the buffer, and returns `false` if it isn't.
+            pub fn mutate_route(&mut self, route: Path) -> bool {
+                self.0.set_struct(Monster::VT_ROUTE, route)
+            }
+        }
+
+        impl ::flatbuffers::Verify for Monster<'_> {
+            fn verify(v: &mut ::flatbuffers::Verifier, pos: usize) -> Result<(), ::flatbuffers::VerifyError> {
+                let mut t = v.verify_table(pos)?;
+                t.visit_field::<Vec3>(Monster::VT_POS, false)?;
+                t.visit_field::<i16>(Monster::VT_MANA, false)?;
+                t.visit_field::<i16>(Monster::VT_HP, false)?;
+                t.visit_string(Monster::VT_NAME, true)?;
+                t.visit_vector::<u8>(Monster::VT_INVENTORY, false)?;
+                t.visit_field::<Color>(Monster::VT_COLOR, false)?;
+                t.visit_field::<Flags>(Monster::VT_FLAGS, false)?;
+                t.visit_vector_of_tables::<Weapon>(Monster::VT_WEAPONS, false)?;
+                t.visit_vector::<Vec3>(Monster::VT_PATH, false)?;
+                t.visit_union(Monster::VT_EQUIPPED_TYPE, Monster::VT_EQUIPPED, false, |v, tag, pos| Equipment(tag).verify_value(v, pos))?;
+                t.visit_union_vector(Monster::VT_LOOT_TYPE, Monster::VT_LOOT, false, |v, tag, pos| Equipment(tag).verify_value(v, pos))?;
+                t.visit_vector_of_strings(Monster::VT_TAGS, false)?;
+                t.visit_table::<Monster>(Monster::VT_ENEMY, false)?;
+                t.visit_field::<Path>(Monster::VT_ROUTE, false)?;
+                t.end()
+            }
+        }
+
+        pub struct MonsterBuilder<'fbb, 'buf> {
+            fbb: &'fbb mut ::flatbuffers::FlatBufferBuilder<'buf>,
+            start: ::flatbuffers::UOffset,
+        }
+
+        impl<'fbb, 'buf> MonsterBuilder<'fbb, 'buf> {
+            pub fn new(fbb: &'fbb mut ::flatbuffers::FlatBufferBuilder<'buf>) -> MonsterBuilder<'fbb, 'buf> {
+                let start = fbb.start_table();
+                MonsterBuilder { fbb, start }
+            }
+
+            pub fn try_new(fbb: &'fbb mut ::flatbuffers::FlatBufferBuilder<'buf>)
+                           -> Result<MonsterBuilder<'fbb, 'buf>, ::flatbuffers::BuilderError> {
+                let start = fbb.try_start_table()?;
+                Ok(MonsterBuilder { fbb, start })
+            }
+
+            pub fn add_pos(&mut self, pos: &Vec3) {
+                self.fbb.add_struct(Monster::VT_POS, pos);
+            }
+
+            pub fn try_add_pos(&mut self, pos: &Vec3) -> Result<(), ::flatbuffers::BuilderError> {
+                self.fbb.try_add_struct(Monster::VT_POS, pos)
+            }
+
+            pub fn add_mana(&mut self, mana: i16) {
+                self.fbb.add_scalar(Monster::VT_MANA, mana, 150);
+            }
+
+            pub fn try_add_mana(&mut self, mana: i16) -> Result<(), ::flatbuffers::BuilderError> {
+                self.fbb.try_add_scalar(Monster::VT_MANA, mana, 150)
+            }
+
+            pub fn add_hp(&mut self, hp: i16) {
+                self.fbb.add_scalar(Monster::VT_HP, hp, 100);
+            }
+
+            pub fn try_add_hp(&mut self, hp: i16) -> Result<(), ::flatbuffers::BuilderError> {
+                self.fbb.try_add_scalar(Monster::VT_HP, hp, 100)
+            }
+
+            pub fn add_name(&mut self, name: ::flatbuffers::Offset<::flatbuffers::Str<'_>>) {
+                self.fbb.add_offset(Monster::VT_NAME, name);
+            }
+
+            pub fn try_add_name(&mut self, name: ::flatbuffers::Offset<::flatbuffers::Str<'_>>) -> Result<(), ::flatbuffers::BuilderError> {
+                self.fbb.try_add_offset(Monster::VT_NAME, name)
+            }
+
+            pub fn add_inventory(&mut self, inventory: ::flatbuffers::Offset<::flatbuffers::Vector<'_, u8>>) {
+                self.fbb.add_offset(Monster::VT_INVENTORY, inventory);
+            }
+
+            pub fn try_add_inventory(&mut self, inventory: ::flatbuffers::Offset<::flatbuffers::Vector<'_, u8>>) -> Result<(), ::flatbuffers::BuilderError> {
+                self.fbb.try_add_offset(Monster::VT_INVENTORY, inventory)
+            }
+
+            pub fn add_color(&mut self, color: Color) {
+                self.fbb.add_scalar(Monster::VT_COLOR, color, Color(2));
+            }
+
+            pub fn try_add_color(&mut self, color: Color) -> Result<(), ::flatbuffers::BuilderError> {
+                self.fbb.try_add_scalar(Monster::VT_COLOR, color, Color(2))
+            }
+
+            pub fn add_flags(&mut self, flags: Flags) {
+                self.fbb.add_scalar(Monster::VT_FLAGS, flags, Flags(1));
+            }
+
+            pub fn try_add_flags(&mut self, flags: Flags) -> Result<(), ::flatbuffers::BuilderError> {
+                self.fbb.try_add_scalar(Monster::VT_FLAGS, flags, Flags(1))
+            }
+
+            pub fn add_weapons(&mut self, weapons: ::flatbuffers::Offset<::flatbuffers::Vector<'_, ::flatbuffers::Offset<Weapon<'_>>>>) {
+                self.fbb.add_offset(Monster::VT_WEAPONS, weapons);
+            }
+
+            pub fn try_add_weapons(&mut self, weapons: ::flatbuffers::Offset<::flatbuffers::Vector<'_, ::flatbuffers::Offset<Weapon<'_>>>>) -> Result<(), ::flatbuffers::BuilderError> {
+                self.fbb.try_add_offset(Monster::VT_WEAPONS, weapons)
+            }
+
+            pub fn add_path(&mut self, path: ::flatbuffers::Offset<::flatbuffers::Vector<'_, ::flatbuffers::ByRef<Vec3>>>) {
+                self.fbb.add_offset(Monster::VT_PATH, path);
+            }
+
+            pub fn try_add_path(&mut self, path: ::flatbuffers::Offset<::flatbuffers::Vector<'_, ::flatbuffers::ByRef<Vec3>>>) -> Result<(), ::flatbuffers::BuilderError> {
+                self.fbb.try_add_offset(Monster::VT_PATH, path)
+            }
+
+            pub fn add_equipped(&mut self, equipped: EquipmentOffset) {
+                self.fbb.add_union(Monster::VT_EQUIPPED_TYPE, Monster::VT_EQUIPPED, equipped);
+            }
+
+            pub fn try_add_equipped(&mut self, equipped: EquipmentOffset) -> Result<(), ::flatbuffers::BuilderError> {
+                self.fbb.try_add_union(Monster::VT_EQUIPPED_TYPE, Monster::VT_EQUIPPED, equipped)
+            }
+
+            pub fn add_loot(&mut self, loot: ::flatbuffers::UnionVectorOffset<EquipmentOffset>) {
+                self.fbb.add_union_vector(Monster::VT_LOOT_TYPE, Monster::VT_LOOT, loot);
+            }
+
+            pub fn try_add_loot(&mut self, loot: ::flatbuffers::UnionVectorOffset<EquipmentOffset>) -> Result<(), ::flatbuffers::BuilderError> {
+                self.fbb.try_add_union_vector(Monster::VT_LOOT_TYPE, Monster::VT_LOOT, loot)
+            }
+
+            pub fn add_tags(&mut self, tags: ::flatbuffers::Offset<::flatbuffers::Vector<'_, ::flatbuffers::Offset<::flatbuffers::Str<'_>>>>) {
+                self.fbb.add_offset(Monster::VT_TAGS, tags);
+            }
+
+            pub fn try_add_tags(&mut self, tags: ::flatbuffers::Offset<::flatbuffers::Vector<'_, ::flatbuffers::Offset<::flatbuffers::Str<'_>>>>) -> Result<(), ::flatbuffers::BuilderError> {
+                self.fbb.try_add_offset(Monster::VT_TAGS, tags)
+            }
+
+            pub fn add_enemy(&mut self, enemy: ::flatbuffers::Offset<Monster<'_>>) {
+                self.fbb.add_offset(Monster::VT_ENEMY, enemy);
+            }
+
+            pub fn try_add_enemy(&mut self, enemy: ::flatbuffers::Offset<Monster<'_>>) -> Result<(), ::flatbuffers::BuilderError> {
+                self.fbb.try_add_offset(Monster::VT_ENEMY, enemy)
+            }
+
+            pub fn add_route(&mut self, route: &Path) {
+                self.fbb.add_struct(Monster::VT_ROUTE, route);
+            }
+
+            pub fn try_add_route(&mut self, route: &Path) -> Result<(), ::flatbuffers::BuilderError> {
+                self.fbb.try_add_struct(Monster::VT_ROUTE, route)
+            }
+
+            pub fn finish(self) -> ::flatbuffers::Offset<Monster<'static>> {
+                let o = self.fbb.end_table(self.start, 17);
+                self.fbb.required(o, Monster::VT_NAME);
+                ::flatbuffers::Offset::new(o)
+            }
+
+            /// Like `finish`, but return an error instead of panicking, e.g. if a required field is
+            /// missing.
+            pub fn try_finish(self) -> Result<::flatbuffers::Offset<Monster<'static>>, ::flatbuffers::BuilderError> {
+                let o = self.fbb.try_end_table(self.start, 17)?;
+                self.fbb.try_required(o, Monster::VT_NAME)?;
+                Ok(::flatbuffers::Offset::new(o))
+            }
+        }
+
+        /// An owned `Monster`, which doesn't borrow a buffer. It can be made from a `Monster` with
+        /// `unpack`, changed freely, and built into a buffer again with `pack`.
+        #[derive(Clone, Debug, PartialEq)]
+        pub struct MonsterT {
+            pub pos: Option<Vec3>,
+            pub mana: i16,
+            pub hp: i16,
+            pub name: Option<::flatbuffers::alloc::string::String>,
+            pub inventory: Option<::flatbuffers::alloc::vec::Vec<u8>>,
+            pub color: Color,
+            pub flags: Flags,
+            pub weapons: Option<::flatbuffers::alloc::vec::Vec<WeaponT>>,
+            pub path: Option<::flatbuffers::alloc::vec::Vec<Vec3>>,
+            pub equipped: EquipmentT,
+            pub loot: Option<::flatbuffers::alloc::vec::Vec<EquipmentT>>,
+            pub tags: Option<::flatbuffers::alloc::vec::Vec<::flatbuffers::alloc::string::String>>,
+            pub enemy: Option<::flatbuffers::alloc::boxed::Box<MonsterT>>,
+            pub route: Option<Path>,
+        }
+
+        impl Default for MonsterT {
+            fn default() -> MonsterT {
+                MonsterT {
+                    pos: Default::default(),
+                    mana: 150,
+                    hp: 100,
+                    name: Default::default(),
+                    inventory: Default::default(),
+                    color: Color(2),
+                    flags: Flags(1),
+                    weapons: Default::default(),
+                    path: Default::default(),
+                    equipped: Default::default(),
+                    loot: Default::default(),
+                    tags: Default::default(),
+                    enemy: Default::default(),
+                    route: Default::default(),
+                }
+            }
+        }
+
+        impl Monster<'_> {
+            /// Copy this table, and everything it refers to, out of the buffer. Strings which aren't
+            /// valid UTF-8 are copied lossily.
+            pub fn unpack(&self) -> MonsterT {
+                MonsterT {
+                    pos: self.pos(),
+                    mana: self.mana(),
+                    hp: self.hp(),
+                    name: self.name().map(|s| ::flatbuffers::alloc::string::String::from_utf8_lossy(s.as_bytes()).into_owned()),
+                    inventory: self.inventory().map(|v| v.iter().collect()),
+                    color: self.color(),
+                    flags: self.flags(),
+                    weapons: self.weapons().map(|v| v.iter().map(|t| t.unpack()).collect()),
+                    path: self.path().map(|v| v.iter().collect()),
+                    equipped: self.equipped().map_or(EquipmentT::NONE, |u| u.unpack()),
+                    loot: self.loot().map(|v| v.iter().map(|u| u.map_or(EquipmentT::NONE, |u| u.unpack())).collect()),
+                    tags: self.tags().map(|v| v.iter().map(|s| ::flatbuffers::alloc::string::String::from_utf8_lossy(s.as_bytes()).into_owned()).collect()),
+                    enemy: self.enemy().map(|t| ::flatbuffers::alloc::boxed::Box::new(t.unpack())),
+                    route: self.route(),
+                }
+            }
+        }
+
+        impl MonsterT {
+            /// Build this table into `fbb`, along with everything it refers to. Like `MonsterBuilder`,
+            /// this panics if a required field is `None`.
+            pub fn pack(&self, fbb: &mut ::flatbuffers::FlatBufferBuilder<'_>) -> ::flatbuffers::Offset<Monster<'static>> {
+                match self.try_pack(fbb) {
+                    Ok(o)  => o,
+                    Err(e) => panic!("{}", e),
+                }
+            }
+
+            /// Like `pack`, but return an error instead of panicking.
+            pub fn try_pack(&self, fbb: &mut ::flatbuffers::FlatBufferBuilder<'_>)
+                            -> Result<::flatbuffers::Offset<Monster<'static>>, ::flatbuffers::BuilderError> {
+                let name = self.name.as_ref().map(|s| fbb.try_create_string(s)).transpose()?;
+                let inventory = self.inventory.as_ref().map(|v| fbb.try_create_vector(v)).transpose()?;
+                let weapons = self.weapons.as_ref().map(|v| {
+                    let v = v.iter().map(|t| t.try_pack(fbb)).collect::<Result<::flatbuffers::alloc::vec::Vec<_>, _>>()?;
+                    fbb.try_create_vector_of_offsets(&v)
+                }).transpose()?;
+                let path = self.path.as_ref().map(|v| fbb.try_create_vector_of_structs(v)).transpose()?;
+                let equipped = self.equipped.try_pack(fbb)?;
+                let loot = self.loot.as_ref().map(|v| {
+                    let v = v.iter().map(|u| u.try_pack(fbb)).collect::<Result<::flatbuffers::alloc::vec::Vec<_>, _>>()?;
+                    Ok(fbb.try_create_union_vector(&v)?.into())
+                }).transpose()?;
+                let tags = self.tags.as_ref().map(|v| {
+                    let v = v.iter().map(|s| fbb.try_create_string(s)).collect::<Result<::flatbuffers::alloc::vec::Vec<_>, _>>()?;
+                    fbb.try_create_vector_of_offsets(&v)
+                }).transpose()?;
+                let enemy = self.enemy.as_ref().map(|t| t.try_pack(fbb)).transpose()?;
+
+                let mut builder = MonsterBuilder::try_new(fbb)?;
+                if let Some(ref x) = self.route { builder.try_add_route(x)?; }
+                if let Some(ref x) = self.pos { builder.try_add_pos(x)?; }
+                if let Some(x) = name { builder.try_add_name(x)?; }
+                if let Some(x) = inventory { builder.try_add_inventory(x)?; }
+                if let Some(x) = weapons { builder.try_add_weapons(x)?; }
+                if let Some(x) = path { builder.try_add_path(x)?; }
+                if let Some(x) = equipped { builder.try_add_equipped(x)?; }
+                if let Some(x) = loot { builder.try_add_loot(x)?; }
+                if let Some(x) = tags { builder.try_add_tags(x)?; }
+                if let Some(x) = enemy { builder.try_add_enemy(x)?; }
+                builder.try_add_mana(self.mana)?;
+                builder.try_add_hp(self.hp)?;
+                builder.try_add_flags(self.flags)?;
+                builder.try_add_color(self.color)?;
+                builder.try_finish()
+            }
+        }
+
+        pub const MONSTER_IDENTIFIER: &str = "MONS";
+
+        /// Check whether a buffer has the `Monster` file identifier.
+        pub fn monster_buffer_has_identifier(buf: &[u8]) -> bool {
+            ::flatbuffers::buffer_has_identifier(buf, MONSTER_IDENTIFIER)
+        }
+
+        /// Access the root `Monster` of a buffer. This does no checking; use
+        /// `get_root_as_monster_verified` for untrusted buffers.
+        pub fn get_root_as_monster(buf: &[u8]) -> Monster<'_> {
+            ::flatbuffers::get_root(buf)
+        }
+
+        /// Access the root `Monster` of a mutable buffer, to change its fields in place.
+        pub fn get_root_as_monster_mut(buf: &mut [u8]) -> MonsterMut<'_> {
+            MonsterMut::from(::flatbuffers::get_root_mut(buf))
+        }
+
+        /// Verify a buffer and access its root `Monster`.
+        pub fn get_root_as_monster_verified(buf: &[u8]) -> Result<Monster<'_>, ::flatbuffers::VerifyError> {
+            ::flatbuffers::get_root_verified_with_identifier(buf, MONSTER_IDENTIFIER)
+        }
+
+        /// Access the root `Monster` of a size-prefixed buffer. This does no checking.
+        pub fn get_size_prefixed_root_as_monster(buf: &[u8]) -> Monster<'_> {
+            ::flatbuffers::get_size_prefixed_root(buf)
+        }
+
+        /// Verify a size-prefixed buffer and access its root `Monster`.
+        pub fn get_size_prefixed_root_as_monster_verified(buf: &[u8]) -> Result<Monster<'_>, ::flatbuffers::VerifyError> {
+            ::flatbuffers::get_size_prefixed_root_verified_with_identifier(buf, MONSTER_IDENTIFIER)
+        }
+
+        pub fn finish_monster_buffer(fbb: &mut ::flatbuffers::FlatBufferBuilder<'_>, root: ::flatbuffers::Offset<Monster<'_>>) {
+            fbb.finish_with_identifier(root, MONSTER_IDENTIFIER)
+        }
+
+        pub fn try_finish_monster_buffer(fbb: &mut ::flatbuffers::FlatBufferBuilder<'_>, root: ::flatbuffers::Offset<Monster<'_>>)
+                -> Result<(), ::flatbuffers::BuilderError> {
+            fbb.try_finish_with_identifier(root, MONSTER_IDENTIFIER)
+        }
+
+        pub fn finish_size_prefixed_monster_buffer(fbb: &mut ::flatbuffers::FlatBufferBuilder<'_>, root: ::flatbuffers::Offset<Monster<'_>>) {
+            fbb.finish_size_prefixed_with_identifier(root, MONSTER_IDENTIFIER)
+        }
+
+        pub fn try_finish_size_prefixed_monster_buffer(fbb: &mut ::flatbuffers::FlatBufferBuilder<'_>, root: ::flatbuffers::Offset<Monster<'_>>)
+                -> Result<(), ::flatbuffers::BuilderError> {
+            fbb.try_finish_size_prefixed_with_identifier(root, MONSTER_IDENTIFIER)
+        }
+
+    }
+
+}
+